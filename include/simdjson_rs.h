@@ -0,0 +1,78 @@
+/* C API of simd-json, build the library with
+ *
+ *     cargo rustc --release --features ffi --crate-type cdylib
+ *
+ * Values are pointers into the document they were parsed from and stay
+ * valid until it is freed. See src/ffi.rs for details.
+ */
+#ifndef SIMDJSON_RS_H
+#define SIMDJSON_RS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* status codes */
+#define SIMDJSON_RS_OK 0
+#define SIMDJSON_RS_INCORRECT_TYPE 1
+#define SIMDJSON_RS_NUMBER_OUT_OF_RANGE 2
+#define SIMDJSON_RS_INDEX_OUT_OF_BOUNDS 3
+#define SIMDJSON_RS_NULL_POINTER 4
+#define SIMDJSON_RS_SYNTAX_ERROR 10
+#define SIMDJSON_RS_UTF8_ERROR 11
+#define SIMDJSON_RS_NUMBER_ERROR 12
+#define SIMDJSON_RS_STRING_ERROR 13
+#define SIMDJSON_RS_DEPTH_ERROR 14
+#define SIMDJSON_RS_CAPACITY_ERROR 15
+#define SIMDJSON_RS_EMPTY 16
+#define SIMDJSON_RS_PANIC 17
+
+/* value types */
+#define SIMDJSON_RS_TYPE_NULL 0
+#define SIMDJSON_RS_TYPE_BOOL 1
+#define SIMDJSON_RS_TYPE_I64 2
+#define SIMDJSON_RS_TYPE_F64 3
+#define SIMDJSON_RS_TYPE_STRING 4
+#define SIMDJSON_RS_TYPE_ARRAY 5
+#define SIMDJSON_RS_TYPE_OBJECT 6
+
+typedef struct simdjson_rs_document simdjson_rs_document;
+typedef struct simdjson_rs_value simdjson_rs_value;
+typedef struct simdjson_rs_members simdjson_rs_members;
+
+const char *simdjson_rs_error_message(int code);
+
+int simdjson_rs_parse(const uint8_t *data, size_t len, simdjson_rs_document **out);
+void simdjson_rs_document_free(simdjson_rs_document *doc);
+const simdjson_rs_value *simdjson_rs_document_root(const simdjson_rs_document *doc);
+
+int simdjson_rs_value_type(const simdjson_rs_value *value);
+int simdjson_rs_value_get_bool(const simdjson_rs_value *value, bool *out);
+int simdjson_rs_value_get_i64(const simdjson_rs_value *value, int64_t *out);
+int simdjson_rs_value_get_u64(const simdjson_rs_value *value, uint64_t *out);
+int simdjson_rs_value_get_f64(const simdjson_rs_value *value, double *out);
+/* strings are not NUL terminated */
+int simdjson_rs_value_get_string(const simdjson_rs_value *value, const char **out, size_t *len);
+
+size_t simdjson_rs_value_len(const simdjson_rs_value *value);
+const simdjson_rs_value *simdjson_rs_value_at(const simdjson_rs_value *value, size_t index);
+const simdjson_rs_value *simdjson_rs_value_get(const simdjson_rs_value *value, const char *key,
+                                               size_t key_len);
+/* iterators must be freed before their document, next returns
+ * SIMDJSON_RS_INDEX_OUT_OF_BOUNDS after the last member */
+simdjson_rs_members *simdjson_rs_value_members(const simdjson_rs_value *value);
+int simdjson_rs_members_next(simdjson_rs_members *members, const char **key, size_t *key_len,
+                             const simdjson_rs_value **out);
+void simdjson_rs_members_free(simdjson_rs_members *members);
+const simdjson_rs_value *simdjson_rs_value_pointer(const simdjson_rs_value *value,
+                                                   const char *pointer, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
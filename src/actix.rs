@@ -0,0 +1,97 @@
+use crate::serde::{from_slice, to_vec};
+use crate::web::{is_json_content_type, SimdJson};
+use actix_web::dev::Payload;
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::web::Bytes;
+use actix_web::{error, FromRequest, HttpRequest, HttpResponse, Responder};
+use serde_ext::de::DeserializeOwned;
+use serde_ext::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+
+impl<T> FromRequest for SimdJson<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let content_type = req.headers().get(CONTENT_TYPE);
+        let json = is_json_content_type(content_type.and_then(|h| h.to_str().ok()));
+        // the body size limit of `PayloadConfig` applies
+        let body = Bytes::from_request(req, payload);
+        Box::pin(async move {
+            if !json {
+                return Err(error::ErrorUnsupportedMediaType(
+                    "Expected request with `Content-Type: application/json`",
+                ));
+            }
+            let mut body = body.await?.to_vec();
+            from_slice(&mut body)
+                .map(SimdJson)
+                .map_err(error::ErrorBadRequest)
+        })
+    }
+}
+
+impl<T> Responder for SimdJson<T>
+where
+    T: Serialize,
+{
+    type Body = actix_web::body::BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        match to_vec(&self.0) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type("application/json")
+                .body(body),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use futures::executor::block_on;
+
+    fn extract(content_type: &str, body: &'static str) -> Result<SimdJson<Vec<u8>>, StatusCode> {
+        let (req, mut payload) = TestRequest::default()
+            .insert_header((CONTENT_TYPE, content_type))
+            .set_payload(body)
+            .to_http_parts();
+        block_on(SimdJson::from_request(&req, &mut payload))
+            .map_err(|e| e.as_response_error().status_code())
+    }
+
+    #[test]
+    fn extractor() {
+        let v = extract("application/json", "[1, 2, 3]").expect("valid");
+        assert_eq!(v, SimdJson(vec![1, 2, 3]));
+        let v = extract("application/vnd.api+json; charset=utf-8", "[1]");
+        assert_eq!(v, Ok(SimdJson(vec![1])));
+        let v = extract("text/plain", "[1]");
+        assert_eq!(v, Err(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+        let v = extract("application/json", "[1, ");
+        assert_eq!(v, Err(StatusCode::BAD_REQUEST));
+        let v = extract("application/json", r#"["a"]"#);
+        assert_eq!(v, Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn response() {
+        let req = TestRequest::default().to_http_request();
+        let response = SimdJson(json!({"a": [1, "b"]})).respond_to(&req);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).expect("content type"),
+            "application/json"
+        );
+        let body = block_on(to_bytes(response.into_body())).expect("body");
+        assert_eq!(&body[..], br#"{"a":[1,"b"]}"#);
+    }
+}
@@ -14,7 +14,7 @@ impl<'de> Deserializer<'de> {
     // Allow it to keep in sync with upstream
     #[allow(clippy::if_not_else)]
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    pub fn parse_str_(&mut self) -> Result<&'de str> {
+    pub(crate) fn parse_str_(&mut self) -> Result<&'de str> {
         // Add 1 to skip the initial "
         let idx = self.iidx + 1;
         let mut padding = [0_u8; 32];
@@ -168,10 +168,16 @@ impl<'de> Deserializer<'de> {
                     // within the unicode codepoint handling code.
                     src_i += bs_dist as usize;
                     dst_i += bs_dist as usize;
-                    let (o, s) = if let Ok(r) =
+                    let (o, s) = if unlikely!(self.options.lossy_utf8) {
+                        handle_unicode_codepoint_lossy(
+                            unsafe { src.get_unchecked(src_i..) },
+                            unsafe { dst.get_unchecked_mut(dst_i..) },
+                        )
+                    } else if let Ok(r) =
                         handle_unicode_codepoint(unsafe { src.get_unchecked(src_i..) }, unsafe {
                             dst.get_unchecked_mut(dst_i..)
-                        }) {
+                        })
+                    {
                         r
                     } else {
                         return Err(self.error(ErrorType::InvlaidUnicodeCodepoint));
@@ -52,6 +52,28 @@ unsafe fn check_utf8(
     }
 }
 
+// Checks that the input is valid UTF-8 using the same routine as stage 1.
+// The final block is always padded with spaces so inputs ending in an
+// incomplete sequence are caught.
+pub(crate) unsafe fn is_valid_utf8(input: &[u8]) -> bool {
+    let len = input.len();
+    let mut has_error: __m256i = _mm256_setzero_si256();
+    let mut previous = AvxProcessedUtfBytes::default();
+    let mut idx: usize = 0;
+    while idx + 64 <= len {
+        let input: SimdInput = fill_input(input.get_unchecked(idx..));
+        check_utf8(&input, &mut has_error, &mut previous);
+        idx += 64;
+    }
+    let mut tmpbuf: [u8; 64] = [0x20; 64];
+    tmpbuf
+        .as_mut_ptr()
+        .copy_from(input.as_ptr().add(idx), len - idx);
+    let input: SimdInput = fill_input(&tmpbuf);
+    check_utf8(&input, &mut has_error, &mut previous);
+    _mm256_testz_si256(has_error, has_error) == 1
+}
+
 /// a straightforward comparison of a mask against input. 5 uops; would be
 /// cheaper in AVX512.
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
@@ -357,11 +379,15 @@ fn finalize_structurals(
 impl<'de> Deserializer<'de> {
     //#[inline(never)]
     #[allow(clippy::cast_possible_truncation)]
-    pub unsafe fn find_structural_bits(input: &[u8]) -> std::result::Result<Vec<u32>, ErrorType> {
+    pub(crate) unsafe fn find_structural_bits(
+        input: &[u8],
+        structural_indexes: &mut Vec<u32>,
+    ) -> std::result::Result<(), ErrorType> {
         let len = input.len();
         // 6 is a heuristic number to estimate it turns out a rate of 1/6 structural caracters lears
         // almost never to relocations.
-        let mut structural_indexes = Vec::with_capacity(len / 6);
+        structural_indexes.clear();
+        structural_indexes.reserve(len / 6);
         structural_indexes.push(0); // push extra root element
 
         let mut has_error: __m256i = _mm256_setzero_si256();
@@ -420,7 +446,7 @@ impl<'de> Deserializer<'de> {
             // take the previous iterations structural bits, not our current iteration,
             // and flatten
             #[allow(clippy::cast_possible_truncation)]
-            flatten_bits(&mut structural_indexes, idx as u32, structurals);
+            flatten_bits(structural_indexes, idx as u32, structurals);
 
             let mut whitespace: u64 = 0;
             find_whitespace_and_structurals(&input, &mut whitespace, &mut structurals);
@@ -465,7 +491,7 @@ impl<'de> Deserializer<'de> {
 
             // take the previous iterations structural bits, not our current iteration,
             // and flatten
-            flatten_bits(&mut structural_indexes, idx as u32, structurals);
+            flatten_bits(structural_indexes, idx as u32, structurals);
 
             let mut whitespace: u64 = 0;
             find_whitespace_and_structurals(&input, &mut whitespace, &mut structurals);
@@ -485,7 +511,7 @@ impl<'de> Deserializer<'de> {
             return Err(ErrorType::Syntax);
         }
         // finally, flatten out the remaining structurals from the last iteration
-        flatten_bits(&mut structural_indexes, idx as u32, structurals);
+        flatten_bits(structural_indexes, idx as u32, structurals);
 
         // a valid JSON file cannot have zero structural indexes - we should have
         // found something (note that we compare to 1 as we always add the root!)
@@ -504,7 +530,7 @@ impl<'de> Deserializer<'de> {
         if _mm256_testz_si256(has_error, has_error) == 0 {
             Err(ErrorType::InvalidUTF8)
         } else {
-            Ok(structural_indexes)
+            Ok(())
         }
     }
 }
@@ -0,0 +1,97 @@
+use crate::serde::{from_slice, to_vec};
+use crate::web::{is_json_content_type, SimdJson};
+use axum_core::extract::{FromRequest, Request};
+use axum_core::response::{IntoResponse, Response};
+use bytes::BytesMut;
+use http::header::{HeaderValue, CONTENT_TYPE};
+use http::StatusCode;
+use serde_ext::de::DeserializeOwned;
+use serde_ext::Serialize;
+
+impl<T, S> FromRequest<S> for SimdJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req.headers().get(CONTENT_TYPE);
+        if !is_json_content_type(content_type.and_then(|h| h.to_str().ok())) {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Expected request with `Content-Type: application/json`",
+            )
+                .into_response());
+        }
+        let mut body = BytesMut::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        match from_slice(&mut body) {
+            Ok(v) => Ok(Self(v)),
+            Err(e) => Err((StatusCode::BAD_REQUEST, e.to_string()).into_response()),
+        }
+    }
+}
+
+impl<T> IntoResponse for SimdJson<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        match to_vec(&self.0) {
+            Ok(body) => (
+                [(CONTENT_TYPE, HeaderValue::from_static("application/json"))],
+                body,
+            )
+                .into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum_core::body::Body;
+    use bytes::Bytes;
+    use futures::executor::block_on;
+
+    fn request(content_type: &str, body: &'static str) -> Request {
+        http::Request::builder()
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .expect("valid request")
+    }
+
+    fn extract(req: Request) -> Result<SimdJson<Vec<u8>>, StatusCode> {
+        block_on(SimdJson::from_request(req, &())).map_err(|r| r.status())
+    }
+
+    #[test]
+    fn extractor() {
+        let v = extract(request("application/json", "[1, 2, 3]")).expect("valid");
+        assert_eq!(v, SimdJson(vec![1, 2, 3]));
+        let v = extract(request("application/vnd.api+json; charset=utf-8", "[1]"));
+        assert_eq!(v, Ok(SimdJson(vec![1])));
+        let v = extract(request("text/plain", "[1]"));
+        assert_eq!(v, Err(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+        let v = extract(request("application/json", "[1, "));
+        assert_eq!(v, Err(StatusCode::BAD_REQUEST));
+        let v = extract(request("application/json", r#"["a"]"#));
+        assert_eq!(v, Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn response() {
+        let response = SimdJson(json!({"a": [1, "b"]})).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).expect("content type"),
+            "application/json"
+        );
+        let req = Request::new(response.into_body());
+        let body = block_on(Bytes::from_request(req, &())).expect("body");
+        assert_eq!(&body[..], br#"{"a":[1,"b"]}"#);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::value::owned::to_value_with_options;
+use crate::{Error, ErrorType, OwnedValue, ParserOptions, Result, SIMDJSON_PADDING};
+use bytes::Buf;
+
+// Copies the remaining bytes of `buf` into a buffer with room for the
+// padding, the parser rewrites its input so `Bytes` can't be parsed in
+// place.
+fn copy_padded<B: Buf>(mut buf: B, options: &ParserOptions) -> Result<Vec<u8>> {
+    let len = buf.remaining();
+    if len > options.max_document_size {
+        return Err(Error::generic(ErrorType::InputTooLarge));
+    }
+    let mut buffer = Vec::with_capacity(len + SIMDJSON_PADDING);
+    while buf.has_remaining() {
+        let chunk = buf.chunk();
+        buffer.extend_from_slice(chunk);
+        let n = chunk.len();
+        buf.advance(n);
+    }
+    Ok(buffer)
+}
+
+/// Parses the JSON document in a `bytes::Buf`, like the `Bytes` of a
+/// request body, into an `OwnedValue`. The input is copied since the
+/// parser needs to rewrite it, the value doesn't borrow from it and can be
+/// sent to, or shared in an `Arc` between, other tasks.
+///
+/// ```
+/// use simd_json::{from_buf, json};
+/// let body = bytes::Bytes::from_static(br#"{"id": 1, "tags": ["a"]}"#);
+/// let v = from_buf(body.clone()).expect("valid json");
+/// assert_eq!(v, json!({"id": 1, "tags": ["a"]}));
+/// ```
+pub fn from_buf<B: Buf>(buf: B) -> Result<OwnedValue> {
+    from_buf_with_options(buf, &ParserOptions::default())
+}
+
+/// Same as `from_buf` but allows tuning the parser using `ParserOptions`.
+pub fn from_buf_with_options<B: Buf>(buf: B, options: &ParserOptions) -> Result<OwnedValue> {
+    let mut buffer = copy_padded(buf, options)?;
+    to_value_with_options(&mut buffer, options)
+}
+
+/// Deserializes the JSON document in a `bytes::Buf` using serde, copying
+/// it first like `from_buf`.
+#[cfg(feature = "serde_impl")]
+pub fn from_buf_as<T, B>(buf: B) -> Result<T>
+where
+    T: serde_ext::de::DeserializeOwned,
+    B: Buf,
+{
+    let options = ParserOptions::default();
+    let mut buffer = copy_padded(buf, &options)?;
+    crate::serde::from_slice_with_options(&mut buffer, &options)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytes::{Bytes, BytesMut};
+
+    #[test]
+    fn chained() {
+        let head = Bytes::from_static(br#"{"a": [1, "#);
+        let tail = BytesMut::from(&br#"2], "b": "x"}"#[..]);
+        let v = from_buf(head.chain(tail)).expect("valid json");
+        assert_eq!(v, json!({"a": [1, 2], "b": "x"}));
+        let options = ParserOptions {
+            max_document_size: 4,
+            ..ParserOptions::default()
+        };
+        let e =
+            from_buf_with_options(Bytes::from_static(b"[1, 2]"), &options).expect_err("too large");
+        assert_eq!(e.error(), &ErrorType::InputTooLarge);
+        assert!(from_buf(Bytes::from_static(b"[1, ")).is_err());
+    }
+
+    #[test]
+    fn shared() {
+        fn shareable<T: Send + Sync + 'static>(_: &T) {}
+        let v = std::sync::Arc::new(from_buf(Bytes::from_static(b"[1]")).expect("valid json"));
+        shareable(&v);
+    }
+
+    #[cfg(feature = "serde_impl")]
+    #[test]
+    fn typed() {
+        let v: Vec<String> = from_buf_as(Bytes::from_static(br#"["a", "b\n"]"#)).expect("valid");
+        assert_eq!(v, vec!["a".to_string(), "b\n".to_string()]);
+    }
+}
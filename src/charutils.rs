@@ -30,6 +30,13 @@ pub fn is_structural_or_whitespace(c: u8) -> u32 {
     unsafe { *STRUCTURAL_OR_WHITESPACE.get_unchecked(c as usize) }
 }
 
+// The whitespace JSON allows, `u8::is_ascii_whitespace` also accepts form
+// feed which the parser rejects.
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub fn is_json_whitespace(c: u8) -> bool {
+    c == b' ' || c == b'\t' || c == b'\n' || c == b'\r'
+}
+
 const DIGITTOVAL: [i8; 256] = [
     -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
     -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
@@ -0,0 +1,89 @@
+use crate::{stry, Deserializer, ErrorType, Result};
+
+/// Parses a JSON array of numbers straight into a `Vec<f64>` without
+/// building a value for each element, integers are converted to floats.
+/// The slice will be rewritten in the process.
+///
+/// ```
+/// let mut d = br#"[1.5, -2, 3e2]"#.to_vec();
+/// assert_eq!(simd_json::to_f64_vec(&mut d).unwrap(), vec![1.5, -2.0, 300.0]);
+/// let mut d = br#"[1, "2"]"#.to_vec();
+/// assert!(simd_json::to_f64_vec(&mut d).is_err());
+/// ```
+pub fn to_f64_vec(s: &mut [u8]) -> Result<Vec<f64>> {
+    let mut de = stry!(Deserializer::from_slice(s));
+    de.parse_number_array(|de, minus| match de.parse_number(minus) {
+        Ok(n) => Ok(n.as_f64().unwrap_or_default()),
+        // Integers too large for a u64 still fit a float
+        Err(ref e) if e.error() == &ErrorType::Overflow => {
+            let n = stry!(de.parse_number_raw(minus, false));
+            n.parse().map_err(|_| de.error(ErrorType::InvalidNumber))
+        }
+        Err(e) => Err(e),
+    })
+}
+
+/// Parses a JSON array of integers straight into a `Vec<i64>` without
+/// building a value for each element. The slice will be rewritten in the
+/// process.
+///
+/// ```
+/// let mut d = br#"[1, -2, 3]"#.to_vec();
+/// assert_eq!(simd_json::to_i64_vec(&mut d).unwrap(), vec![1, -2, 3]);
+/// let mut d = br#"[1, 2.5]"#.to_vec();
+/// assert!(simd_json::to_i64_vec(&mut d).is_err());
+/// ```
+pub fn to_i64_vec(s: &mut [u8]) -> Result<Vec<i64>> {
+    let mut de = stry!(Deserializer::from_slice(s));
+    de.parse_number_array(|de, minus| {
+        let n = stry!(de.parse_number(minus));
+        match n.as_i64() {
+            Some(i) => Ok(i),
+            None if n.is_u64() => Err(de.error(ErrorType::Overflow)),
+            None => Err(de.error(ErrorType::ExpectedInteger)),
+        }
+    })
+}
+
+impl<'de> Deserializer<'de> {
+    // Parses the document as an array of numbers using `parse` for each
+    // element
+    fn parse_number_array<T, F>(&mut self, mut parse: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&mut Self, bool) -> Result<T>,
+    {
+        if self.next_() != b'[' {
+            return Err(self.error(ErrorType::ExpectedArray));
+        }
+        let es = self.count_elements();
+        let mut res = Vec::with_capacity(es);
+        for _ in 0..es {
+            let n = match self.next_() {
+                b'-' => stry!(parse(self, true)),
+                b'0'..=b'9' => stry!(parse(self, false)),
+                _ => return Err(self.error(ErrorType::ExpectedNumber)),
+            };
+            res.push(n);
+            self.skip();
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn number_arrays() {
+        assert_eq!(super::to_f64_vec(&mut b"[]".to_vec()), Ok(vec![]));
+        assert_eq!(
+            super::to_f64_vec(&mut b"[18446744073709551616, 0]".to_vec()),
+            Ok(vec![18_446_744_073_709_551_616.0, 0.0])
+        );
+        assert!(super::to_i64_vec(&mut b"[18446744073709551616]".to_vec()).is_err());
+        assert!(super::to_i64_vec(&mut b"1".to_vec()).is_err());
+        assert!(super::to_i64_vec(&mut b"[[1]]".to_vec()).is_err());
+        assert!(super::to_f64_vec(&mut b"[1, null]".to_vec()).is_err());
+        let mut d = b"[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18]".to_vec();
+        assert_eq!(super::to_i64_vec(&mut d), Ok((1..=18).collect()));
+    }
+}
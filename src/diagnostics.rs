@@ -0,0 +1,407 @@
+use crate::pointer::{char_at, hex4, string_end, unescape};
+use crate::value::{borrowed, owned, push_token};
+use crate::{
+    BigNumberBehavior, BorrowedValue, Deserializer, Error, ErrorType, KeyInterner, OwnedValue,
+    ParserOptions, Result,
+};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// A finding in a document that doesn't stop it from being parsed but
+/// that strict consumers may want to log or reject the document for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Byte index in the input of the key, string or number concerned
+    pub index: usize,
+    /// What was found
+    pub kind: DiagnosticKind,
+}
+
+/// The kinds of `Diagnostic`s
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    /// An object has a key more than once, the parsed object keeps only one
+    /// of the members: the first by default, the last with `preserve-order`
+    /// or `sorted-objects`. Reported for the second and any later member
+    /// with the key.
+    DuplicateKey {
+        /// The key
+        key: String,
+        /// JSON pointer ([RFC 6901]) to the duplicate member
+        ///
+        /// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+        pointer: String,
+    },
+    /// A string contains an escaped surrogate without its other half, with
+    /// `lossy_utf8` it is replaced with U+FFFD
+    LoneSurrogate,
+    /// A number can't be represented exactly: a float that doesn't round
+    /// trip, so the closest `f64` prints as a different number like for
+    /// `9007199254740993.0`, one out of the range of `f64` or an integer
+    /// too large for 64 bits that is saturated or converted to a float
+    LossyNumber,
+}
+
+// Checks the escapes in the raw contents of a string for surrogates that
+// aren't part of a pair
+fn has_lone_surrogate(raw: &[u8]) -> bool {
+    let mut i = 0;
+    while let Some(&c) = raw.get(i) {
+        if c != b'\\' {
+            i += 1;
+            continue;
+        }
+        if raw.get(i + 1) != Some(&b'u') {
+            i += 2;
+            continue;
+        }
+        let code = hex4(raw.get(i + 2..).unwrap_or_default()).unwrap_or_default();
+        i += 6;
+        if (0xd800..0xdc00).contains(&code) {
+            let low = match raw.get(i..i + 2) {
+                Some(b"\\u") => hex4(raw.get(i + 2..).unwrap_or_default()),
+                _ => None,
+            };
+            match low {
+                Some(low) if (0xdc00..0xe000).contains(&low) => i += 6,
+                _ => return true,
+            }
+        } else if (0xdc00..0xe000).contains(&code) {
+            return true;
+        }
+    }
+    false
+}
+
+fn is_lossy_number(number: &[u8], options: &ParserOptions) -> bool {
+    if options.lossless_numbers {
+        return false;
+    }
+    let number = match std::str::from_utf8(number) {
+        Ok(n) => n,
+        Err(_) => return false,
+    };
+    if !number.contains(&['.', 'e', 'E'][..]) {
+        let big = number.parse::<i64>().is_err() && number.parse::<u64>().is_err();
+        return big && options.big_numbers != BigNumberBehavior::Raw;
+    }
+    // the shortest representation of a float reads back as the same float,
+    // if it differs from the literal the literal wasn't exact
+    match number.parse::<f64>() {
+        Ok(f) if f.is_finite() => decimal(number) != decimal(&format!("{:e}", f)),
+        Ok(_) => true,
+        Err(_) => false,
+    }
+}
+
+// The significant digits of a number and the power of ten they are
+// multiplied with, so `1.50e2` and `150` both are ("15", 1)
+#[allow(clippy::cast_possible_wrap)]
+fn decimal(number: &str) -> (String, i64) {
+    let number = number.trim_start_matches('-');
+    let (mantissa, exponent) = match number.find(&['e', 'E'][..]) {
+        Some(e) => {
+            let exponent = &number[e + 1..];
+            // out of range either way, it only has to stay comparable
+            let saturated = if exponent.starts_with('-') {
+                i64::MIN / 2
+            } else {
+                i64::MAX / 2
+            };
+            (&number[..e], exponent.parse().unwrap_or(saturated))
+        }
+        None => (number, 0),
+    };
+    let (int, frac) = match mantissa.find('.') {
+        Some(dot) => (&mantissa[..dot], &mantissa[dot + 1..]),
+        None => (mantissa, ""),
+    };
+    let digits = format!("{}{}", int, frac);
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    if significant.is_empty() {
+        return (String::new(), 0);
+    }
+    let trailing = (digits.len() - significant.len()) as i64;
+    (
+        significant.to_string(),
+        exponent - frac.len() as i64 + trailing,
+    )
+}
+
+// An open array or object while walking the structural indexes
+enum Frame {
+    // the index of the current element
+    Array(usize),
+    // the keys seen so far and the current key
+    Object(HashSet<String>, String),
+}
+
+// JSON pointer to `key` in the innermost object of `stack`
+fn pointer(stack: &[Frame], key: &str) -> String {
+    let mut path = String::new();
+    for frame in stack.iter().take(stack.len().saturating_sub(1)) {
+        match frame {
+            Frame::Array(i) => push_token(&mut path, &i.to_string()),
+            Frame::Object(_, k) => push_token(&mut path, k),
+        }
+    }
+    push_token(&mut path, key);
+    path
+}
+
+// Walks the structural indexes collecting diagnostics, `offset` is added
+// to the indexes reported. Malformed input is left to the parser.
+fn diagnose(
+    input: &[u8],
+    structurals: &[u32],
+    offset: usize,
+    options: &ParserOptions,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut expect_key = false;
+    for i in 1..structurals.len() {
+        let start = structurals[i] as usize;
+        let c = match char_at(input, structurals, i) {
+            Some(c) => c,
+            None => break,
+        };
+        match c {
+            b'{' => {
+                stack.push(Frame::Object(HashSet::new(), String::new()));
+                expect_key = true;
+            }
+            b'[' => {
+                stack.push(Frame::Array(0));
+                expect_key = false;
+            }
+            b'}' | b']' => {
+                stack.pop();
+                expect_key = false;
+            }
+            b',' => match stack.last_mut() {
+                Some(Frame::Array(i)) => *i += 1,
+                Some(Frame::Object(..)) => expect_key = true,
+                None => (),
+            },
+            b':' => expect_key = false,
+            b'"' => {
+                let (end, escaped) = match string_end(input, start + 1) {
+                    Some(e) => e,
+                    None => break,
+                };
+                let raw = &input[start + 1..end];
+                if escaped && has_lone_surrogate(raw) {
+                    diagnostics.push(Diagnostic {
+                        index: start + offset,
+                        kind: DiagnosticKind::LoneSurrogate,
+                    });
+                }
+                if expect_key {
+                    let key = if escaped {
+                        unescape(raw).unwrap_or_else(|| String::from_utf8_lossy(raw).into_owned())
+                    } else {
+                        String::from_utf8_lossy(raw).into_owned()
+                    };
+                    if let Some(Frame::Object(keys, current)) = stack.last_mut() {
+                        if keys.insert(key.clone()) {
+                            *current = key;
+                        } else {
+                            current.clone_from(&key);
+                            diagnostics.push(Diagnostic {
+                                index: start + offset,
+                                kind: DiagnosticKind::DuplicateKey {
+                                    pointer: pointer(&stack, &key),
+                                    key,
+                                },
+                            });
+                        }
+                    }
+                    expect_key = false;
+                }
+            }
+            b'-' | b'0'..=b'9' => {
+                let len = input[start..]
+                    .iter()
+                    .position(|c| c.is_ascii_whitespace() || b",:]}[{\"".contains(c))
+                    .unwrap_or(input.len() - start);
+                if is_lossy_number(&input[start..start + len], options) {
+                    diagnostics.push(Diagnostic {
+                        index: start + offset,
+                        kind: DiagnosticKind::LossyNumber,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+    diagnostics
+}
+
+// Runs stage 1 and collects the diagnostics before the input gets
+// rewritten
+fn diagnostics(
+    input: &mut [u8],
+    options: &ParserOptions,
+) -> Result<(Range<usize>, Vec<u32>, Vec<Diagnostic>)> {
+    if input.len() > options.max_document_size {
+        return Err(Error::generic(ErrorType::InputTooLarge));
+    }
+    let range = options.content_range(input);
+    let content = &input[range.clone()];
+    let structurals = match Deserializer::find_structural_indexes(content) {
+        Ok(s) => s,
+        Err(t) => return Err(Error::generic(t)),
+    };
+    let diagnostics = diagnose(content, &structurals, range.start, options);
+    Ok((range, structurals, diagnostics))
+}
+
+/// Parses an owned value and collects non-fatal findings in the document,
+/// like duplicate keys, see `DiagnosticKind`. They are found by looking at
+/// the document once more before parsing it, so this is slower than
+/// `to_owned_value_with_options`.
+///
+/// ```
+/// use simd_json::{to_owned_value_with_diagnostics, DiagnosticKind, ParserOptions};
+/// let mut d = br#"{"id": 1, "id": 12345678901234567890123}"#.to_vec();
+/// let options = ParserOptions {
+///     big_numbers: simd_json::BigNumberBehavior::Float,
+///     ..ParserOptions::default()
+/// };
+/// let (_value, diagnostics) = to_owned_value_with_diagnostics(&mut d, &options).unwrap();
+/// let kinds: Vec<_> = diagnostics.into_iter().map(|d| d.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     vec![
+///         DiagnosticKind::DuplicateKey {
+///             key: "id".to_string(),
+///             pointer: "/id".to_string()
+///         },
+///         DiagnosticKind::LossyNumber
+///     ]
+/// );
+/// ```
+pub fn to_owned_value_with_diagnostics(
+    input: &mut [u8],
+    options: &ParserOptions,
+) -> Result<(OwnedValue, Vec<Diagnostic>)> {
+    let (range, structurals, diagnostics) = stry!(diagnostics(input, options));
+    let value = stry!(owned::to_value_with_structural_indexes(
+        &mut input[range],
+        structurals,
+        options,
+        &mut KeyInterner::new()
+    ));
+    Ok((value, diagnostics))
+}
+
+/// Same as `to_owned_value_with_diagnostics` but parses a borrowed value.
+pub fn to_borrowed_value_with_diagnostics<'v>(
+    input: &'v mut [u8],
+    options: &ParserOptions,
+) -> Result<(BorrowedValue<'v>, Vec<Diagnostic>)> {
+    let (range, structurals, diagnostics) = stry!(diagnostics(input, options));
+    let value = stry!(borrowed::to_value_with_structural_indexes(
+        &mut input[range],
+        structurals,
+        options
+    ));
+    Ok((value, diagnostics))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kinds(input: &str, options: &ParserOptions) -> Vec<(usize, DiagnosticKind)> {
+        let mut d = input.as_bytes().to_vec();
+        let (_, diagnostics) = to_borrowed_value_with_diagnostics(&mut d, options).expect("valid");
+        diagnostics.into_iter().map(|d| (d.index, d.kind)).collect()
+    }
+
+    fn duplicate(key: &str, pointer: &str) -> DiagnosticKind {
+        DiagnosticKind::DuplicateKey {
+            key: key.to_string(),
+            pointer: pointer.to_string(),
+        }
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let options = ParserOptions::default();
+        assert_eq!(kinds(r#"{"a": 1, "b": {"a": 2}}"#, &options), vec![]);
+        assert_eq!(
+            kinds(r#"{"a": [{"a": 1}], "a": 2}"#, &options),
+            vec![(18, duplicate("a", "/a"))]
+        );
+        assert_eq!(
+            kinds(r#"[{"x": 1, "x": {"x": 2}}, {"x": 3}]"#, &options),
+            vec![(10, duplicate("x", "/0/x"))]
+        );
+        let input = r#"{"a/b": [0, {"c": 1, "c": 2}], "d": {"~": 1, "\u007e": 2}}"#;
+        assert_eq!(
+            kinds(input, &options),
+            vec![
+                (21, duplicate("c", "/a~1b/1/c")),
+                (45, duplicate("~", "/d/~0"))
+            ]
+        );
+        assert_eq!(kinds(r#"["a", "a", {"b": "a"}]"#, &options), vec![]);
+    }
+
+    #[test]
+    fn lone_surrogates() {
+        let options = ParserOptions {
+            lossy_utf8: true,
+            ..ParserOptions::default()
+        };
+        assert_eq!(kinds(r#"["😀", "\\ud83d"]"#, &options), vec![]);
+        assert_eq!(
+            kinds(r#"["a\ud83d", "\ude00", "\ud83dA"]"#, &options),
+            vec![
+                (1, DiagnosticKind::LoneSurrogate),
+                (12, DiagnosticKind::LoneSurrogate),
+                (22, DiagnosticKind::LoneSurrogate)
+            ]
+        );
+    }
+
+    #[test]
+    fn lossy_numbers() {
+        let options = ParserOptions::default();
+        let exact = "[1, -2, 0.1, 1.5e300, 18446744073709551615, -9223372036854775808, 1000000000000000000000.0]";
+        assert_eq!(kinds(exact, &options), vec![]);
+        let exact = "[0.30000000000000004, -0.0, 2.50e-3, 1E+2, 9007199254740992.0]";
+        assert_eq!(kinds(exact, &options), vec![]);
+        assert_eq!(
+            kinds(
+                "[9007199254740993.0, 1e-400, 1.00000000000000001]",
+                &options
+            ),
+            vec![
+                (1, DiagnosticKind::LossyNumber),
+                (21, DiagnosticKind::LossyNumber),
+                (29, DiagnosticKind::LossyNumber)
+            ]
+        );
+        assert_eq!(
+            kinds("[0.5, 3.14159265358979323846, 1e3]", &options),
+            vec![(6, DiagnosticKind::LossyNumber)]
+        );
+        let options = ParserOptions {
+            big_numbers: BigNumberBehavior::Saturate,
+            ..ParserOptions::default()
+        };
+        assert_eq!(
+            kinds("[18446744073709551616]", &options),
+            vec![(1, DiagnosticKind::LossyNumber)]
+        );
+        let options = ParserOptions {
+            lossless_numbers: true,
+            ..ParserOptions::default()
+        };
+        assert_eq!(kinds("[3.14159265358979323846]", &options), vec![]);
+    }
+}
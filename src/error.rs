@@ -1,4 +1,5 @@
 use std::fmt;
+use std::io;
 
 /// Error types encountered while parsing
 #[derive(Debug, PartialEq)]
@@ -45,25 +46,27 @@ pub enum ErrorType {
     InvalidExponent,
     /// Invalid number
     InvalidNumber,
-    /// Inbalid UTF8 codepoint
+    /// Invalid UTF8 codepoint
     InvalidUTF8,
     /// Invalid Unicode escape sequence
     InvalidUnicodeEscape,
-    /// Inbalid Unicode codepoint
+    /// Invalid Unicode codepoint
     InvlaidUnicodeCodepoint,
     /// Object Key isn't a string
     KeyMustBeAString,
     /// Non structural character
     NoStructure,
-    /// Parser Erropr
+    /// Parser Error
     Parser,
     /// Early End Of File
     EOF,
     /// Generic serde error
     Serde(String),
+    /// IO error while reading the input
+    IO(String),
     /// Generic syntax error
     Syntax,
-    /// Training characters
+    /// Trailing characters
     TrailingCharacters,
     /// Unexpected character
     UnexpectedCharacter,
@@ -79,10 +82,180 @@ pub enum ErrorType {
     ExpectedObjectKey,
     /// Overflow of a limited buffer
     Overflow,
+    /// The document is nested deeper than the configured maximum depth
+    DepthExceeded,
+    /// The input is larger than the configured maximum document size or
+    /// the 4 GiB the parser supports
+    InputTooLarge,
+    /// A string is longer than the configured maximum string length
+    StringTooLong,
+    /// An array or object has more elements than the configured maximum
+    TooManyElements,
+    /// The document is a scalar but the options only allow arrays and
+    /// objects
+    ScalarRoot,
+}
+
+/// Stable numeric codes of the error types, to map errors to the error
+/// codes of a protocol without matching on their messages. The number of
+/// an error type never changes, new error types get new numbers.
+///
+/// ```
+/// use simd_json::ErrorCode;
+/// let mut d = br#"{"a": [1, 2}"#.to_vec();
+/// let e = simd_json::to_owned_value(&mut d).unwrap_err();
+/// assert_eq!(e.code(), ErrorCode::ExpectedArrayContent);
+/// assert_eq!(e.code() as u16, 36);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum ErrorCode {
+    /// The key of a map isn't a string
+    BadKeyType = 1,
+    /// The data ended early
+    EarlyEnd = 2,
+    /// Expected an array
+    ExpectedArray = 3,
+    /// Expected a `,` in an array
+    ExpectedArrayComma = 4,
+    /// expected an boolean
+    ExpectedBoolean = 5,
+    /// Expected an enum
+    ExpectedEnum = 6,
+    /// Expected a float
+    ExpectedFloat = 7,
+    /// Expected an integer
+    ExpectedInteger = 8,
+    /// Expected a map
+    ExpectedMap = 9,
+    /// Expected an `:` to seperate key and value in an object
+    ExpectedObjectColon = 10,
+    /// Expected a `,` in an object
+    ExpectedMapComma = 11,
+    /// Expected the object to end
+    ExpectedMapEnd = 12,
+    /// Expected a null
+    ExpectedNull = 13,
+    /// Expected a number
+    ExpectedNumber = 14,
+    /// Expected a signed number
+    ExpectedSigned = 15,
+    /// Expected a string
+    ExpectedString = 16,
+    /// Expected an unsigned number
+    ExpectedUnsigned = 17,
+    /// Internal error
+    InternalError = 18,
+    /// Invalid escape sequence
+    InvalidEscape = 19,
+    /// Invalid exponent in a floating point number
+    InvalidExponent = 20,
+    /// Invalid number
+    InvalidNumber = 21,
+    /// Invalid UTF8 codepoint
+    InvalidUTF8 = 22,
+    /// Invalid Unicode escape sequence
+    InvalidUnicodeEscape = 23,
+    /// Invalid Unicode codepoint
+    InvlaidUnicodeCodepoint = 24,
+    /// Object Key isn't a string
+    KeyMustBeAString = 25,
+    /// Non structural character
+    NoStructure = 26,
+    /// Parser Error
+    Parser = 27,
+    /// Early End Of File
+    EOF = 28,
+    /// Generic serde error
+    Serde = 29,
+    /// IO error while reading the input
+    IO = 30,
+    /// Generic syntax error
+    Syntax = 31,
+    /// Trailing characters
+    TrailingCharacters = 32,
+    /// Unexpected character
+    UnexpectedCharacter = 33,
+    /// Unexpected end
+    UnexpectedEnd = 34,
+    /// Unterminated string
+    UnterminatedString = 35,
+    /// Expected Array elements
+    ExpectedArrayContent = 36,
+    /// Expected Object elements
+    ExpectedObjectContent = 37,
+    /// Expected Object Key
+    ExpectedObjectKey = 38,
+    /// Overflow of a limited buffer
+    Overflow = 39,
+    /// The document is nested deeper than the configured maximum depth
+    DepthExceeded = 40,
+    /// The input is larger than the configured maximum document size or
+    /// the 4 GiB the parser supports
+    InputTooLarge = 41,
+    /// A string is longer than the configured maximum string length
+    StringTooLong = 42,
+    /// An array or object has more elements than the configured maximum
+    TooManyElements = 43,
+    /// The document is a scalar but the options only allow arrays and
+    /// objects
+    ScalarRoot = 44,
+}
+
+impl ErrorType {
+    /// The stable code of the error type
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ErrorType::BadKeyType => ErrorCode::BadKeyType,
+            ErrorType::EarlyEnd => ErrorCode::EarlyEnd,
+            ErrorType::ExpectedArray => ErrorCode::ExpectedArray,
+            ErrorType::ExpectedArrayComma => ErrorCode::ExpectedArrayComma,
+            ErrorType::ExpectedBoolean => ErrorCode::ExpectedBoolean,
+            ErrorType::ExpectedEnum => ErrorCode::ExpectedEnum,
+            ErrorType::ExpectedFloat => ErrorCode::ExpectedFloat,
+            ErrorType::ExpectedInteger => ErrorCode::ExpectedInteger,
+            ErrorType::ExpectedMap => ErrorCode::ExpectedMap,
+            ErrorType::ExpectedObjectColon => ErrorCode::ExpectedObjectColon,
+            ErrorType::ExpectedMapComma => ErrorCode::ExpectedMapComma,
+            ErrorType::ExpectedMapEnd => ErrorCode::ExpectedMapEnd,
+            ErrorType::ExpectedNull => ErrorCode::ExpectedNull,
+            ErrorType::ExpectedNumber => ErrorCode::ExpectedNumber,
+            ErrorType::ExpectedSigned => ErrorCode::ExpectedSigned,
+            ErrorType::ExpectedString => ErrorCode::ExpectedString,
+            ErrorType::ExpectedUnsigned => ErrorCode::ExpectedUnsigned,
+            ErrorType::InternalError => ErrorCode::InternalError,
+            ErrorType::InvalidEscape => ErrorCode::InvalidEscape,
+            ErrorType::InvalidExponent => ErrorCode::InvalidExponent,
+            ErrorType::InvalidNumber => ErrorCode::InvalidNumber,
+            ErrorType::InvalidUTF8 => ErrorCode::InvalidUTF8,
+            ErrorType::InvalidUnicodeEscape => ErrorCode::InvalidUnicodeEscape,
+            ErrorType::InvlaidUnicodeCodepoint => ErrorCode::InvlaidUnicodeCodepoint,
+            ErrorType::KeyMustBeAString => ErrorCode::KeyMustBeAString,
+            ErrorType::NoStructure => ErrorCode::NoStructure,
+            ErrorType::Parser => ErrorCode::Parser,
+            ErrorType::EOF => ErrorCode::EOF,
+            ErrorType::Serde(_) => ErrorCode::Serde,
+            ErrorType::IO(_) => ErrorCode::IO,
+            ErrorType::Syntax => ErrorCode::Syntax,
+            ErrorType::TrailingCharacters => ErrorCode::TrailingCharacters,
+            ErrorType::UnexpectedCharacter => ErrorCode::UnexpectedCharacter,
+            ErrorType::UnexpectedEnd => ErrorCode::UnexpectedEnd,
+            ErrorType::UnterminatedString => ErrorCode::UnterminatedString,
+            ErrorType::ExpectedArrayContent => ErrorCode::ExpectedArrayContent,
+            ErrorType::ExpectedObjectContent => ErrorCode::ExpectedObjectContent,
+            ErrorType::ExpectedObjectKey => ErrorCode::ExpectedObjectKey,
+            ErrorType::Overflow => ErrorCode::Overflow,
+            ErrorType::DepthExceeded => ErrorCode::DepthExceeded,
+            ErrorType::InputTooLarge => ErrorCode::InputTooLarge,
+            ErrorType::StringTooLong => ErrorCode::StringTooLong,
+            ErrorType::TooManyElements => ErrorCode::TooManyElements,
+            ErrorType::ScalarRoot => ErrorCode::ScalarRoot,
+        }
+    }
 }
 
 /// Parser error
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct Error {
     /// Strucutral the error was encountered at
     structural: usize,
@@ -92,6 +265,8 @@ pub struct Error {
     character: char,
     /// Tyep of error
     error: ErrorType,
+    /// The error that caused this one, like the IO error reading the input
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
 }
 
 impl Error {
@@ -101,6 +276,7 @@ impl Error {
             index,
             character,
             error,
+            source: None,
         }
     }
     pub(crate) fn generic(t: ErrorType) -> Self {
@@ -109,9 +285,46 @@ impl Error {
             index: 0,
             character: '💩', //this is the poop emoji
             error: t,
+            source: None,
         }
     }
+    pub(crate) fn io(e: io::Error) -> Self {
+        Self::generic(ErrorType::IO(e.to_string())).with_source(e)
+    }
+    pub(crate) fn with_source<E>(mut self, source: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Byte index in the input the error was encountered at
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Type of the error
+    pub fn error(&self) -> &ErrorType {
+        &self.error
+    }
+
+    /// The stable code of the type of the error
+    pub fn code(&self) -> ErrorCode {
+        self.error.code()
+    }
+}
+
+// the source is left out, it only adds detail to the error type
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        self.structural == other.structural
+            && self.index == other.index
+            && self.character == other.character
+            && self.error == other.error
+    }
 }
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -122,6 +335,41 @@ impl fmt::Display for Error {
     }
 }
 
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.source {
+            Some(e) => Some(&**e),
+            None => None,
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    /// IO errors are unwrapped, other errors become `InvalidData` errors or
+    /// `UnexpectedEof` if the input ended early.
+    fn from(e: Error) -> Self {
+        if let Some(source) = e.source {
+            match source.downcast::<io::Error>() {
+                Ok(source) => return *source,
+                Err(source) => {
+                    let e = Error {
+                        source: Some(source),
+                        ..e
+                    };
+                    return io::Error::new(io::ErrorKind::InvalidData, e);
+                }
+            }
+        }
+        let kind = match e.error {
+            ErrorType::EarlyEnd | ErrorType::EOF | ErrorType::UnexpectedEnd => {
+                io::ErrorKind::UnexpectedEof
+            }
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, e)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -133,4 +381,38 @@ mod test {
             "InternalError at character 0 ('\u{1f4a9}')"
         )
     }
+
+    #[test]
+    fn codes() {
+        assert_eq!(ErrorType::BadKeyType.code() as u16, 1);
+        assert_eq!(ErrorType::IO(String::new()).code(), ErrorCode::IO);
+        assert_eq!(ErrorType::ScalarRoot.code() as u16, 44);
+        let e = Error::generic(ErrorType::DepthExceeded);
+        assert_eq!(e.code(), ErrorCode::DepthExceeded);
+    }
+
+    #[test]
+    fn source() {
+        use std::error::Error as _;
+        let e = Error::generic(ErrorType::UnexpectedEnd);
+        assert!(e.source().is_none());
+        assert_eq!(io::Error::from(e).kind(), io::ErrorKind::UnexpectedEof);
+
+        let e = Error::io(io::Error::new(io::ErrorKind::BrokenPipe, "oh no"));
+        assert_eq!(e.error(), &ErrorType::IO("oh no".to_string()));
+        assert_eq!(e.source().expect("source").to_string(), "oh no");
+        assert_eq!(e, Error::generic(ErrorType::IO("oh no".to_string())));
+        let e = io::Error::from(e);
+        assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+
+        let mut d = b"\"\xff\"".to_vec();
+        let e = crate::unescape_str(&mut d).expect_err("invalid utf8");
+        assert!(e.source().expect("source").is::<std::str::Utf8Error>());
+        let e = io::Error::from(e);
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        let inner = e.into_inner().expect("inner error");
+        let inner = inner.downcast::<Error>().expect("parse error");
+        assert_eq!(inner.error(), &ErrorType::InvalidUTF8);
+        assert!(inner.source().is_some());
+    }
 }
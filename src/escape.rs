@@ -0,0 +1,124 @@
+use crate::pointer::string_end;
+use crate::value::generator::{BaseGenerator, WriterGenerator};
+use crate::{
+    validate_utf8, Deserializer, Error, ErrorType, OwnedValue, ParserOptions, Result,
+    SIMDJSON_PADDING,
+};
+
+/// Unescapes a single JSON string literal, including its quotes, in place
+/// using the string routine of the parser and returns its contents.
+///
+/// The literal has to fill the whole input, it is checked for valid UTF-8,
+/// control characters and invalid escapes the same way the parser does.
+///
+/// ```
+/// let mut d = r#""a\tb ü\"""#.as_bytes().to_vec();
+/// assert_eq!(simd_json::unescape_str(&mut d).unwrap(), "a\tb ü\"");
+/// let mut d = br#""a" "b""#.to_vec();
+/// assert!(simd_json::unescape_str(&mut d).is_err());
+/// ```
+pub fn unescape_str(input: &mut [u8]) -> Result<&str> {
+    let len = input.len();
+    if input.first() != Some(&b'"') {
+        let c = input.first().map_or(' ', |c| *c as char);
+        return Err(Error::new(0, 0, c, ErrorType::ExpectedString));
+    }
+    let (end, escaped) = match string_end(input, 1) {
+        Some(e) => e,
+        None => return Err(Error::new(0, len, ' ', ErrorType::UnterminatedString)),
+    };
+    if end + 1 != len {
+        let c = input[end + 1] as char;
+        return Err(Error::new(0, end + 1, c, ErrorType::TrailingCharacters));
+    }
+    if let Some(i) = input[1..end].iter().position(|c| *c < 0x20) {
+        let c = input[i + 1] as char;
+        return Err(Error::new(0, i + 1, c, ErrorType::UnexpectedCharacter));
+    }
+    if let Err(e) = validate_utf8(&input[1..end]) {
+        let offset = e.valid_up_to() + 1;
+        let c = input[offset] as char;
+        return Err(Error::new(0, offset, c, ErrorType::InvalidUTF8).with_source(e));
+    }
+    if !escaped {
+        return Ok(unsafe { std::str::from_utf8_unchecked(&input[1..end]) });
+    }
+    let mut de = Deserializer {
+        input,
+        strings: vec![0; len + SIMDJSON_PADDING],
+        structural_indexes: vec![0],
+        idx: 0,
+        counts: Vec::new(),
+        str_offset: 0,
+        iidx: 0,
+        options: ParserOptions::default(),
+    };
+    de.parse_str_()
+}
+
+/// Appends `s` as a JSON string literal, with quotes, to `out` escaping
+/// what JSON requires using the SIMD routine of the generator.
+///
+/// ```
+/// let mut out = Vec::new();
+/// simd_json::escape_str_into("a\tb \"ü\"", &mut out);
+/// assert_eq!(out, r#""a\tb \"ü\"""#.as_bytes());
+/// ```
+pub fn escape_str_into(s: &str, out: &mut Vec<u8>) {
+    let mut g: WriterGenerator<_, OwnedValue> = WriterGenerator::new(out);
+    // writing to a `Vec` can't fail
+    let _ = g.write_string(s);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unescaped(input: &str) -> Result<String> {
+        let mut d = input.as_bytes().to_vec();
+        unescape_str(&mut d).map(String::from)
+    }
+
+    fn fails(input: &[u8], t: ErrorType) {
+        let mut d = input.to_vec();
+        assert_eq!(unescape_str(&mut d).expect_err("invalid").error(), &t);
+    }
+
+    #[test]
+    fn unescape() {
+        assert_eq!(unescaped(r#""""#).unwrap(), "");
+        assert_eq!(unescaped(r#""plain""#).unwrap(), "plain");
+        assert_eq!(
+            unescaped(r#""\"\\\/\b\f\n\r\t 😀""#).unwrap(),
+            "\"\\/\u{8}\u{c}\n\r\t 😀"
+        );
+        let long = "x\\n".repeat(40);
+        assert_eq!(
+            unescaped(&format!("\"{}\"", long)).unwrap(),
+            "x\n".repeat(40)
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        fails(b"plain", ErrorType::ExpectedString);
+        fails(b"", ErrorType::ExpectedString);
+        fails(br#""abc"#, ErrorType::UnterminatedString);
+        fails(br#""abc\""#, ErrorType::UnterminatedString);
+        fails(br#""a" "#, ErrorType::TrailingCharacters);
+        fails(b"\"a\nb\"", ErrorType::UnexpectedCharacter);
+        fails(b"\"a\xffb\"", ErrorType::InvalidUTF8);
+        fails(br#""\x""#, ErrorType::InvalidEscape);
+        fails(br#""\ud83d""#, ErrorType::InvlaidUnicodeCodepoint);
+    }
+
+    #[test]
+    fn round_trip() {
+        let s = "quotes \" backslash \\ control \u{1} \u{1f} tab \t ü€😀 ".repeat(3);
+        let mut out = b"[".to_vec();
+        escape_str_into(&s, &mut out);
+        assert_eq!(out[0], b'[');
+        let mut d = out[1..].to_vec();
+        assert_eq!(unescape_str(&mut d).unwrap(), s);
+    }
+}
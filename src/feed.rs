@@ -0,0 +1,400 @@
+use crate::charutils::is_json_whitespace;
+use crate::value::owned::to_value_with_options;
+use crate::{Error, ErrorType, OwnedValue, ParserOptions, Result};
+
+// Where the scanner is relative to the document currently being buffered
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    // Between documents, skipping whitespace
+    Between,
+    // Inside an object or array
+    Container {
+        depth: usize,
+        in_string: bool,
+        escaped: bool,
+    },
+    // Inside a top level string
+    Str {
+        escaped: bool,
+    },
+    // Inside a top level number or literal
+    Scalar,
+}
+
+/// An incremental parser for a stream of concatenated JSON documents that
+/// arrives in arbitrary chunks, for example frames split across socket
+/// reads.
+///
+/// Chunks are buffered with `feed` until a document is complete, complete
+/// documents are then parsed and returned by `next_document`. Documents can
+/// be separated by whitespace (or nothing for objects, arrays and strings).
+/// A top level number or literal is only known to be complete once the
+/// next whitespace or document starts, or `finish` is called.
+///
+/// A document is buffered until it is complete, so for input from
+/// untrusted peers set `ParserOptions::max_document_size`: a document that
+/// grows larger is returned as an `ErrorType::InputTooLarge` error right
+/// away, the rest of it is skipped without being buffered.
+///
+/// ```
+/// use simd_json::{json, FeedParser};
+/// let mut p = FeedParser::new();
+/// p.feed(b"{\"a\": [1, ");
+/// assert!(p.next_document().is_none());
+/// p.feed(b"2]}{\"b\"");
+/// assert_eq!(p.next_document().unwrap().unwrap(), json!({"a": [1, 2]}));
+/// assert!(p.next_document().is_none());
+/// ```
+#[derive(Debug)]
+pub struct FeedParser {
+    options: ParserOptions,
+    buffer: Vec<u8>,
+    // bytes before this offset belong to documents already returned
+    consumed: usize,
+    scanner: Scanner,
+    // if the current document was too large and is being skipped
+    skipping: bool,
+}
+
+impl Default for FeedParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FeedParser {
+    /// Creates a new feed parser using the default `ParserOptions`
+    pub fn new() -> Self {
+        Self::with_options(ParserOptions::default())
+    }
+
+    /// Creates a new feed parser using the given `ParserOptions` for each
+    /// document
+    pub fn with_options(options: ParserOptions) -> Self {
+        Self {
+            options,
+            buffer: Vec::new(),
+            consumed: 0,
+            scanner: Scanner::default(),
+            skipping: false,
+        }
+    }
+
+    /// Appends a chunk of input
+    pub fn feed(&mut self, chunk: &[u8]) {
+        // Returned documents are removed once they make up half of the
+        // buffer, so every byte is moved at most once on average
+        if self.consumed > 0 && self.consumed >= self.buffer.len() / 2 {
+            self.buffer.drain(..self.consumed);
+            self.scanner.shift(self.consumed);
+            self.consumed = 0;
+        }
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Number of bytes buffered that don't belong to a returned document yet
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.consumed
+    }
+
+    /// Parses and returns the next complete document, or `None` if more
+    /// input is needed. A document that fails to parse is returned as an
+    /// error and parsing continues after it.
+    pub fn next_document(&mut self) -> Option<Result<OwnedValue>> {
+        loop {
+            match self.scanner.scan(&self.buffer) {
+                Some(end) if self.skipping => {
+                    self.skipping = false;
+                    self.done(end);
+                }
+                Some(end) => return Some(self.parse(end)),
+                None => return self.check_size(),
+            }
+        }
+    }
+
+    /// Signals the end of the input and parses whatever is left in the
+    /// buffer, returns `None` if there's nothing but whitespace left. An
+    /// incomplete document is returned as an error.
+    pub fn finish(&mut self) -> Option<Result<OwnedValue>> {
+        if let Some(document) = self.next_document() {
+            return Some(document);
+        }
+        if self.scanner.is_between() || self.skipping {
+            self.buffer.clear();
+            self.consumed = 0;
+            self.scanner = Scanner::default();
+            self.skipping = false;
+            return None;
+        }
+        let end = self.buffer.len();
+        Some(self.parse(end))
+    }
+
+    fn parse(&mut self, end: usize) -> Result<OwnedValue> {
+        let result =
+            to_value_with_options(&mut self.buffer[self.scanner.start()..end], &self.options);
+        self.done(end);
+        result
+    }
+
+    // Marks the document ending at `end` as returned
+    fn done(&mut self, end: usize) {
+        self.consumed = end;
+        self.scanner.finished();
+    }
+
+    // Fails the current, incomplete, document once it is larger than
+    // `max_document_size` and drops what is buffered of it
+    fn check_size(&mut self) -> Option<Result<OwnedValue>> {
+        if self.scanner.is_between() {
+            return None;
+        }
+        let start = self.scanner.start();
+        if self.skipping {
+            self.buffer.truncate(start);
+            self.scanner.rewind();
+            None
+        } else if self.buffer.len() - start > self.options.max_document_size {
+            self.buffer.truncate(start);
+            self.scanner.rewind();
+            self.skipping = true;
+            Some(Err(Error::generic(ErrorType::InputTooLarge)))
+        } else {
+            None
+        }
+    }
+}
+
+/// Finds the boundaries of concatenated documents without parsing them
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Scanner {
+    start: usize,
+    scanned: usize,
+    state: State,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            scanned: 0,
+            state: State::Between,
+        }
+    }
+}
+
+impl Scanner {
+    /// Offset of the first byte of the current document
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+
+    /// True if no document has been started since the last boundary
+    pub(crate) fn is_between(&self) -> bool {
+        self.state == State::Between
+    }
+
+    /// Ends the current document, the next byte scanned may start a new
+    /// one
+    pub(crate) fn finished(&mut self) {
+        self.state = State::Between;
+    }
+
+    /// Adjusts the offsets after the first `n` bytes, which don't belong
+    /// to the current document, were removed from the buffer
+    pub(crate) fn shift(&mut self, n: usize) {
+        self.start = self.start.saturating_sub(n);
+        self.scanned -= n;
+    }
+
+    /// Continues the current document at its start after the bytes
+    /// scanned of it were removed from the end of the buffer
+    pub(crate) fn rewind(&mut self) {
+        self.scanned = self.start;
+    }
+
+    // Scans `buffer` for the end of the current document, continuing where
+    // the last call stopped. Returns the offset one past its last byte.
+    pub(crate) fn scan(&mut self, buffer: &[u8]) -> Option<usize> {
+        while let Some(&c) = buffer.get(self.scanned) {
+            let i = self.scanned;
+            self.scanned += 1;
+            match &mut self.state {
+                State::Between => {
+                    if is_json_whitespace(c) {
+                        continue;
+                    }
+                    self.start = i;
+                    self.state = match c {
+                        b'{' | b'[' => State::Container {
+                            depth: 1,
+                            in_string: false,
+                            escaped: false,
+                        },
+                        b'"' => State::Str { escaped: false },
+                        _ => State::Scalar,
+                    };
+                }
+                State::Container {
+                    depth,
+                    in_string: true,
+                    escaped,
+                } => {
+                    if *escaped {
+                        *escaped = false;
+                    } else if c == b'\\' {
+                        *escaped = true;
+                    } else if c == b'"' {
+                        self.state = State::Container {
+                            depth: *depth,
+                            in_string: false,
+                            escaped: false,
+                        };
+                    }
+                }
+                State::Container {
+                    depth, in_string, ..
+                } => match c {
+                    b'"' => *in_string = true,
+                    b'{' | b'[' => *depth += 1,
+                    b'}' | b']' => {
+                        *depth -= 1;
+                        if *depth == 0 {
+                            return Some(i + 1);
+                        }
+                    }
+                    _ => (),
+                },
+                State::Str { escaped } => {
+                    if *escaped {
+                        *escaped = false;
+                    } else if c == b'\\' {
+                        *escaped = true;
+                    } else if c == b'"' {
+                        return Some(i + 1);
+                    }
+                }
+                State::Scalar => {
+                    if is_json_whitespace(c) || b"{}[]\",:".contains(&c) {
+                        // the delimiter belongs to the next document
+                        self.scanned = i;
+                        return Some(i);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ErrorType;
+
+    #[test]
+    fn byte_by_byte() {
+        let input = br#"{"a": "}]\"{", "b": [1, {}]}  ["x"]"s\"}" 42 true
+            {"c": null}-1.5"#;
+        let mut p = FeedParser::new();
+        let mut docs = Vec::new();
+        for b in input.iter() {
+            p.feed(&[*b]);
+            while let Some(doc) = p.next_document() {
+                docs.push(doc.expect("valid document"));
+            }
+        }
+        assert_eq!(docs.len(), 6);
+        assert_eq!(p.finish(), Some(Ok(json!(-1.5))));
+        assert_eq!(p.finish(), None);
+        assert_eq!(p.buffered(), 0);
+        assert_eq!(
+            docs,
+            vec![
+                json!({"a": "}]\"{", "b": [1, {}]}),
+                json!(["x"]),
+                json!("s\"}"),
+                json!(42),
+                json!(true),
+                json!({"c": null}),
+            ]
+        );
+    }
+
+    #[test]
+    fn recovers_from_errors() {
+        let mut p = FeedParser::new();
+        p.feed(b"[1, 2,] {\"a\": 1} ] [3]");
+        assert!(p.next_document().expect("document").is_err());
+        assert_eq!(p.next_document(), Some(Ok(json!({"a": 1}))));
+        assert!(p.next_document().expect("document").is_err());
+        assert_eq!(p.next_document(), Some(Ok(json!([3]))));
+        assert_eq!(p.next_document(), None);
+    }
+
+    #[test]
+    fn form_feed_is_no_separator() {
+        let mut p = FeedParser::new();
+        p.feed(b"1\x0c2 3");
+        assert!(p.next_document().expect("document").is_err());
+        assert_eq!(p.finish(), Some(Ok(json!(3))));
+    }
+
+    #[test]
+    fn too_large() {
+        let mut p = FeedParser::with_options(ParserOptions {
+            max_document_size: 16,
+            ..ParserOptions::default()
+        });
+        p.feed(b"[1] [\"");
+        assert_eq!(p.next_document(), Some(Ok(json!([1]))));
+        assert_eq!(p.next_document(), None);
+        p.feed(b"[{\\\"}]");
+        p.feed(&[b'a'; 16]);
+        let e = p.next_document().expect("document").expect_err("too large");
+        assert_eq!(e.error(), &ErrorType::InputTooLarge);
+        // an unterminated document doesn't grow the buffer
+        for _ in 0..1000 {
+            p.feed(b"aaaaaaaa");
+            assert_eq!(p.next_document(), None);
+            assert!(p.buffered() <= 16);
+        }
+        p.feed(b"\"] [2]\"ok\"");
+        assert_eq!(p.next_document(), Some(Ok(json!([2]))));
+        assert_eq!(p.next_document(), Some(Ok(json!("ok"))));
+        assert_eq!(p.finish(), None);
+
+        p.feed(b"{\"a\": \"too long for the limit");
+        assert!(p.finish().expect("document").is_err());
+        assert_eq!(p.finish(), None);
+        assert_eq!(p.buffered(), 0);
+    }
+
+    #[test]
+    fn many_documents_in_a_chunk() {
+        let mut p = FeedParser::new();
+        let chunk = b"[1] ".repeat(1000);
+        for _ in 0..3 {
+            p.feed(&chunk);
+            for _ in 0..1000 {
+                assert_eq!(p.next_document(), Some(Ok(json!([1]))));
+            }
+            assert_eq!(p.next_document(), None);
+            assert_eq!(p.buffered(), 1);
+        }
+    }
+
+    #[test]
+    fn incomplete_at_finish() {
+        let mut p = FeedParser::new();
+        p.feed(b" \n ");
+        assert_eq!(p.next_document(), None);
+        assert_eq!(p.finish(), None);
+        p.feed(b"{\"a\": [");
+        assert_eq!(p.next_document(), None);
+        let e = p.finish().expect("document").expect_err("incomplete");
+        assert_ne!(e.error(), &ErrorType::InternalError);
+        assert_eq!(p.buffered(), 0);
+    }
+}
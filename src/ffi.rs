@@ -0,0 +1,567 @@
+//! See `include/simdjson_rs.h` for the matching declarations. Build the
+//! crate as a C library with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or
+//! `staticlib`).
+//!
+//! Parsing produces a document handle that owns all values in it, values
+//! are handed out as pointers into the document that stay valid until it
+//! is freed with `simdjson_rs_document_free`. Functions return one of the
+//! status codes below, accessors for values of another type return
+//! `INCORRECT_TYPE` and leave their output untouched. A panic never
+//! unwinds into C, functions catch it and return `PANIC`, null or their
+//! neutral value.
+
+use crate::pointer::{resolve, tokens};
+use crate::value::owned::{to_value, Object};
+use crate::{ErrorType, ObjectAccess, ObjectIter, OwnedValue, ValueAccess, ValueType};
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+/// Success
+pub const OK: c_int = 0;
+/// The value has a different type than the accessor reads
+pub const INCORRECT_TYPE: c_int = 1;
+/// The number doesn't fit the requested integer type
+pub const NUMBER_OUT_OF_RANGE: c_int = 2;
+/// An array index is out of bounds or a member iterator is exhausted
+pub const INDEX_OUT_OF_BOUNDS: c_int = 3;
+/// A required pointer argument was null
+pub const NULL_POINTER: c_int = 4;
+/// The input isn't valid JSON
+pub const SYNTAX_ERROR: c_int = 10;
+/// The input isn't valid UTF-8
+pub const UTF8_ERROR: c_int = 11;
+/// A number in the input is malformed or too large
+pub const NUMBER_ERROR: c_int = 12;
+/// A string in the input has an invalid escape or is unterminated
+pub const STRING_ERROR: c_int = 13;
+/// The input is nested too deeply
+pub const DEPTH_ERROR: c_int = 14;
+/// The input exceeds a configured size limit
+pub const CAPACITY_ERROR: c_int = 15;
+/// The input is empty
+pub const EMPTY: c_int = 16;
+/// A bug in the library caused a panic, which was caught
+pub const PANIC: c_int = 17;
+
+/// `null`, as returned by `simdjson_rs_value_type`
+pub const TYPE_NULL: c_int = 0;
+/// A boolean
+pub const TYPE_BOOL: c_int = 1;
+/// An integer
+pub const TYPE_I64: c_int = 2;
+/// A float
+pub const TYPE_F64: c_int = 3;
+/// A string
+pub const TYPE_STRING: c_int = 4;
+/// An array
+pub const TYPE_ARRAY: c_int = 5;
+/// An object
+pub const TYPE_OBJECT: c_int = 6;
+
+/// A parsed document
+pub struct Document(OwnedValue);
+
+/// An iterator over the members of an object, it borrows the document the
+/// object is in
+pub struct Members(<Object as ObjectIter<'static>>::Iter);
+
+// Runs `f` and returns `on_panic` if it panics, unwinding into C is
+// undefined behaviour. Nothing is shared between calls, so there is no
+// state a panic could leave broken.
+fn guard<T, F: FnOnce() -> T>(on_panic: T, f: F) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(on_panic)
+}
+
+fn error_code(error: &ErrorType) -> c_int {
+    match error {
+        ErrorType::InvalidUTF8 => UTF8_ERROR,
+        ErrorType::InvalidNumber | ErrorType::InvalidExponent | ErrorType::Overflow => NUMBER_ERROR,
+        ErrorType::InvalidEscape
+        | ErrorType::InvalidUnicodeEscape
+        | ErrorType::InvlaidUnicodeCodepoint
+        | ErrorType::UnterminatedString => STRING_ERROR,
+        ErrorType::DepthExceeded => DEPTH_ERROR,
+        ErrorType::InputTooLarge | ErrorType::StringTooLong | ErrorType::TooManyElements => {
+            CAPACITY_ERROR
+        }
+        ErrorType::EOF => EMPTY,
+        _ => SYNTAX_ERROR,
+    }
+}
+
+/// Returns a static, NUL terminated description of a status code
+#[no_mangle]
+pub extern "C" fn simdjson_rs_error_message(code: c_int) -> *const c_char {
+    let message: &'static [u8] = guard(b"internal error\0", || match code {
+        OK => b"ok\0",
+        INCORRECT_TYPE => b"incorrect type\0",
+        NUMBER_OUT_OF_RANGE => b"number out of range\0",
+        INDEX_OUT_OF_BOUNDS => b"index out of bounds\0",
+        NULL_POINTER => b"null pointer\0",
+        SYNTAX_ERROR => b"syntax error\0",
+        UTF8_ERROR => b"invalid UTF-8\0",
+        NUMBER_ERROR => b"invalid number\0",
+        STRING_ERROR => b"invalid string\0",
+        DEPTH_ERROR => b"maximum depth exceeded\0",
+        CAPACITY_ERROR => b"capacity exceeded\0",
+        EMPTY => b"empty input\0",
+        PANIC => b"internal error\0",
+        _ => b"unknown error\0",
+    });
+    message.as_ptr() as *const c_char
+}
+
+/// Parses `len` bytes at `data` and stores the document in `out`. The
+/// input is copied, so it can be freed once this returns.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` to writable memory
+/// for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_parse(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut Document,
+) -> c_int {
+    if (data.is_null() && len > 0) || out.is_null() {
+        return NULL_POINTER;
+    }
+    guard(PANIC, || {
+        let mut input = if len == 0 {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(data, len).to_vec()
+        };
+        match to_value(&mut input) {
+            Ok(value) => {
+                *out = Box::into_raw(Box::new(Document(value)));
+                OK
+            }
+            Err(e) => error_code(e.error()),
+        }
+    })
+}
+
+/// Frees a document, all values of it become invalid. Passing null does
+/// nothing.
+///
+/// # Safety
+///
+/// `doc` must be null or a document returned by `simdjson_rs_parse` that
+/// wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_document_free(doc: *mut Document) {
+    if !doc.is_null() {
+        guard((), || drop(Box::from_raw(doc)));
+    }
+}
+
+/// The root value of a document
+///
+/// # Safety
+///
+/// `doc` must be null or a valid document.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_document_root(doc: *const Document) -> *const OwnedValue {
+    guard(ptr::null(), || match doc.as_ref() {
+        Some(doc) => &doc.0,
+        None => ptr::null(),
+    })
+}
+
+/// The type of a value as one of the `TYPE_*` constants, `-1` for null
+///
+/// # Safety
+///
+/// `value` must be null or a valid value.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_type(value: *const OwnedValue) -> c_int {
+    guard(-1, || match value.as_ref().map(ValueAccess::value_type) {
+        Some(ValueType::Null) => TYPE_NULL,
+        Some(ValueType::Bool) => TYPE_BOOL,
+        Some(ValueType::I64) => TYPE_I64,
+        Some(ValueType::F64) => TYPE_F64,
+        Some(ValueType::String) => TYPE_STRING,
+        Some(ValueType::Array) => TYPE_ARRAY,
+        Some(ValueType::Object) => TYPE_OBJECT,
+        None => -1,
+    })
+}
+
+// Reads a value with `get` and stores it in `out`
+unsafe fn read<T, F>(value: *const OwnedValue, out: *mut T, get: F) -> c_int
+where
+    F: FnOnce(&OwnedValue) -> std::result::Result<T, c_int>,
+{
+    match value.as_ref() {
+        Some(_) if out.is_null() => NULL_POINTER,
+        Some(value) => guard(PANIC, || match get(value) {
+            Ok(v) => {
+                *out = v;
+                OK
+            }
+            Err(code) => code,
+        }),
+        None => NULL_POINTER,
+    }
+}
+
+/// Reads a boolean
+///
+/// # Safety
+///
+/// `value` must be null or a valid value, `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_get_bool(
+    value: *const OwnedValue,
+    out: *mut bool,
+) -> c_int {
+    read(value, out, |v| v.as_bool().ok_or(INCORRECT_TYPE))
+}
+
+/// Reads an integer that fits an `int64_t`
+///
+/// # Safety
+///
+/// `value` must be null or a valid value, `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_get_i64(
+    value: *const OwnedValue,
+    out: *mut i64,
+) -> c_int {
+    read(value, out, |v| match v.as_i64() {
+        Some(i) => Ok(i),
+        None if v.is_u64() => Err(NUMBER_OUT_OF_RANGE),
+        None => Err(INCORRECT_TYPE),
+    })
+}
+
+/// Reads a non negative integer
+///
+/// # Safety
+///
+/// `value` must be null or a valid value, `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_get_u64(
+    value: *const OwnedValue,
+    out: *mut u64,
+) -> c_int {
+    read(value, out, |v| match v.as_u64() {
+        Some(u) => Ok(u),
+        None if v.is_i64() => Err(NUMBER_OUT_OF_RANGE),
+        None => Err(INCORRECT_TYPE),
+    })
+}
+
+/// Reads a number as a `double`, integers are converted
+///
+/// # Safety
+///
+/// `value` must be null or a valid value, `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_get_f64(
+    value: *const OwnedValue,
+    out: *mut f64,
+) -> c_int {
+    read(value, out, |v| v.cast_f64().ok_or(INCORRECT_TYPE))
+}
+
+/// Reads a string, stores a pointer to its UTF-8 bytes in `out` and their
+/// number in `len`. The string is not NUL terminated and may contain NUL
+/// characters.
+///
+/// # Safety
+///
+/// `value` must be null or a valid value, `out` and `len` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_get_string(
+    value: *const OwnedValue,
+    out: *mut *const c_char,
+    len: *mut usize,
+) -> c_int {
+    if len.is_null() {
+        return NULL_POINTER;
+    }
+    read(value, out, |v| match v.as_str() {
+        Some(s) => {
+            *len = s.len();
+            Ok(s.as_ptr() as *const c_char)
+        }
+        None => Err(INCORRECT_TYPE),
+    })
+}
+
+/// The number of elements of an array or members of an object, `0` for
+/// other values
+///
+/// # Safety
+///
+/// `value` must be null or a valid value.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_len(value: *const OwnedValue) -> usize {
+    guard(0, || match value.as_ref() {
+        Some(OwnedValue::Array(a)) => a.len(),
+        Some(OwnedValue::Object(o)) => o.len(),
+        _ => 0,
+    })
+}
+
+/// The element of an array at `index`, null if the value isn't an array
+/// or the index is out of bounds
+///
+/// # Safety
+///
+/// `value` must be null or a valid value.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_at(
+    value: *const OwnedValue,
+    index: usize,
+) -> *const OwnedValue {
+    guard(ptr::null(), || match value.as_ref() {
+        Some(OwnedValue::Array(a)) => a.get(index).map_or(ptr::null(), |v| v),
+        _ => ptr::null(),
+    })
+}
+
+/// The value of the member `key` (`key_len` UTF-8 bytes) of an object,
+/// null if the value isn't an object or has no such member
+///
+/// # Safety
+///
+/// `value` must be null or a valid value, `key` must point to `key_len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_get(
+    value: *const OwnedValue,
+    key: *const c_char,
+    key_len: usize,
+) -> *const OwnedValue {
+    guard(ptr::null(), || {
+        match (value.as_ref(), str_arg(key, key_len)) {
+            (Some(value), Some(key)) => value.get(key).map_or(ptr::null(), |v| v),
+            _ => ptr::null(),
+        }
+    })
+}
+
+/// Starts iterating over the members of an object, null if the value
+/// isn't an object. The iterator must be freed with
+/// `simdjson_rs_members_free` before the document is.
+///
+/// # Safety
+///
+/// `value` must be null or a valid value.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_members(value: *const OwnedValue) -> *mut Members {
+    guard(ptr::null_mut(), || match value.as_ref() {
+        Some(OwnedValue::Object(o)) => {
+            // callers free the iterator before the document
+            let o: &'static Object = &*(o as *const Object);
+            Box::into_raw(Box::new(Members(ObjectAccess::iter(o))))
+        }
+        _ => ptr::null_mut(),
+    })
+}
+
+/// Advances a member iterator, stores the key of the next member (not NUL
+/// terminated) in `key` and `key_len` and its value in `out`. Returns
+/// `INDEX_OUT_OF_BOUNDS` once all members were visited.
+///
+/// # Safety
+///
+/// `members` must be null or an iterator returned by
+/// `simdjson_rs_value_members` whose document wasn't freed yet, `key`,
+/// `key_len` and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_members_next(
+    members: *mut Members,
+    key: *mut *const c_char,
+    key_len: *mut usize,
+    out: *mut *const OwnedValue,
+) -> c_int {
+    let members = match members.as_mut() {
+        Some(members) if !key.is_null() && !key_len.is_null() && !out.is_null() => members,
+        _ => return NULL_POINTER,
+    };
+    guard(PANIC, || match members.0.next() {
+        Some((k, v)) => {
+            *key = k.as_ptr() as *const c_char;
+            *key_len = k.len();
+            *out = v;
+            OK
+        }
+        None => INDEX_OUT_OF_BOUNDS,
+    })
+}
+
+/// Frees a member iterator. Passing null does nothing.
+///
+/// # Safety
+///
+/// `members` must be null or an iterator returned by
+/// `simdjson_rs_value_members` that wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_members_free(members: *mut Members) {
+    if !members.is_null() {
+        guard((), || drop(Box::from_raw(members)));
+    }
+}
+
+/// The value referenced by a JSON pointer (`len` UTF-8 bytes) relative to
+/// `value`, null if it doesn't resolve
+///
+/// # Safety
+///
+/// `value` must be null or a valid value, `pointer` must point to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn simdjson_rs_value_pointer(
+    value: *const OwnedValue,
+    pointer: *const c_char,
+    len: usize,
+) -> *const OwnedValue {
+    guard(ptr::null(), || {
+        match (value.as_ref(), str_arg(pointer, len).and_then(tokens)) {
+            (Some(value), Some(tokens)) => resolve(value, &tokens).map_or(ptr::null(), |v| v),
+            _ => ptr::null(),
+        }
+    })
+}
+
+// Borrows a string argument, `None` if it's null or not UTF-8
+unsafe fn str_arg<'a>(s: *const c_char, len: usize) -> Option<&'a str> {
+    if len == 0 {
+        Some("")
+    } else if s.is_null() {
+        None
+    } else {
+        std::str::from_utf8(slice::from_raw_parts(s as *const u8, len)).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::CStr;
+
+    unsafe fn string(value: *const OwnedValue) -> &'static str {
+        let mut s = ptr::null();
+        let mut len = 0;
+        assert_eq!(simdjson_rs_value_get_string(value, &mut s, &mut len), OK);
+        std::str::from_utf8(slice::from_raw_parts(s as *const u8, len)).unwrap()
+    }
+
+    #[test]
+    fn parse_and_access() {
+        let input = br#"{"a": [1, -2, 2.5, "x\u0000y", 18446744073709551615], "b": {"c": true}, "d": null}"#;
+        unsafe {
+            let mut doc = ptr::null_mut();
+            assert_eq!(simdjson_rs_parse(input.as_ptr(), input.len(), &mut doc), OK);
+            let root = simdjson_rs_document_root(doc);
+            assert_eq!(simdjson_rs_value_type(root), TYPE_OBJECT);
+            assert_eq!(simdjson_rs_value_len(root), 3);
+
+            let a = simdjson_rs_value_get(root, b"a".as_ptr() as *const c_char, 1);
+            assert_eq!(simdjson_rs_value_type(a), TYPE_ARRAY);
+            assert_eq!(simdjson_rs_value_len(a), 5);
+            let mut i = 0;
+            assert_eq!(
+                simdjson_rs_value_get_i64(simdjson_rs_value_at(a, 1), &mut i),
+                OK
+            );
+            assert_eq!(i, -2);
+            let mut u = 0;
+            assert_eq!(
+                simdjson_rs_value_get_u64(simdjson_rs_value_at(a, 1), &mut u),
+                NUMBER_OUT_OF_RANGE
+            );
+            let mut f = 0.0;
+            assert_eq!(
+                simdjson_rs_value_get_f64(simdjson_rs_value_at(a, 0), &mut f),
+                OK
+            );
+            assert_eq!(f, 1.0);
+            assert_eq!(
+                simdjson_rs_value_get_i64(simdjson_rs_value_at(a, 2), &mut i),
+                INCORRECT_TYPE
+            );
+            assert_eq!(i, -2);
+            assert_eq!(string(simdjson_rs_value_at(a, 3)), "x\0y");
+            assert_eq!(
+                simdjson_rs_value_get_i64(simdjson_rs_value_at(a, 4), &mut i),
+                NUMBER_OUT_OF_RANGE
+            );
+            assert_eq!(
+                simdjson_rs_value_get_u64(simdjson_rs_value_at(a, 4), &mut u),
+                OK
+            );
+            assert_eq!(u, u64::max_value());
+            assert!(simdjson_rs_value_at(a, 5).is_null());
+
+            let p = b"/b/c";
+            let c = simdjson_rs_value_pointer(root, p.as_ptr() as *const c_char, p.len());
+            let mut b = false;
+            assert_eq!(simdjson_rs_value_get_bool(c, &mut b), OK);
+            assert!(b);
+            let d = simdjson_rs_value_get(root, b"d".as_ptr() as *const c_char, 1);
+            assert_eq!(simdjson_rs_value_type(d), TYPE_NULL);
+            assert!(simdjson_rs_value_get(root, b"e".as_ptr() as *const c_char, 1).is_null());
+
+            let mut keys = Vec::new();
+            let members = simdjson_rs_value_members(root);
+            let (mut key, mut len, mut v) = (ptr::null(), 0, ptr::null());
+            while simdjson_rs_members_next(members, &mut key, &mut len, &mut v) == OK {
+                assert!(!v.is_null());
+                keys.push(
+                    std::str::from_utf8(slice::from_raw_parts(key as *const u8, len)).unwrap(),
+                );
+            }
+            assert_eq!(
+                simdjson_rs_members_next(members, &mut key, &mut len, &mut v),
+                INDEX_OUT_OF_BOUNDS
+            );
+            simdjson_rs_members_free(members);
+            keys.sort();
+            assert_eq!(keys, vec!["a", "b", "d"]);
+            assert!(simdjson_rs_value_members(a).is_null());
+            simdjson_rs_document_free(doc);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        unsafe {
+            let mut doc = ptr::null_mut();
+            for (input, code) in &[
+                (&b"[1, 2"[..], SYNTAX_ERROR),
+                (b"[\"\\x\"]", STRING_ERROR),
+                (b"[1.e]", NUMBER_ERROR),
+                (b"[\"\xff\"]", UTF8_ERROR),
+                (b"", EMPTY),
+            ] {
+                assert_eq!(
+                    simdjson_rs_parse(input.as_ptr(), input.len(), &mut doc),
+                    *code
+                );
+            }
+            assert!(doc.is_null());
+            assert_eq!(simdjson_rs_parse(ptr::null(), 1, &mut doc), NULL_POINTER);
+            assert!(simdjson_rs_document_root(ptr::null()).is_null());
+            assert_eq!(simdjson_rs_value_type(ptr::null()), -1);
+            simdjson_rs_document_free(ptr::null_mut());
+            let message = CStr::from_ptr(simdjson_rs_error_message(UTF8_ERROR));
+            assert_eq!(message.to_str(), Ok("invalid UTF-8"));
+        }
+    }
+
+    #[test]
+    fn panics_are_caught() {
+        assert_eq!(guard(PANIC, || panic!("bug")), PANIC);
+        assert_eq!(guard(PANIC, || OK), OK);
+    }
+}
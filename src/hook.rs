@@ -0,0 +1,124 @@
+use crate::Error;
+#[cfg(feature = "metrics")]
+use std::sync::OnceLock;
+
+/// Receives counts of the work the parser does, to feed them into metrics.
+/// It is installed for the whole process with `set_parse_hook` and called
+/// from the parsing threads, so implementations should be cheap, like
+/// bumping atomic counters. Requires the `metrics` feature, without it the
+/// parser doesn't look for a hook at all.
+///
+/// ```
+/// use simd_json::{set_parse_hook, ParseHook};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// #[derive(Default)]
+/// struct Counters {
+///     documents: AtomicUsize,
+///     bytes: AtomicUsize,
+/// }
+///
+/// impl ParseHook for Counters {
+///     fn document(&self, bytes: usize) {
+///         self.documents.fetch_add(1, Ordering::Relaxed);
+///         self.bytes.fetch_add(bytes, Ordering::Relaxed);
+///     }
+/// }
+///
+/// static COUNTERS: Counters = Counters {
+///     documents: AtomicUsize::new(0),
+///     bytes: AtomicUsize::new(0),
+/// };
+/// assert!(set_parse_hook(&COUNTERS));
+/// let mut d = br#"{"a": 1}"#.to_vec();
+/// simd_json::to_owned_value(&mut d).unwrap();
+/// assert_eq!(COUNTERS.documents.load(Ordering::Relaxed), 1);
+/// assert_eq!(COUNTERS.bytes.load(Ordering::Relaxed), 8);
+/// ```
+#[cfg(feature = "metrics")]
+pub trait ParseHook: Send + Sync {
+    /// Called for every document the parser starts on with its size in
+    /// bytes, documents that turn out invalid included.
+    fn document(&self, _bytes: usize) {}
+
+    /// Called for documents rejected while finding or validating their
+    /// structure, errors found while building values aren't reported.
+    fn error(&self, _error: &Error) {}
+}
+
+#[cfg(feature = "metrics")]
+static HOOK: OnceLock<&'static dyn ParseHook> = OnceLock::new();
+
+/// Installs `hook` for the rest of the process, returns false if a hook
+/// was installed before, it can't be replaced.
+#[cfg(feature = "metrics")]
+pub fn set_parse_hook(hook: &'static dyn ParseHook) -> bool {
+    HOOK.set(hook).is_ok()
+}
+
+#[cfg(feature = "metrics")]
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub(crate) fn document(bytes: usize) {
+    if let Some(hook) = HOOK.get() {
+        hook.document(bytes);
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub(crate) fn error(error: &Error) {
+    if let Some(hook) = HOOK.get() {
+        hook.error(error);
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub(crate) fn document(_bytes: usize) {}
+
+#[cfg(not(feature = "metrics"))]
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub(crate) fn error(_error: &Error) {}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn spans() {
+        use std::sync::{Arc, Mutex};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        #[derive(Default)]
+        struct Spans(Arc<Mutex<Vec<&'static str>>>);
+
+        impl Subscriber for Spans {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes) -> Id {
+                let mut names = self.0.lock().unwrap();
+                names.push(span.metadata().name());
+                Id::from_u64(names.len() as u64)
+            }
+            fn record(&self, _: &Id, _: &Record) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event) {}
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let spans = Spans::default();
+        let names = spans.0.clone();
+        tracing::subscriber::with_default(spans, || {
+            let mut d = br#"{"a": [1, 2]}"#.to_vec();
+            crate::to_borrowed_value(&mut d).expect("valid");
+            let mut d = br#"{"a": [1, 2]"#.to_vec();
+            assert!(crate::to_owned_value(&mut d).is_err());
+        });
+        assert_eq!(
+            *names.lock().unwrap(),
+            vec!["stage1", "stage2", "dom", "stage1", "stage2"]
+        );
+    }
+}
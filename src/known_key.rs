@@ -1,9 +1,35 @@
+use crate::value::ObjectMap;
 use crate::{ValueTrait, ValueType};
+#[cfg(not(any(feature = "preserve-order", feature = "sorted-objects")))]
 use halfbrown::RawEntryMut;
+#[cfg(feature = "preserve-order")]
+use indexmap::map::raw_entry_v1::{
+    RawEntryApiV1, RawEntryBuilder, RawEntryBuilderMut, RawEntryMut,
+};
+#[cfg(feature = "preserve-order")]
+use indexmap::IndexMap;
 use std::borrow::{Borrow, Cow};
 use std::fmt;
 use std::hash::{BuildHasher, Hash, Hasher};
 
+// `IndexMap` has the same raw entry API as halfbrown under a versioned name
+#[cfg(feature = "preserve-order")]
+trait RawEntryApi<K, V, S> {
+    fn raw_entry(&self) -> RawEntryBuilder<'_, K, V, S>;
+    fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, K, V, S>;
+}
+
+#[cfg(feature = "preserve-order")]
+impl<K, V, S> RawEntryApi<K, V, S> for IndexMap<K, V, S> {
+    fn raw_entry(&self) -> RawEntryBuilder<'_, K, V, S> {
+        self.raw_entry_v1()
+    }
+
+    fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, K, V, S> {
+        self.raw_entry_mut_v1()
+    }
+}
+
 /// Well known key that can be looked up in a `Value` faster.
 /// It achives this by memorizing the hash.
 #[derive(Debug, Clone, PartialEq)]
@@ -33,7 +59,7 @@ where
 {
     fn from(key: S) -> Self {
         let key = Cow::from(key);
-        let hash_builder = halfbrown::DefaultHashBuilder::default();
+        let hash_builder = crate::ObjectHasher::default();
         let mut hasher = hash_builder.build_hasher();
         key.hash(&mut hasher);
         Self {
@@ -44,6 +70,17 @@ where
 }
 
 impl<'key> KnownKey<'key> {
+    /// The key itself
+    ///
+    /// ```rust
+    /// use simd_json::KnownKey;
+    /// assert_eq!(KnownKey::from("answer").key(), "answer");
+    /// ```
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
     /// Looks up this key in a `Value`, returns None if the
     /// key wasn't present or `target` isn't an object
     ///
@@ -62,12 +99,9 @@ impl<'key> KnownKey<'key> {
         'key: 'value,
         'value: 'borrow,
         V: ValueTrait + 'value,
-        V::Key: Hash + Eq + Borrow<str>,
+        V::Key: Hash + Eq + Ord + Borrow<str>,
     {
-        target
-            .as_object()
-            .and_then(|m| m.raw_entry().from_key_hashed_nocheck(self.hash, &self.key))
-            .map(|kv| kv.1)
+        target.as_object().and_then(|m| get(m, self))
     }
 
     /// Looks up this key in a `Value`, returns None if the
@@ -95,17 +129,9 @@ impl<'key> KnownKey<'key> {
         'key: 'value,
         'value: 'borrow,
         V: ValueTrait + 'value,
-        V::Key: Hash + Eq + Borrow<str>,
+        V::Key: Hash + Eq + Ord + Borrow<str>,
     {
-        target.as_object_mut().and_then(|m| {
-            match m
-                .raw_entry_mut()
-                .from_key_hashed_nocheck(self.hash, &self.key)
-            {
-                RawEntryMut::Occupied(e) => Some(e.into_mut()),
-                RawEntryMut::Vacant(_e) => None,
-            }
-        })
+        target.as_object_mut().and_then(|m| get_mut(m, self))
     }
 
     /// Looks up this key in a `Value`, inserts `with` when the key
@@ -146,7 +172,7 @@ impl<'key> KnownKey<'key> {
         'key: 'value,
         'value: 'borrow,
         V: ValueTrait + 'value,
-        V::Key: Hash + Eq + Borrow<str> + From<Cow<'key, str>>,
+        V::Key: Hash + Eq + Ord + Borrow<str> + From<Cow<'key, str>>,
         F: FnOnce() -> V,
     {
         if !target.is_object() {
@@ -154,12 +180,7 @@ impl<'key> KnownKey<'key> {
         }
         target
             .as_object_mut()
-            .map(|m| {
-                m.raw_entry_mut()
-                    .from_key_hashed_nocheck(self.hash, &self.key)
-                    .or_insert_with(|| (self.key.clone().into(), with()))
-                    .1
-            })
+            .map(|m| get_or_insert_with(m, self, with))
             .ok_or(Error::NotAnObject(ValueType::Null))
     }
 
@@ -197,46 +218,197 @@ impl<'key> KnownKey<'key> {
         'key: 'value,
         'value: 'borrow,
         V: ValueTrait + 'value,
-        V::Key: Hash + Eq + Borrow<str> + From<Cow<'key, str>>,
+        V::Key: Hash + Eq + Ord + Borrow<str> + From<Cow<'key, str>>,
     {
         if !target.is_object() {
             return Err(Error::NotAnObject(target.value_type()));
         }
 
-        Ok(target.as_object_mut().and_then(|m| {
-            match m
-                .raw_entry_mut()
-                .from_key_hashed_nocheck(self.hash, &self.key)
-            {
-                RawEntryMut::Occupied(mut e) => Some(e.insert(value)),
-                RawEntryMut::Vacant(e) => {
-                    e.insert_hashed_nocheck(self.hash, self.key.clone().into(), value);
-                    None
-                }
-            }
-        }))
+        Ok(target.as_object_mut().and_then(|m| insert(m, self, value)))
+    }
+
+    /// Removes this key from a `Value`, returns the removed value or None
+    /// if the key wasn't present or `target` isn't an object
+    ///
+    /// ```rust
+    /// use simd_json::*;
+    /// let mut object = json!({
+    ///   "answer": 42,
+    ///   "key": 7
+    /// });
+    /// let known_key = KnownKey::from("answer");
+    ///
+    /// assert_eq!(known_key.remove(&mut object), Some(OwnedValue::from(42)));
+    /// assert_eq!(known_key.remove(&mut object), None);
+    /// assert_eq!(object, json!({"key": 7}));
+    /// ```
+    #[inline]
+    pub fn remove<'borrow, 'value, V>(&self, target: &'borrow mut V) -> Option<V>
+    where
+        'key: 'value,
+        'value: 'borrow,
+        V: ValueTrait + 'value,
+        V::Key: Hash + Eq + Ord + Borrow<str>,
+    {
+        target.as_object_mut().and_then(|m| remove(m, self))
+    }
+}
+
+// The lookups of known keys for the hash map backends, which skip hashing
+// the key by using the memorized hash
+
+#[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+fn get<'m, K, V>(m: &'m ObjectMap<K, V>, known: &KnownKey) -> Option<&'m V>
+where
+    K: Hash + Eq + Borrow<str>,
+{
+    m.raw_entry()
+        .from_key_hashed_nocheck(known.hash, known.key())
+        .map(|kv| kv.1)
+}
+
+#[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+fn get_mut<'m, K, V>(m: &'m mut ObjectMap<K, V>, known: &KnownKey) -> Option<&'m mut V>
+where
+    K: Hash + Eq + Borrow<str>,
+{
+    match m
+        .raw_entry_mut()
+        .from_key_hashed_nocheck(known.hash, known.key())
+    {
+        RawEntryMut::Occupied(e) => Some(e.into_mut()),
+        RawEntryMut::Vacant(_e) => None,
+    }
+}
+
+#[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+fn get_or_insert_with<'m, 'key, K, V, F>(
+    m: &'m mut ObjectMap<K, V>,
+    known: &KnownKey<'key>,
+    with: F,
+) -> &'m mut V
+where
+    K: Hash + Eq + Borrow<str> + From<Cow<'key, str>>,
+    F: FnOnce() -> V,
+{
+    m.raw_entry_mut()
+        .from_key_hashed_nocheck(known.hash, known.key())
+        .or_insert_with(|| (known.key.clone().into(), with()))
+        .1
+}
+
+#[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+fn insert<'key, K, V>(m: &mut ObjectMap<K, V>, known: &KnownKey<'key>, value: V) -> Option<V>
+where
+    K: Hash + Eq + Borrow<str> + From<Cow<'key, str>>,
+{
+    match m
+        .raw_entry_mut()
+        .from_key_hashed_nocheck(known.hash, known.key())
+    {
+        RawEntryMut::Occupied(mut e) => Some(e.insert(value)),
+        RawEntryMut::Vacant(e) => {
+            e.insert_hashed_nocheck(known.hash, known.key.clone().into(), value);
+            None
+        }
+    }
+}
+
+#[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+fn remove<K, V>(m: &mut ObjectMap<K, V>, known: &KnownKey) -> Option<V>
+where
+    K: Hash + Eq + Borrow<str>,
+{
+    match m
+        .raw_entry_mut()
+        .from_key_hashed_nocheck(known.hash, known.key())
+    {
+        #[cfg(not(feature = "preserve-order"))]
+        RawEntryMut::Occupied(e) => Some(e.remove()),
+        // keep the order of the remaining members
+        #[cfg(feature = "preserve-order")]
+        RawEntryMut::Occupied(e) => Some(e.shift_remove()),
+        RawEntryMut::Vacant(_e) => None,
+    }
+}
+
+// A `BTreeMap` has no use for the hash, known keys are looked up like any
+// other key
+
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+fn get<'m, K, V>(m: &'m ObjectMap<K, V>, known: &KnownKey) -> Option<&'m V>
+where
+    K: Ord + Borrow<str>,
+{
+    m.get(known.key())
+}
+
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+fn get_mut<'m, K, V>(m: &'m mut ObjectMap<K, V>, known: &KnownKey) -> Option<&'m mut V>
+where
+    K: Ord + Borrow<str>,
+{
+    m.get_mut(known.key())
+}
+
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+fn get_or_insert_with<'m, 'key, K, V, F>(
+    m: &'m mut ObjectMap<K, V>,
+    known: &KnownKey<'key>,
+    with: F,
+) -> &'m mut V
+where
+    K: Ord + Borrow<str> + From<Cow<'key, str>>,
+    F: FnOnce() -> V,
+{
+    m.entry(known.key.clone().into()).or_insert_with(with)
+}
+
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+fn insert<'key, K, V>(m: &mut ObjectMap<K, V>, known: &KnownKey<'key>, value: V) -> Option<V>
+where
+    K: Ord + Borrow<str> + From<Cow<'key, str>>,
+{
+    match m.get_mut(known.key()) {
+        Some(old) => Some(std::mem::replace(old, value)),
+        None => {
+            m.insert(known.key.clone().into(), value);
+            None
+        }
     }
 }
 
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+fn remove<K, V>(m: &mut ObjectMap<K, V>, known: &KnownKey) -> Option<V>
+where
+    K: Ord + Borrow<str>,
+{
+    m.remove(known.key())
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unnecessary_operation, clippy::non_ascii_literal)]
     use super::*;
     use crate::borrowed::*;
-    use crate::{BorrowedValue, ValueTrait};
+    use crate::{BorrowedValue, StaticNode, ValueAccess};
 
     #[test]
     fn known_key() {
         use std::borrow::Cow;
-        let mut o = Object::new();
+        let mut o = Object::default();
         o.insert("key".into(), 1.into());
         let key1 = KnownKey::from(Cow::Borrowed("key"));
         let key2 = KnownKey::from(Cow::Borrowed("cake"));
 
         let mut v = BorrowedValue::Object(o);
 
-        assert!(key1.lookup(&BorrowedValue::Null).is_none());
-        assert!(key2.lookup(&BorrowedValue::Null).is_none());
+        assert!(key1
+            .lookup(&BorrowedValue::Static(StaticNode::Null))
+            .is_none());
+        assert!(key2
+            .lookup(&BorrowedValue::Static(StaticNode::Null))
+            .is_none());
         assert!(key1.lookup(&v).is_some());
         assert!(key2.lookup(&v).is_none());
         assert!(key1.lookup_mut(&mut v).is_some());
@@ -246,14 +418,14 @@ mod tests {
     #[test]
     fn known_key_insert() {
         use std::borrow::Cow;
-        let mut o = Object::new();
+        let mut o = Object::default();
         o.insert("key".into(), 1.into());
         let key1 = KnownKey::from(Cow::Borrowed("key"));
         let key2 = KnownKey::from(Cow::Borrowed("cake"));
 
         let mut v = BorrowedValue::Object(o);
 
-        let mut v1 = BorrowedValue::Null;
+        let mut v1 = BorrowedValue::Static(StaticNode::Null);
         assert!(key1.insert(&mut v1, 2.into()).is_err());
         assert!(key2.insert(&mut v1, 2.into()).is_err());
         assert_eq!(key1.insert(&mut v, 2.into()).unwrap(), Some(1.into()));
@@ -265,14 +437,14 @@ mod tests {
     #[test]
     fn lookup_or_insert_mut() {
         use std::borrow::Cow;
-        let mut o = Object::new();
+        let mut o = Object::default();
         o.insert("key".into(), 1.into());
         let key1 = KnownKey::from(Cow::Borrowed("key"));
         let key2 = KnownKey::from(Cow::Borrowed("cake"));
 
         let mut v = BorrowedValue::Object(o);
 
-        let mut v1 = BorrowedValue::Null;
+        let mut v1 = BorrowedValue::Static(StaticNode::Null);
         assert!(key1.lookup_or_insert_mut(&mut v1, || 2.into()).is_err());
         assert!(key2.lookup_or_insert_mut(&mut v1, || 2.into()).is_err());
 
@@ -288,7 +460,8 @@ mod tests {
     #[test]
     fn known_key_map() {
         use std::borrow::Cow;
-        let mut o = Object::with_capacity(128);
+        let mut o = crate::value::object_with_capacity(128);
+        #[cfg(not(any(feature = "preserve-order", feature = "sorted-objects")))]
         assert!(o.is_map());
         let key1 = KnownKey::from(Cow::Borrowed("key"));
         let key2 = KnownKey::from(Cow::Borrowed("cake"));
@@ -296,8 +469,12 @@ mod tests {
         o.insert("key".into(), 1.into());
         let v = BorrowedValue::Object(o);
 
-        assert!(key1.lookup(&BorrowedValue::Null).is_none());
-        assert!(key2.lookup(&BorrowedValue::Null).is_none());
+        assert!(key1
+            .lookup(&BorrowedValue::Static(StaticNode::Null))
+            .is_none());
+        assert!(key2
+            .lookup(&BorrowedValue::Static(StaticNode::Null))
+            .is_none());
         assert!(key1.lookup(&v).is_some());
         assert!(key2.lookup(&v).is_none());
     }
@@ -305,14 +482,14 @@ mod tests {
     #[test]
     fn known_key_insert_map() {
         use std::borrow::Cow;
-        let mut o = Object::with_capacity(128);
+        let mut o = crate::value::object_with_capacity(128);
         o.insert("key".into(), 1.into());
         let key1 = KnownKey::from(Cow::Borrowed("key"));
         let key2 = KnownKey::from(Cow::Borrowed("cake"));
 
         let mut v = BorrowedValue::Object(o);
 
-        let mut v1 = BorrowedValue::Null;
+        let mut v1 = BorrowedValue::Static(StaticNode::Null);
         assert!(key1.insert(&mut v1, 2.into()).is_err());
         assert!(key2.insert(&mut v1, 2.into()).is_err());
         assert_eq!(key1.insert(&mut v, 2.into()).unwrap(), Some(1.into()));
@@ -320,4 +497,23 @@ mod tests {
         assert_eq!(v["key"], 2);
         assert_eq!(v["cake"], 3);
     }
+
+    #[test]
+    fn known_key_remove() {
+        let key1 = KnownKey::from("key");
+        for capacity in &[0, 128] {
+            let mut o = crate::value::object_with_capacity(*capacity);
+            o.insert("key".into(), 1.into());
+            o.insert("cake".into(), 2.into());
+            let mut v = BorrowedValue::Object(o);
+
+            assert!(key1
+                .remove(&mut BorrowedValue::Static(StaticNode::Null))
+                .is_none());
+            assert_eq!(key1.remove(&mut v), Some(1.into()));
+            assert!(key1.remove(&mut v).is_none());
+            assert!(key1.lookup(&v).is_none());
+            assert_eq!(v["cake"], 2);
+        }
+    }
 }
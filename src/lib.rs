@@ -107,19 +107,52 @@ extern crate serde as serde_ext;
 pub mod serde;
 
 mod charutils;
+mod columnar;
 #[macro_use]
 mod macros;
+#[cfg(feature = "actix")]
+mod actix;
+#[cfg(feature = "axum")]
+mod axum;
+#[cfg(feature = "bytes")]
+mod buf;
+mod diagnostics;
 mod error;
+mod escape;
+mod feed;
+#[cfg(feature = "ffi")]
+/// A C API for the parser
+pub mod ffi;
+mod hook;
+mod load;
+/// Reading newline delimited JSON and JSON text sequences
+pub mod ndjson;
 mod numberparse;
+mod options;
 mod parsedjson;
+mod parser;
+mod pointer;
+/// Traits and types to work with the DOM values
+pub mod prelude;
+mod projection;
+mod recover;
+mod reformat;
+#[cfg(feature = "schema")]
+/// JSON Schema validation
+pub mod schema;
+#[cfg(feature = "spans")]
+mod spans;
 mod stringparse;
+mod validate;
+#[cfg(any(feature = "axum", feature = "actix"))]
+mod web;
 
 #[cfg(target_feature = "avx2")]
 mod avx2;
 #[cfg(target_feature = "avx2")]
 pub use crate::avx2::deser::*;
 #[cfg(target_feature = "avx2")]
-use crate::avx2::stage1::SIMDJSON_PADDING;
+use crate::avx2::stage1::{is_valid_utf8, SIMDJSON_PADDING};
 
 #[cfg(all(
     any(target_arch = "x86", target_arch = "x86_64"),
@@ -135,26 +168,73 @@ pub use crate::sse42::deser::*;
     any(target_arch = "x86", target_arch = "x86_64"),
     not(target_feature = "avx2")
 ))]
-use crate::sse42::stage1::SIMDJSON_PADDING;
+use crate::sse42::stage1::{is_valid_utf8, SIMDJSON_PADDING};
 
 #[cfg(all(target_feature = "neon", feature = "neon"))]
 mod neon;
 #[cfg(all(target_feature = "neon", feature = "neon"))]
 pub use crate::neon::deser::*;
 #[cfg(all(target_feature = "neon", feature = "neon"))]
-use crate::neon::stage1::SIMDJSON_PADDING;
+use crate::neon::stage1::{is_valid_utf8, SIMDJSON_PADDING};
 
 mod stage2;
+mod tape;
 /// simd-json JSON-DOM value
 pub mod value;
+mod writer;
 
-use crate::numberparse::Number;
-#[cfg(not(target_feature = "neon"))]
+use crate::parser::Buffers;
 use std::mem;
 use std::str;
 
-pub use crate::error::{Error, ErrorType};
+#[cfg(all(feature = "bytes", feature = "serde_impl"))]
+pub use crate::buf::from_buf_as;
+#[cfg(feature = "bytes")]
+pub use crate::buf::{from_buf, from_buf_with_options};
+pub use crate::columnar::{to_f64_vec, to_i64_vec};
+pub use crate::diagnostics::{
+    to_borrowed_value_with_diagnostics, to_owned_value_with_diagnostics, Diagnostic, DiagnosticKind,
+};
+pub use crate::error::{Error, ErrorCode, ErrorType};
+pub use crate::escape::{escape_str_into, unescape_str};
+pub use crate::feed::FeedParser;
+#[cfg(feature = "metrics")]
+pub use crate::hook::{set_parse_hook, ParseHook};
+#[cfg(feature = "serde_impl")]
+pub use crate::load::load_as;
+#[cfg(feature = "gzip")]
+pub use crate::load::{from_gzip_reader, from_gzip_reader_with_options};
+#[cfg(feature = "zstd")]
+pub use crate::load::{from_zstd_reader, from_zstd_reader_with_options};
+pub use crate::load::{load, load_with_options};
+pub use crate::options::{
+    BigNumberBehavior, CompactOptions, FieldMatching, FloatFormat, KeyCase, ParserOptions,
+    RedactMode, Redaction, SerializeOptions, TrailingData, Truncation, DEFAULT_MAX_DEPTH,
+};
+pub use crate::parser::{with_parser, Parser};
+pub use crate::pointer::exists;
+#[cfg(feature = "serde_impl")]
+pub use crate::pointer::get;
+pub use crate::projection::Projection;
+pub use crate::recover::{
+    to_owned_value_recovering, to_owned_value_recovering_with_options, Recovered,
+};
+pub use crate::reformat::{minify, prettify};
+#[cfg(feature = "serde_impl")]
+pub use crate::serde::{from_value, to_value};
+#[cfg(feature = "spans")]
+pub use crate::spans::{
+    to_borrowed_value_with_spans, to_borrowed_value_with_spans_and_options,
+    to_owned_value_with_spans, to_owned_value_with_spans_and_options, Spans,
+};
+pub use crate::tape::Tape;
+pub use crate::validate::{
+    find_structural_indexes, validate, validate_utf8, validate_with_options,
+};
 pub use crate::value::*;
+#[cfg(any(feature = "axum", feature = "actix"))]
+pub use crate::web::SimdJson;
+pub use crate::writer::JsonWriter;
 
 /// simd-json Result type
 pub type Result<T> = std::result::Result<T, Error>;
@@ -164,7 +244,24 @@ mod known_key;
 #[cfg(feature = "known-key")]
 pub use known_key::{Error as KnownKeyError, KnownKey};
 
-pub(crate) struct Deserializer<'de> {
+/// The parser, this is a `serde::Deserializer` for typed deserialization
+/// (with the `serde_impl` feature), including stateful deserialization using
+/// `serde::de::DeserializeSeed`.
+///
+/// ```
+/// # #[cfg(feature = "serde_impl")] {
+/// use serde::Deserialize;
+/// let mut d = br#"{"id": 1}"#.to_vec();
+/// let mut de = simd_json::Deserializer::from_slice(&mut d).unwrap();
+/// #[derive(Deserialize)]
+/// struct Doc {
+///     id: u8,
+/// }
+/// let doc = Doc::deserialize(&mut de).unwrap();
+/// assert_eq!(doc.id, 1);
+/// # }
+/// ```
+pub struct Deserializer<'de> {
     // This string starts with the input data and characters are truncated off
     // the beginning as data is parsed.
     input: &'de mut [u8],
@@ -175,6 +272,7 @@ pub(crate) struct Deserializer<'de> {
     counts: Vec<usize>,
     str_offset: usize,
     iidx: usize,
+    options: ParserOptions,
 }
 
 impl<'de> Deserializer<'de> {
@@ -186,56 +284,151 @@ impl<'de> Deserializer<'de> {
     // That way basic use cases are satisfied by something like
     // `serde_json::from_str(...)` while advanced use cases that require a
     // deserializer can make one with `serde_json::Deserializer::from_str(...)`.
+    /// Runs the first stage of the parser over `input`, the slice will be
+    /// rewritten while deserializing.
     pub fn from_slice(input: &'de mut [u8]) -> Result<Self> {
-        // We have to pick an initial size of the structural indexes.
-        // 6 is a heuristic that seems to work well for the benchmark
-        // data and limit re-allocation frequency.
+        Self::from_slice_with_options(input, &ParserOptions::default())
+    }
 
+    /// Same as `from_slice` but allows tuning the parser using `ParserOptions`.
+    pub fn from_slice_with_options(input: &'de mut [u8], options: &ParserOptions) -> Result<Self> {
+        Self::from_slice_with_buffers(input, options, &mut Buffers::default())
+    }
+
+    // Same as `from_slice_with_options` but takes the buffers of the parser
+    // from `buffers`, they can be given back with `into_buffers`.
+    pub(crate) fn from_slice_with_buffers(
+        input: &'de mut [u8],
+        options: &ParserOptions,
+        buffers: &mut Buffers,
+    ) -> Result<Self> {
         let len = input.len();
+        if unlikely!(len > options.max_document_size) {
+            return Err(Error::generic(ErrorType::InputTooLarge));
+        }
+        let range = options.content_range(input);
+        let input = &mut input[range];
+        hook::document(input.len());
 
-        let buf_start: usize = input.as_ptr() as *const () as usize;
-        let needs_relocation = (buf_start + input.len()) % page_size::get() < SIMDJSON_PADDING;
+        if let Err(t) = Deserializer::find_structural_indexes_into(
+            input,
+            &mut buffers.structural_indexes,
+            &mut buffers.copy,
+        ) {
+            let e = Error::generic(t);
+            hook::error(&e);
+            return Err(e);
+        }
 
-        let s1_result: std::result::Result<Vec<u32>, ErrorType> = if needs_relocation {
-            let mut data: Vec<u8> = Vec::with_capacity(len + SIMDJSON_PADDING);
-            unsafe {
-                data.set_len(len + 1);
-                data.as_mut_slice()
-                    .get_unchecked_mut(0..len)
-                    .clone_from_slice(input);
-                *(data.get_unchecked_mut(len)) = 0;
-                data.set_len(len);
-                Deserializer::find_structural_bits(&data)
-            }
-        } else {
-            unsafe { Deserializer::find_structural_bits(input) }
-        };
-        let structural_indexes = match s1_result {
-            Ok(i) => i,
-            Err(t) => {
-                return Err(Error::generic(t));
-            }
+        Self::from_buffers(input, buffers, options)
+    }
+
+    // Runs stage 2 on the input using structural indexes previously
+    // computed by `find_structural_indexes`, this allows stage 1 to run
+    // elsewhere (e.g. on another thread) ahead of time.
+    pub(crate) fn from_structural_indexes(
+        input: &'de mut [u8],
+        structural_indexes: Vec<u32>,
+        options: &ParserOptions,
+    ) -> Result<Self> {
+        hook::document(input.len());
+        let mut buffers = Buffers {
+            structural_indexes,
+            ..Buffers::default()
         };
+        Self::from_buffers(input, &mut buffers, options)
+    }
 
-        let counts = Deserializer::validate(input, &structural_indexes)?;
+    // Runs stage 2 on the input using the structural indexes in `buffers`
+    fn from_buffers(
+        input: &'de mut [u8],
+        buffers: &mut Buffers,
+        options: &ParserOptions,
+    ) -> Result<Self> {
+        let len = input.len();
+        if let Err(e) = Deserializer::validate_into(
+            input,
+            &buffers.structural_indexes,
+            options,
+            &mut buffers.counts,
+        ) {
+            hook::error(&e);
+            return Err(e);
+        }
 
         // Set length to allow slice access in ARM code
-        let mut strings = Vec::with_capacity(len + SIMDJSON_PADDING);
+        let mut strings = mem::take(&mut buffers.strings);
+        strings.clear();
+        strings.reserve(len + SIMDJSON_PADDING);
         unsafe {
             strings.set_len(len + SIMDJSON_PADDING);
         }
 
         Ok(Deserializer {
-            counts,
-            structural_indexes,
+            counts: mem::take(&mut buffers.counts),
+            structural_indexes: mem::take(&mut buffers.structural_indexes),
             input,
             idx: 0,
             strings,
             str_offset: 0,
             iidx: 0,
+            options: options.clone(),
         })
     }
 
+    // Gives the buffers taken by `from_slice_with_buffers` back
+    pub(crate) fn into_buffers(self, buffers: &mut Buffers) {
+        buffers.structural_indexes = self.structural_indexes;
+        buffers.counts = self.counts;
+        buffers.strings = self.strings;
+    }
+
+    // Runs stage 1 on the input, the input is copied first if reading
+    // past its end could cross into the next page.
+    pub(crate) fn find_structural_indexes(
+        input: &[u8],
+    ) -> std::result::Result<Vec<u32>, ErrorType> {
+        let mut structural_indexes = Vec::new();
+        Self::find_structural_indexes_into(input, &mut structural_indexes, &mut Vec::new())?;
+        Ok(structural_indexes)
+    }
+
+    // Structural indexes are `u32`s, offsets in larger inputs would wrap
+    // around.
+    #[inline]
+    fn check_input_len(len: usize) -> std::result::Result<(), ErrorType> {
+        if unlikely!(len > u32::max_value() as usize) {
+            Err(ErrorType::InputTooLarge)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Same as `find_structural_indexes` but writes the indexes into
+    // `structural_indexes`, `copy` holds the relocated input if needed.
+    pub(crate) fn find_structural_indexes_into(
+        input: &[u8],
+        structural_indexes: &mut Vec<u32>,
+        copy: &mut Vec<u8>,
+    ) -> std::result::Result<(), ErrorType> {
+        let len = input.len();
+        trace_span!("stage1", bytes = len);
+        stry!(Self::check_input_len(len));
+        let buf_start: usize = input.as_ptr() as *const () as usize;
+        let needs_relocation = (buf_start + len) % page_size::get() < SIMDJSON_PADDING;
+
+        if needs_relocation {
+            copy.clear();
+            copy.reserve(len + SIMDJSON_PADDING);
+            copy.extend_from_slice(input);
+            copy.push(0);
+            copy.truncate(len);
+            unsafe { Deserializer::find_structural_bits(copy, structural_indexes) }
+        } else {
+            unsafe { Deserializer::find_structural_bits(input, structural_indexes) }
+        }
+    }
+
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn skip(&mut self) {
         self.idx += 1;
@@ -267,6 +460,17 @@ impl<'de> Deserializer<'de> {
         unsafe { *self.counts.get_unchecked(self.idx) }
     }
 
+    // Parses a string and enforces the configured maximum string length
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn parse_str(&mut self) -> Result<&'de str> {
+        let s = stry!(self.parse_str_());
+        if unlikely!(s.len() > self.options.max_string_length) {
+            Err(self.error(ErrorType::StringTooLong))
+        } else {
+            Ok(s)
+        }
+    }
+
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn parse_number_root(&mut self, minus: bool) -> Result<Number> {
         let input = unsafe { &self.input.get_unchecked(self.iidx..) };
@@ -279,11 +483,18 @@ impl<'de> Deserializer<'de> {
         self.parse_number_int(&copy, minus)
     }
 
+    // The number parser reads up to 16 bytes past the end of a number, so
+    // numbers that end close to the end of the input are copied first. A
+    // number ends before the next structural character, if there is one.
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn parse_number(&mut self, minus: bool) -> Result<Number> {
         let input = unsafe { &self.input.get_unchecked(self.iidx..) };
         let len = input.len();
-        if len < SIMDJSON_PADDING {
+        let end = self
+            .structural_indexes
+            .get(self.idx + 1)
+            .map_or(self.input.len(), |i| *i as usize);
+        if self.input.len() - end < SIMDJSON_PADDING {
             let mut copy = vec![0_u8; len + SIMDJSON_PADDING];
             unsafe {
                 copy.as_mut_ptr().copy_from(input.as_ptr(), len);
@@ -294,10 +505,34 @@ impl<'de> Deserializer<'de> {
         }
     }
 
+    // Validates the number at the current position and returns it as it
+    // appears in the input, integers too large for an `i64` are accepted.
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_number_(&mut self, minus: bool) -> Result<Number> {
-        let input = unsafe { &self.input.get_unchecked(self.iidx..) };
-        self.parse_number_int(input, minus)
+    fn parse_number_raw(&mut self, minus: bool, root: bool) -> Result<&'de str> {
+        let r = if root {
+            self.parse_number_root(minus)
+        } else {
+            self.parse_number(minus)
+        };
+        match r {
+            // overflows are only reported for otherwise valid integers
+            Err(ref e) if e.error() == &ErrorType::Overflow => (),
+            Err(e) => return Err(e),
+            Ok(_) => (),
+        }
+        let start = self.iidx;
+        let len = unsafe { self.input.get_unchecked(start..) }
+            .iter()
+            .take_while(|c| match c {
+                b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E' => true,
+                _ => false,
+            })
+            .count();
+        // numbers are ASCII only
+        unsafe {
+            let v = self.input.get_unchecked(start..start + len) as *const [u8] as *const str;
+            Ok(&*v)
+        }
     }
 }
 
@@ -307,7 +542,7 @@ mod tests {
     use super::serde::from_slice;
     use super::{
         owned::to_value, owned::Object, owned::Value, to_borrowed_value, to_owned_value,
-        Deserializer,
+        Deserializer, ErrorType, StaticNode,
     };
     use halfbrown::HashMap;
     use proptest::prelude::*;
@@ -366,6 +601,59 @@ mod tests {
         assert!(v_serde.is_err());
     }
 
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn input_len_limit() {
+        let max = u32::MAX as usize;
+        assert_eq!(Deserializer::check_input_len(max), Ok(()));
+        assert_eq!(
+            Deserializer::check_input_len(max + 1),
+            Err(ErrorType::InputTooLarge)
+        );
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    #[ignore]
+    fn larger_than_4gib() {
+        // ignored since it allocates 4 GiB, `input_len_limit` covers the
+        // check itself. Zeroed memory is mapped lazily, the input is
+        // rejected before it is read
+        let mut d = vec![0_u8; u32::max_value() as usize + 1];
+        let e = Deserializer::from_slice(&mut d).err().expect("too large");
+        assert_eq!(e.error(), &ErrorType::InputTooLarge);
+    }
+
+    #[test]
+    fn unpadded_input() {
+        // values at the very end of an allocation without any slack, tools
+        // like miri or valgrind flag reads past it
+        let docs = [
+            "[true]",
+            "[false]",
+            "{\"a\":null}",
+            "[12345678901234567]",
+            "[1.25e3]",
+            "[\"\\u00fc\\ud83d\\ude00\"]",
+            "123456789012345678",
+            "null",
+        ];
+        for doc in &docs {
+            let expected: serde_json::Value = serde_json::from_str(doc).expect("valid");
+            let mut d = doc.as_bytes().to_vec().into_boxed_slice();
+            let v = to_owned_value(&mut d).expect("valid");
+            assert_eq!(serde_json::to_value(v).expect("valid"), expected);
+            let mut d = doc.as_bytes().to_vec().into_boxed_slice();
+            let v = to_borrowed_value(&mut d).expect("valid");
+            assert_eq!(serde_json::to_value(v).expect("valid"), expected);
+            let mut d = doc.as_bytes().to_vec().into_boxed_slice();
+            let v: serde_json::Value = from_slice(&mut d).expect("valid");
+            assert_eq!(v, expected);
+        }
+        let mut d = b"[\"\\u00\"]".to_vec().into_boxed_slice();
+        assert!(to_owned_value(&mut d).is_err());
+    }
+
     #[test]
     fn bool_true() {
         let mut d = String::from("true");
@@ -401,7 +689,7 @@ mod tests {
         let v_serde: serde_json::Value = serde_json::from_slice(d).expect("");
         let v_simd: serde_json::Value = from_slice(&mut d).expect("");
         assert_eq!(v_simd, v_serde);
-        assert_eq!(to_value(&mut d1), Ok(Value::Null));
+        assert_eq!(to_value(&mut d1), Ok(Value::Static(StaticNode::Null)));
     }
 
     #[test]
@@ -584,8 +872,8 @@ mod tests {
             to_value(&mut d1),
             Ok(Value::Array(vec![
                 Value::Array(vec![]),
-                Value::Null,
-                Value::Null,
+                Value::Static(StaticNode::Null),
+                Value::Static(StaticNode::Null),
             ]))
         );
         assert_eq!(v_simd, v_serde);
@@ -705,8 +993,8 @@ mod tests {
         assert_eq!(
             to_value(&mut d1),
             Ok(Value::Array(vec![
-                Value::Object(Object::new()),
-                Value::Null
+                Value::Object(Object::default()),
+                Value::Static(StaticNode::Null)
             ]))
         );
     }
@@ -726,7 +1014,7 @@ mod tests {
         let mut d1 = d.clone();
         let mut d1 = unsafe { d1.as_bytes_mut() };
         let mut d = unsafe { d.as_bytes_mut() };
-        assert_eq!(to_value(&mut d1), Ok(Value::Null));
+        assert_eq!(to_value(&mut d1), Ok(Value::Static(StaticNode::Null)));
         let v_serde: serde_json::Value = serde_json::from_slice(d).expect("");
         let v_simd: serde_json::Value = from_slice(&mut d).expect("");
         assert_eq!(v_simd, v_serde);
@@ -739,7 +1027,10 @@ mod tests {
         let mut d = unsafe { d.as_bytes_mut() };
         assert_eq!(
             to_value(&mut d1),
-            Ok(Value::Array(vec![Value::Null, Value::Null,]))
+            Ok(Value::Array(vec![
+                Value::Static(StaticNode::Null),
+                Value::Static(StaticNode::Null),
+            ]))
         );
         let v_serde: serde_json::Value = serde_json::from_slice(d).expect("");
         let v_simd: serde_json::Value = from_slice(&mut d).expect("");
@@ -755,8 +1046,8 @@ mod tests {
         assert_eq!(
             to_value(&mut d1),
             Ok(Value::Array(vec![Value::Array(vec![
-                Value::Null,
-                Value::Null,
+                Value::Static(StaticNode::Null),
+                Value::Static(StaticNode::Null),
             ])]))
         );
 
@@ -777,8 +1068,8 @@ mod tests {
         assert_eq!(
             to_value(&mut d1),
             Ok(Value::Array(vec![Value::Array(vec![Value::Array(vec![
-                Value::Null,
-                Value::Null,
+                Value::Static(StaticNode::Null),
+                Value::Static(StaticNode::Null),
             ])])]))
         );
     }
@@ -839,7 +1130,7 @@ mod tests {
         let v_serde: serde_json::Value = serde_json::from_slice(d).expect("");
         let v_simd: serde_json::Value = from_slice(&mut d).expect("");
         assert_eq!(v_simd, v_serde);
-        let mut h = Object::new();
+        let mut h = Object::default();
         h.insert("snot".into(), Value::from("badger"));
         assert_eq!(to_value(&mut d1), Ok(Value::Object(h)));
     }
@@ -853,7 +1144,7 @@ mod tests {
         let v_serde: serde_json::Value = serde_json::from_slice(d).expect("");
         let v_simd: serde_json::Value = from_slice(&mut d).expect("");
         assert_eq!(v_simd, v_serde);
-        let mut h = Object::new();
+        let mut h = Object::default();
         h.insert("snot".into(), Value::from("badger"));
         h.insert("badger".into(), Value::from("snot"));
         assert_eq!(to_value(&mut d1), Ok(Value::Object(h)));
@@ -1115,8 +1406,8 @@ mod tests {
     //6.576692109929364e305
     fn arb_json() -> BoxedStrategy<String> {
         let leaf = prop_oneof![
-            Just(Value::Null),
-            any::<bool>().prop_map(Value::Bool),
+            Just(Value::Static(StaticNode::Null)),
+            any::<bool>().prop_map(Value::from),
             // (-1.0e306f64..1.0e306f64).prop_map(|f| json!(f)), // The float parsing of simd and serde are too different
             any::<i64>().prop_map(|i| json!(i)),
             ".*".prop_map(Value::from),
@@ -1139,8 +1430,8 @@ mod tests {
 
     fn arb_json_value() -> BoxedStrategy<Value> {
         let leaf = prop_oneof![
-            Just(Value::Null),
-            any::<bool>().prop_map(Value::Bool),
+            Just(Value::Static(StaticNode::Null)),
+            any::<bool>().prop_map(Value::from),
             //(-1.0e306f64..1.0e306f64).prop_map(|f| json!(f)), // damn you float!
             any::<i64>().prop_map(|i| json!(i)),
             ".*".prop_map(Value::from),
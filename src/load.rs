@@ -0,0 +1,180 @@
+use crate::value::owned::to_value_with_options;
+use crate::{Error, ErrorType, OwnedValue, ParserOptions, Result, SIMDJSON_PADDING};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// Reads the whole file into a buffer with room for the padding stage 1
+// reads past the end of the input, so the input doesn't need to be
+// relocated before parsing.
+fn read_padded(path: &Path, options: &ParserOptions) -> Result<Vec<u8>> {
+    let io_error = Error::io;
+    let mut file = File::open(path).map_err(io_error)?;
+    let len = file.metadata().map_err(io_error)?.len();
+    if len > options.max_document_size as u64 {
+        return Err(Error::generic(ErrorType::InputTooLarge));
+    }
+    let mut buffer = Vec::with_capacity(len as usize + SIMDJSON_PADDING);
+    file.read_to_end(&mut buffer).map_err(io_error)?;
+    Ok(buffer)
+}
+
+// Reads everything from `reader` into a buffer, failing as soon as it
+// grows larger than the maximum document size.
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+fn read_all<R: Read>(reader: R, options: &ParserOptions) -> Result<Vec<u8>> {
+    let limit = (options.max_document_size as u64).saturating_add(1);
+    let mut buffer = Vec::new();
+    reader
+        .take(limit)
+        .read_to_end(&mut buffer)
+        .map_err(Error::io)?;
+    if buffer.len() > options.max_document_size {
+        return Err(Error::generic(ErrorType::InputTooLarge));
+    }
+    Ok(buffer)
+}
+
+/// Decompresses gzip compressed input from `reader` and parses the JSON
+/// document in it. Streams of multiple gzip members, as written by
+/// appending to a compressed file, are read as one.
+///
+/// ```no_run
+/// let file = std::fs::File::open("data/twitter.json.gz").expect("file");
+/// let v = simd_json::from_gzip_reader(file).expect("valid file");
+/// ```
+#[cfg(feature = "gzip")]
+pub fn from_gzip_reader<R: Read>(reader: R) -> Result<OwnedValue> {
+    from_gzip_reader_with_options(reader, &ParserOptions::default())
+}
+
+/// Same as `from_gzip_reader` but allows tuning the parser using
+/// `ParserOptions`, `max_document_size` limits the decompressed size.
+#[cfg(feature = "gzip")]
+pub fn from_gzip_reader_with_options<R: Read>(
+    reader: R,
+    options: &ParserOptions,
+) -> Result<OwnedValue> {
+    let mut buffer = read_all(flate2::read::MultiGzDecoder::new(reader), options)?;
+    to_value_with_options(&mut buffer, options)
+}
+
+/// Decompresses zstd compressed input from `reader` and parses the JSON
+/// document in it.
+#[cfg(feature = "zstd")]
+pub fn from_zstd_reader<R: Read>(reader: R) -> Result<OwnedValue> {
+    from_zstd_reader_with_options(reader, &ParserOptions::default())
+}
+
+/// Same as `from_zstd_reader` but allows tuning the parser using
+/// `ParserOptions`, `max_document_size` limits the decompressed size.
+#[cfg(feature = "zstd")]
+pub fn from_zstd_reader_with_options<R: Read>(
+    reader: R,
+    options: &ParserOptions,
+) -> Result<OwnedValue> {
+    let decoder = zstd::stream::read::Decoder::new(reader).map_err(Error::io)?;
+    let mut buffer = read_all(decoder, options)?;
+    to_value_with_options(&mut buffer, options)
+}
+
+/// Reads and parses the JSON document in the file at `path`.
+///
+/// ```no_run
+/// let v = simd_json::load("data/twitter.json").expect("valid file");
+/// ```
+pub fn load<P: AsRef<Path>>(path: P) -> Result<OwnedValue> {
+    load_with_options(path, &ParserOptions::default())
+}
+
+/// Same as `load` but allows tuning the parser using `ParserOptions`.
+pub fn load_with_options<P: AsRef<Path>>(path: P, options: &ParserOptions) -> Result<OwnedValue> {
+    let mut buffer = read_padded(path.as_ref(), options)?;
+    to_value_with_options(&mut buffer, options)
+}
+
+/// Reads the file at `path` and deserializes it into `T` using serde.
+#[cfg(feature = "serde_impl")]
+pub fn load_as<T, P>(path: P) -> Result<T>
+where
+    T: serde_ext::de::DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let options = ParserOptions::default();
+    let mut buffer = read_padded(path.as_ref(), &options)?;
+    crate::serde::from_slice_with_options(&mut buffer, &options)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValueAccess;
+
+    #[test]
+    fn load_file() {
+        let v = load("data/pass/pass02.json").expect("valid file");
+        assert!(v.is_array());
+        let e = load("data/fail/fail02.json").expect_err("invalid file");
+        assert!(!e.to_string().is_empty());
+        let e = load("data/does-not-exist.json").expect_err("missing file");
+        assert!(match e.error() {
+            ErrorType::IO(_) => true,
+            _ => false,
+        });
+        let options = ParserOptions {
+            max_document_size: 4,
+            ..ParserOptions::default()
+        };
+        let e = load_with_options("data/pass/pass02.json", &options).expect_err("too large");
+        assert_eq!(e.error(), &ErrorType::InputTooLarge);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+        let mut compressed = Vec::new();
+        for part in &[&b"{\"a\": "[..], b"[1, 2]}"] {
+            let mut e = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            e.write_all(part).expect("compressed");
+            compressed.extend(e.finish().expect("compressed"));
+        }
+        let v = from_gzip_reader(&compressed[..]).expect("valid input");
+        assert_eq!(v, json!({"a": [1, 2]}));
+        let options = ParserOptions {
+            max_document_size: 8,
+            ..ParserOptions::default()
+        };
+        let e = from_gzip_reader_with_options(&compressed[..], &options).expect_err("too large");
+        assert_eq!(e.error(), &ErrorType::InputTooLarge);
+        let e = from_gzip_reader(&b"not gzip"[..]).expect_err("invalid input");
+        assert!(match e.error() {
+            ErrorType::IO(_) => true,
+            _ => false,
+        });
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd() {
+        let compressed = zstd::encode_all(&br#"{"a": [1, 2]}"#[..], 3).expect("compressed");
+        let v = from_zstd_reader(&compressed[..]).expect("valid input");
+        assert_eq!(v, json!({"a": [1, 2]}));
+        let options = ParserOptions {
+            max_document_size: 8,
+            ..ParserOptions::default()
+        };
+        let e = from_zstd_reader_with_options(&compressed[..], &options).expect_err("too large");
+        assert_eq!(e.error(), &ErrorType::InputTooLarge);
+        assert!(from_zstd_reader(&b"not zstd"[..]).is_err());
+    }
+
+    #[cfg(feature = "serde_impl")]
+    #[test]
+    fn load_typed() {
+        let v: serde_json::Value = load_as("data/pass/pass02.json").expect("valid file");
+        assert!(v.is_array());
+        assert!(load_as::<Vec<String>, _>("data/fail/fail02.json").is_err());
+    }
+}
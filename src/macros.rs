@@ -264,15 +264,15 @@ macro_rules! json_internal {
     //////////////////////////////////////////////////////////////////////////
 
     (null) => {
-        $crate::value::owned::Value::Null
+        $crate::value::owned::Value::Static($crate::StaticNode::Null)
     };
 
     (true) => {
-        $crate::value::owned::Value::Bool(true)
+        $crate::value::owned::Value::Static($crate::StaticNode::Bool(true))
     };
 
     (false) => {
-        $crate::value::owned::Value::Bool(false)
+        $crate::value::owned::Value::Static($crate::StaticNode::Bool(false))
     };
 
     ([]) => {
@@ -284,12 +284,12 @@ macro_rules! json_internal {
     };
 
     ({}) => {
-        $crate::value::owned::Value::Object($crate::value::owned::Object::new())
+        $crate::value::owned::Value::Object($crate::value::owned::Object::default())
     };
 
     ({ $($tt:tt)+ }) => {
         $crate::value::owned::Value::Object({
-            let mut object = $crate::value::owned::Object::new();
+            let mut object = $crate::value::owned::Object::default();
             json_internal!(@object object () ($($tt)+) ($($tt)+));
             object
         })
@@ -408,6 +408,15 @@ macro_rules! stry {
     };
 }
 
+// Enters a tracing span for the rest of the enclosing block, with the
+// `tracing` feature.
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!($($args)*).entered();
+    };
+}
+
 #[cfg(test)]
 mod test {
     use crate::*;
@@ -415,12 +424,13 @@ mod test {
     #[test]
     fn array() {
         let v: OwnedValue = json!(vec![1]);
-        assert_eq!(OwnedValue::Array(vec![OwnedValue::I64(1)]), v);
+        assert_eq!(OwnedValue::Array(vec![OwnedValue::from(1)]), v);
     }
 
     #[test]
     fn obj() {
         let v: OwnedValue = json!(hashmap! {"test" => 1});
-        assert_eq!(OwnedValue::Object(hashmap! {"test".into() => 1.into()}), v);
+        let o = hashmap! {"test".into() => 1.into()};
+        assert_eq!(OwnedValue::Object(o.into_iter().collect()), v);
     }
 }
@@ -0,0 +1,634 @@
+use crate::charutils::is_json_whitespace;
+use crate::value::owned::{
+    to_value_with_interner, to_value_with_options, to_value_with_structural_indexes,
+};
+use crate::{Deserializer, Error, ErrorType, KeyInterner, OwnedValue, ParserOptions};
+use std::fmt;
+use std::io::BufRead;
+use std::mem;
+use std::ops::Range;
+use std::sync::mpsc;
+use std::thread;
+
+#[cfg(feature = "ndjson-columns")]
+mod columns;
+#[cfg(feature = "ndjson-columns")]
+pub use self::columns::{ColumnBuilder, Field};
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "async")]
+pub use self::stream::AsyncReader;
+
+/// A line of the input that failed to parse
+#[derive(Debug, PartialEq)]
+pub struct LineError {
+    /// Line number (starting at 1) of the offending line
+    pub line: usize,
+    /// Byte range of the line in the input, excluding the line terminator
+    pub range: Range<usize>,
+    /// The error encountered while parsing the line
+    pub error: Error,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {} (bytes {}..{}): {}",
+            self.line, self.range.start, self.range.end, self.error
+        )
+    }
+}
+
+impl std::error::Error for LineError {}
+
+type BadLineSink = Box<dyn FnMut(&LineError, &[u8])>;
+
+// The record separator that starts every record of a JSON text sequence
+const RS: u8 = 0x1e;
+
+/// Reads newline delimited JSON, yielding one value per line.
+///
+/// A line that fails to parse does not abort the stream, it is yielded as
+/// an `Err` and reading continues with the next line. Blank lines are
+/// skipped. IO errors end the stream after being reported.
+///
+/// ```
+/// use simd_json::ndjson::Reader;
+/// let input = "{\"a\": 1}\n{broken\n[2]\n";
+/// let values: Vec<_> = Reader::new(input.as_bytes()).collect();
+/// assert_eq!(values.len(), 3);
+/// assert!(values[0].is_ok());
+/// assert_eq!(values[1].as_ref().unwrap_err().line, 2);
+/// assert!(values[2].is_ok());
+/// ```
+pub struct Reader<R: BufRead> {
+    lines: Lines<R>,
+    options: ParserOptions,
+    raw: Vec<u8>,
+    sink: Option<BadLineSink>,
+    keys: Option<KeyInterner>,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a new reader using the default `ParserOptions`
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    /// Creates a new reader using the given `ParserOptions` for each line
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            lines: Lines {
+                reader,
+                buffer: Vec::new(),
+                line: 0,
+                offset: 0,
+                done: false,
+                delimiter: b'\n',
+            },
+            options,
+            raw: Vec::new(),
+            sink: None,
+            keys: None,
+        }
+    }
+
+    /// Registers a sink that is called with the error and the raw bytes of
+    /// every line that fails to parse, for example to write them to a
+    /// dead letter file. Since parsing rewrites the line in place, setting
+    /// a sink requires a copy of each line to be kept.
+    pub fn with_bad_line_sink<F>(mut self, sink: F) -> Self
+    where
+        F: FnMut(&LineError, &[u8]) + 'static,
+    {
+        self.sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Interns object keys across all lines with the given `KeyInterner`,
+    /// so records with the same keys share them (requires the `arc-keys`
+    /// feature to have an effect).
+    pub fn with_key_interner(mut self, keys: KeyInterner) -> Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    /// Reads a JSON text sequence ([RFC 7464], `application/json-seq`)
+    /// instead: records start with a record separator (`0x1e`) and usually
+    /// end with a line feed, they may span multiple lines. Empty records are
+    /// skipped, records with a number, `true`, `false` or `null` that don't
+    /// end in whitespace may have been cut off and are rejected with
+    /// `ErrorType::UnexpectedEnd`. Line numbers count records.
+    ///
+    /// ```
+    /// use simd_json::{json, ndjson::Reader};
+    /// let input = b"\x1e{\"a\":\n 1}\n\x1e42\n\x1e\x1e[2]\n\x1e4";
+    /// let values: Vec<_> = Reader::new(&input[..]).json_seq().collect();
+    /// assert_eq!(values.len(), 4);
+    /// assert_eq!(values[0], Ok(json!({"a": 1})));
+    /// assert_eq!(values[2], Ok(json!([2])));
+    /// assert!(values[3].is_err());
+    /// ```
+    ///
+    /// [RFC 7464]: https://tools.ietf.org/html/rfc7464
+    pub fn json_seq(mut self) -> Self {
+        self.lines.delimiter = RS;
+        self
+    }
+
+    /// Number of lines read so far
+    pub fn line(&self) -> usize {
+        self.lines.line
+    }
+
+    /// Number of bytes read so far
+    pub fn offset(&self) -> usize {
+        self.lines.offset
+    }
+
+    /// Turns the reader into a pipelined one: reading lines and finding
+    /// their structural indexes (stage 1) moves to a background thread
+    /// that runs up to `depth` lines ahead, while stage 2 and building the
+    /// values happens on the consuming thread. This pays off for streams
+    /// of large documents where both stages take a similar amount of time.
+    /// Like the stream mode of simdjson the stages overlap across documents,
+    /// each one is still indexed as a whole: a single huge line gains
+    /// nothing, stage 2 can't start before stage 1 found all of its
+    /// structural indexes.
+    ///
+    /// The values, errors and the bad line sink behave exactly as with the
+    /// sequential reader, a panic on the background thread (e.g. in the
+    /// underlying reader) is raised again by `next`. The background thread
+    /// stops once the returned reader is dropped.
+    ///
+    /// ```
+    /// use simd_json::ndjson::Reader;
+    /// let input = "[1]\n[2]\n[3]\n";
+    /// let values: Vec<_> = Reader::new(input.as_bytes())
+    ///     .pipelined(2)
+    ///     .collect::<Result<_, _>>()
+    ///     .expect("valid input");
+    /// assert_eq!(values.len(), 3);
+    /// ```
+    pub fn pipelined(self, depth: usize) -> Pipelined
+    where
+        R: Send + 'static,
+    {
+        let Reader {
+            mut lines,
+            options,
+            raw,
+            sink,
+            keys,
+        } = self;
+        let worker_options = options.clone();
+        let (line, offset) = (lines.line, lines.offset);
+        let (sender, receiver) = mpsc::sync_channel(depth);
+        // line buffers go back to the background thread once parsed
+        let (recycle, recycled) = mpsc::channel::<Vec<u8>>();
+        let worker = thread::spawn(move || {
+            while let Some(next) = lines.next_line() {
+                let indexed = next.map(|(start, len)| {
+                    let spare = recycled.try_recv().unwrap_or_default();
+                    let mut buffer = mem::replace(&mut lines.buffer, spare);
+                    buffer.truncate(len);
+                    let content = worker_options.content_range(&buffer);
+                    let structural_indexes = if len > worker_options.max_document_size {
+                        Err(ErrorType::InputTooLarge)
+                    } else if lines.is_truncated(&buffer) {
+                        Err(ErrorType::UnexpectedEnd)
+                    } else {
+                        Deserializer::find_structural_indexes(&buffer[content.clone()])
+                    };
+                    Indexed {
+                        line: lines.line,
+                        offset: lines.offset,
+                        start,
+                        buffer,
+                        content,
+                        structural_indexes,
+                    }
+                });
+                if sender.send(Message::Line(indexed)).is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send(Message::End {
+                line: lines.line,
+                offset: lines.offset,
+            });
+        });
+        Pipelined {
+            receiver,
+            recycle,
+            worker: Some(worker),
+            line,
+            offset,
+            options,
+            raw,
+            sink,
+            keys,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = std::result::Result<OwnedValue, LineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, len) = match self.lines.next_line()? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+        let truncated = self.lines.is_truncated(&self.lines.buffer[..len]);
+        let line = &mut self.lines.buffer[..len];
+        if self.sink.is_some() {
+            self.raw.clear();
+            self.raw.extend_from_slice(line);
+        }
+        let result = if truncated {
+            Err(Error::generic(ErrorType::UnexpectedEnd))
+        } else {
+            match self.keys.as_mut() {
+                Some(keys) => to_value_with_interner(line, &self.options, keys),
+                None => to_value_with_options(line, &self.options),
+            }
+        };
+        Some(result.map_err(|error| self.line_error(start, len, error)))
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    // Reports the current line as bad to the sink
+    fn line_error(&mut self, start: usize, len: usize, error: Error) -> LineError {
+        let e = LineError {
+            line: self.lines.line,
+            range: start..start + len,
+            error,
+        };
+        if let Some(sink) = self.sink.as_mut() {
+            sink(&e, &self.raw);
+        }
+        e
+    }
+}
+
+// Splits the input into lines, or the records of a JSON text sequence,
+// skipping blank ones.
+struct Lines<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    line: usize,
+    offset: usize,
+    done: bool,
+    // `\n` for lines or `RS` for records
+    delimiter: u8,
+}
+
+impl<R: BufRead> Lines<R> {
+    // Reads the next non blank line into the buffer and returns its start
+    // offset and its length without the line terminator.
+    fn next_line(&mut self) -> Option<std::result::Result<(usize, usize), LineError>> {
+        while !self.done {
+            self.buffer.clear();
+            let read = match self.reader.read_until(self.delimiter, &mut self.buffer) {
+                Ok(0) => {
+                    self.done = true;
+                    return None;
+                }
+                Ok(read) => read,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(LineError {
+                        line: self.line + 1,
+                        range: self.offset..self.offset,
+                        error: Error::io(e),
+                    }));
+                }
+            };
+            let start = self.offset;
+            self.offset += read;
+
+            let mut len = self.buffer.len();
+            if self.buffer.last() == Some(&self.delimiter) {
+                len -= 1;
+                if self.delimiter == b'\n' && len > 0 && self.buffer[len - 1] == b'\r' {
+                    len -= 1;
+                }
+            }
+            // a sequence starts with a separator, it doesn't end a record
+            if self.delimiter == RS && start == 0 && len == 0 {
+                continue;
+            }
+            self.line += 1;
+            if is_blank(&self.buffer[..len]) {
+                continue;
+            }
+            return Some(Ok((start, len)));
+        }
+        None
+    }
+
+    // A record of a JSON text sequence that is a number or literal not
+    // followed by whitespace may have been cut off by the producer.
+    fn is_truncated(&self, record: &[u8]) -> bool {
+        if self.delimiter != RS || record.last().map_or(true, |c| is_json_whitespace(*c)) {
+            return false;
+        }
+        match record.iter().find(|c| !is_json_whitespace(**c)) {
+            Some(b'-') | Some(b'0'..=b'9') | Some(b't') | Some(b'f') | Some(b'n') => true,
+            _ => false,
+        }
+    }
+}
+
+// What the background thread of a pipelined reader sends
+enum Message {
+    Line(std::result::Result<Indexed, LineError>),
+    // the final counts once the input is exhausted
+    End { line: usize, offset: usize },
+}
+
+// A line with nothing but whitespace, only the whitespace JSON allows
+// counts so a line with a form feed is reported as invalid rather than
+// skipped.
+fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(|c| is_json_whitespace(*c))
+}
+
+// A line together with the result of running stage 1 on it
+struct Indexed {
+    line: usize,
+    // bytes read up to and including this line
+    offset: usize,
+    start: usize,
+    buffer: Vec<u8>,
+    // the part of the buffer stage 1 ran on
+    content: Range<usize>,
+    structural_indexes: std::result::Result<Vec<u32>, ErrorType>,
+}
+
+/// A newline delimited JSON reader that finds structural indexes on a
+/// background thread, see `Reader::pipelined`.
+pub struct Pipelined {
+    receiver: mpsc::Receiver<Message>,
+    recycle: mpsc::Sender<Vec<u8>>,
+    worker: Option<thread::JoinHandle<()>>,
+    line: usize,
+    offset: usize,
+    options: ParserOptions,
+    raw: Vec<u8>,
+    sink: Option<BadLineSink>,
+    keys: Option<KeyInterner>,
+}
+
+impl Pipelined {
+    /// Number of lines read so far, counting only lines that were yielded
+    /// or skipped, not the ones the background thread has read ahead
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Number of bytes read so far, like `line` without the read ahead
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Iterator for Pipelined {
+    type Item = std::result::Result<OwnedValue, LineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Indexed {
+            line,
+            offset,
+            start,
+            mut buffer,
+            content,
+            structural_indexes,
+        } = match self.receiver.recv() {
+            Ok(Message::Line(Ok(indexed))) => indexed,
+            Ok(Message::Line(Err(e))) => return Some(Err(e)),
+            Ok(Message::End { line, offset }) => {
+                self.line = line;
+                self.offset = offset;
+                return None;
+            }
+            // the background thread ended without sending `End`
+            Err(mpsc::RecvError) => {
+                if let Some(Err(panic)) = self.worker.take().map(thread::JoinHandle::join) {
+                    std::panic::resume_unwind(panic);
+                }
+                return None;
+            }
+        };
+        self.line = line;
+        self.offset = offset;
+        if self.sink.is_some() {
+            self.raw.clear();
+            self.raw.extend_from_slice(&buffer);
+        }
+        let len = buffer.len();
+        let mut fresh_keys = KeyInterner::default();
+        let keys = self.keys.as_mut().unwrap_or(&mut fresh_keys);
+        let result = match structural_indexes {
+            Ok(structural_indexes) => to_value_with_structural_indexes(
+                &mut buffer[content],
+                structural_indexes,
+                &self.options,
+                keys,
+            ),
+            // stage 1 can't repair invalid UTF-8, so parse the line again
+            Err(ErrorType::InvalidUTF8) if self.options.lossy_utf8 => {
+                to_value_with_interner(&mut buffer[content], &self.options, keys)
+            }
+            Err(t) => Err(Error::generic(t)),
+        };
+        // the background thread may be done already
+        let _ = self.recycle.send(buffer);
+        Some(result.map_err(|error| {
+            let e = LineError {
+                line,
+                range: start..start + len,
+                error,
+            };
+            if let Some(sink) = self.sink.as_mut() {
+                sink(&e, &self.raw);
+            }
+            e
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::io;
+    use std::rc::Rc;
+
+    #[test]
+    fn reads_lines() {
+        let input = b"{\"a\": 1}\n\n \t \n[1, 2]\r\n42";
+        let values: Vec<_> = Reader::new(&input[..])
+            .collect::<std::result::Result<_, _>>()
+            .expect("valid input");
+        assert_eq!(values, vec![json!({"a": 1}), json!([1, 2]), json!(42)]);
+        // form feed isn't JSON whitespace, a line of it is bad and not blank
+        let mut reader = Reader::new(&b"[1]\n\x0c\n[2]"[..]);
+        assert_eq!(reader.next(), Some(Ok(json!([1]))));
+        assert_eq!(reader.next().expect("line").expect_err("bad line").line, 2);
+        assert_eq!(reader.next(), Some(Ok(json!([2]))));
+    }
+
+    #[test]
+    fn recovers_from_bad_lines() {
+        let input = b"[1]\n{\"a\": }\n[2]\nnope\r\n[3]\n";
+        let mut reader = Reader::new(&input[..]);
+        assert_eq!(reader.next(), Some(Ok(json!([1]))));
+        let e = reader.next().expect("line").expect_err("bad line");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.range, 4..11);
+        assert_eq!(&input[e.range.clone()], b"{\"a\": }");
+        assert_eq!(reader.next(), Some(Ok(json!([2]))));
+        let e = reader.next().expect("line").expect_err("bad line");
+        assert_eq!(e.line, 4);
+        assert_eq!(&input[e.range.clone()], b"nope");
+        assert_eq!(reader.next(), Some(Ok(json!([3]))));
+        assert_eq!(reader.next(), None);
+        assert_eq!(reader.line(), 5);
+        assert_eq!(reader.offset(), input.len());
+    }
+
+    #[test]
+    fn bad_line_sink() {
+        let bad = Rc::new(RefCell::new(Vec::new()));
+        let bad1 = bad.clone();
+        let input = b"[1]\n[\"broken\n[2]\n";
+        let good = Reader::new(&input[..])
+            .with_bad_line_sink(move |e, raw| bad1.borrow_mut().push((e.line, raw.to_vec())))
+            .filter(std::result::Result::is_ok)
+            .count();
+        assert_eq!(good, 2);
+        assert_eq!(*bad.borrow(), vec![(2, b"[\"broken".to_vec())]);
+    }
+
+    #[test]
+    fn pipelined() {
+        let mut input = Vec::new();
+        for i in 0..100 {
+            if i % 7 == 3 {
+                input.extend_from_slice(b"{\"broken\": }\n");
+            } else {
+                input.extend_from_slice(
+                    format!("{{\"n\": {}, \"s\": \"x{}\"}}\r\n\n", i, i).as_bytes(),
+                );
+            }
+        }
+        input.extend_from_slice(b"[\"\xff\"]");
+        let sequential: Vec<_> = Reader::new(&input[..]).collect();
+
+        let bad = Rc::new(RefCell::new(Vec::new()));
+        let bad1 = bad.clone();
+        let pipelined: Vec<_> = Reader::new(io::Cursor::new(input.clone()))
+            .with_bad_line_sink(move |e, raw| bad1.borrow_mut().push((e.line, raw.to_vec())))
+            .pipelined(4)
+            .collect();
+        assert_eq!(pipelined, sequential);
+        assert_eq!(bad.borrow().len(), 15);
+        assert_eq!(bad.borrow()[0], (7, b"{\"broken\": }".to_vec()));
+
+        let options = ParserOptions {
+            lossy_utf8: true,
+            ..ParserOptions::default()
+        };
+        let sequential: Vec<_> = Reader::with_options(&input[..], options.clone()).collect();
+        let pipelined: Vec<_> = Reader::with_options(io::Cursor::new(input.clone()), options)
+            .pipelined(4)
+            .collect();
+        assert_eq!(pipelined, sequential);
+        assert_eq!(pipelined.last(), Some(&Ok(json!(["\u{fffd}"]))));
+
+        input.extend_from_slice(b"\n\n");
+        let mut sequential = Reader::new(&input[..]);
+        let mut pipelined = Reader::new(io::Cursor::new(input.clone())).pipelined(4);
+        loop {
+            let next = sequential.next();
+            assert_eq!(pipelined.next(), next);
+            assert_eq!(pipelined.line(), sequential.line());
+            assert_eq!(pipelined.offset(), sequential.offset());
+            if next.is_none() {
+                break;
+            }
+        }
+        assert_eq!(pipelined.offset(), input.len());
+
+        let options = ParserOptions {
+            max_document_size: 8,
+            ..ParserOptions::default()
+        };
+        let mut reader =
+            Reader::with_options(io::Cursor::new(b"[1]\n[1, 2, 3]\n".to_vec()), options)
+                .pipelined(1);
+        assert_eq!(reader.next(), Some(Ok(json!([1]))));
+        let e = reader.next().expect("line").expect_err("too large");
+        assert_eq!(e.error.error(), &ErrorType::InputTooLarge);
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn json_seq() {
+        let input = b"\x1e{\"a\": [1,\n2]}\n\x1e\n\x1e\"s\"\x1etrue\n\x1e{\"b\n\x1e-1\n\x1e12";
+        let values: Vec<_> = Reader::new(&input[..]).json_seq().collect();
+        assert_eq!(values.len(), 6);
+        assert_eq!(values[0], Ok(json!({"a": [1, 2]})));
+        assert_eq!(values[1], Ok(json!("s")));
+        assert_eq!(values[2], Ok(json!(true)));
+        let e = values[3].as_ref().expect_err("broken");
+        assert_eq!(e.line, 5);
+        assert_eq!(&input[e.range.clone()], b"{\"b\n");
+        assert_eq!(values[4], Ok(json!(-1)));
+        let e = values[5].as_ref().expect_err("truncated");
+        assert_eq!(e.error.error(), &ErrorType::UnexpectedEnd);
+        assert_eq!(&input[e.range.clone()], b"12");
+
+        let pipelined: Vec<_> = Reader::new(io::Cursor::new(input.to_vec()))
+            .json_seq()
+            .pipelined(2)
+            .collect();
+        assert_eq!(pipelined, values);
+    }
+
+    struct Failing;
+    impl io::Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "oh no"))
+        }
+    }
+
+    struct Panicking;
+    impl io::Read for Panicking {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            panic!("reader panicked")
+        }
+    }
+
+    #[test]
+    fn pipelined_panic() {
+        let input = io::BufReader::new(io::Read::chain(&b"[1]\n[2"[..], Panicking));
+        let mut reader = Reader::new(input).pipelined(1);
+        assert_eq!(reader.next(), Some(Ok(json!([1]))));
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| reader.next()))
+            .expect_err("panic");
+        assert_eq!(panic.downcast_ref(), Some(&"reader panicked"));
+    }
+
+    #[test]
+    fn io_error_ends_stream() {
+        let mut reader = Reader::new(io::BufReader::new(Failing));
+        let e = reader.next().expect("error").expect_err("io error");
+        assert_eq!(e.error.error(), &ErrorType::IO("oh no".to_string()));
+        assert_eq!(reader.next(), None);
+    }
+}
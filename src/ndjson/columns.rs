@@ -0,0 +1,247 @@
+use super::{LineError, Reader};
+use crate::pointer::skip_value;
+use crate::{Deserializer, ErrorType, Result};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// A member of a record read by `Reader::read_columns`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field<'r> {
+    /// `null`, also used for members missing from a record
+    Null,
+    /// A boolean
+    Bool(bool),
+    /// An integer that fits an `i64`
+    I64(i64),
+    /// Any other number
+    F64(f64),
+    /// A string
+    Str(&'r str),
+    /// An array or object as its JSON text
+    Json(&'r str),
+}
+
+/// Receives the members of records column by column, implement this for
+/// the column builders of a dataframe or columnar format (e.g. arrow-rs
+/// array builders) to fill them without building a value per record.
+pub trait ColumnBuilder {
+    /// Appends a field to the column with the index `column`, every column
+    /// gets one field per record.
+    fn append(&mut self, column: usize, field: Field<'_>);
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Reads up to `max_records` lines holding objects and appends the
+    /// members named in `columns` to `builder`, other members are skipped
+    /// without being parsed and a member that repeats replaces the earlier
+    /// one. Returns the number of records appended, `0` once the input is
+    /// exhausted.
+    ///
+    /// A line that fails to parse or isn't an object ends the batch with
+    /// an `Err` after being passed to the bad line sink, none of its fields
+    /// are appended but the records before it stay in the builder. Reading
+    /// continues with the next line on the next call.
+    ///
+    /// ```
+    /// use simd_json::ndjson::{ColumnBuilder, Field, Reader};
+    /// #[derive(Default)]
+    /// struct Columns {
+    ///     ids: Vec<i64>,
+    ///     names: Vec<Option<String>>,
+    /// }
+    /// impl ColumnBuilder for Columns {
+    ///     fn append(&mut self, column: usize, field: Field) {
+    ///         match (column, field) {
+    ///             (0, Field::I64(id)) => self.ids.push(id),
+    ///             (1, Field::Str(name)) => self.names.push(Some(name.to_string())),
+    ///             (1, _) => self.names.push(None),
+    ///             _ => panic!("unexpected field {:?}", field),
+    ///         }
+    ///     }
+    /// }
+    /// let input = "{\"id\": 1, \"name\": \"a\"}\n{\"tags\": [], \"id\": 2}\n";
+    /// let mut reader = Reader::new(input.as_bytes());
+    /// let mut columns = Columns::default();
+    /// assert_eq!(reader.read_columns(&["id", "name"], &mut columns, 1024), Ok(2));
+    /// assert_eq!(columns.ids, vec![1, 2]);
+    /// assert_eq!(columns.names, vec![Some("a".to_string()), None]);
+    /// assert_eq!(reader.read_columns(&["id", "name"], &mut columns, 1024), Ok(0));
+    /// ```
+    pub fn read_columns<B>(
+        &mut self,
+        columns: &[&str],
+        builder: &mut B,
+        max_records: usize,
+    ) -> std::result::Result<usize, LineError>
+    where
+        B: ColumnBuilder,
+    {
+        let index: HashMap<&str, usize> =
+            columns.iter().enumerate().map(|(i, c)| (*c, i)).collect();
+        let mut records = 0;
+        while records < max_records {
+            let (start, len) = match self.lines.next_line() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => return Err(e),
+                None => break,
+            };
+            let line = &mut self.lines.buffer[..len];
+            if self.sink.is_some() {
+                self.raw.clear();
+                self.raw.extend_from_slice(line);
+            }
+            let mut row = vec![Field::Null; columns.len()];
+            let result = Deserializer::from_slice_with_options(line, &self.options)
+                .and_then(|mut de| de.parse_record(&index, &mut row));
+            if let Err(error) = result {
+                return Err(self.line_error(start, len, error));
+            }
+            for (column, field) in row.into_iter().enumerate() {
+                builder.append(column, field);
+            }
+            records += 1;
+        }
+        Ok(records)
+    }
+}
+
+impl<'de> Deserializer<'de> {
+    // Reads the members of an object that are in `index` into `row`
+    fn parse_record(&mut self, index: &HashMap<&str, usize>, row: &mut [Field<'de>]) -> Result<()> {
+        if self.next_() != b'{' {
+            return Err(self.error(ErrorType::ExpectedMap));
+        }
+        let es = self.count_elements();
+        if es == 0 {
+            self.skip();
+        }
+        for _ in 0..es {
+            self.skip();
+            let key = stry!(self.parse_str());
+            self.skip();
+            match index.get(key) {
+                Some(column) => row[*column] = stry!(self.parse_field()),
+                None => self.skip_field(),
+            }
+            self.skip();
+        }
+        Ok(())
+    }
+
+    fn parse_field(&mut self) -> Result<Field<'de>> {
+        Ok(match self.next_() {
+            b'"' => Field::Str(stry!(self.parse_str())),
+            b'n' => Field::Null,
+            b't' => Field::Bool(true),
+            b'f' => Field::Bool(false),
+            c @ b'-' | c @ b'0'..=b'9' => match self.parse_number(c == b'-') {
+                Ok(n) => match n.as_i64() {
+                    Some(i) => Field::I64(i),
+                    None => Field::F64(n.as_f64().unwrap_or_default()),
+                },
+                Err(ref e) if e.error() == &ErrorType::Overflow => {
+                    let n = stry!(self.parse_number_raw(c == b'-', false));
+                    match n.parse() {
+                        Ok(f) => Field::F64(f),
+                        Err(_) => return Err(self.error(ErrorType::InvalidNumber)),
+                    }
+                }
+                Err(e) => return Err(e),
+            },
+            b'[' | b'{' => {
+                let start = self.structural_indexes[self.idx] as usize;
+                let end = self.skip_container();
+                let json = &self.input[start..=end];
+                // stage 1 validated the input to be UTF-8
+                Field::Json(unsafe { &*(std::str::from_utf8_unchecked(json) as *const str) })
+            }
+            _ => return Err(self.error(ErrorType::UnexpectedCharacter)),
+        })
+    }
+
+    // Moves past the value of a member without parsing it
+    fn skip_field(&mut self) {
+        if let b'[' | b'{' = self.next_() {
+            self.skip_container();
+        }
+    }
+
+    // Moves to the closing bracket of the array or object at the current
+    // position, returns its offset in the input
+    fn skip_container(&mut self) -> usize {
+        self.idx = skip_value(self.input, &self.structural_indexes, self.idx) - 1;
+        self.iidx = self.structural_indexes[self.idx] as usize;
+        self.iidx
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Default)]
+    struct Rows(Vec<Vec<String>>);
+
+    impl ColumnBuilder for Rows {
+        fn append(&mut self, column: usize, field: Field<'_>) {
+            if column == 0 {
+                self.0.push(Vec::new());
+            }
+            if let Some(row) = self.0.last_mut() {
+                row.push(format!("{:?}", field));
+            }
+        }
+    }
+
+    #[test]
+    fn read_columns() {
+        let input = concat!(
+            "{\"a\": 1, \"b\": \"x\\ny\", \"c\": [1, {\"d\": []}]}\n",
+            "{\"c\": {\"e\": null}, \"skip\": [[{}]], \"a\": -1.5, \"b\": true}\n",
+            "\n",
+            "{}\n",
+            "{\"a\": 18446744073709551616, \"b\": null, \"a\": 2}\n",
+            "{\"a\": 18446744073709551616}\n",
+        );
+        let mut reader = Reader::new(input.as_bytes());
+        let mut rows = Rows::default();
+        assert_eq!(reader.read_columns(&["a", "b", "c"], &mut rows, 3), Ok(3));
+        assert_eq!(reader.read_columns(&["a", "b", "c"], &mut rows, 3), Ok(2));
+        assert_eq!(reader.read_columns(&["a", "b", "c"], &mut rows, 3), Ok(0));
+        assert_eq!(
+            rows.0,
+            vec![
+                vec!["I64(1)", "Str(\"x\\ny\")", "Json(\"[1, {\\\"d\\\": []}]\")"],
+                vec!["F64(-1.5)", "Bool(true)", "Json(\"{\\\"e\\\": null}\")"],
+                vec!["Null", "Null", "Null"],
+                vec!["I64(2)", "Null", "Null"],
+                vec!["F64(1.8446744073709552e19)", "Null", "Null"],
+            ]
+        );
+        assert_eq!(reader.line(), 6);
+    }
+
+    #[test]
+    fn bad_records() {
+        let bad = Rc::new(RefCell::new(Vec::new()));
+        let bad1 = bad.clone();
+        let input = b"{\"a\": 1}\n[1]\n{\"a\": }\n{\"a\": 2}\n";
+        let mut reader = Reader::new(&input[..])
+            .with_bad_line_sink(move |e, raw| bad1.borrow_mut().push((e.line, raw.to_vec())));
+        let mut rows = Rows::default();
+        let e = reader
+            .read_columns(&["a"], &mut rows, 10)
+            .expect_err("array");
+        assert_eq!(e.error.error(), &ErrorType::ExpectedMap);
+        assert_eq!(e.line, 2);
+        assert!(reader.read_columns(&["a"], &mut rows, 10).is_err());
+        assert_eq!(reader.read_columns(&["a"], &mut rows, 10), Ok(1));
+        assert_eq!(rows.0, vec![vec!["I64(1)"], vec!["I64(2)"]]);
+        assert_eq!(
+            *bad.borrow(),
+            vec![(2, b"[1]".to_vec()), (3, b"{\"a\": }".to_vec())]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use super::{is_blank, LineError};
+use crate::value::owned::to_value_with_options;
+use crate::{Error, OwnedValue, ParserOptions};
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::AsyncBufRead;
+
+/// Reads newline delimited JSON from a tokio reader, it is the `Stream`
+/// counterpart of `Reader` and yields the same values and errors: a line
+/// that fails to parse is yielded as an `Err` and reading continues with
+/// the next line, blank lines are skipped and IO errors end the stream.
+///
+/// ```
+/// # futures::executor::block_on(async {
+/// use futures::StreamExt;
+/// use simd_json::{json, ndjson::AsyncReader};
+/// let input = b"{\"a\": 1}\n{broken\n[2]\n";
+/// let values: Vec<_> = AsyncReader::new(&input[..]).collect().await;
+/// assert_eq!(values[0], Ok(json!({"a": 1})));
+/// assert_eq!(values[1].as_ref().unwrap_err().line, 2);
+/// assert_eq!(values[2], Ok(json!([2])));
+/// # });
+/// ```
+pub struct AsyncReader<R> {
+    reader: R,
+    options: ParserOptions,
+    buffer: Vec<u8>,
+    line: usize,
+    offset: usize,
+    done: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncReader<R> {
+    /// Creates a new reader using the default `ParserOptions`
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    /// Creates a new reader using the given `ParserOptions` for each line
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            reader,
+            options,
+            buffer: Vec::new(),
+            line: 0,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Number of lines read so far
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Number of bytes read so far
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for AsyncReader<R> {
+    type Item = std::result::Result<OwnedValue, LineError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while !this.done {
+            let available = match Pin::new(&mut this.reader).poll_fill_buf(cx) {
+                Poll::Ready(Ok(available)) => available,
+                Poll::Ready(Err(e)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(LineError {
+                        line: this.line + 1,
+                        range: this.offset..this.offset,
+                        error: Error::io(e),
+                    })));
+                }
+                Poll::Pending => return Poll::Pending,
+            };
+            let eof = available.is_empty();
+            let used = available
+                .iter()
+                .position(|c| *c == b'\n')
+                .map_or(available.len(), |i| i + 1);
+            this.buffer.extend_from_slice(&available[..used]);
+            Pin::new(&mut this.reader).consume(used);
+            if eof {
+                // the last line may not end with a line feed
+                this.done = true;
+                if this.buffer.is_empty() {
+                    break;
+                }
+            } else if this.buffer.last() != Some(&b'\n') {
+                continue;
+            }
+
+            let start = this.offset;
+            this.offset += this.buffer.len();
+            let mut len = this.buffer.len();
+            if this.buffer.last() == Some(&b'\n') {
+                len -= 1;
+                if len > 0 && this.buffer[len - 1] == b'\r' {
+                    len -= 1;
+                }
+            }
+            this.line += 1;
+            if is_blank(&this.buffer[..len]) {
+                this.buffer.clear();
+                continue;
+            }
+            let result = to_value_with_options(&mut this.buffer[..len], &this.options);
+            this.buffer.clear();
+            let line = this.line;
+            return Poll::Ready(Some(result.map_err(|error| LineError {
+                line,
+                range: start..start + len,
+                error,
+            })));
+        }
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::StreamExt;
+    use std::io;
+    use tokio::io::{AsyncRead, BufReader, ReadBuf};
+
+    #[test]
+    fn reads_lines() {
+        let input = b"{\"a\": 1}\n\n  \n[1, 2]\r\n{\"a\": }\n42";
+        let mut reader = AsyncReader::new(&input[..]);
+        let values: Vec<_> = block_on((&mut reader).collect());
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[0], Ok(json!({"a": 1})));
+        assert_eq!(values[1], Ok(json!([1, 2])));
+        let e = values[2].as_ref().expect_err("bad line");
+        assert_eq!(e.line, 5);
+        assert_eq!(&input[e.range.clone()], b"{\"a\": }");
+        assert_eq!(values[3], Ok(json!(42)));
+        assert_eq!(reader.line(), 6);
+        assert_eq!(reader.offset(), input.len());
+    }
+
+    #[test]
+    fn lines_across_reads() {
+        // a tiny buffer so lines are split across many reads
+        let input = b"[\"a long line\", 1, 2, 3]\n{\"b\": [true, null]}\n";
+        let reader = AsyncReader::new(BufReader::with_capacity(4, &input[..]));
+        let values: Vec<_> = block_on(reader.collect());
+        assert_eq!(
+            values,
+            vec![
+                Ok(json!(["a long line", 1, 2, 3])),
+                Ok(json!({"b": [true, null]}))
+            ]
+        );
+    }
+
+    struct Failing;
+
+    impl AsyncRead for Failing {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "boom")))
+        }
+    }
+
+    #[test]
+    fn io_error_ends_stream() {
+        let values: Vec<_> = block_on(AsyncReader::new(BufReader::new(Failing)).collect());
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].as_ref().expect_err("io error").line, 1);
+    }
+}
@@ -157,6 +157,27 @@ unsafe fn check_utf8(input: &SimdInput, state: &mut Utf8CheckingState) {
     }
 }
 
+// Checks that the input is valid UTF-8 using the same routine as stage 1.
+// The final block is always padded with spaces so inputs ending in an
+// incomplete sequence are caught.
+pub(crate) unsafe fn is_valid_utf8(input: &[u8]) -> bool {
+    let len = input.len();
+    let mut utf8_state: Utf8CheckingState = Utf8CheckingState::default();
+    let mut idx: usize = 0;
+    while idx + 64 <= len {
+        let input: SimdInput = fill_input(input.get_unchecked(idx..));
+        check_utf8(&input, &mut utf8_state);
+        idx += 64;
+    }
+    let mut tmpbuf: [u8; 64] = [0x20; 64];
+    tmpbuf
+        .as_mut_ptr()
+        .copy_from(input.as_ptr().add(idx), len - idx);
+    let input: SimdInput = fill_input(&tmpbuf);
+    check_utf8(&input, &mut utf8_state);
+    is_utf8_status_ok(utf8_state.has_error)
+}
+
 // a straightforward comparison of a mask against input
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 fn cmp_mask_against_input(input: &SimdInput, m: u8) -> u64 {
@@ -459,11 +480,15 @@ pub fn find_bs_bits_and_quote_bits(v0: uint8x16_t, v1: uint8x16_t) -> ParseStrin
 
 impl<'de> Deserializer<'de> {
     //#[inline(never)]
-    pub unsafe fn find_structural_bits(input: &[u8]) -> std::result::Result<Vec<u32>, ErrorType> {
+    pub(crate) unsafe fn find_structural_bits(
+        input: &[u8],
+        structural_indexes: &mut Vec<u32>,
+    ) -> std::result::Result<(), ErrorType> {
         let len = input.len();
         // 6 is a heuristic number to estimate it turns out a rate of 1/6 structural caracters lears
         // almost never to relocations.
-        let mut structural_indexes = Vec::with_capacity(len / 6);
+        structural_indexes.clear();
+        structural_indexes.reserve(len / 6);
         structural_indexes.push(0); // push extra root element
 
         let mut utf8_state: Utf8CheckingState = Utf8CheckingState::default();
@@ -521,7 +546,7 @@ impl<'de> Deserializer<'de> {
 
             // take the previous iterations structural bits, not our current iteration,
             // and flatten
-            flatten_bits(&mut structural_indexes, idx as u32, structurals);
+            flatten_bits(structural_indexes, idx as u32, structurals);
 
             let mut whitespace: u64 = 0;
             find_whitespace_and_structurals(&input, &mut whitespace, &mut structurals);
@@ -566,7 +591,7 @@ impl<'de> Deserializer<'de> {
 
             // take the previous iterations structural bits, not our current iteration,
             // and flatten
-            flatten_bits(&mut structural_indexes, idx as u32, structurals);
+            flatten_bits(structural_indexes, idx as u32, structurals);
 
             let mut whitespace: u64 = 0;
             find_whitespace_and_structurals(&input, &mut whitespace, &mut structurals);
@@ -586,7 +611,7 @@ impl<'de> Deserializer<'de> {
             return Err(ErrorType::Syntax);
         }
         // finally, flatten out the remaining structurals from the last iteration
-        flatten_bits(&mut structural_indexes, idx as u32, structurals);
+        flatten_bits(structural_indexes, idx as u32, structurals);
 
         // a valid JSON file cannot have zero structural indexes - we should have
         // found something (note that we compare to 1 as we always add the root!)
@@ -603,7 +628,7 @@ impl<'de> Deserializer<'de> {
         }
 
         if is_utf8_status_ok(utf8_state.has_error) {
-            Ok(structural_indexes)
+            Ok(())
         } else {
             Err(ErrorType::InvalidUTF8)
         }
@@ -0,0 +1,968 @@
+use crate::charutils::is_json_whitespace;
+use crate::feed::Scanner;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Default maximum nesting depth of arrays and objects, this matches
+/// the default of the c++ simdjson implementation.
+pub const DEFAULT_MAX_DEPTH: usize = 1024;
+
+/// Options to tune the behaviour of the parser. The defaults match the
+/// behaviour of the plain parsing functions like `to_owned_value`.
+///
+/// ```
+/// use simd_json::ParserOptions;
+/// let options = ParserOptions {
+///     max_depth: 2,
+///     ..ParserOptions::default()
+/// };
+/// let mut d = br#"[[[1]]]"#.to_vec();
+/// assert!(simd_json::to_owned_value_with_options(&mut d, &options).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserOptions {
+    /// Maximum nesting depth of arrays and objects, documents nested deeper
+    /// than this are rejected with `ErrorType::DepthExceeded`. Defaults to
+    /// `DEFAULT_MAX_DEPTH`, before this option existed the depth was
+    /// unlimited which `usize::MAX` restores.
+    pub max_depth: usize,
+    /// Maximum size of the input in bytes, larger inputs are rejected
+    /// with `ErrorType::InputTooLarge` before any parsing happens. Inputs
+    /// larger than 4 GiB are always rejected.
+    pub max_document_size: usize,
+    /// Maximum length of a string or object key in bytes (after
+    /// unescaping), longer strings are rejected with `ErrorType::StringTooLong`.
+    pub max_string_length: usize,
+    /// Maximum number of elements in a single array or members in a single
+    /// object, larger ones are rejected with `ErrorType::TooManyElements`.
+    pub max_elements: usize,
+    /// Keep numbers as they appear in the input (`RawNumber`) so that
+    /// serializing the value reproduces them exactly, e.g. `1.30` or `1e2`.
+    /// They are converted when accessed.
+    pub lossless_numbers: bool,
+    /// What to do with integers that don't fit into an `i64` or a `u64`.
+    pub big_numbers: BigNumberBehavior,
+    /// Skip a UTF-8 byte order mark (`EF BB BF`) at the start of the input.
+    pub skip_bom: bool,
+    /// Ignore NUL bytes (and JSON whitespace mixed with them) before and
+    /// after the document, as left behind by producers writing into fixed
+    /// size or zero padded buffers.
+    pub trim_nul_padding: bool,
+    /// Replace invalid UTF-8 in the input and lone surrogates in `\u`
+    /// escapes with U+FFFD instead of rejecting the document. Lone
+    /// surrogates are replaced by all parsers, invalid UTF-8 can only be
+    /// replaced when parsing into values that don't borrow from the input:
+    /// `to_owned_value_with_options`, `load_with_options` and the ndjson
+    /// reader.
+    pub lossy_utf8: bool,
+    /// How object keys are matched to the fields of structs when
+    /// deserializing them with serde.
+    pub field_matching: FieldMatching,
+    /// Rewrite all object keys to this case when parsing into values. Keys
+    /// that end up the same after rewriting are collapsed, the last one
+    /// wins.
+    pub key_case: Option<KeyCase>,
+    /// Intern string values of at most this many bytes when parsing into
+    /// `OwnedValue`s, so repeated values of low cardinality fields like
+    /// `"level": "INFO"` share one allocation. Strings can only be shared
+    /// with the `arc-strings` feature, the interner keeps every distinct
+    /// string it has seen alive so the limit should be kept small.
+    pub intern_strings: Option<usize>,
+    /// Accept documents that are a single scalar like `42`, `"hi"`, `true`
+    /// or `null`, as RFC 8259 allows. Without it only arrays and objects
+    /// are accepted and scalars are rejected with `ErrorType::ScalarRoot`.
+    pub allow_scalar_root: bool,
+    /// What to do with content after the end of the document.
+    pub trailing: TrailingData,
+}
+
+impl ParserOptions {
+    // The part of the input the parser looks at once a BOM, NUL padding and
+    // trailing data are skipped as requested.
+    pub(crate) fn content_range(&self, input: &[u8]) -> Range<usize> {
+        let mut start = 0;
+        let mut end = input.len();
+        if self.skip_bom && input.starts_with(b"\xef\xbb\xbf") {
+            start = 3;
+        }
+        if self.trim_nul_padding {
+            let is_padding = |c: &u8| *c == 0 || is_json_whitespace(*c);
+            while start < end && is_padding(&input[start]) {
+                start += 1;
+            }
+            while end > start && is_padding(&input[end - 1]) {
+                end -= 1;
+            }
+        }
+        if self.trailing == TrailingData::Ignore {
+            end = end.min(self.document_end(input));
+        }
+        start..end
+    }
+
+    // The offset one past the end of the first document in the input, or
+    // the end of the input if it doesn't end before that.
+    pub(crate) fn document_end(&self, input: &[u8]) -> usize {
+        let mut start = 0;
+        if self.skip_bom && input.starts_with(b"\xef\xbb\xbf") {
+            start = 3;
+        }
+        if self.trim_nul_padding {
+            let is_padding = |c: &u8| *c == 0 || is_json_whitespace(*c);
+            while input.get(start).map_or(false, is_padding) {
+                start += 1;
+            }
+        }
+        // the scanner skips leading whitespace itself
+        Scanner::default()
+            .scan(&input[start..])
+            .map_or(input.len(), |end| start + end)
+    }
+}
+
+/// How integers that don't fit into an `i64` (if negative) or a `u64` are
+/// handled by the parser.
+///
+/// ```
+/// use simd_json::{BigNumberBehavior, ParserOptions, ValueAccess};
+/// let options = ParserOptions {
+///     big_numbers: BigNumberBehavior::Saturate,
+///     ..ParserOptions::default()
+/// };
+/// let mut d = br#"[18446744073709551616]"#.to_vec();
+/// let v = simd_json::to_owned_value_with_options(&mut d, &options).unwrap();
+/// assert_eq!(v.get_idx(0).and_then(ValueAccess::as_u64), Some(u64::max_value()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BigNumberBehavior {
+    /// Reject them with `ErrorType::Overflow`.
+    Error,
+    /// Clamp them to `u64::max_value()` or `i64::min_value()`.
+    Saturate,
+    /// Convert them to the nearest `f64`.
+    Float,
+    /// Keep the literal as a `RawNumber`, serde visitors get it as a string.
+    /// With `lossless_numbers` all numbers are kept like this regardless of
+    /// this option.
+    Raw,
+}
+
+/// What the parser does with content after the end of the document, like
+/// the next frame of a protocol that sends documents back to back.
+///
+/// ```
+/// use simd_json::{json, ParserOptions, TrailingData};
+/// let options = ParserOptions {
+///     trailing: TrailingData::Ignore,
+///     ..ParserOptions::default()
+/// };
+/// let mut d = br#"{"a": 1} {"b"#.to_vec();
+/// let v = simd_json::to_owned_value_with_options(&mut d, &options).unwrap();
+/// assert_eq!(v, json!({"a": 1}));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrailingData {
+    /// Reject anything but whitespace with `ErrorType::TrailingCharacters`.
+    Error,
+    /// Ignore everything after the end of the document, it isn't looked at
+    /// so it doesn't have to be valid JSON or even valid UTF-8. The
+    /// `*_with_rest` functions return where it starts.
+    Ignore,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_document_size: usize::max_value(),
+            max_string_length: usize::max_value(),
+            max_elements: usize::max_value(),
+            lossless_numbers: false,
+            big_numbers: BigNumberBehavior::Error,
+            skip_bom: false,
+            trim_nul_padding: false,
+            lossy_utf8: false,
+            field_matching: FieldMatching::default(),
+            key_case: None,
+            intern_strings: None,
+            allow_scalar_root: true,
+            trailing: TrailingData::Error,
+        }
+    }
+}
+
+/// Relaxed matching of object keys to struct fields for typed
+/// deserialization, for input whose key casing varies and can't be
+/// handled with serde attributes. Keys are first looked up in `renames`,
+/// then matched exactly and, if `case_insensitive` is set, ignoring ASCII
+/// case. Keys that match no field are passed on unchanged.
+///
+/// ```
+/// use serde::Deserialize;
+/// use simd_json::{FieldMatching, ParserOptions};
+/// #[derive(Deserialize)]
+/// struct User {
+///     user_id: u64,
+///     name: String,
+/// }
+/// let mut options = ParserOptions::default();
+/// options.field_matching.case_insensitive = true;
+/// options
+///     .field_matching
+///     .renames
+///     .insert("userId".to_string(), "user_id".to_string());
+/// let mut d = br#"{"userId": 7, "NAME": "ann"}"#.to_vec();
+/// let user: User = simd_json::serde::from_slice_with_options(&mut d, &options).unwrap();
+/// assert_eq!((user.user_id, user.name.as_str()), (7, "ann"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldMatching {
+    /// Match keys to fields ignoring ASCII case.
+    pub case_insensitive: bool,
+    /// Keys to rename before matching them, e.g. `"userId"` to `"user_id"`.
+    pub renames: HashMap<String, String>,
+}
+
+impl FieldMatching {
+    /// If keys are matched exactly, as serde does by default.
+    pub fn is_exact(&self) -> bool {
+        !self.case_insensitive && self.renames.is_empty()
+    }
+
+    // The field `key` is matched to, if any.
+    #[cfg(feature = "serde_impl")]
+    pub(crate) fn field(&self, key: &str, fields: &[&'static str]) -> Option<&'static str> {
+        let key = self.renames.get(key).map_or(key, String::as_str);
+        fields.iter().copied().find(|f| *f == key).or_else(|| {
+            if self.case_insensitive {
+                fields.iter().copied().find(|f| f.eq_ignore_ascii_case(key))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Options controlling the output of the generator, used by
+/// `encode_with_options` and `write_with_options` on the value types.
+/// The defaults produce the same compact output as `encode`.
+///
+/// ```
+/// use simd_json::{json, SerializeOptions};
+/// let options = SerializeOptions {
+///     trailing_newline: true,
+///     max_line_width: Some(20),
+///     ..SerializeOptions::pretty()
+/// };
+/// let v = json!({"a": [1, 2, 3]});
+/// assert_eq!(v.encode_with_options(&options), "{\n  \"a\": [1, 2, 3]\n}\n");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerializeOptions {
+    /// Number of spaces per indentation level, `None` writes everything
+    /// on a single line.
+    pub indent: Option<u16>,
+    /// Terminate the output with a newline.
+    pub trailing_newline: bool,
+    /// Maximum line width when indenting. Arrays that only contain scalars
+    /// are written on one line if they fit, or filled onto as few lines as
+    /// possible otherwise, instead of placing each element on its own line.
+    pub max_line_width: Option<usize>,
+    /// Write a space after the `:` separating keys and values.
+    pub space_after_colon: bool,
+    /// Write a space after `,` when the next element is on the same line.
+    pub space_after_comma: bool,
+    /// How floats are written.
+    pub float_format: FloatFormat,
+    /// Escape all non-ASCII characters in strings as `\uXXXX`, using
+    /// surrogate pairs for characters outside the basic multilingual plane.
+    pub escape_non_ascii: bool,
+    /// Escape `<`, `>` and `&` as `\u003c`, `\u003e` and `\u0026` and `/`
+    /// as `\/` so the output can be embedded in HTML `<script>` tags.
+    pub escape_html: bool,
+    /// Object members to mask or leave out of the output.
+    pub redact: Redaction,
+    /// Limits on how much of a value is written.
+    pub truncate: Truncation,
+    /// Write all object keys in this case, keys are matched against
+    /// `redact` before they are rewritten.
+    pub key_case: Option<KeyCase>,
+}
+
+/// Limits for bounding the size of the output, for example when logging
+/// samples of payloads. Whatever exceeds a limit is replaced by a string
+/// saying how much was left out:
+///
+/// * strings longer than `max_string_length` characters are cut and end
+///   in `…(+N chars)`
+/// * arrays with more than `max_items` elements end in an `"…(+N items)"`
+///   element, objects with more than `max_items` members in a
+///   `"…": "(+N members)"` member
+/// * non-empty arrays and objects nested more than `max_depth` levels deep
+///   are written as `"…(N items)"` or `"…(N members)"`, a `max_depth` of
+///   `0` elides the value itself if it is an array or object
+///
+/// ```
+/// use simd_json::{json, SerializeOptions, Truncation};
+/// let v = json!({"id": "0123456789", "meta": {"a": 1, "b": 2}, "rows": [[1], [2], [3]]});
+/// let options = SerializeOptions {
+///     truncate: Truncation {
+///         max_string_length: Some(4),
+///         max_items: Some(2),
+///         max_depth: Some(1),
+///     },
+///     ..SerializeOptions::default()
+/// };
+/// assert_eq!(
+///     v.encode_with_options(&options),
+///     r#"{"id":"0123…(+6 chars)","meta":"…(2 members)","…":"(+1 members)"}"#
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Truncation {
+    /// Maximum number of characters of strings, object keys are never cut
+    pub max_string_length: Option<usize>,
+    /// Maximum number of elements of arrays and members of objects
+    pub max_items: Option<usize>,
+    /// Maximum nesting depth of arrays and objects
+    pub max_depth: Option<usize>,
+}
+
+/// Object members the generator masks or drops while encoding, so
+/// sensitive fields never end up in the output without having to copy
+/// and modify the value first. A member is redacted if its key is one of
+/// `keys` or its JSON pointer matches one of `pointers`, where a `*` token
+/// in a pointer matches any key or array index.
+///
+/// ```
+/// use simd_json::{json, Redaction, RedactMode, SerializeOptions};
+/// let v = json!({"cards": [{"number": "4111"}], "password": "hunter2", "user": "ann"});
+/// let redact = Redaction {
+///     keys: vec!["password".to_string()],
+///     pointers: vec!["/cards/*/number".to_string()],
+///     ..Redaction::default()
+/// };
+/// let mut options = SerializeOptions {
+///     redact,
+///     ..SerializeOptions::default()
+/// };
+/// assert_eq!(
+///     v.encode_with_options(&options),
+///     r#"{"cards":[{"number":"***"}],"password":"***","user":"ann"}"#
+/// );
+/// options.redact.mode = RedactMode::Drop;
+/// assert_eq!(v.encode_with_options(&options), r#"{"cards":[{}],"user":"ann"}"#);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Redaction {
+    /// Keys of members to redact wherever they appear
+    pub keys: Vec<String>,
+    /// JSON pointers ([RFC 6901]) of members to redact
+    ///
+    /// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+    pub pointers: Vec<String>,
+    /// What happens to redacted members
+    pub mode: RedactMode,
+}
+
+impl Redaction {
+    /// If nothing is redacted
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.pointers.is_empty()
+    }
+}
+
+/// How redacted members are written, see `Redaction`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedactMode {
+    /// Write `"***"` in place of the value
+    Mask,
+    /// Leave out the member entirely
+    Drop,
+}
+
+impl Default for RedactMode {
+    fn default() -> Self {
+        RedactMode::Mask
+    }
+}
+
+/// Formatting of floats in the generator, numbers kept as `RawNumber`
+/// are always written as they are.
+///
+/// ```
+/// use simd_json::{json, FloatFormat, SerializeOptions};
+/// let v = json!([1e21, 0.5, 1.25e-7]);
+/// let with = |float_format| SerializeOptions {
+///     float_format,
+///     ..SerializeOptions::default()
+/// };
+/// assert_eq!(v.encode_with_options(&with(FloatFormat::Shortest)), "[1e21,0.5,1.25e-7]");
+/// assert_eq!(v.encode_with_options(&with(FloatFormat::Fixed(2))), "[1000000000000000000000.00,0.50,0.00]");
+/// assert_eq!(
+///     v.encode_with_options(&with(FloatFormat::Scientific { threshold: 10 })),
+///     "[1e21,0.5,0.000000125]"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FloatFormat {
+    /// The shortest representation that round-trips, using exponent
+    /// notation for very large and small numbers. This matches `encode`.
+    Shortest,
+    /// A fixed number of digits after the decimal point, never using
+    /// exponent notation.
+    Fixed(u16),
+    /// The shortest representation that round-trips, using exponent
+    /// notation only when the absolute decimal exponent is at least
+    /// `threshold`. `u16::max_value()` never uses exponent notation.
+    Scientific {
+        /// Smallest absolute exponent written in exponent notation
+        threshold: u16,
+    },
+}
+
+impl SerializeOptions {
+    /// Options matching the output of `encode_pp`.
+    pub fn pretty() -> Self {
+        Self {
+            indent: Some(2),
+            trailing_newline: false,
+            max_line_width: None,
+            space_after_colon: true,
+            space_after_comma: true,
+            float_format: FloatFormat::Shortest,
+            escape_non_ascii: false,
+            escape_html: false,
+            redact: Redaction::default(),
+            truncate: Truncation::default(),
+            key_case: None,
+        }
+    }
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self {
+            indent: None,
+            trailing_newline: false,
+            max_line_width: None,
+            space_after_colon: false,
+            space_after_comma: false,
+            float_format: FloatFormat::Shortest,
+            escape_non_ascii: false,
+            escape_html: false,
+            redact: Redaction::default(),
+            truncate: Truncation::default(),
+            key_case: None,
+        }
+    }
+}
+
+/// Naming convention object keys are rewritten to by `ParserOptions` and
+/// `SerializeOptions`. Keys are split into words at `_`, `-` and spaces,
+/// between a lowercase letter or digit and an uppercase letter, and before
+/// the last uppercase letter of an acronym followed by a lowercase one.
+///
+/// ```
+/// use simd_json::KeyCase;
+/// assert_eq!(KeyCase::SnakeCase.apply("userHTTPId"), "user_http_id");
+/// assert_eq!(KeyCase::CamelCase.apply("user_id"), "userId");
+/// assert_eq!(KeyCase::PascalCase.apply("user-id"), "UserId");
+/// assert_eq!(KeyCase::KebabCase.apply("UserId"), "user-id");
+/// assert_eq!(KeyCase::Lowercase.apply("X-Request-ID"), "x-request-id");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyCase {
+    /// `snake_case`
+    SnakeCase,
+    /// `camelCase`
+    CamelCase,
+    /// `PascalCase`
+    PascalCase,
+    /// `kebab-case`
+    KebabCase,
+    /// ASCII letters lowercased, the key is otherwise kept as it is, this
+    /// normalizes keys for `ValueAccess::get_ignore_case`
+    Lowercase,
+}
+
+impl KeyCase {
+    /// Rewrites `key` to this case
+    pub fn apply(self, key: &str) -> String {
+        // the separator between words and if the first and the following
+        // words start with an uppercase letter
+        let (separator, capitalize_first, capitalize_rest) = match self {
+            Self::Lowercase => return key.to_ascii_lowercase(),
+            Self::SnakeCase => (Some('_'), false, false),
+            Self::KebabCase => (Some('-'), false, false),
+            Self::CamelCase => (None, false, true),
+            Self::PascalCase => (None, true, true),
+        };
+        let mut out = String::with_capacity(key.len() + 4);
+        for (i, word) in words(key).into_iter().enumerate() {
+            if i > 0 {
+                out.extend(separator);
+            }
+            let mut chars = word.chars();
+            if (i == 0 && capitalize_first) || (i > 0 && capitalize_rest) {
+                if let Some(c) = chars.next() {
+                    out.extend(c.to_uppercase());
+                }
+            }
+            out.extend(chars.flat_map(char::to_lowercase));
+        }
+        out
+    }
+}
+
+// Splits a key into the words `KeyCase` joins again
+fn words(key: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = key.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, &(idx, c)) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c == ' ' {
+            if let Some(start) = start.take() {
+                words.push(&key[start..idx]);
+            }
+            continue;
+        }
+        match start {
+            None => start = Some(idx),
+            Some(s) if c.is_uppercase() => {
+                let prev = chars[i - 1].1;
+                let next_lower = chars.get(i + 1).map_or(false, |(_, n)| n.is_lowercase());
+                if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower) {
+                    words.push(&key[s..idx]);
+                    start = Some(idx);
+                }
+            }
+            Some(_) => (),
+        }
+    }
+    if let Some(start) = start {
+        words.push(&key[start..]);
+    }
+    words
+}
+
+/// What `ValueTrait::compact_with_options` removes, by default `null`
+/// members and members that are empty arrays or objects. Array elements
+/// are only removed with `in_arrays`, as this changes the indexes of the
+/// remaining elements.
+///
+/// ```
+/// use simd_json::{json, CompactOptions, ValueAccess, ValueTrait};
+/// let mut v = json!({"a": null, "b": [null, {}], "c": {"d": []}, "e": 0});
+/// let options = CompactOptions {
+///     in_arrays: true,
+///     ..CompactOptions::default()
+/// };
+/// assert_eq!(v.compact_with_options(&options), 6);
+/// assert_eq!(v, json!({"e": 0}));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompactOptions {
+    /// Remove `null` values
+    pub nulls: bool,
+    /// Remove empty arrays
+    pub empty_arrays: bool,
+    /// Remove empty objects
+    pub empty_objects: bool,
+    /// Also remove array elements, not only object members
+    pub in_arrays: bool,
+}
+
+impl Default for CompactOptions {
+    fn default() -> Self {
+        Self {
+            nulls: true,
+            empty_arrays: true,
+            empty_objects: true,
+            in_arrays: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{to_borrowed_value_with_options, to_owned_value_with_options, ErrorType};
+
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut d = Vec::with_capacity(depth * 2);
+        d.resize(depth, b'[');
+        d.resize(depth * 2, b']');
+        d
+    }
+
+    #[test]
+    fn default_depth() {
+        // building values doesn't recurse, dropping them does, give that
+        // enough stack in debug builds
+        let test = std::thread::Builder::new()
+            .stack_size(16 * 1024 * 1024)
+            .spawn(|| {
+                let options = ParserOptions::default();
+                let mut d = nested(DEFAULT_MAX_DEPTH);
+                assert!(to_owned_value_with_options(&mut d, &options).is_ok());
+                let mut d = nested(DEFAULT_MAX_DEPTH + 1);
+                let e = to_owned_value_with_options(&mut d, &options).expect_err("too deep");
+                assert_eq!(e.error(), &ErrorType::DepthExceeded);
+                assert_eq!(e.index(), DEFAULT_MAX_DEPTH);
+                let unlimited = ParserOptions {
+                    max_depth: usize::MAX,
+                    ..ParserOptions::default()
+                };
+                let mut d = nested(DEFAULT_MAX_DEPTH + 1);
+                assert!(to_owned_value_with_options(&mut d, &unlimited).is_ok());
+                let mut d = nested(DEFAULT_MAX_DEPTH + 1);
+                assert!(to_borrowed_value_with_options(&mut d, &unlimited).is_ok());
+            })
+            .expect("spawn");
+        test.join().expect("no panic");
+    }
+
+    #[test]
+    fn custom_depth() {
+        let options = ParserOptions {
+            max_depth: 2,
+            ..ParserOptions::default()
+        };
+        let mut d = br#"{"a": [1]}"#.to_vec();
+        assert!(to_borrowed_value_with_options(&mut d, &options).is_ok());
+        let mut d = br#"{"a": [{}]}"#.to_vec();
+        let e = to_borrowed_value_with_options(&mut d, &options).expect_err("too deep");
+        assert_eq!(e.error(), &ErrorType::DepthExceeded);
+        assert_eq!(e.index(), 7);
+    }
+
+    #[test]
+    fn document_size() {
+        let options = ParserOptions {
+            max_document_size: 8,
+            ..ParserOptions::default()
+        };
+        let mut d = br#"[1,2,3]"#.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_ok());
+        let mut d = br#"[1,2,3,4]"#.to_vec();
+        let e = to_owned_value_with_options(&mut d, &options).expect_err("too large");
+        assert_eq!(e.error(), &ErrorType::InputTooLarge);
+    }
+
+    #[test]
+    fn string_length() {
+        let options = ParserOptions {
+            max_string_length: 3,
+            ..ParserOptions::default()
+        };
+        let mut d = br#"{"abc": "d\ne"}"#.to_vec();
+        assert!(to_borrowed_value_with_options(&mut d, &options).is_ok());
+        let mut d = br#"{"abc": "d\n\ne"}"#.to_vec();
+        let e = to_borrowed_value_with_options(&mut d, &options).expect_err("too long");
+        assert_eq!(e.error(), &ErrorType::StringTooLong);
+        assert_eq!(e.index(), 8);
+        let mut d = br#"{"abcd": 1}"#.to_vec();
+        let e = to_owned_value_with_options(&mut d, &options).expect_err("too long");
+        assert_eq!(e.error(), &ErrorType::StringTooLong);
+        let mut d = br#"["abcd"]"#.to_vec();
+        let e = crate::serde::from_slice_with_options::<Vec<String>>(&mut d, &options)
+            .expect_err("too long");
+        assert_eq!(e.error(), &ErrorType::StringTooLong);
+    }
+
+    #[test]
+    fn elements() {
+        let options = ParserOptions {
+            max_elements: 2,
+            ..ParserOptions::default()
+        };
+        let mut d = br#"[[1, 2], {"a": 1, "b": 2}]"#.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_ok());
+        let mut d = br#"[[1, 2, 3]]"#.to_vec();
+        let e = to_owned_value_with_options(&mut d, &options).expect_err("too many");
+        assert_eq!(e.error(), &ErrorType::TooManyElements);
+        let mut d = br#"{"a": 1, "b": 2, "c": 3}"#.to_vec();
+        let e = to_owned_value_with_options(&mut d, &options).expect_err("too many");
+        assert_eq!(e.error(), &ErrorType::TooManyElements);
+    }
+
+    #[test]
+    fn lossless_numbers() {
+        use crate::{BorrowedValue, OwnedValue, ValueAccess, ValueType};
+        let options = ParserOptions {
+            lossless_numbers: true,
+            ..ParserOptions::default()
+        };
+        let input = r#"{"a":1.30,"b":[1e2,-0,42],"c":-12.5E-1}"#;
+        let mut d = input.as_bytes().to_vec();
+        let v = to_borrowed_value_with_options(&mut d, &options).expect("valid");
+        assert_eq!(v.encode(), input);
+        assert_eq!(v["a"], BorrowedValue::RawNumber("1.30".into()));
+        assert_eq!(v["a"].as_f64(), Some(1.3));
+        assert_eq!(
+            v["b"].get_idx(0).map(ValueAccess::value_type),
+            Some(ValueType::F64)
+        );
+        assert_eq!(v["b"].get_idx(2).and_then(ValueAccess::as_i64), Some(42));
+        assert_eq!(v, json!({"a": 1.3, "b": [100.0, 0, 42], "c": -1.25}));
+
+        let mut d = input.as_bytes().to_vec();
+        let v = to_owned_value_with_options(&mut d, &options).expect("valid");
+        assert_eq!(v.encode(), input);
+        assert_eq!(v["b"].get_idx(1), Some(&OwnedValue::RawNumber("-0".into())));
+        let s = serde_json::to_string(&v).expect("serializable");
+        assert_eq!(s, r#"{"a":1.3,"b":[100.0,0,42],"c":-1.25}"#);
+        let mut d = br#"[1.2.3]"#.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_err());
+    }
+
+    #[test]
+    fn bom_and_padding() {
+        let input = b"\xef\xbb\xbf \x00{\"a\": [1]}\n\x00\x00\x00";
+        let mut d = input.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &ParserOptions::default()).is_err());
+
+        let options = ParserOptions {
+            skip_bom: true,
+            ..ParserOptions::default()
+        };
+        let mut d = b"\xef\xbb\xbf{\"a\": [1]} ".to_vec();
+        let v = to_owned_value_with_options(&mut d, &options).expect("valid");
+        assert_eq!(v, json!({"a": [1]}));
+        let mut d = input.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_err());
+
+        let options = ParserOptions {
+            skip_bom: true,
+            trim_nul_padding: true,
+            ..ParserOptions::default()
+        };
+        let mut d = input.to_vec();
+        let v = to_borrowed_value_with_options(&mut d, &options).expect("valid");
+        assert_eq!(v, json!({"a": [1]}));
+        let mut d = input.to_vec();
+        assert!(crate::validate_with_options(&mut d, &options).is_ok());
+        let mut d = b"\x00\x00".to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_err());
+        let mut d = b"[1]\x0c\x00".to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_err());
+    }
+
+    #[test]
+    fn lossy_utf8() {
+        use crate::ValueAccess;
+        let options = ParserOptions {
+            lossy_utf8: true,
+            ..ParserOptions::default()
+        };
+        let input = b"[\"a\xffb\", \"\\ud800\", \"\\udc00x\\ud83d\\ude00\", \"\\ud800\\u0041\"]";
+        let mut d = input.to_vec();
+        let e = to_owned_value_with_options(&mut d, &ParserOptions::default()).expect_err("strict");
+        assert_eq!(e.error(), &ErrorType::InvalidUTF8);
+
+        let mut d = input.to_vec();
+        let v = to_owned_value_with_options(&mut d, &options).expect("lossy");
+        assert_eq!(
+            v,
+            json!(["a\u{fffd}b", "\u{fffd}", "\u{fffd}x😀", "\u{fffd}A"])
+        );
+
+        // borrowed values can replace surrogates but not invalid bytes
+        let mut d = br#"["\ud800", "\udfff"]"#.to_vec();
+        let v = to_borrowed_value_with_options(&mut d, &options).expect("lossy");
+        assert_eq!(v.get_idx(1).and_then(ValueAccess::as_str), Some("\u{fffd}"));
+        let mut d = input.to_vec();
+        assert!(to_borrowed_value_with_options(&mut d, &options).is_err());
+
+        let mut d = br#"["\u00zz"]"#.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_err());
+    }
+
+    #[test]
+    fn big_numbers() {
+        use crate::{BorrowedValue, OwnedValue, ValueAccess};
+        let input = r#"[18446744073709551616, -9223372036854775809, -9223372036854775808]"#;
+        let with = |big_numbers| ParserOptions {
+            big_numbers,
+            ..ParserOptions::default()
+        };
+
+        let mut d = input.as_bytes().to_vec();
+        let e = to_owned_value_with_options(&mut d, &with(BigNumberBehavior::Error))
+            .expect_err("overflow");
+        assert_eq!(e.error(), &ErrorType::Overflow);
+
+        let mut d = input.as_bytes().to_vec();
+        let v =
+            to_owned_value_with_options(&mut d, &with(BigNumberBehavior::Saturate)).expect("valid");
+        assert_eq!(
+            v,
+            json!([u64::max_value(), i64::min_value(), i64::min_value()])
+        );
+
+        let mut d = input.as_bytes().to_vec();
+        let v =
+            to_borrowed_value_with_options(&mut d, &with(BigNumberBehavior::Float)).expect("valid");
+        assert_eq!(
+            v.get_idx(0).and_then(ValueAccess::as_f64),
+            Some(1.844_674_407_370_955_2e19)
+        );
+        assert_eq!(
+            v.get_idx(2).and_then(ValueAccess::as_i64),
+            Some(i64::min_value())
+        );
+
+        let mut d = input.as_bytes().to_vec();
+        let v =
+            to_borrowed_value_with_options(&mut d, &with(BigNumberBehavior::Raw)).expect("valid");
+        assert_eq!(
+            v.get_idx(0),
+            Some(&BorrowedValue::RawNumber("18446744073709551616".into()))
+        );
+        assert_eq!(
+            v.get_idx(2).and_then(ValueAccess::as_i64),
+            Some(i64::min_value())
+        );
+        let mut d = input.as_bytes().to_vec();
+        let v = to_owned_value_with_options(&mut d, &with(BigNumberBehavior::Raw)).expect("valid");
+        assert_eq!(
+            v.get_idx(1),
+            Some(&OwnedValue::RawNumber("-9223372036854775809".into()))
+        );
+
+        let mut d = input.as_bytes().to_vec();
+        let v: Vec<serde_json::Value> =
+            crate::serde::from_slice_with_options(&mut d, &with(BigNumberBehavior::Raw))
+                .expect("valid");
+        assert_eq!(v[0], "18446744073709551616");
+
+        let mut d = br#"[184467440737095516160x]"#.to_vec();
+        let e = to_owned_value_with_options(&mut d, &with(BigNumberBehavior::Raw))
+            .expect_err("invalid");
+        assert_eq!(e.error(), &ErrorType::InvalidNumber);
+    }
+
+    #[test]
+    fn scalars_ignore_depth() {
+        let options = ParserOptions {
+            max_depth: 0,
+            ..ParserOptions::default()
+        };
+        let mut d = br#"42"#.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_ok());
+        let mut d = br#"[]"#.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_err());
+    }
+
+    #[test]
+    fn scalar_root() {
+        let options = ParserOptions {
+            allow_scalar_root: false,
+            ..ParserOptions::default()
+        };
+        for doc in &["42", "-1.5", r#""hi""#, "true", "false", " null "] {
+            let mut d = doc.as_bytes().to_vec();
+            let v = crate::to_owned_value(&mut d).expect("scalar");
+            let mut d = doc.as_bytes().to_vec();
+            assert_eq!(crate::to_borrowed_value(&mut d).expect("scalar"), v);
+            let mut d = doc.as_bytes().to_vec();
+            let e = to_owned_value_with_options(&mut d, &options).expect_err("scalar");
+            assert_eq!(e.error(), &ErrorType::ScalarRoot);
+            let mut d = doc.as_bytes().to_vec();
+            let e = to_borrowed_value_with_options(&mut d, &options).expect_err("scalar");
+            assert_eq!(e.error(), &ErrorType::ScalarRoot);
+            let mut d = doc.as_bytes().to_vec();
+            let e = crate::validate_with_options(&mut d, &options).expect_err("scalar");
+            assert_eq!(e.error(), &ErrorType::ScalarRoot);
+        }
+        let mut d = br#"{"a": 1}"#.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &options).is_ok());
+        let mut d = br#"[1]"#.to_vec();
+        assert!(to_borrowed_value_with_options(&mut d, &options).is_ok());
+        let mut d = br#"x"#.to_vec();
+        let e = to_owned_value_with_options(&mut d, &options).expect_err("invalid");
+        assert_ne!(e.error(), &ErrorType::ScalarRoot);
+    }
+
+    #[test]
+    fn trailing_data() {
+        let ignore = ParserOptions {
+            trailing: TrailingData::Ignore,
+            ..ParserOptions::default()
+        };
+        for (doc, expected) in &[
+            (&b"{\"a\": 1} {\"b"[..], json!({"a": 1})),
+            (b"[1]\xff\xfe", json!([1])),
+            (b" \"s\"x", json!("s")),
+            (b"42 garbage", json!(42)),
+            (b"null,", json!(null)),
+        ] {
+            let mut d = doc.to_vec();
+            let e = crate::to_owned_value(&mut d).expect_err("trailing data");
+            assert_ne!(e.error(), &ErrorType::InternalError);
+            let mut d = doc.to_vec();
+            let v = to_owned_value_with_options(&mut d, &ignore).expect("ignored");
+            assert_eq!(&v, expected);
+            let mut d = doc.to_vec();
+            let v = to_borrowed_value_with_options(&mut d, &ignore).expect("ignored");
+            assert_eq!(&v, expected);
+            let mut d = doc.to_vec();
+            assert!(crate::validate_with_options(&mut d, &ignore).is_ok());
+        }
+        let mut d = br#"{"a": 1} 2"#.to_vec();
+        let e = crate::to_owned_value(&mut d).expect_err("trailing data");
+        assert_eq!(e.error(), &ErrorType::TrailingCharacters);
+        let options = ParserOptions {
+            trim_nul_padding: true,
+            skip_bom: true,
+            ..ignore.clone()
+        };
+        let mut d = b"\xef\xbb\xbf\0 \0[1]\0\0 junk".to_vec();
+        let v = to_owned_value_with_options(&mut d, &options).expect("ignored");
+        assert_eq!(v, json!([1]));
+        let mut d = br#"[1, {"a"#.to_vec();
+        assert!(to_owned_value_with_options(&mut d, &ignore).is_err());
+    }
+
+    #[test]
+    fn with_rest() {
+        let options = ParserOptions::default();
+        let mut d = br#" {"a": [1]}[2] 3"#.to_vec();
+        let (v, end) = crate::to_owned_value_with_rest(&mut d, &options).expect("valid");
+        assert_eq!((v, end), (json!({"a": [1]}), 11));
+        let end = {
+            let (v, end) =
+                crate::to_borrowed_value_with_rest(&mut d[11..], &options).expect("valid");
+            assert_eq!(v, json!([2]));
+            end
+        };
+        assert_eq!(end, 3);
+        let (v, end) = crate::to_owned_value_with_rest(&mut d[14..], &options).expect("valid");
+        assert_eq!((v, end), (json!(3), 2));
+        let mut d = br#"[1"#.to_vec();
+        assert!(crate::to_owned_value_with_rest(&mut d, &options).is_err());
+    }
+
+    #[cfg(feature = "serde_impl")]
+    #[test]
+    fn typed_scalar_root() {
+        use crate::serde::{from_slice, from_slice_with_options};
+        let mut d = br#"42"#.to_vec();
+        assert_eq!(from_slice::<u8>(&mut d).expect("scalar"), 42);
+        let mut d = br#""hi""#.to_vec();
+        assert_eq!(from_slice::<String>(&mut d).expect("scalar"), "hi");
+        let mut d = br#"null"#.to_vec();
+        assert_eq!(from_slice::<Option<bool>>(&mut d).expect("scalar"), None);
+        let options = ParserOptions {
+            allow_scalar_root: false,
+            ..ParserOptions::default()
+        };
+        let mut d = br#"true"#.to_vec();
+        let e = from_slice_with_options::<bool>(&mut d, &options).expect_err("scalar");
+        assert_eq!(e.error(), &ErrorType::ScalarRoot);
+        let mut d = br#"[true]"#.to_vec();
+        let v: Vec<bool> = from_slice_with_options(&mut d, &options).expect("array");
+        assert_eq!(v, vec![true]);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::value::{borrowed, owned};
+#[cfg(feature = "serde_impl")]
+use crate::Deserializer;
+use crate::{BorrowedValue, KeyInterner, OwnedValue, ParserOptions, Result};
+use std::cell::RefCell;
+
+// Buffers for documents larger than this are released after `with_parser`
+// so a single huge document doesn't keep its memory alive on the thread.
+const MAX_POOLED_SIZE: usize = 16 * 1024 * 1024;
+
+// The memory the parser allocates for a document
+#[derive(Debug, Default)]
+pub(crate) struct Buffers {
+    pub(crate) structural_indexes: Vec<u32>,
+    pub(crate) counts: Vec<usize>,
+    pub(crate) strings: Vec<u8>,
+    // the input is copied here if it ends too close to a page boundary
+    pub(crate) copy: Vec<u8>,
+}
+
+/// A parser that keeps the memory it allocates for a document (the
+/// structural indexes, element counts and scratch space for strings) and
+/// reuses it for the next one, saving these allocations when parsing many
+/// documents.
+///
+/// ```
+/// use simd_json::{json, Parser};
+/// let mut parser = Parser::new();
+/// for i in 0..3 {
+///     let mut d = format!(r#"{{"id": {}}}"#, i).into_bytes();
+///     assert_eq!(parser.to_owned_value(&mut d).unwrap(), json!({"id": i}));
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Parser {
+    pub(crate) buffers: Buffers,
+}
+
+impl Parser {
+    /// Creates a parser, it doesn't allocate until the first document
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Same as `to_owned_value` but reuses the parser's memory
+    pub fn to_owned_value(&mut self, input: &mut [u8]) -> Result<OwnedValue> {
+        self.to_owned_value_with_options(input, &ParserOptions::default())
+    }
+
+    /// Same as `to_owned_value_with_options` but reuses the parser's memory
+    pub fn to_owned_value_with_options(
+        &mut self,
+        input: &mut [u8],
+        options: &ParserOptions,
+    ) -> Result<OwnedValue> {
+        owned::to_value_with_buffers(
+            input,
+            options,
+            &mut KeyInterner::default(),
+            &mut self.buffers,
+        )
+    }
+
+    /// Same as `to_borrowed_value` but reuses the parser's memory
+    pub fn to_borrowed_value<'v>(&mut self, input: &'v mut [u8]) -> Result<BorrowedValue<'v>> {
+        self.to_borrowed_value_with_options(input, &ParserOptions::default())
+    }
+
+    /// Same as `to_borrowed_value_with_options` but reuses the parser's
+    /// memory
+    pub fn to_borrowed_value_with_options<'v>(
+        &mut self,
+        input: &'v mut [u8],
+        options: &ParserOptions,
+    ) -> Result<BorrowedValue<'v>> {
+        borrowed::to_value_with_buffers(input, options, &mut self.buffers)
+    }
+
+    /// Same as `serde::from_slice` but reuses the parser's memory
+    #[cfg(feature = "serde_impl")]
+    pub fn from_slice<'de, T>(&mut self, input: &'de mut [u8]) -> Result<T>
+    where
+        T: serde_ext::Deserialize<'de>,
+    {
+        self.from_slice_with_options(input, &ParserOptions::default())
+    }
+
+    /// Same as `serde::from_slice_with_options` but reuses the parser's
+    /// memory
+    #[cfg(feature = "serde_impl")]
+    pub fn from_slice_with_options<'de, T>(
+        &mut self,
+        input: &'de mut [u8],
+        options: &ParserOptions,
+    ) -> Result<T>
+    where
+        T: serde_ext::Deserialize<'de>,
+    {
+        let mut de = stry!(Deserializer::from_slice_with_buffers(
+            input,
+            options,
+            &mut self.buffers
+        ));
+        let r = T::deserialize(&mut de);
+        de.into_buffers(&mut self.buffers);
+        r
+    }
+
+    /// Releases the memory held by the parser
+    pub fn clear(&mut self) {
+        self.buffers = Buffers::default();
+    }
+
+    fn size(&self) -> usize {
+        self.buffers.strings.capacity() + self.buffers.copy.capacity()
+    }
+}
+
+thread_local! {
+    static PARSER: RefCell<Parser> = RefCell::new(Parser::new());
+}
+
+/// Calls `f` with the parser of the current thread, so documents parsed on
+/// the same thread reuse its memory without passing a `Parser` around.
+/// With the `parser-pool` feature the plain parsing functions like
+/// `to_owned_value` and `serde::from_slice` use it as well.
+///
+/// The parser is only borrowed for the duration of `f`, so this is fine in
+/// async runtimes that move tasks between threads as long as `f` doesn't
+/// await. Nested calls get a fresh parser and memory for documents larger
+/// than 16 MiB isn't kept.
+///
+/// ```
+/// use simd_json::{json, with_parser};
+/// let mut d = br#"[1, 2]"#.to_vec();
+/// let v = with_parser(|p| p.to_owned_value(&mut d)).unwrap();
+/// assert_eq!(v, json!([1, 2]));
+/// ```
+pub fn with_parser<F, T>(f: F) -> T
+where
+    F: FnOnce(&mut Parser) -> T,
+{
+    let mut f = Some(f);
+    let pooled = PARSER.try_with(|p| {
+        let mut parser = p.try_borrow_mut().ok()?;
+        let f = f.take()?;
+        let r = f(&mut parser);
+        if parser.size() > MAX_POOLED_SIZE {
+            parser.clear();
+        }
+        Some(r)
+    });
+    match (pooled, f) {
+        (Ok(Some(r)), _) => r,
+        // the parser is in use or the thread is shutting down
+        (_, Some(f)) => f(&mut Parser::new()),
+        (_, None) => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reuse() {
+        let mut parser = Parser::new();
+        let mut d = br#"{"a": [1, 2, "x\"y"]}"#.to_vec();
+        let v = parser.to_owned_value(&mut d).expect("valid");
+        assert_eq!(v, json!({"a": [1, 2, "x\"y"]}));
+        let capacity = parser.buffers.strings.capacity();
+        assert!(capacity > 0);
+        let mut d = br#"[1, 2"#.to_vec();
+        assert!(parser.to_borrowed_value(&mut d).is_err());
+        let mut d = br#"["b"]"#.to_vec();
+        let v = parser.to_borrowed_value(&mut d).expect("valid");
+        assert_eq!(v, json!(["b"]));
+        assert_eq!(parser.buffers.strings.capacity(), capacity);
+        parser.clear();
+        assert_eq!(parser.size(), 0);
+    }
+
+    #[test]
+    fn nested() {
+        let v = with_parser(|p| {
+            let mut d = br#"{"a": 1}"#.to_vec();
+            let inner = with_parser(|p| p.to_owned_value(&mut d));
+            let mut d = br#"{"b": 2}"#.to_vec();
+            (inner, p.to_owned_value(&mut d))
+        });
+        assert_eq!(v.0.expect("valid"), json!({"a": 1}));
+        assert_eq!(v.1.expect("valid"), json!({"b": 2}));
+    }
+
+    #[cfg(feature = "serde_impl")]
+    #[test]
+    fn from_slice() {
+        let mut d = br#"[1, 2, 3]"#.to_vec();
+        let v: Vec<u8> = with_parser(|p| p.from_slice(&mut d)).expect("valid");
+        assert_eq!(v, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::{Deserializer, OwnedValue, ValueAccess};
+#[cfg(feature = "serde_impl")]
+use crate::{Error, ParserOptions, Result};
+use std::borrow::Cow;
+
+/// Checks if the value referenced by a JSON pointer ([RFC 6901]) is present
+/// in the input without parsing it into a value.
+///
+/// Only the structural indexes of the input are computed, values not on the
+/// path are skipped and only keys along the path are compared (and unescaped
+/// if they contain escapes). As the grammar of the input is not validated the
+/// result for malformed input is unspecified, but it never panics.
+///
+/// ```
+/// let mut d = br#"{"user": {"roles": ["admin", "dev"]}}"#.to_vec();
+/// assert!(simd_json::exists(&mut d, "/user/roles/1"));
+/// assert!(!simd_json::exists(&mut d, "/user/roles/2"));
+/// assert!(!simd_json::exists(&mut d, "/user/name"));
+/// ```
+///
+/// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+pub fn exists(input: &mut [u8], pointer: &str) -> bool {
+    let structurals = match Deserializer::find_structural_indexes(input) {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    locate(input, &structurals, pointer).is_some()
+}
+
+/// Deserializes only the value referenced by a JSON pointer ([RFC 6901]),
+/// returns `Ok(None)` if the pointer doesn't resolve.
+///
+/// The path is resolved the same way as in `exists`, skipping over
+/// everything not on it, only the referenced subtree is then validated and
+/// deserialized. Note that the input will be rewritten in the process.
+///
+/// ```
+/// let mut d = br#"{"data": {"items": [{"id": 7, "tags": ["a"]}]}}"#.to_vec();
+/// let id: Option<u32> = simd_json::get(&mut d, "/data/items/0/id").unwrap();
+/// assert_eq!(id, Some(7));
+/// let mut d = br#"{"data": {"items": []}}"#.to_vec();
+/// let id: Option<u32> = simd_json::get(&mut d, "/data/items/0/id").unwrap();
+/// assert_eq!(id, None);
+/// ```
+///
+/// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+#[cfg(feature = "serde_impl")]
+pub fn get<'de, T>(input: &'de mut [u8], pointer: &str) -> Result<Option<T>>
+where
+    T: serde_ext::Deserialize<'de>,
+{
+    let structurals = match Deserializer::find_structural_indexes(input) {
+        Ok(s) => s,
+        Err(t) => return Err(Error::generic(t)),
+    };
+    let start = match locate(input, &structurals, pointer) {
+        Some(start) => start,
+        None => return Ok(None),
+    };
+    let end = skip_value(input, &structurals, start);
+    // the subtree gets its own extra root element
+    let mut subtree = Vec::with_capacity(end - start + 1);
+    subtree.push(0);
+    subtree.extend_from_slice(&structurals[start..end]);
+    let mut de = stry!(Deserializer::from_structural_indexes(
+        input,
+        subtree,
+        &ParserOptions::default()
+    ));
+    T::deserialize(&mut de).map(Some)
+}
+
+// Returns the structural index of the value referenced by `pointer`
+pub(crate) fn locate(input: &[u8], structurals: &[u32], pointer: &str) -> Option<usize> {
+    let tokens = tokens(pointer)?;
+    // the first structural index is the extra root element
+    let mut i = 1;
+    for token in tokens {
+        match char_at(input, structurals, i) {
+            Some(b'{') => {
+                i += 1;
+                loop {
+                    if char_at(input, structurals, i) != Some(b'"') {
+                        return None;
+                    }
+                    let found = key_matches(input, structurals[i] as usize + 1, &token)?;
+                    // skip the key and the colon
+                    i += 2;
+                    if found {
+                        break;
+                    }
+                    i = skip_value(input, structurals, i);
+                    if char_at(input, structurals, i) != Some(b',') {
+                        return None;
+                    }
+                    i += 1;
+                }
+            }
+            Some(b'[') => {
+                let index = array_index(&token)?;
+                i += 1;
+                if char_at(input, structurals, i) == Some(b']') {
+                    return None;
+                }
+                for _ in 0..index {
+                    i = skip_value(input, structurals, i);
+                    if char_at(input, structurals, i) != Some(b',') {
+                        return None;
+                    }
+                    i += 1;
+                }
+            }
+            _ => return None,
+        }
+    }
+    char_at(input, structurals, i).map(|_| i)
+}
+
+// Follows already split reference tokens through a value
+pub(crate) fn resolve<'v, T>(mut value: &'v OwnedValue, tokens: &[T]) -> Option<&'v OwnedValue>
+where
+    T: AsRef<str>,
+{
+    for token in tokens {
+        let token = token.as_ref();
+        value = match value {
+            OwnedValue::Object(_) => value.get(token)?,
+            OwnedValue::Array(_) => value.get_idx(array_index(token)?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+// Splits a pointer into its unescaped reference tokens
+pub(crate) fn tokens(pointer: &str) -> Option<Vec<Cow<'_, str>>> {
+    if pointer.is_empty() {
+        Some(Vec::new())
+    } else if pointer.starts_with('/') {
+        Some(pointer[1..].split('/').map(unescape_token).collect())
+    } else {
+        None
+    }
+}
+
+#[inline]
+pub(crate) fn char_at(input: &[u8], structurals: &[u32], i: usize) -> Option<u8> {
+    structurals
+        .get(i)
+        .and_then(|idx| input.get(*idx as usize))
+        .cloned()
+}
+
+// Returns the structural index following the value starting at `i`
+pub(crate) fn skip_value(input: &[u8], structurals: &[u32], mut i: usize) -> usize {
+    let mut depth: usize = 0;
+    while let Some(c) = char_at(input, structurals, i) {
+        i += 1;
+        match c {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        if depth == 0 {
+            break;
+        }
+    }
+    i
+}
+
+// `~1` and `~0` are the only escapes in pointer tokens
+fn unescape_token(token: &str) -> Cow<'_, str> {
+    if token.contains('~') {
+        Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    if token.is_empty()
+        || (token.len() > 1 && token.starts_with('0'))
+        || !token.bytes().all(|b| b.is_ascii_digit())
+    {
+        None
+    } else {
+        token.parse().ok()
+    }
+}
+
+// Finds the closing quote of the string starting at `start` (after the
+// opening quote), also returns if the string contains escapes.
+pub(crate) fn string_end(input: &[u8], start: usize) -> Option<(usize, bool)> {
+    let mut end = start;
+    let mut escaped = false;
+    loop {
+        match *input.get(end)? {
+            b'"' => return Some((end, escaped)),
+            b'\\' => {
+                escaped = true;
+                end += 2;
+            }
+            _ => end += 1,
+        }
+    }
+}
+
+// Compares the key starting at `start` (after the opening quote) with
+// `token`, returns `None` if the key isn't terminated or has invalid escapes.
+pub(crate) fn key_matches(input: &[u8], start: usize, token: &str) -> Option<bool> {
+    let (end, escaped) = string_end(input, start)?;
+    let raw = input.get(start..end)?;
+    if escaped {
+        unescape(raw).map(|key| key == token)
+    } else {
+        Some(raw == token.as_bytes())
+    }
+}
+
+pub(crate) fn hex4(raw: &[u8]) -> Option<u32> {
+    let s = std::str::from_utf8(raw.get(..4)?).ok()?;
+    u32::from_str_radix(s, 16).ok()
+}
+
+pub(crate) fn unescape(raw: &[u8]) -> Option<String> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while let Some(&c) = raw.get(i) {
+        if c != b'\\' {
+            out.push(c);
+            i += 1;
+            continue;
+        }
+        let e = *raw.get(i + 1)?;
+        i += 2;
+        let ch = match e {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let mut code = hex4(raw.get(i..)?)?;
+                i += 4;
+                if (0xD800..0xDC00).contains(&code) {
+                    if raw.get(i..i + 2)? != b"\\u" {
+                        return None;
+                    }
+                    let low = hex4(raw.get(i + 2..)?)?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return None;
+                    }
+                    i += 6;
+                    code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                }
+                std::char::from_u32(code)?
+            }
+            _ => return None,
+        };
+        let mut buf = [0; 4];
+        out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(input: &str, pointer: &str) -> bool {
+        let mut d = input.as_bytes().to_vec();
+        exists(&mut d, pointer)
+    }
+
+    #[test]
+    fn objects() {
+        let input = r#"{"a": {"b": [1, {"c": null}]}, "d": 2, "": 3}"#;
+        assert!(check(input, ""));
+        assert!(check(input, "/a"));
+        assert!(check(input, "/a/b"));
+        assert!(check(input, "/a/b/1/c"));
+        assert!(check(input, "/d"));
+        assert!(check(input, "/"));
+        assert!(!check(input, "/a/c"));
+        assert!(!check(input, "/a/b/1/c/d"));
+        assert!(!check(input, "/e"));
+        assert!(!check(input, "a"));
+    }
+
+    #[test]
+    fn arrays() {
+        let input = r#"[[1, 2], {"a": [3]}, "x", []]"#;
+        assert!(check(input, "/0/1"));
+        assert!(check(input, "/1/a/0"));
+        assert!(check(input, "/2"));
+        assert!(check(input, "/3"));
+        assert!(!check(input, "/3/0"));
+        assert!(!check(input, "/4"));
+        assert!(!check(input, "/-"));
+        assert!(!check(input, "/01"));
+        assert!(!check(input, "/a"));
+    }
+
+    #[test]
+    fn escapes() {
+        let input = r#"{"a/b": 1, "m~n": 2, "ä\"": 3, "\u00fc\ud83d\ude00": 4}"#;
+        assert!(check(input, "/a~1b"));
+        assert!(check(input, "/m~0n"));
+        assert!(check(input, "/ä\""));
+        assert!(check(input, "/ü😀"));
+        assert!(!check(input, "/a/b"));
+    }
+
+    #[cfg(feature = "serde_impl")]
+    #[test]
+    fn get() {
+        #[derive(serde_ext::Deserialize, Debug, PartialEq)]
+        struct Item<'a> {
+            id: u32,
+            name: &'a str,
+        }
+        let input = r#"{"skip": [1, {"x": "}"}], "items": [{"id": 1, "name": "a"}, {"id": 2, "name": "b\"c"}], "n": 3}"#;
+        let mut d = input.as_bytes().to_vec();
+        let item: Option<Item> = super::get(&mut d, "/items/1").unwrap();
+        assert_eq!(
+            item,
+            Some(Item {
+                id: 2,
+                name: "b\"c"
+            })
+        );
+        let mut d = input.as_bytes().to_vec();
+        assert_eq!(super::get::<u8>(&mut d, "/n").unwrap(), Some(3));
+        let mut d = input.as_bytes().to_vec();
+        assert_eq!(super::get::<String>(&mut d, "/items/2/name").unwrap(), None);
+        let mut d = input.as_bytes().to_vec();
+        assert!(super::get::<String>(&mut d, "/n").is_err());
+        let mut d = br#"{"a": [1, ]}"#.to_vec();
+        assert!(super::get::<Vec<u8>>(&mut d, "/a").is_err());
+        let mut d = input.as_bytes().to_vec();
+        let all: Option<crate::OwnedValue> = super::get(&mut d, "").unwrap();
+        assert_eq!(all.unwrap()["n"], 3);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(!check(r#"{"a": "#, "/b"));
+        assert!(!check(r#"{"a" "#, "/a/b"));
+        assert!(!check(r#"[1, 2"#, "/5"));
+        assert!(!check("", ""));
+    }
+}
@@ -0,0 +1,23 @@
+//! Everything needed to work with the DOM values, `use simd_json::prelude::*;`
+//! brings the value traits into scope so their methods can be called.
+//!
+//! ```
+//! use simd_json::prelude::*;
+//!
+//! let mut d = br#"{"id": 7, "tags": ["a", "b"]}"#.to_vec();
+//! let mut v: OwnedValue = simd_json::to_owned_value(&mut d).unwrap();
+//! assert_eq!(v.get_u64("id"), Some(7));
+//! assert_eq!(v.get("tags").and_then(|t| t.get_idx(1)).and_then(|t| t.as_str()), Some("b"));
+//! if let Some(o) = v.as_object_mut() {
+//!     o.insert("ok".into(), true.into());
+//! }
+//! let id: u8 = v["id"].as_u64().unwrap().try_into().unwrap();
+//! assert_eq!(id, 7);
+//! assert_eq!(v, json!({"id": 7, "tags": ["a", "b"], "ok": true}));
+//! ```
+
+pub use crate::json;
+pub use crate::value::{
+    ArrayAccess, BorrowedValue, ObjectAccess, OwnedValue, ValueAccess, ValueTrait, ValueType,
+};
+pub use std::convert::{TryFrom, TryInto};
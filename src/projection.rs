@@ -0,0 +1,339 @@
+use crate::pointer::{array_index, char_at, key_matches, resolve, skip_value, tokens};
+use crate::value::object_with_capacity;
+use crate::value::owned::to_value_with_structural_indexes;
+use crate::{Deserializer, Error, KeyInterner, OwnedValue, ParserOptions, Result};
+
+// Where the walk found the value of a path
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Found {
+    Missing,
+    // Starts at the given structural index
+    At(usize),
+    // Inside the value of another path, the remaining reference tokens
+    // start at the given depth
+    Within(usize, usize),
+}
+
+/// Extracts the values of several JSON pointers ([RFC 6901]) from a
+/// document in a single pass.
+///
+/// Values not on any of the paths are skipped over without being parsed,
+/// only the referenced subtrees are validated and turned into values. Paths
+/// behind malformed parts of the input are treated as missing.
+///
+/// ```
+/// use simd_json::{json, Projection};
+/// let p = Projection::new().path("/user/id").path("/tags/1").path("/missing");
+/// let mut d = br#"{"user": {"id": 7, "name": "x"}, "tags": ["a", "b"]}"#.to_vec();
+/// assert_eq!(p.project(&mut d).unwrap(), vec![Some(json!(7)), Some(json!("b")), None]);
+/// ```
+///
+/// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+#[derive(Debug, Clone, Default)]
+pub struct Projection {
+    options: ParserOptions,
+    pointers: Vec<String>,
+    tokens: Vec<Option<Vec<String>>>,
+}
+
+impl Projection {
+    /// Creates an empty projection using the default `ParserOptions`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty projection using the given `ParserOptions` to parse
+    /// the extracted values
+    pub fn with_options(options: ParserOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Adds a path to extract, a pointer that isn't valid never matches
+    pub fn path(mut self, pointer: &str) -> Self {
+        self.tokens
+            .push(tokens(pointer).map(|t| t.into_iter().map(|t| t.into_owned()).collect()));
+        self.pointers.push(pointer.to_string());
+        self
+    }
+
+    /// The registered paths in the order they were added
+    pub fn paths(&self) -> &[String] {
+        &self.pointers
+    }
+
+    /// Extracts the values of all paths, in the order they were added. The
+    /// value of a path that doesn't resolve is `None`. Note that the input
+    /// will be rewritten in the process.
+    pub fn project(&self, input: &mut [u8]) -> Result<Vec<Option<OwnedValue>>> {
+        let range = self.options.content_range(input);
+        let input = &mut input[range];
+        let structurals = match Deserializer::find_structural_indexes(input) {
+            Ok(s) => s,
+            Err(t) => return Err(Error::generic(t)),
+        };
+        self.project_with_structural_indexes(input, &structurals)
+    }
+
+    // Same as `project` on input stage 1 already ran on
+    pub(crate) fn project_with_structural_indexes(
+        &self,
+        input: &mut [u8],
+        structurals: &[u32],
+    ) -> Result<Vec<Option<OwnedValue>>> {
+        let found = {
+            let mut walk = Walk {
+                input,
+                structurals,
+                tokens: &self.tokens,
+                found: vec![Found::Missing; self.tokens.len()],
+            };
+            let active: Vec<usize> = (0..self.tokens.len())
+                .filter(|id| self.tokens[*id].is_some())
+                .collect();
+            walk.visit(1, 0, &active);
+            walk.found
+        };
+
+        let mut values = vec![None; found.len()];
+        let mut keys = KeyInterner::new();
+        for (id, f) in found.iter().enumerate() {
+            if let Found::At(start) = *f {
+                let end = skip_value(input, structurals, start);
+                // the subtree gets its own extra root element
+                let mut subtree = Vec::with_capacity(end - start + 1);
+                subtree.push(0);
+                subtree.extend_from_slice(&structurals[start..end]);
+                let value = stry!(to_value_with_structural_indexes(
+                    input,
+                    subtree,
+                    &self.options,
+                    &mut keys
+                ));
+                values[id] = Some(value);
+            }
+        }
+        // values nested in other values can't be parsed separately as
+        // parsing rewrites escaped strings in place
+        for (id, f) in found.iter().enumerate() {
+            if let Found::Within(owner, depth) = *f {
+                let rest = self.tokens[id].as_ref().map_or(&[][..], |t| &t[depth..]);
+                values[id] = values[owner]
+                    .as_ref()
+                    .and_then(|v| resolve(v, rest))
+                    .cloned();
+            }
+        }
+        Ok(values)
+    }
+
+    /// Same as `project` but returns an object mapping each pointer that
+    /// resolved to its value
+    pub fn project_object(&self, input: &mut [u8]) -> Result<OwnedValue> {
+        let values = stry!(self.project(input));
+        let mut object = object_with_capacity(values.len());
+        for (pointer, value) in self.pointers.iter().zip(values) {
+            if let Some(value) = value {
+                object.insert(pointer.as_str().into(), value);
+            }
+        }
+        Ok(OwnedValue::Object(object))
+    }
+
+    /// Same as `project` but deserializes the values into `T`, which is
+    /// typically a tuple with one element per path. Paths that don't
+    /// resolve are passed as `null`.
+    ///
+    /// ```
+    /// use simd_json::Projection;
+    /// let p = Projection::new().path("/id").path("/meta/host").path("/meta/port");
+    /// let mut d = br#"{"id": 3, "meta": {"host": "h", "tls": true}}"#.to_vec();
+    /// let (id, host, port): (u64, String, Option<u16>) = p.project_as(&mut d).unwrap();
+    /// assert_eq!((id, host.as_str(), port), (3, "h", None));
+    /// ```
+    #[cfg(feature = "serde_impl")]
+    pub fn project_as<T>(&self, input: &mut [u8]) -> Result<T>
+    where
+        T: serde_ext::de::DeserializeOwned,
+    {
+        let values = stry!(self.project(input));
+        let array = values
+            .into_iter()
+            .map(|v| v.unwrap_or(OwnedValue::Static(crate::StaticNode::Null)))
+            .collect();
+        crate::serde::from_owned_value(OwnedValue::Array(array))
+    }
+}
+
+struct Walk<'a> {
+    input: &'a [u8],
+    structurals: &'a [u32],
+    tokens: &'a [Option<Vec<String>>],
+    found: Vec<Found>,
+}
+
+impl<'a> Walk<'a> {
+    // Visits the value at structural index `i` that the `active` paths lead
+    // to after `depth` tokens, returns the structural index following it or
+    // `None` if the input is malformed.
+    fn visit(&mut self, i: usize, depth: usize, active: &[usize]) -> Option<usize> {
+        let mut owner = None;
+        let mut deeper = Vec::with_capacity(active.len());
+        for &id in active {
+            if self.path(id).len() == depth && owner.is_none() {
+                owner = Some(id);
+                self.found[id] = Found::At(i);
+            } else {
+                deeper.push(id);
+            }
+        }
+        if let Some(owner) = owner {
+            for id in deeper {
+                self.found[id] = Found::Within(owner, depth);
+            }
+            return Some(skip_value(self.input, self.structurals, i));
+        }
+
+        match char_at(self.input, self.structurals, i)? {
+            b'{' => {
+                let mut i = i + 1;
+                if char_at(self.input, self.structurals, i)? == b'}' {
+                    return Some(i + 1);
+                }
+                loop {
+                    if char_at(self.input, self.structurals, i)? != b'"'
+                        || char_at(self.input, self.structurals, i + 1)? != b':'
+                    {
+                        return None;
+                    }
+                    let start = self.structurals[i] as usize + 1;
+                    let mut matching = Vec::new();
+                    for &id in &deeper {
+                        // with duplicate keys the first one wins
+                        if self.found[id] == Found::Missing
+                            && key_matches(self.input, start, &self.path(id)[depth])?
+                        {
+                            matching.push(id);
+                        }
+                    }
+                    // skip the key and the colon
+                    i = self.member(i + 2, depth, &matching)?;
+                    match char_at(self.input, self.structurals, i)? {
+                        b',' => i += 1,
+                        b'}' => return Some(i + 1),
+                        _ => return None,
+                    }
+                }
+            }
+            b'[' => {
+                let mut i = i + 1;
+                if char_at(self.input, self.structurals, i)? == b']' {
+                    return Some(i + 1);
+                }
+                let mut index = 0;
+                loop {
+                    let matching: Vec<usize> = deeper
+                        .iter()
+                        .cloned()
+                        .filter(|id| array_index(&self.path(*id)[depth]) == Some(index))
+                        .collect();
+                    i = self.member(i, depth, &matching)?;
+                    match char_at(self.input, self.structurals, i)? {
+                        b',' => i += 1,
+                        b']' => return Some(i + 1),
+                        _ => return None,
+                    }
+                    index += 1;
+                }
+            }
+            _ => Some(i + 1),
+        }
+    }
+
+    fn member(&mut self, i: usize, depth: usize, matching: &[usize]) -> Option<usize> {
+        if matching.is_empty() {
+            Some(skip_value(self.input, self.structurals, i))
+        } else {
+            self.visit(i, depth + 1, matching)
+        }
+    }
+
+    fn path(&self, id: usize) -> &'a [String] {
+        self.tokens[id].as_ref().map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn single_pass() {
+        let p = Projection::new()
+            .path("/a/b")
+            .path("/c/1/d")
+            .path("/c/0")
+            .path("/e")
+            .path("/a/x")
+            .path("nope")
+            .path("/c/3");
+        let mut d = br#"{"a": {"x": [1, {"b": 0}], "b": "\"b\""}, "c": [null, {"d": [2]}, 3], "e": {}, "a": {"b": 1}}"#.to_vec();
+        let values = p.project(&mut d).expect("valid");
+        assert_eq!(
+            values,
+            vec![
+                Some(json!("\"b\"")),
+                Some(json!([2])),
+                Some(json!(null)),
+                Some(json!({})),
+                Some(json!([1, {"b": 0}])),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_paths() {
+        let p = Projection::new()
+            .path("/a")
+            .path("/a/b/0")
+            .path("/a")
+            .path("/a/c")
+            .path("");
+        let mut d = br#"{"a": {"b": ["x\ny"]}}"#.to_vec();
+        let values = p.project(&mut d).expect("valid");
+        assert_eq!(
+            values,
+            vec![
+                Some(json!({"b": ["x\ny"]})),
+                Some(json!("x\ny")),
+                Some(json!({"b": ["x\ny"]})),
+                None,
+                Some(json!({"a": {"b": ["x\ny"]}})),
+            ]
+        );
+        let mut d = br#"{"a": {"b": ["x\ny"]}}"#.to_vec();
+        assert_eq!(
+            Projection::new()
+                .path("/a/b")
+                .path("/z")
+                .project_object(&mut d)
+                .expect("valid"),
+            json!({"/a/b": ["x\ny"]})
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let p = Projection::new().path("/a").path("/b");
+        let mut d = br#"{"a": [1, ], "b": 2}"#.to_vec();
+        assert!(p.project(&mut d).is_err());
+        let mut d = br#"{"a" 1 "b": 2}"#.to_vec();
+        assert_eq!(p.project(&mut d).expect("not reached"), vec![None, None]);
+        let mut d = br#"{"#.to_vec();
+        assert_eq!(p.project(&mut d).expect("not reached"), vec![None, None]);
+    }
+}
@@ -0,0 +1,627 @@
+use crate::charutils::is_json_whitespace;
+use crate::value::owned::{key, Object};
+use crate::{BigNumberBehavior, Error, ErrorType, OwnedValue, ParserOptions, StaticNode};
+
+/// The result of parsing with error recovery, see
+/// `to_owned_value_recovering`.
+#[derive(Debug, PartialEq)]
+pub struct Recovered {
+    /// Everything that could be parsed, `None` if no value was found at all
+    pub value: Option<OwnedValue>,
+    /// The errors encountered, in input order
+    pub diagnostics: Vec<Error>,
+}
+
+impl Recovered {
+    /// True if the input parsed without any errors, nothing was repaired
+    /// or dropped
+    pub fn is_clean(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// Parses the input into an owned value, recovering from errors instead of
+/// stopping at the first one.
+///
+/// On an error a diagnostic is recorded and parsing resynchronizes at the
+/// next structural character: values that can't be parsed are dropped,
+/// missing commas, colons and closing brackets are assumed, trailing
+/// commas are skipped, numbers JSON doesn't allow like `01` or `1.` keep
+/// the value they read as and unterminated strings end at the end of their
+/// line. Integers that don't fit into an `i64` or a `u64` are handled as
+/// `ParserOptions::big_numbers` says, by default they're recorded as an
+/// `ErrorType::Overflow` and keep the nearest float, floats too large for an
+/// `f64` are dropped. This is a lot slower than the regular parser and meant for
+/// tooling like editors that has to work with incomplete documents.
+///
+/// ```
+/// use simd_json::{json, to_owned_value_recovering, ErrorType};
+/// let r = to_owned_value_recovering(br#"{"a": [1, 2,], "b": tru, "c": {"d": "x"#);
+/// assert_eq!(r.value, Some(json!({"a": [1, 2], "c": {"d": "x"}})));
+/// let errors: Vec<_> = r.diagnostics.iter().map(|e| e.error()).collect();
+/// assert_eq!(
+///     errors,
+///     vec![
+///         &ErrorType::ExpectedArrayContent,
+///         &ErrorType::UnexpectedCharacter,
+///         &ErrorType::UnterminatedString,
+///         &ErrorType::EarlyEnd,
+///         &ErrorType::EarlyEnd,
+///     ]
+/// );
+/// ```
+pub fn to_owned_value_recovering(input: &[u8]) -> Recovered {
+    to_owned_value_recovering_with_options(input, &ParserOptions::default())
+}
+
+/// Same as `to_owned_value_recovering` but allows tuning the parser using
+/// `ParserOptions`, only the depth limit, `big_numbers` and the handling of
+/// byte order marks and NUL padding apply.
+pub fn to_owned_value_recovering_with_options(input: &[u8], options: &ParserOptions) -> Recovered {
+    let range = options.content_range(input);
+    let mut parser = Parser {
+        input: &input[..range.end],
+        pos: range.start,
+        depth: options.max_depth,
+        big_numbers: options.big_numbers,
+        diagnostics: Vec::new(),
+    };
+    parser.whitespace();
+    let value = if parser.peek().is_none() {
+        parser.error(ErrorType::EOF);
+        None
+    } else {
+        let value = parser.value();
+        parser.whitespace();
+        if parser.peek().is_some() {
+            parser.error(ErrorType::TrailingCharacters);
+        }
+        value
+    };
+    Recovered {
+        value,
+        diagnostics: parser.diagnostics,
+    }
+}
+
+struct Parser<'i> {
+    input: &'i [u8],
+    pos: usize,
+    // remaining nesting depth
+    depth: usize,
+    big_numbers: BigNumberBehavior,
+    diagnostics: Vec<Error>,
+}
+
+impl<'i> Parser<'i> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).cloned()
+    }
+
+    fn error(&mut self, error: ErrorType) {
+        let character = self.peek().map_or(' ', char::from);
+        self.diagnostics
+            .push(Error::new(0, self.pos, character, error));
+    }
+
+    fn whitespace(&mut self) {
+        while self.peek().map_or(false, is_json_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // Skips at least one byte and everything up to the next structural
+    // character or whitespace
+    fn resync(&mut self) {
+        self.pos += 1;
+        while let Some(c) = self.peek() {
+            if is_json_whitespace(c) || b",:[]{}\"".contains(&c) {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    // Parses the value at the current position, containers and strings
+    // always produce a (partial) value, `None` means nothing was consumed
+    // or the scalar was malformed.
+    fn value(&mut self) -> Option<OwnedValue> {
+        match self.peek() {
+            Some(b'{') | Some(b'[') if self.depth == 0 => {
+                self.error(ErrorType::DepthExceeded);
+                None
+            }
+            Some(b'{') => {
+                self.depth -= 1;
+                let object = self.object();
+                self.depth += 1;
+                Some(object)
+            }
+            Some(b'[') => {
+                self.depth -= 1;
+                let array = self.array();
+                self.depth += 1;
+                Some(array)
+            }
+            Some(b'"') => Some(OwnedValue::from(self.string())),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(b't') => self.literal("true", OwnedValue::Static(StaticNode::Bool(true))),
+            Some(b'f') => self.literal("false", OwnedValue::Static(StaticNode::Bool(false))),
+            Some(b'n') => self.literal("null", OwnedValue::Static(StaticNode::Null)),
+            Some(_) => {
+                self.error(ErrorType::UnexpectedCharacter);
+                None
+            }
+            None => {
+                self.error(ErrorType::EarlyEnd);
+                None
+            }
+        }
+    }
+
+    fn array(&mut self) -> OwnedValue {
+        self.pos += 1;
+        let mut array = Vec::new();
+        let mut after_comma = false;
+        loop {
+            self.whitespace();
+            match self.peek() {
+                Some(b']') => {
+                    if after_comma {
+                        self.error(ErrorType::ExpectedArrayContent);
+                    }
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'}') => {
+                    // the array isn't closed, leave the bracket to the parent
+                    self.error(ErrorType::ExpectedArrayContent);
+                    break;
+                }
+                None => {
+                    self.error(ErrorType::EarlyEnd);
+                    break;
+                }
+                Some(b',') => {
+                    self.error(ErrorType::ExpectedArrayContent);
+                    self.pos += 1;
+                    continue;
+                }
+                _ => (),
+            }
+            match self.value() {
+                Some(v) => array.push(v),
+                None => self.resync(),
+            }
+            self.whitespace();
+            after_comma = false;
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    after_comma = true;
+                }
+                Some(b']') | Some(b'}') | None => (),
+                Some(_) => self.error(ErrorType::ExpectedArrayComma),
+            }
+        }
+        OwnedValue::Array(array)
+    }
+
+    fn object(&mut self) -> OwnedValue {
+        self.pos += 1;
+        let mut object = Object::default();
+        let mut after_comma = false;
+        loop {
+            self.whitespace();
+            match self.peek() {
+                Some(b'}') => {
+                    if after_comma {
+                        self.error(ErrorType::ExpectedObjectKey);
+                    }
+                    self.pos += 1;
+                    break;
+                }
+                Some(b']') => {
+                    // the object isn't closed, leave the bracket to the parent
+                    self.error(ErrorType::ExpectedObjectContent);
+                    break;
+                }
+                None => {
+                    self.error(ErrorType::EarlyEnd);
+                    break;
+                }
+                Some(b'"') => (),
+                Some(b',') => {
+                    self.error(ErrorType::ExpectedObjectKey);
+                    self.pos += 1;
+                    continue;
+                }
+                Some(_) => {
+                    self.error(ErrorType::ExpectedObjectKey);
+                    self.resync();
+                    continue;
+                }
+            }
+            let name = self.string();
+            self.whitespace();
+            if self.peek() == Some(b':') {
+                self.pos += 1;
+            } else {
+                self.error(ErrorType::ExpectedObjectColon);
+            }
+            self.whitespace();
+            match self.peek() {
+                Some(b',') | Some(b'}') | Some(b']') => {
+                    self.error(ErrorType::ExpectedObjectContent)
+                }
+                _ => match self.value() {
+                    Some(v) => {
+                        object.insert(key(name), v);
+                    }
+                    None => self.resync(),
+                },
+            }
+            self.whitespace();
+            after_comma = false;
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    after_comma = true;
+                }
+                Some(b'}') | Some(b']') | None => (),
+                Some(_) => self.error(ErrorType::ExpectedMapComma),
+            }
+        }
+        OwnedValue::Object(object)
+    }
+
+    // Parses the string starting at the current (opening) quote, an
+    // unterminated string ends at the end of the line
+    fn string(&mut self) -> String {
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\n') | None => {
+                    self.error(ErrorType::UnterminatedString);
+                    break;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    self.escape(&mut bytes);
+                }
+                Some(c) => {
+                    bytes.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        match String::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => {
+                self.error(ErrorType::InvalidUTF8);
+                String::from_utf8_lossy(e.as_bytes()).into_owned()
+            }
+        }
+    }
+
+    // Decodes the escape after a backslash, invalid escapes are kept as
+    // they are
+    fn escape(&mut self, bytes: &mut Vec<u8>) {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return,
+        };
+        self.pos += 1;
+        let decoded = match c {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => match self.unicode() {
+                Some(c) => c,
+                None => {
+                    self.error(ErrorType::InvalidUnicodeEscape);
+                    std::char::REPLACEMENT_CHARACTER
+                }
+            },
+            _ => {
+                self.pos -= 1;
+                self.error(ErrorType::InvalidEscape);
+                bytes.push(b'\\');
+                return;
+            }
+        };
+        let mut buf = [0; 4];
+        bytes.extend_from_slice(decoded.encode_utf8(&mut buf).as_bytes());
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.input.get(self.pos..self.pos + 4)?;
+        let code = u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+        self.pos += 4;
+        Some(code)
+    }
+
+    fn unicode(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return std::char::from_u32(high);
+        }
+        if self.input.get(self.pos..self.pos + 2) != Some(b"\\u") {
+            return None;
+        }
+        self.pos += 2;
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
+        }
+        std::char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
+
+    fn number(&mut self) -> Option<OwnedValue> {
+        let start = self.pos;
+        while self
+            .peek()
+            .map_or(false, |c| c.is_ascii_digit() || b"+-.eE".contains(&c))
+        {
+            self.pos += 1;
+        }
+        // only ascii bytes were consumed
+        let text = std::str::from_utf8(&self.input[start..self.pos]).unwrap_or_default();
+        let is_float = text.contains(&['.', 'e', 'E'][..]);
+        let value = if is_float {
+            match text.parse::<f64>() {
+                Ok(f) if f.is_infinite() => {
+                    self.pos = start;
+                    self.error(ErrorType::InvalidExponent);
+                    return None;
+                }
+                f => f.ok().map(OwnedValue::from),
+            }
+        } else {
+            text.parse::<i64>()
+                .map(OwnedValue::from)
+                .or_else(|_| text.parse::<u64>().map(OwnedValue::from))
+                .ok()
+                .or_else(|| self.big_integer(start, text))
+        };
+        if value.is_none() {
+            self.pos = start;
+            self.error(ErrorType::InvalidNumber);
+        } else if !is_json_number(text.as_bytes()) {
+            // numbers like `01` or `1.` are repaired to what they parse to
+            let end = self.pos;
+            self.pos = start;
+            self.error(ErrorType::InvalidNumber);
+            self.pos = end;
+        }
+        value
+    }
+
+    // Integers that don't fit into an `i64` or a `u64`, handled like the
+    // regular parser does
+    fn big_integer(&mut self, start: usize, text: &str) -> Option<OwnedValue> {
+        let float = text.parse::<f64>().ok()?;
+        Some(match self.big_numbers {
+            BigNumberBehavior::Error => {
+                let end = self.pos;
+                self.pos = start;
+                self.error(ErrorType::Overflow);
+                self.pos = end;
+                OwnedValue::from(float)
+            }
+            BigNumberBehavior::Saturate if text.starts_with('-') => OwnedValue::from(i64::MIN),
+            BigNumberBehavior::Saturate => OwnedValue::from(u64::MAX),
+            BigNumberBehavior::Float => OwnedValue::from(float),
+            BigNumberBehavior::Raw => OwnedValue::RawNumber(text.to_string()),
+        })
+    }
+
+    fn literal(&mut self, literal: &str, value: OwnedValue) -> Option<OwnedValue> {
+        let end = self.pos + literal.len();
+        let terminated = self
+            .input
+            .get(end)
+            .map_or(true, |c| !c.is_ascii_alphanumeric());
+        if self.input.get(self.pos..end) == Some(literal.as_bytes()) && terminated {
+            self.pos = end;
+            Some(value)
+        } else {
+            self.error(ErrorType::UnexpectedCharacter);
+            None
+        }
+    }
+}
+
+// Checks `text` against the JSON number grammar,
+// `-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?`
+fn is_json_number(text: &[u8]) -> bool {
+    fn digits(text: &[u8]) -> usize {
+        text.iter().take_while(|c| c.is_ascii_digit()).count()
+    }
+    let mut i = 0;
+    if text.first() == Some(&b'-') {
+        i += 1;
+    }
+    match text.get(i) {
+        Some(b'0') => i += 1,
+        Some(b'1'..=b'9') => i += digits(&text[i..]),
+        _ => return false,
+    }
+    if text.get(i) == Some(&b'.') {
+        let n = digits(&text[i + 1..]);
+        if n == 0 {
+            return false;
+        }
+        i += 1 + n;
+    }
+    if let Some(b'e') | Some(b'E') = text.get(i) {
+        i += 1;
+        if let Some(b'+') | Some(b'-') = text.get(i) {
+            i += 1;
+        }
+        let n = digits(&text[i..]);
+        if n == 0 {
+            return false;
+        }
+        i += n;
+    }
+    i == text.len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn recover(input: &str) -> (Option<OwnedValue>, Vec<(usize, String)>) {
+        let r = to_owned_value_recovering(input.as_bytes());
+        let diagnostics = r
+            .diagnostics
+            .iter()
+            .map(|e| (e.index(), format!("{:?}", e.error())))
+            .collect();
+        (r.value, diagnostics)
+    }
+
+    fn d(index: usize, error: &str) -> (usize, String) {
+        (index, error.to_string())
+    }
+
+    #[test]
+    fn clean_input() {
+        let input = r#" {"a": [1, -2.5e1, "x\n\ud83d\ude00"], "b": {"c": null, "d": false}} "#;
+        let r = to_owned_value_recovering(input.as_bytes());
+        assert!(r.is_clean());
+        let mut data = input.as_bytes().to_vec();
+        assert_eq!(
+            r.value,
+            Some(crate::to_owned_value(&mut data).expect("valid"))
+        );
+    }
+
+    #[test]
+    fn resynchronizes() {
+        assert_eq!(
+            recover(r#"[1 2, @@, {"a" 3, "b": }, "\q", 01x]"#),
+            (
+                Some(json!([1, 2, {"a": 3}, "\\q", 1])),
+                vec![
+                    d(3, "ExpectedArrayComma"),
+                    d(6, "UnexpectedCharacter"),
+                    d(15, "ExpectedObjectColon"),
+                    d(23, "ExpectedObjectContent"),
+                    d(28, "InvalidEscape"),
+                    d(32, "InvalidNumber"),
+                    d(34, "ExpectedArrayComma"),
+                    d(34, "UnexpectedCharacter"),
+                ]
+            )
+        );
+        assert_eq!(
+            recover("{\"a\": [1}, \"b\": 2} x"),
+            (
+                Some(json!({"a": [1]})),
+                vec![d(8, "ExpectedArrayContent"), d(9, "TrailingCharacters")]
+            )
+        );
+        assert_eq!(
+            recover("[\"ab\n, 2]"),
+            (Some(json!(["ab", 2])), vec![d(4, "UnterminatedString")])
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            recover("[0, -0.5, 1e3, 2E-2, 3.25e+1]"),
+            (Some(json!([0, -0.5, 1000.0, 0.02, 32.5])), vec![])
+        );
+        assert_eq!(
+            recover("[7., 01, -01, 1.e3, .5]"),
+            (
+                Some(json!([7.0, 1, -1, 1000.0])),
+                vec![
+                    d(1, "InvalidNumber"),
+                    d(5, "InvalidNumber"),
+                    d(9, "InvalidNumber"),
+                    d(14, "InvalidNumber"),
+                    d(20, "UnexpectedCharacter"),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn out_of_range_numbers() {
+        let input = "[18446744073709551616, -9223372036854775809, 1e999, 2]";
+        assert_eq!(
+            recover(input),
+            (
+                Some(json!([
+                    1.844_674_407_370_955_2e19,
+                    -9.223_372_036_854_776e18,
+                    2
+                ])),
+                vec![
+                    d(1, "Overflow"),
+                    d(23, "Overflow"),
+                    d(45, "InvalidExponent"),
+                ]
+            )
+        );
+        let with = |big_numbers| ParserOptions {
+            big_numbers,
+            ..ParserOptions::default()
+        };
+        let r = to_owned_value_recovering_with_options(
+            input.as_bytes(),
+            &with(BigNumberBehavior::Saturate),
+        );
+        assert_eq!(r.value, Some(json!([u64::MAX, i64::MIN, 2])));
+        assert_eq!(r.diagnostics.len(), 1);
+        let r =
+            to_owned_value_recovering_with_options(input.as_bytes(), &with(BigNumberBehavior::Raw));
+        assert_eq!(
+            r.value,
+            Some(OwnedValue::Array(vec![
+                OwnedValue::RawNumber("18446744073709551616".to_string()),
+                OwnedValue::RawNumber("-9223372036854775809".to_string()),
+                json!(2),
+            ]))
+        );
+    }
+
+    #[test]
+    fn json_whitespace() {
+        assert_eq!(recover(" \t\r\n[1]\n"), (Some(json!([1])), vec![]));
+        assert!(!to_owned_value_recovering(b"\x0c1").is_clean());
+        assert!(!to_owned_value_recovering(b"[1]\x0c").is_clean());
+    }
+
+    #[test]
+    fn nothing_to_parse() {
+        assert_eq!(recover("  "), (None, vec![d(2, "EOF")]));
+        assert_eq!(
+            recover("]"),
+            (
+                None,
+                vec![d(0, "UnexpectedCharacter"), d(0, "TrailingCharacters")]
+            )
+        );
+        let deep = "[".repeat(2000);
+        let r = to_owned_value_recovering(deep.as_bytes());
+        assert!(r
+            .diagnostics
+            .iter()
+            .any(|e| e.error() == &ErrorType::DepthExceeded));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::charutils::is_not_structural_or_whitespace;
+use crate::{Deserializer, Error, Result};
+
+// Returns the end of the token starting at `idx`, a structural character,
+// a string including its quotes or a number or literal.
+fn token_end(input: &[u8], idx: usize) -> usize {
+    match input[idx] {
+        b'"' => {
+            let mut end = idx + 1;
+            while let Some(c) = input.get(end) {
+                match c {
+                    b'"' => return end + 1,
+                    b'\\' => end += 2,
+                    _ => end += 1,
+                }
+            }
+            input.len()
+        }
+        b'{' | b'}' | b'[' | b']' | b',' | b':' => idx + 1,
+        _ => {
+            let mut end = idx + 1;
+            while input
+                .get(end)
+                .map_or(false, |c| is_not_structural_or_whitespace(*c) != 0)
+            {
+                end += 1;
+            }
+            end
+        }
+    }
+}
+
+// Calls `f` with every token of the input in order
+fn for_each_token<F>(input: &[u8], mut f: F) -> Result<()>
+where
+    F: FnMut(&[u8]),
+{
+    let structural_indexes =
+        Deserializer::find_structural_indexes(input).map_err(Error::generic)?;
+    // the first structural index is the extra root element
+    for idx in structural_indexes.iter().skip(1) {
+        let idx = *idx as usize;
+        if idx >= input.len() {
+            break;
+        }
+        f(&input[idx..token_end(input, idx)]);
+    }
+    Ok(())
+}
+
+/// Removes all insignificant whitespace from the JSON in `src` and appends
+/// the result to `dst`.
+///
+/// This works directly on the structural indexes of the input, no value is
+/// built and strings are copied as they are, escapes included. The input is
+/// not validated beyond what is needed to find the structural indexes, so
+/// for malformed input the output is unspecified.
+///
+/// ```
+/// let mut src = b"{ \"a\" : [ 1, 2 ],\n  \"b c\": null }".to_vec();
+/// let mut dst = Vec::new();
+/// simd_json::minify(&mut src, &mut dst).expect("valid input");
+/// assert_eq!(dst, br#"{"a":[1,2],"b c":null}"#.to_vec());
+/// ```
+pub fn minify(src: &mut [u8], dst: &mut Vec<u8>) -> Result<()> {
+    dst.reserve(src.len());
+    for_each_token(src, |token| dst.extend_from_slice(token))
+}
+
+/// Pretty prints the JSON in `src` with `spaces_per_indent` spaces per
+/// level of nesting and appends the result to `dst`.
+///
+/// Like `minify` this works directly on the structural indexes, so huge
+/// documents can be reformatted without building values. The layout matches
+/// the one of `encode_pp` on the value types. The input is not
+/// validated beyond what is needed to find the structural indexes, so for
+/// malformed input the output is unspecified.
+///
+/// ```
+/// let mut src = br#"{"a":[1,2],"b":{}}"#.to_vec();
+/// let mut dst = Vec::new();
+/// simd_json::prettify(&mut src, &mut dst, 2).expect("valid input");
+/// assert_eq!(
+///     String::from_utf8(dst).unwrap(),
+///     "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {}\n}"
+/// );
+/// ```
+pub fn prettify(src: &mut [u8], dst: &mut Vec<u8>, spaces_per_indent: u16) -> Result<()> {
+    let spaces_per_indent = usize::from(spaces_per_indent);
+    let mut dent: usize = 0;
+    // set after an opening bracket until we know if the container is empty
+    let mut opened = false;
+    dst.reserve(src.len());
+    let new_line = |dst: &mut Vec<u8>, dent: usize| {
+        dst.push(b'\n');
+        dst.resize(dst.len() + dent * spaces_per_indent, b' ');
+    };
+    for_each_token(src, |token| {
+        let c = token[0];
+        if opened {
+            opened = false;
+            if c == b'}' || c == b']' {
+                dst.push(c);
+                return;
+            }
+            dent += 1;
+            new_line(dst, dent);
+        }
+        match c {
+            b'{' | b'[' => {
+                dst.push(c);
+                opened = true;
+            }
+            b'}' | b']' => {
+                dent = dent.saturating_sub(1);
+                new_line(dst, dent);
+                dst.push(c);
+            }
+            b',' => {
+                dst.push(b',');
+                new_line(dst, dent);
+            }
+            b':' => dst.extend_from_slice(b": "),
+            _ => dst.extend_from_slice(token),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(input: &str) -> String {
+        let mut src = input.as_bytes().to_vec();
+        let mut dst = Vec::new();
+        minify(&mut src, &mut dst).expect("valid input");
+        String::from_utf8(dst).expect("valid utf8")
+    }
+
+    #[test]
+    fn minifies() {
+        assert_eq!(
+            check("\t{\n  \"a \\\" b\" : [ 1.5e3 , -2, true , false,null ] ,\r\n \"{ }\": {} }  "),
+            r#"{"a \" b":[1.5e3,-2,true,false,null],"{ }":{}}"#
+        );
+        assert_eq!(check("  42  "), "42");
+        assert_eq!(check(r#" "  x  " "#), r#""  x  ""#);
+        assert_eq!(check("[ ]"), "[]");
+    }
+
+    #[test]
+    fn prettifies_like_encode_pp() {
+        let input = r#"{"a": [1, {"b": [], "c": {}}, "x\"]"], "d": {"e": null}, "f": []}"#;
+        let mut src = input.as_bytes().to_vec();
+        let mut dst = Vec::new();
+        prettify(&mut src, &mut dst, 2).expect("valid input");
+        let mut d = input.as_bytes().to_vec();
+        let v = crate::to_owned_value(&mut d).expect("valid input");
+        let expected = v.encode_pp();
+        assert_eq!(String::from_utf8(dst).expect("valid utf8"), expected);
+
+        let mut src = b" 42 ".to_vec();
+        let mut dst = Vec::new();
+        prettify(&mut src, &mut dst, 2).expect("valid input");
+        assert_eq!(dst, b"42");
+    }
+}
@@ -0,0 +1,1172 @@
+mod infer;
+
+pub use self::infer::{infer, Inferrer};
+use crate::pointer::{resolve, tokens};
+use crate::{OwnedValue, StaticNode, ValueAccess, ValueTrait, ValueType};
+use regex::Regex;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fmt;
+use std::hash::Hash;
+
+/// A part of a schema that can't be compiled
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    /// JSON pointer to the offending part of the schema
+    pub pointer: String,
+    /// What is wrong with it
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at schema {:?}", self.message, self.pointer)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// A way in which a value violates a schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// JSON pointer to the offending part of the value
+    pub instance_path: String,
+    /// The schema keyword that was violated
+    pub keyword: &'static str,
+    /// Description of the violation
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {:?}", self.message, self.instance_path)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// A compiled JSON Schema (draft-07) that values can be validated against.
+///
+/// All validation keywords of draft-07 are supported, `format` is treated
+/// as an annotation and not checked. References (`$ref`) have to point into
+/// the schema itself (like `#/definitions/name`), a `$ref` overrides all
+/// other keywords of its schema as specified by draft-07. A cycle of
+/// references that never descends into the value, like `{"$ref": "#"}`,
+/// is rejected when compiling as validating against it wouldn't terminate.
+///
+/// ```
+/// use simd_json::json;
+/// use simd_json::schema::Schema;
+///
+/// let schema = Schema::compile(&json!({
+///     "type": "object",
+///     "properties": {
+///         "id": {"type": "integer", "minimum": 1},
+///         "tags": {"type": "array", "items": {"type": "string"}}
+///     },
+///     "required": ["id"]
+/// }))
+/// .unwrap();
+///
+/// assert!(schema.is_valid(&json!({"id": 3, "tags": ["a"]})));
+/// let errors = schema.validate(&json!({"id": 0, "tags": ["a", 2]})).unwrap_err();
+/// let paths: Vec<_> = errors.iter().map(|e| e.instance_path.as_str()).collect();
+/// assert_eq!(paths, vec!["/id", "/tags/1"]);
+/// ```
+#[derive(Debug)]
+pub struct Schema {
+    nodes: Vec<Node>,
+}
+
+impl Schema {
+    /// Compiles a schema
+    pub fn compile(schema: &OwnedValue) -> Result<Self, SchemaError> {
+        let mut compiler = Compiler {
+            root: schema,
+            nodes: Vec::new(),
+            refs: HashMap::new(),
+            ref_pointers: HashMap::new(),
+        };
+        compiler.compile(schema, "")?;
+        compiler.check_cycles()?;
+        Ok(Self {
+            nodes: compiler.nodes,
+        })
+    }
+
+    /// Checks if a value conforms to the schema
+    pub fn is_valid<V>(&self, value: &V) -> bool
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str> + Hash + Eq + Ord,
+    {
+        self.validate(value).is_ok()
+    }
+
+    /// Validates a value against the schema, returning all violations
+    pub fn validate<V>(&self, value: &V) -> Result<(), Vec<ValidationError>>
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str> + Hash + Eq + Ord,
+    {
+        let mut errors = Vec::new();
+        self.check(0, value, &mut String::new(), &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn passes<V>(&self, id: usize, value: &V, path: &str) -> bool
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str> + Hash + Eq + Ord,
+    {
+        let mut errors = Vec::new();
+        self.check(id, value, &mut path.to_string(), &mut errors);
+        errors.is_empty()
+    }
+
+    fn check_at<V>(
+        &self,
+        id: usize,
+        value: &V,
+        token: &str,
+        path: &mut String,
+        errors: &mut Vec<ValidationError>,
+    ) where
+        V: ValueTrait,
+        V::Key: Borrow<str> + Hash + Eq + Ord,
+    {
+        let len = path.len();
+        push_token(path, token);
+        self.check(id, value, path, errors);
+        path.truncate(len);
+    }
+
+    #[allow(clippy::too_many_lines, clippy::float_cmp)]
+    fn check<V>(&self, id: usize, value: &V, path: &mut String, errors: &mut Vec<ValidationError>)
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str> + Hash + Eq + Ord,
+    {
+        let node = &self.nodes[id];
+        macro_rules! fail {
+            ($keyword:expr, $message:expr $(,)?) => {
+                errors.push(ValidationError {
+                    instance_path: path.clone(),
+                    keyword: $keyword,
+                    message: $message,
+                })
+            };
+        }
+        if let Some(fixed) = node.fixed {
+            if !fixed {
+                fail!("false", "no value is allowed here".to_string());
+            }
+            return;
+        }
+        if let Some(target) = node.reference {
+            return self.check(target, value, path, errors);
+        }
+
+        if !node.types.is_empty() && !node.types.iter().any(|t| t.matches(value)) {
+            let names: Vec<_> = node.types.iter().map(|t| t.name()).collect();
+            fail!(
+                "type",
+                format!(
+                    "expected {}, found {}",
+                    names.join(" or "),
+                    value.value_type()
+                ),
+            );
+        }
+        if let Some(allowed) = &node.enumeration {
+            if !allowed.iter().any(|a| equal(value, a)) {
+                fail!("enum", "value is not one of the allowed values".to_string());
+            }
+        }
+        if let Some(constant) = &node.constant {
+            if !equal(value, constant) {
+                fail!("const", "value doesn't equal the constant".to_string());
+            }
+        }
+
+        if let (ValueType::I64, Some(x)) | (ValueType::F64, Some(x)) =
+            (value.value_type(), value.cast_f64())
+        {
+            if let Some(min) = node.minimum {
+                if x < min {
+                    fail!("minimum", format!("{} is less than {}", x, min));
+                }
+            }
+            if let Some(max) = node.maximum {
+                if x > max {
+                    fail!("maximum", format!("{} is greater than {}", x, max));
+                }
+            }
+            if let Some(min) = node.exclusive_minimum {
+                if x <= min {
+                    fail!(
+                        "exclusiveMinimum",
+                        format!("{} is not greater than {}", x, min),
+                    );
+                }
+            }
+            if let Some(max) = node.exclusive_maximum {
+                if x >= max {
+                    fail!(
+                        "exclusiveMaximum",
+                        format!("{} is not less than {}", x, max)
+                    );
+                }
+            }
+            if let Some(m) = node.multiple_of {
+                let exact = decimal(value)
+                    .zip(node.multiple_of_decimal)
+                    .and_then(|(x, m)| is_multiple(x, m));
+                // only numbers with too many digits for an i128 are left to
+                // floating point, allowing for a few ULPs of rounding
+                let multiple = exact.unwrap_or_else(|| {
+                    let q = x / m;
+                    (q - q.round()).abs() <= 4.0 * f64::EPSILON * q.abs()
+                });
+                if !multiple {
+                    fail!("multipleOf", format!("{} is not a multiple of {}", x, m));
+                }
+            }
+        }
+
+        if let Some(s) = value.as_str() {
+            let len = s.chars().count();
+            if let Some(min) = node.min_length {
+                if len < min {
+                    fail!("minLength", format!("string is shorter than {}", min));
+                }
+            }
+            if let Some(max) = node.max_length {
+                if len > max {
+                    fail!("maxLength", format!("string is longer than {}", max));
+                }
+            }
+            if let Some(re) = &node.pattern {
+                if !re.is_match(s) {
+                    fail!(
+                        "pattern",
+                        format!("string doesn't match the pattern {:?}", re.as_str()),
+                    );
+                }
+            }
+        }
+
+        if let Some(array) = value.as_array() {
+            if let Some(min) = node.min_items {
+                if array.len() < min {
+                    fail!("minItems", format!("array has fewer than {} items", min));
+                }
+            }
+            if let Some(max) = node.max_items {
+                if array.len() > max {
+                    fail!("maxItems", format!("array has more than {} items", max));
+                }
+            }
+            if node.unique_items {
+                let duplicate = array
+                    .iter()
+                    .enumerate()
+                    .any(|(i, a)| array[i + 1..].iter().any(|b| equal(a, b)));
+                if duplicate {
+                    fail!("uniqueItems", "array items are not unique".to_string());
+                }
+            }
+            if let Some(contains) = node.contains {
+                let mut item_path = String::new();
+                let found = array.iter().enumerate().any(|(i, item)| {
+                    item_path.clear();
+                    item_path.push_str(path);
+                    push_token(&mut item_path, &i.to_string());
+                    self.passes(contains, item, &item_path)
+                });
+                if !found {
+                    fail!(
+                        "contains",
+                        "array contains no item matching the schema".to_string(),
+                    );
+                }
+            }
+            match &node.items {
+                Items::Any => (),
+                Items::All(schema) => {
+                    for (i, item) in array.iter().enumerate() {
+                        self.check_at(*schema, item, &i.to_string(), path, errors);
+                    }
+                }
+                Items::Tuple(schemas) => {
+                    for (i, item) in array.iter().enumerate() {
+                        if let Some(schema) = schemas.get(i).or(node.additional_items.as_ref()) {
+                            self.check_at(*schema, item, &i.to_string(), path, errors);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            if let Some(min) = node.min_properties {
+                if object.len() < min {
+                    fail!(
+                        "minProperties",
+                        format!("object has fewer than {} properties", min),
+                    );
+                }
+            }
+            if let Some(max) = node.max_properties {
+                if object.len() > max {
+                    fail!(
+                        "maxProperties",
+                        format!("object has more than {} properties", max),
+                    );
+                }
+            }
+            for name in &node.required {
+                if object.get(name.as_str()).is_none() {
+                    fail!("required", format!("missing property {:?}", name));
+                }
+            }
+            for (name, dependency) in &node.dependencies {
+                if object.get(name.as_str()).is_none() {
+                    continue;
+                }
+                match dependency {
+                    Dependency::Properties(required) => {
+                        for r in required {
+                            if object.get(r.as_str()).is_none() {
+                                fail!(
+                                    "dependencies",
+                                    format!("missing property {:?} required by {:?}", r, name),
+                                );
+                            }
+                        }
+                    }
+                    Dependency::Schema(schema) => self.check(*schema, value, path, errors),
+                }
+            }
+            for (key, member) in object {
+                let key: &str = key.borrow();
+                if let Some(names) = node.property_names {
+                    let name = OwnedValue::from(key);
+                    if !self.passes(names, &name, path) {
+                        fail!(
+                            "propertyNames",
+                            format!("property name {:?} doesn't match the schema", key)
+                        );
+                    }
+                }
+                let mut matched = false;
+                if let Some(schema) = node.properties.get(key) {
+                    matched = true;
+                    self.check_at(*schema, member, key, path, errors);
+                }
+                for (re, schema) in &node.pattern_properties {
+                    if re.is_match(key) {
+                        matched = true;
+                        self.check_at(*schema, member, key, path, errors);
+                    }
+                }
+                match node.additional_properties {
+                    Some(schema) if !matched && self.nodes[schema].fixed == Some(false) => {
+                        fail!(
+                            "additionalProperties",
+                            format!("property {:?} is not allowed", key)
+                        );
+                    }
+                    Some(schema) if !matched => self.check_at(schema, member, key, path, errors),
+                    _ => (),
+                }
+            }
+        }
+
+        for schema in &node.all_of {
+            self.check(*schema, value, path, errors);
+        }
+        if let Some(any_of) = &node.any_of {
+            if !any_of.iter().any(|s| self.passes(*s, value, path)) {
+                fail!(
+                    "anyOf",
+                    "value doesn't match any of the schemas".to_string()
+                );
+            }
+        }
+        if let Some(one_of) = &node.one_of {
+            let matches = one_of
+                .iter()
+                .filter(|s| self.passes(**s, value, path))
+                .count();
+            if matches != 1 {
+                fail!(
+                    "oneOf",
+                    format!("value matches {} of the schemas instead of one", matches)
+                );
+            }
+        }
+        if let Some(not) = node.not {
+            if self.passes(not, value, path) {
+                fail!(
+                    "not",
+                    "value matches a schema it must not match".to_string()
+                );
+            }
+        }
+        if let Some(condition) = node.condition {
+            let branch = if self.passes(condition, value, path) {
+                node.then
+            } else {
+                node.otherwise
+            };
+            if let Some(branch) = branch {
+                self.check(branch, value, path, errors);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl Type {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "null" => Self::Null,
+            "boolean" => Self::Boolean,
+            "integer" => Self::Integer,
+            "number" => Self::Number,
+            "string" => Self::String,
+            "array" => Self::Array,
+            "object" => Self::Object,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean => "boolean",
+            Self::Integer => "integer",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Array => "array",
+            Self::Object => "object",
+        }
+    }
+
+    #[allow(clippy::float_cmp)]
+    fn matches<V: ValueTrait>(self, value: &V) -> bool {
+        match (self, value.value_type()) {
+            (Self::Null, ValueType::Null)
+            | (Self::Boolean, ValueType::Bool)
+            | (Self::Integer, ValueType::I64)
+            | (Self::Number, ValueType::I64)
+            | (Self::Number, ValueType::F64)
+            | (Self::String, ValueType::String)
+            | (Self::Array, ValueType::Array)
+            | (Self::Object, ValueType::Object) => true,
+            // a float without a fractional part counts as an integer
+            (Self::Integer, ValueType::F64) => value.as_f64().map_or(false, |f| f.fract() == 0.0),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Items {
+    Any,
+    All(usize),
+    Tuple(Vec<usize>),
+}
+
+impl Default for Items {
+    fn default() -> Self {
+        Self::Any
+    }
+}
+
+#[derive(Debug)]
+enum Dependency {
+    Properties(Vec<String>),
+    Schema(usize),
+}
+
+// A compiled schema, subschemas are referred to by their index
+#[derive(Debug, Default)]
+struct Node {
+    // set for `true` and `false` schemas
+    fixed: Option<bool>,
+    reference: Option<usize>,
+    types: Vec<Type>,
+    enumeration: Option<Vec<OwnedValue>>,
+    constant: Option<OwnedValue>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    multiple_of: Option<f64>,
+    multiple_of_decimal: Option<Decimal>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+    items: Items,
+    additional_items: Option<usize>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    unique_items: bool,
+    contains: Option<usize>,
+    properties: HashMap<String, usize>,
+    pattern_properties: Vec<(Regex, usize)>,
+    additional_properties: Option<usize>,
+    required: Vec<String>,
+    min_properties: Option<usize>,
+    max_properties: Option<usize>,
+    property_names: Option<usize>,
+    dependencies: Vec<(String, Dependency)>,
+    all_of: Vec<usize>,
+    any_of: Option<Vec<usize>>,
+    one_of: Option<Vec<usize>>,
+    not: Option<usize>,
+    condition: Option<usize>,
+    then: Option<usize>,
+    otherwise: Option<usize>,
+}
+
+impl Node {
+    // subschemas that apply to the value itself rather than to a part of it
+    fn in_place(&self) -> impl Iterator<Item = usize> + '_ {
+        let dependencies = self.dependencies.iter().filter_map(|(_, d)| match d {
+            Dependency::Schema(s) => Some(s),
+            Dependency::Properties(_) => None,
+        });
+        self.reference
+            .iter()
+            .chain(&self.all_of)
+            .chain(self.any_of.iter().flatten())
+            .chain(self.one_of.iter().flatten())
+            .chain(&self.not)
+            .chain(&self.condition)
+            .chain(&self.then)
+            .chain(&self.otherwise)
+            .chain(dependencies)
+            .copied()
+    }
+}
+
+struct Compiler<'s> {
+    root: &'s OwnedValue,
+    nodes: Vec<Node>,
+    // nodes created for each reference, so recursive schemas terminate
+    refs: HashMap<String, usize>,
+    // where the `$ref` of each node using one is, for reporting cycles
+    ref_pointers: HashMap<usize, String>,
+}
+
+fn error<M: Into<String>>(pointer: &str, message: M) -> SchemaError {
+    SchemaError {
+        pointer: pointer.to_string(),
+        message: message.into(),
+    }
+}
+
+fn push_token(path: &mut String, token: &str) {
+    path.push('/');
+    for c in token.chars() {
+        match c {
+            '~' => path.push_str("~0"),
+            '/' => path.push_str("~1"),
+            c => path.push(c),
+        }
+    }
+}
+
+fn child(pointer: &str, token: &str) -> String {
+    let mut path = pointer.to_string();
+    push_token(&mut path, token);
+    path
+}
+
+impl<'s> Compiler<'s> {
+    fn compile(&mut self, schema: &OwnedValue, pointer: &str) -> Result<usize, SchemaError> {
+        let id = self.nodes.len();
+        self.nodes.push(Node::default());
+        let node = match schema {
+            OwnedValue::Static(StaticNode::Bool(b)) => Node {
+                fixed: Some(*b),
+                ..Node::default()
+            },
+            OwnedValue::Object(_) => self.node(schema, pointer)?,
+            _ => return Err(error(pointer, "a schema has to be an object or a boolean")),
+        };
+        if node.reference.is_some() {
+            self.ref_pointers.insert(id, child(pointer, "$ref"));
+        }
+        self.nodes[id] = node;
+        Ok(id)
+    }
+
+    fn check_cycles(&self) -> Result<(), SchemaError> {
+        let mut done = vec![false; self.nodes.len()];
+        let mut stack = Vec::new();
+        for id in 0..self.nodes.len() {
+            self.visit(id, &mut done, &mut stack)?;
+        }
+        Ok(())
+    }
+
+    // Walks the subschemas applying to the same value, coming back to one
+    // on the stack means validation would recurse without ever ending
+    fn visit(
+        &self,
+        id: usize,
+        done: &mut [bool],
+        stack: &mut Vec<usize>,
+    ) -> Result<(), SchemaError> {
+        if done[id] {
+            return Ok(());
+        }
+        if let Some(start) = stack.iter().position(|s| *s == id) {
+            let pointer = stack[start..]
+                .iter()
+                .find_map(|s| self.ref_pointers.get(s))
+                .map_or("", String::as_str);
+            return Err(error(
+                pointer,
+                "reference cycle that never descends into the value",
+            ));
+        }
+        stack.push(id);
+        for next in self.nodes[id].in_place() {
+            self.visit(next, done, stack)?;
+        }
+        stack.pop();
+        done[id] = true;
+        Ok(())
+    }
+
+    fn reference(&mut self, reference: &str, pointer: &str) -> Result<usize, SchemaError> {
+        if let Some(id) = self.refs.get(reference) {
+            return Ok(*id);
+        }
+        if !reference.starts_with('#') {
+            return Err(error(
+                pointer,
+                "only references within the schema are supported",
+            ));
+        }
+        let fragment = &reference[1..];
+        let target = tokens(fragment)
+            .and_then(|t| resolve(self.root, &t))
+            .ok_or_else(|| error(pointer, format!("can't resolve reference {:?}", reference)))?;
+        let id = self.nodes.len();
+        self.nodes.push(Node::default());
+        self.refs.insert(reference.to_string(), id);
+        let target = self.compile(target, fragment)?;
+        self.nodes[id].reference = Some(target);
+        Ok(id)
+    }
+
+    fn node(&mut self, schema: &OwnedValue, pointer: &str) -> Result<Node, SchemaError> {
+        if let Some(reference) = schema.get("$ref") {
+            let at = child(pointer, "$ref");
+            let reference = reference
+                .as_str()
+                .ok_or_else(|| error(&at, "has to be a string"))?;
+            return Ok(Node {
+                reference: Some(self.reference(reference, &at)?),
+                ..Node::default()
+            });
+        }
+
+        let mut node = Node::default();
+        match schema.get("type") {
+            None => (),
+            Some(t) if t.is_str() => node.types = vec![type_of(t, &child(pointer, "type"))?],
+            Some(OwnedValue::Array(types)) => {
+                let at = child(pointer, "type");
+                for t in types {
+                    node.types.push(type_of(t, &at)?);
+                }
+            }
+            Some(_) => {
+                return Err(error(
+                    &child(pointer, "type"),
+                    "has to be a string or an array",
+                ))
+            }
+        }
+        if let Some(allowed) = schema.get("enum") {
+            node.enumeration = Some(
+                allowed
+                    .as_array()
+                    .ok_or_else(|| error(&child(pointer, "enum"), "has to be an array"))?
+                    .clone(),
+            );
+        }
+        node.constant = schema.get("const").cloned();
+
+        node.minimum = number(schema, "minimum", pointer)?;
+        node.maximum = number(schema, "maximum", pointer)?;
+        node.multiple_of = number(schema, "multipleOf", pointer)?;
+        if node.multiple_of.map_or(false, |m| m <= 0.0) {
+            return Err(error(&child(pointer, "multipleOf"), "has to be positive"));
+        }
+        node.multiple_of_decimal = schema.get("multipleOf").and_then(decimal);
+        // draft-04 used booleans modifying `minimum` and `maximum`
+        match schema.get_bool("exclusiveMinimum") {
+            Some(true) => node.exclusive_minimum = node.minimum.take(),
+            Some(false) => (),
+            None => node.exclusive_minimum = number(schema, "exclusiveMinimum", pointer)?,
+        }
+        match schema.get_bool("exclusiveMaximum") {
+            Some(true) => node.exclusive_maximum = node.maximum.take(),
+            Some(false) => (),
+            None => node.exclusive_maximum = number(schema, "exclusiveMaximum", pointer)?,
+        }
+
+        node.min_length = count(schema, "minLength", pointer)?;
+        node.max_length = count(schema, "maxLength", pointer)?;
+        if let Some(pattern) = string(schema, "pattern", pointer)? {
+            node.pattern = Some(regex(pattern, &child(pointer, "pattern"))?);
+        }
+
+        match schema.get("items") {
+            None => (),
+            Some(OwnedValue::Array(schemas)) => {
+                let at = child(pointer, "items");
+                let mut items = Vec::with_capacity(schemas.len());
+                for (i, s) in schemas.iter().enumerate() {
+                    items.push(self.compile(s, &child(&at, &i.to_string()))?);
+                }
+                node.items = Items::Tuple(items);
+                node.additional_items = self.subschema(schema, "additionalItems", pointer)?;
+            }
+            Some(s) => node.items = Items::All(self.compile(s, &child(pointer, "items"))?),
+        }
+        node.min_items = count(schema, "minItems", pointer)?;
+        node.max_items = count(schema, "maxItems", pointer)?;
+        node.unique_items = schema.get("uniqueItems").and_then(OwnedValue::as_bool) == Some(true);
+        node.contains = self.subschema(schema, "contains", pointer)?;
+
+        if let Some(properties) = schema.get("properties") {
+            let at = child(pointer, "properties");
+            let properties = properties
+                .as_object()
+                .ok_or_else(|| error(&at, "has to be an object"))?;
+            for (name, s) in properties {
+                let id = self.compile(s, &child(&at, name))?;
+                node.properties.insert(name.to_string(), id);
+            }
+        }
+        if let Some(properties) = schema.get("patternProperties") {
+            let at = child(pointer, "patternProperties");
+            let properties = properties
+                .as_object()
+                .ok_or_else(|| error(&at, "has to be an object"))?;
+            for (pattern, s) in properties {
+                let at = child(&at, pattern);
+                let re = regex(pattern, &at)?;
+                node.pattern_properties.push((re, self.compile(s, &at)?));
+            }
+        }
+        node.additional_properties = self.subschema(schema, "additionalProperties", pointer)?;
+        node.required = strings(schema, "required", pointer)?.unwrap_or_default();
+        node.min_properties = count(schema, "minProperties", pointer)?;
+        node.max_properties = count(schema, "maxProperties", pointer)?;
+        node.property_names = self.subschema(schema, "propertyNames", pointer)?;
+        if let Some(dependencies) = schema.get("dependencies") {
+            let at = child(pointer, "dependencies");
+            let dependencies = dependencies
+                .as_object()
+                .ok_or_else(|| error(&at, "has to be an object"))?;
+            for (name, d) in dependencies {
+                let at = child(&at, name);
+                let dependency = if d.is_array() {
+                    Dependency::Properties(string_list(d, &at)?)
+                } else {
+                    Dependency::Schema(self.compile(d, &at)?)
+                };
+                node.dependencies.push((name.to_string(), dependency));
+            }
+        }
+
+        node.all_of = self
+            .subschemas(schema, "allOf", pointer)?
+            .unwrap_or_default();
+        node.any_of = self.subschemas(schema, "anyOf", pointer)?;
+        node.one_of = self.subschemas(schema, "oneOf", pointer)?;
+        node.not = self.subschema(schema, "not", pointer)?;
+        node.condition = self.subschema(schema, "if", pointer)?;
+        if node.condition.is_some() {
+            node.then = self.subschema(schema, "then", pointer)?;
+            node.otherwise = self.subschema(schema, "else", pointer)?;
+        }
+        Ok(node)
+    }
+
+    fn subschema(
+        &mut self,
+        schema: &OwnedValue,
+        keyword: &str,
+        pointer: &str,
+    ) -> Result<Option<usize>, SchemaError> {
+        match schema.get(keyword) {
+            Some(s) => self.compile(s, &child(pointer, keyword)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn subschemas(
+        &mut self,
+        schema: &OwnedValue,
+        keyword: &str,
+        pointer: &str,
+    ) -> Result<Option<Vec<usize>>, SchemaError> {
+        let at = child(pointer, keyword);
+        let schemas = match schema.get(keyword) {
+            Some(OwnedValue::Array(schemas)) if !schemas.is_empty() => schemas,
+            Some(_) => return Err(error(&at, "has to be a non empty array")),
+            None => return Ok(None),
+        };
+        let mut ids = Vec::with_capacity(schemas.len());
+        for (i, s) in schemas.iter().enumerate() {
+            ids.push(self.compile(s, &child(&at, &i.to_string()))?);
+        }
+        Ok(Some(ids))
+    }
+}
+
+fn type_of(t: &OwnedValue, pointer: &str) -> Result<Type, SchemaError> {
+    t.as_str()
+        .and_then(Type::parse)
+        .ok_or_else(|| error(pointer, format!("unknown type {}", t)))
+}
+
+fn number(schema: &OwnedValue, keyword: &str, pointer: &str) -> Result<Option<f64>, SchemaError> {
+    match schema.get(keyword) {
+        Some(n) => n
+            .cast_f64()
+            .map(Some)
+            .ok_or_else(|| error(&child(pointer, keyword), "has to be a number")),
+        None => Ok(None),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn count(schema: &OwnedValue, keyword: &str, pointer: &str) -> Result<Option<usize>, SchemaError> {
+    match schema.get(keyword) {
+        Some(n) => n
+            .as_u64()
+            .map(|n| Some(n as usize))
+            .ok_or_else(|| error(&child(pointer, keyword), "has to be a non negative integer")),
+        None => Ok(None),
+    }
+}
+
+fn string<'v>(
+    schema: &'v OwnedValue,
+    keyword: &str,
+    pointer: &str,
+) -> Result<Option<&'v str>, SchemaError> {
+    match schema.get(keyword) {
+        Some(s) => s
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| error(&child(pointer, keyword), "has to be a string")),
+        None => Ok(None),
+    }
+}
+
+fn strings(
+    schema: &OwnedValue,
+    keyword: &str,
+    pointer: &str,
+) -> Result<Option<Vec<String>>, SchemaError> {
+    match schema.get(keyword) {
+        Some(list) => string_list(list, &child(pointer, keyword)).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn string_list(list: &OwnedValue, pointer: &str) -> Result<Vec<String>, SchemaError> {
+    list.as_array()
+        .and_then(|a| a.iter().map(|s| s.as_str().map(String::from)).collect())
+        .ok_or_else(|| error(pointer, "has to be an array of strings"))
+}
+
+fn regex(pattern: &str, pointer: &str) -> Result<Regex, SchemaError> {
+    Regex::new(pattern).map_err(|e| error(pointer, format!("invalid pattern: {}", e)))
+}
+
+// A number as an integer and a count of decimal places, 0.25 is (25, 2)
+type Decimal = (i128, u32);
+
+// Floats use their shortest representation that round trips, so 0.1 is a
+// tenth rather than the binary fraction closest to it. `None` for numbers
+// with too many digits.
+fn decimal<V: ValueAccess>(value: &V) -> Option<Decimal> {
+    if let Some(i) = value.as_i128().or_else(|| value.as_u64().map(i128::from)) {
+        return Some((i, 0));
+    }
+    // `Display` for floats never uses an exponent
+    let s = value.as_f64()?.to_string();
+    let (int, frac) = match s.find('.') {
+        Some(dot) => (&s[..dot], &s[dot + 1..]),
+        None => (s.as_str(), ""),
+    };
+    let digits = format!("{}{}", int, frac).parse().ok()?;
+    Some((digits, frac.len().try_into().ok()?))
+}
+
+// `None` if scaling both to the same decimal places overflows
+fn is_multiple(x: Decimal, m: Decimal) -> Option<bool> {
+    let places = x.1.max(m.1);
+    let scale = |(n, p): Decimal| 10_i128.checked_pow(places - p)?.checked_mul(n);
+    Some(scale(x)? % scale(m)? == 0)
+}
+
+// Compares two values the way JSON Schema does, numbers are equal if their
+// mathematical values are.
+#[allow(clippy::float_cmp)]
+fn equal<A, B>(a: &A, b: &B) -> bool
+where
+    A: ValueTrait,
+    A::Key: Borrow<str> + Hash + Eq + Ord,
+    B: ValueTrait,
+    B::Key: Borrow<str> + Hash + Eq + Ord,
+{
+    match (a.value_type(), b.value_type()) {
+        (ValueType::Null, ValueType::Null) => true,
+        (ValueType::Bool, ValueType::Bool) => a.as_bool() == b.as_bool(),
+        (ValueType::I64, ValueType::I64) => match (a.as_i64(), b.as_i64()) {
+            (Some(x), Some(y)) => x == y,
+            // above `i64::MAX` on either side
+            _ => match (a.as_u64(), b.as_u64()) {
+                (Some(x), Some(y)) => x == y,
+                _ => a.cast_f64() == b.cast_f64(),
+            },
+        },
+        (ValueType::I64, ValueType::F64)
+        | (ValueType::F64, ValueType::I64)
+        | (ValueType::F64, ValueType::F64) => a.cast_f64() == b.cast_f64(),
+        (ValueType::String, ValueType::String) => a.as_str() == b.as_str(),
+        (ValueType::Array, ValueType::Array) => match (a.as_array(), b.as_array()) {
+            (Some(x), Some(y)) => x.len() == y.len() && x.iter().zip(y).all(|(x, y)| equal(x, y)),
+            _ => false,
+        },
+        (ValueType::Object, ValueType::Object) => match (a.as_object(), b.as_object()) {
+            (Some(x), Some(y)) => {
+                x.len() == y.len()
+                    && y.iter().all(|(k, v)| {
+                        let k: &str = k.borrow();
+                        x.get(k).map_or(false, |w| equal(w, v))
+                    })
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{to_borrowed_value, BorrowedValue};
+
+    fn errors(schema: &OwnedValue, value: &OwnedValue) -> Vec<(String, &'static str)> {
+        Schema::compile(schema)
+            .expect("valid schema")
+            .validate(value)
+            .err()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| (e.instance_path, e.keyword))
+            .collect()
+    }
+
+    fn e(path: &str, keyword: &'static str) -> (String, &'static str) {
+        (path.to_string(), keyword)
+    }
+
+    #[test]
+    fn scalars() {
+        let schema = json!({"type": ["integer", "string"], "minimum": 2, "exclusiveMaximum": 5,
+                            "multipleOf": 2, "maxLength": 2, "pattern": "^a"});
+        assert!(errors(&schema, &json!(4)).is_empty());
+        assert!(errors(&schema, &json!(4.0)).is_empty());
+        assert!(errors(&schema, &json!("ab")).is_empty());
+        assert_eq!(
+            errors(&schema, &json!(5)),
+            vec![e("", "exclusiveMaximum"), e("", "multipleOf")]
+        );
+        assert_eq!(
+            errors(&schema, &json!(1.5)),
+            vec![e("", "type"), e("", "minimum"), e("", "multipleOf")]
+        );
+        assert_eq!(
+            errors(&schema, &json!("bäc")),
+            vec![e("", "maxLength"), e("", "pattern")]
+        );
+        assert_eq!(errors(&schema, &json!(null)), vec![e("", "type")]);
+        let schema = json!({"enum": [1, "a", [1.0, {"b": null}]], "const": [1, {"b": null}]});
+        assert!(errors(&schema, &json!([1, {"b": null}])).is_empty());
+        assert_eq!(errors(&schema, &json!(1)), vec![e("", "const")]);
+        assert_eq!(
+            errors(&schema, &json!([1, {"b": 1}])),
+            vec![e("", "enum"), e("", "const")]
+        );
+        assert!(errors(&json!({"multipleOf": 0.1}), &json!(0.3)).is_empty());
+        assert!(errors(&json!({"multipleOf": 0.01}), &json!(-1e22)).is_empty());
+        assert!(errors(&json!({"multipleOf": 3}), &json!(u64::MAX)).is_empty());
+        let not_multiple = vec![e("", "multipleOf")];
+        assert_eq!(
+            errors(
+                &json!({"multipleOf": 0.1}),
+                &json!(0.300_000_000_000_000_04)
+            ),
+            not_multiple
+        );
+        assert_eq!(
+            errors(&json!({"multipleOf": 2}), &json!(9_007_199_254_740_993_u64)),
+            not_multiple
+        );
+        assert_eq!(
+            errors(&json!({"multipleOf": 3}), &json!(u64::MAX - 1)),
+            not_multiple
+        );
+        assert_eq!(
+            errors(&json!({"const": u64::MAX}), &json!(u64::MAX - 1)),
+            vec![e("", "const")]
+        );
+        assert!(errors(&json!({"enum": [u64::MAX]}), &json!(u64::MAX)).is_empty());
+    }
+
+    #[test]
+    fn containers() {
+        let schema = json!({
+            "properties": {"a/b": {"type": "array", "items": [{"type": "string"}],
+                                   "additionalItems": false, "uniqueItems": true,
+                                   "contains": {"const": "x"}},
+                           "c": true},
+            "patternProperties": {"^n": {"type": "number"}},
+            "additionalProperties": false,
+            "required": ["a/b", "c"],
+            "propertyNames": {"maxLength": 3},
+            "dependencies": {"nn": ["n"], "n": {"minProperties": 3}}
+        });
+        assert!(errors(&schema, &json!({"a/b": ["x"], "c": null})).is_empty());
+        let mut found = errors(
+            &schema,
+            &json!({"a/b": ["y", "y", 1], "nn": "1", "long": 1, "c": 1}),
+        );
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                e("", "additionalProperties"),
+                e("", "dependencies"),
+                e("", "propertyNames"),
+                e("/a~1b", "contains"),
+                e("/a~1b", "uniqueItems"),
+                e("/a~1b/1", "false"),
+                e("/a~1b/2", "false"),
+                e("/nn", "type"),
+            ]
+        );
+    }
+
+    #[test]
+    fn combinators_and_refs() {
+        let schema = json!({
+            "definitions": {
+                "node": {
+                    "type": "object",
+                    "properties": {"children": {"type": "array", "items": {"$ref": "#/definitions/node"}}},
+                    "if": {"required": ["leaf"]},
+                    "then": {"properties": {"leaf": {"const": true}}},
+                    "else": {"required": ["children"]}
+                }
+            },
+            "allOf": [{"$ref": "#/definitions/node"}],
+            "anyOf": [{"required": ["root"]}, {"required": ["children"]}],
+            "oneOf": [{"maxProperties": 1}, {"minProperties": 3}],
+            "not": {"required": ["forbidden"]}
+        });
+        let s = Schema::compile(&schema).expect("valid schema");
+        assert!(s.is_valid(&json!({"children": [{"leaf": true}, {"children": []}]})));
+        let found: Vec<_> = s
+            .validate(&json!({"children": [{"leaf": false}, {}], "forbidden": 1}))
+            .unwrap_err()
+            .into_iter()
+            .map(|e| (e.instance_path, e.keyword))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                e("/children/0/leaf", "const"),
+                e("/children/1", "required"),
+                e("", "oneOf"),
+                e("", "not")
+            ]
+        );
+    }
+
+    #[test]
+    fn borrowed_values() {
+        let s =
+            Schema::compile(&json!({"properties": {"a": {"enum": [[1, "x"]]}}})).expect("valid");
+        let mut d = br#"{"a": [1.0, "x"]}"#.to_vec();
+        let v: BorrowedValue = to_borrowed_value(&mut d).expect("valid");
+        assert!(s.is_valid(&v));
+        let mut d = br#"{"a": [1, "y"]}"#.to_vec();
+        let v: BorrowedValue = to_borrowed_value(&mut d).expect("valid");
+        assert!(!s.is_valid(&v));
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        let pointer = |schema: OwnedValue| Schema::compile(&schema).expect_err("invalid").pointer;
+        assert_eq!(pointer(json!(1)), "");
+        assert_eq!(pointer(json!({"type": "text"})), "/type");
+        assert_eq!(
+            pointer(json!({"properties": {"a": {"pattern": "("}}})),
+            "/properties/a/pattern"
+        );
+        assert_eq!(
+            pointer(json!({"items": [{"$ref": "#/nope"}]})),
+            "/items/0/$ref"
+        );
+        assert_eq!(pointer(json!({"$ref": "other.json#"})), "/$ref");
+        assert_eq!(pointer(json!({"anyOf": []})), "/anyOf");
+        assert_eq!(pointer(json!({"$ref": "#"})), "/$ref");
+        assert_eq!(
+            pointer(json!({
+                "definitions": {"a": {"$ref": "#/definitions/b"},
+                                "b": {"allOf": [{"$ref": "#/definitions/a"}]}},
+                "$ref": "#/definitions/a"
+            })),
+            "/definitions/a/$ref"
+        );
+        assert_eq!(
+            pointer(json!({"anyOf": [{"type": "null"}, {"not": {"$ref": "#"}}]})),
+            "/anyOf/1/not/$ref"
+        );
+        // recursion into a part of the value terminates
+        assert!(Schema::compile(&json!({"properties": {"a": {"$ref": "#"}}})).is_ok());
+    }
+}
@@ -0,0 +1,341 @@
+use crate::value::object_with_capacity;
+use crate::value::owned::Object;
+use crate::{OwnedValue, ValueTrait, ValueType};
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// Infers a JSON Schema from sample values.
+///
+/// Every value added is merged into a summary of the types seen at each
+/// position, the ranges of numbers, string lengths and array sizes, and
+/// which object fields were present in all samples. The summary can be
+/// turned into a draft-07 schema or printed as one line per field.
+///
+/// ```
+/// use simd_json::json;
+/// use simd_json::schema::Inferrer;
+///
+/// let mut i = Inferrer::new();
+/// i.add(&json!({"id": 1, "tags": ["a"]}));
+/// i.add(&json!({"id": 7, "name": null}));
+/// assert_eq!(
+///     i.to_schema(),
+///     json!({
+///         "type": "object",
+///         "properties": {
+///             "id": {"type": "integer", "minimum": 1, "maximum": 7},
+///             "name": {"type": "null"},
+///             "tags": {"type": "array", "minItems": 1, "maxItems": 1,
+///                      "items": {"type": "string", "minLength": 1, "maxLength": 1}}
+///         },
+///         "required": ["id"]
+///     })
+/// );
+/// assert_eq!(
+///     i.to_string(),
+///     "(root): object\n/id: integer 1..7\n/name: null, optional 1/2\n\
+///      /tags: array 1..1 items, optional 1/2\n/tags/*: string 1..1 chars\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Inferrer {
+    root: Shape,
+}
+
+impl Inferrer {
+    /// Creates an inferrer that hasn't seen any values yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merges a sample value into the summary
+    pub fn add<V>(&mut self, value: &V)
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str> + Hash + Eq,
+    {
+        self.root.add(value);
+    }
+
+    /// Number of samples added so far
+    pub fn samples(&self) -> usize {
+        self.root.count
+    }
+
+    /// Builds a draft-07 schema that all samples conform to
+    pub fn to_schema(&self) -> OwnedValue {
+        self.root.to_schema()
+    }
+}
+
+impl<'v, V> Extend<&'v V> for Inferrer
+where
+    V: ValueTrait + 'v,
+    V::Key: Borrow<str> + Hash + Eq,
+{
+    fn extend<I: IntoIterator<Item = &'v V>>(&mut self, values: I) {
+        for v in values {
+            self.add(v);
+        }
+    }
+}
+
+impl fmt::Display for Inferrer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.root.describe(f, "(root)", None)
+    }
+}
+
+/// Infers a schema from all `values`, see `Inferrer`
+pub fn infer<'v, V, I>(values: I) -> OwnedValue
+where
+    I: IntoIterator<Item = &'v V>,
+    V: ValueTrait + 'v,
+    V::Key: Borrow<str> + Hash + Eq,
+{
+    let mut inferrer = Inferrer::new();
+    inferrer.extend(values);
+    inferrer.to_schema()
+}
+
+// What was seen at one position of the samples
+#[derive(Debug, Clone, Default)]
+struct Shape {
+    count: usize,
+    nulls: usize,
+    bools: usize,
+    integers: usize,
+    floats: usize,
+    numbers: Option<(f64, f64)>,
+    strings: usize,
+    lengths: Option<(usize, usize)>,
+    arrays: usize,
+    sizes: Option<(usize, usize)>,
+    items: Option<Box<Shape>>,
+    objects: usize,
+    properties: BTreeMap<String, Shape>,
+}
+
+fn widen<T: PartialOrd + Copy>(range: &mut Option<(T, T)>, x: T) {
+    *range = Some(match *range {
+        Some((min, max)) => (if x < min { x } else { min }, if x > max { x } else { max }),
+        None => (x, x),
+    });
+}
+
+impl Shape {
+    fn add<V>(&mut self, value: &V)
+    where
+        V: ValueTrait,
+        V::Key: Borrow<str> + Hash + Eq,
+    {
+        self.count += 1;
+        match value.value_type() {
+            ValueType::Null => self.nulls += 1,
+            ValueType::Bool => self.bools += 1,
+            ValueType::I64 | ValueType::F64 => {
+                if value.value_type() == ValueType::I64 {
+                    self.integers += 1;
+                } else {
+                    self.floats += 1;
+                }
+                if let Some(x) = value.cast_f64() {
+                    widen(&mut self.numbers, x);
+                }
+            }
+            ValueType::String => {
+                self.strings += 1;
+                let len = value.as_str().map_or(0, |s| s.chars().count());
+                widen(&mut self.lengths, len);
+            }
+            ValueType::Array => {
+                self.arrays += 1;
+                let array = value.as_array().map_or(&[][..], Vec::as_slice);
+                widen(&mut self.sizes, array.len());
+                for item in array {
+                    self.items.get_or_insert_with(Box::default).add(item);
+                }
+            }
+            ValueType::Object => {
+                self.objects += 1;
+                if let Some(object) = value.as_object() {
+                    for (key, member) in object {
+                        let key: &str = key.borrow();
+                        if let Some(shape) = self.properties.get_mut(key) {
+                            shape.add(member);
+                        } else {
+                            let mut shape = Shape::default();
+                            shape.add(member);
+                            self.properties.insert(key.to_string(), shape);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn types(&self) -> Vec<&'static str> {
+        let mut types = Vec::new();
+        if self.nulls > 0 {
+            types.push("null");
+        }
+        if self.bools > 0 {
+            types.push("boolean");
+        }
+        // `number` includes integers
+        if self.floats > 0 {
+            types.push("number");
+        } else if self.integers > 0 {
+            types.push("integer");
+        }
+        if self.strings > 0 {
+            types.push("string");
+        }
+        if self.arrays > 0 {
+            types.push("array");
+        }
+        if self.objects > 0 {
+            types.push("object");
+        }
+        types
+    }
+
+    fn required(&self) -> impl Iterator<Item = &String> {
+        let objects = self.objects;
+        self.properties
+            .iter()
+            .filter(move |(_, shape)| shape.count == objects)
+            .map(|(name, _)| name)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn number(&self, x: f64) -> OwnedValue {
+        if self.floats == 0 {
+            OwnedValue::from(x as i64)
+        } else {
+            OwnedValue::from(x)
+        }
+    }
+
+    fn to_schema(&self) -> OwnedValue {
+        let mut schema = Object::default();
+        let types = self.types();
+        if let [t] = types.as_slice() {
+            schema.insert("type".into(), (*t).into());
+        } else if !types.is_empty() {
+            let types = types.into_iter().map(OwnedValue::from).collect();
+            schema.insert("type".into(), OwnedValue::Array(types));
+        }
+        if let Some((min, max)) = self.numbers {
+            schema.insert("minimum".into(), self.number(min));
+            schema.insert("maximum".into(), self.number(max));
+        }
+        if let Some((min, max)) = self.lengths {
+            schema.insert("minLength".into(), (min as u64).into());
+            schema.insert("maxLength".into(), (max as u64).into());
+        }
+        if let Some((min, max)) = self.sizes {
+            schema.insert("minItems".into(), (min as u64).into());
+            schema.insert("maxItems".into(), (max as u64).into());
+        }
+        if let Some(items) = &self.items {
+            schema.insert("items".into(), items.to_schema());
+        }
+        if self.objects > 0 {
+            let mut properties = object_with_capacity(self.properties.len());
+            for (name, shape) in &self.properties {
+                properties.insert(name.as_str().into(), shape.to_schema());
+            }
+            schema.insert("properties".into(), OwnedValue::Object(properties));
+            let required = self.required().map(|name| name.as_str().into()).collect();
+            schema.insert("required".into(), OwnedValue::Array(required));
+        }
+        OwnedValue::Object(schema)
+    }
+
+    // Writes a line for this position followed by those of its children,
+    // `parent` is the number of objects holding this field
+    fn describe(&self, f: &mut fmt::Formatter, path: &str, parent: Option<usize>) -> fmt::Result {
+        let types = self.types();
+        if types.is_empty() {
+            write!(f, "{}: nothing", path)?;
+        } else {
+            write!(f, "{}: {}", path, types.join(" | "))?;
+        }
+        if let Some((min, max)) = self.numbers {
+            write!(f, " {}..{}", min, max)?;
+        }
+        if let Some((min, max)) = self.lengths {
+            write!(f, " {}..{} chars", min, max)?;
+        }
+        if let Some((min, max)) = self.sizes {
+            write!(f, " {}..{} items", min, max)?;
+        }
+        if let Some(parent) = parent {
+            if self.count < parent {
+                write!(f, ", optional {}/{}", self.count, parent)?;
+            }
+        }
+        writeln!(f)?;
+        for (name, shape) in &self.properties {
+            let mut child = if path == "(root)" {
+                String::new()
+            } else {
+                path.to_string()
+            };
+            child.push('/');
+            child.push_str(&name.replace('~', "~0").replace('/', "~1"));
+            shape.describe(f, &child, Some(self.objects))?;
+        }
+        if let Some(items) = &self.items {
+            let prefix = if path == "(root)" { "" } else { path };
+            items.describe(f, &format!("{}/*", prefix), None)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::Schema;
+    use crate::{to_borrowed_value, BorrowedValue};
+
+    #[test]
+    fn samples_conform() {
+        let samples = vec![
+            json!({"a": [1, 2.5], "b": "x", "c": {"d": true}}),
+            json!({"a": [], "b": null, "c": {"d": false, "e": -3}}),
+            json!({"a": [0, "z"], "c": {}}),
+        ];
+        let schema = infer(&samples);
+        assert_eq!(schema["required"], json!(["a", "c"]));
+        assert_eq!(
+            schema["properties"]["a"]["items"]["type"],
+            json!(["number", "string"])
+        );
+        assert_eq!(schema["properties"]["c"]["properties"]["e"]["minimum"], -3);
+        let compiled = Schema::compile(&schema).expect("valid schema");
+        for s in &samples {
+            assert!(compiled.is_valid(s));
+        }
+        assert!(!compiled.is_valid(&json!({"a": [true], "c": {}})));
+    }
+
+    #[test]
+    fn borrowed_and_empty() {
+        let mut i = Inferrer::new();
+        assert_eq!(i.to_schema(), json!({}));
+        assert_eq!(i.to_string(), "(root): nothing\n");
+        let mut d = br#"[{"k/~": 1.5}]"#.to_vec();
+        let v: BorrowedValue = to_borrowed_value(&mut d).expect("valid");
+        i.add(&v);
+        assert_eq!(i.samples(), 1);
+        assert_eq!(
+            i.to_string(),
+            "(root): array 1..1 items\n/*: object\n/*/k~1~0: number 1.5..1.5\n"
+        );
+    }
+}
@@ -7,12 +7,15 @@
 /// directly to structs this is th4 place to go.
 ///
 mod de;
+mod se;
+mod stream;
 mod value;
+pub use self::stream::StreamDeserializer;
 pub use self::value::*;
-use crate::numberparse::Number;
-use crate::{stry, Deserializer, Error, ErrorType, Result};
-use crate::{BorrowedValue, OwnedValue};
-use serde_ext::Deserialize;
+use crate::{stry, Deserializer, Error, ErrorType, ParserOptions, Result};
+use crate::{BorrowedValue, Number, OwnedValue, StaticNode};
+use serde_ext::de::IntoDeserializer;
+use serde_ext::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 
@@ -23,7 +26,8 @@ type ConvertResult<T> = std::result::Result<T, SerdeConversionError>;
 pub enum SerdeConversionError {
     /// Serde can not reflect NAN or Infinity
     NanOrInfinity,
-    /// A integer was to large, simd-json uses i64 for all integers
+    /// A integer was to large, no longer returned since integers up to
+    /// `u64::max_value()` are supported
     IntegerTooLarge,
     /// Something horrible went wrong, please open a ticket at <https://simd-json.rs>
     Oops,
@@ -51,10 +55,96 @@ pub fn from_slice<'a, T>(s: &'a mut [u8]) -> Result<T>
 where
     T: Deserialize<'a>,
 {
-    let mut deserializer = stry!(Deserializer::from_slice(s));
+    #[cfg(feature = "parser-pool")]
+    return crate::with_parser(move |p| p.from_slice(s));
+    #[cfg(not(feature = "parser-pool"))]
+    {
+        let mut deserializer = stry!(Deserializer::from_slice(s));
 
-    T::deserialize(&mut deserializer)
+        T::deserialize(&mut deserializer)
+    }
+}
+
+/// Same as `from_slice` but allows tuning the parser using `ParserOptions`.
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+pub fn from_slice_with_options<'a, T>(s: &'a mut [u8], options: &ParserOptions) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    #[cfg(feature = "parser-pool")]
+    return crate::with_parser(move |p| p.from_slice_with_options(s, options));
+    #[cfg(not(feature = "parser-pool"))]
+    {
+        let mut deserializer = stry!(Deserializer::from_slice_with_options(s, options));
+
+        T::deserialize(&mut deserializer)
+    }
+}
+
+/// Parses the first document in a byte slice using a serde deserializer
+/// and returns it together with the number of bytes it took up, so the
+/// next document (or whatever else follows) starts at that offset. The
+/// document's part of the slice will be rewritten in the process.
+pub fn from_slice_with_rest<'a, T>(s: &'a mut [u8]) -> Result<(T, usize)>
+where
+    T: Deserialize<'a>,
+{
+    let mut stream = StreamDeserializer::new(s);
+    match stream.next() {
+        Some(Ok(v)) => Ok((v, stream.byte_offset())),
+        Some(Err(e)) => Err(e),
+        None => Err(Error::generic(ErrorType::EOF)),
+    }
+}
+
+/// Converts a `T` into an `OwnedValue`, the counterpart of `from_value`.
+///
+/// ```
+/// use simd_json::json;
+/// #[derive(serde::Serialize)]
+/// struct Point {
+///     x: i64,
+///     y: i64,
+/// }
+/// let v = simd_json::to_value(Point { x: 1, y: 2 }).unwrap();
+/// assert_eq!(v, json!({"x": 1, "y": 2}));
+/// ```
+pub fn to_value<T>(value: T) -> Result<OwnedValue>
+where
+    T: Serialize,
+{
+    to_owned_value(value)
+}
+
+/// Deserializes a `T` from an `OwnedValue` or a `BorrowedValue`, taken by
+/// value or by reference. Deserializing from a `BorrowedValue` lets `T`
+/// borrow strings that weren't unescaped from the input.
+///
+/// ```
+/// use simd_json::json;
+/// let v = json!({"x": 1, "tags": ["a", "b"]});
+/// let (x, tags): (i64, Vec<String>) = (
+///     simd_json::from_value(&v["x"]).unwrap(),
+///     simd_json::from_value(&v["tags"]).unwrap(),
+/// );
+/// assert_eq!((x, tags.len()), (1, 2));
+/// #[derive(serde::Deserialize)]
+/// struct User<'a> {
+///     name: &'a str,
+/// }
+/// let mut d = br#"{"name": "x"}"#.to_vec();
+/// let v = simd_json::to_borrowed_value(&mut d).unwrap();
+/// let user: User = simd_json::from_value(v).unwrap();
+/// assert_eq!(user.name, "x");
+/// ```
+pub fn from_value<'de, T, V>(value: V) -> Result<T>
+where
+    T: Deserialize<'de>,
+    V: IntoDeserializer<'de, Error>,
+{
+    T::deserialize(value.into_deserializer())
 }
+
 /// parses a str  using a serde deserializer.
 /// note that the slice will be rewritten in the process and
 /// might not remain a valid utf8 string in its entirety.
@@ -63,12 +153,115 @@ pub fn from_str<'a, T>(s: &'a mut str) -> Result<T>
 where
     T: Deserialize<'a>,
 {
-    let mut deserializer = stry!(Deserializer::from_slice(unsafe { s.as_bytes_mut() }));
+    from_slice(unsafe { s.as_bytes_mut() })
+}
 
-    T::deserialize(&mut deserializer)
+/// Reads the whole of `reader` into a buffer and deserializes it using
+/// serde. Since the parser needs the complete document this doesn't save
+/// memory over reading the input yourself, it only saves the boilerplate.
+pub fn from_reader<R, T>(mut reader: R) -> Result<T>
+where
+    R: std::io::Read,
+    T: serde_ext::de::DeserializeOwned,
+{
+    let mut buffer = Vec::new();
+    if let Err(e) = reader.read_to_end(&mut buffer) {
+        return Err(Error::io(e));
+    }
+    from_slice(&mut buffer)
+}
+
+/// Same as `from_reader` but reads the document from a tokio `AsyncRead`,
+/// like a request body, requires the `async` feature.
+///
+/// ```
+/// # futures::executor::block_on(async {
+/// let v: Vec<u32> = simd_json::serde::from_tokio_reader(&b"[1, 2, 3]"[..])
+///     .await
+///     .unwrap();
+/// assert_eq!(v, vec![1, 2, 3]);
+/// # });
+/// ```
+#[cfg(feature = "async")]
+pub async fn from_tokio_reader<R, T>(mut reader: R) -> Result<T>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    T: serde_ext::de::DeserializeOwned,
+{
+    use tokio::io::AsyncReadExt;
+    let mut buffer = Vec::new();
+    if let Err(e) = reader.read_to_end(&mut buffer).await {
+        return Err(Error::io(e));
+    }
+    from_slice(&mut buffer)
 }
 
-impl std::error::Error for Error {}
+/// Converts the JSON document in a byte slice to another serde format in a
+/// single pass, each value is handed to `serializer` as it is parsed so no
+/// DOM is built. Parse errors are reported as errors of the serializer.
+/// note that the slice will be rewritten in the process.
+///
+/// ```
+/// let mut d = br#"{"a": [1, 2.5, "x"], "b": null}"#.to_vec();
+/// let mut out = Vec::new();
+/// simd_json::serde::transcode(&mut d, &mut serde_json::Serializer::new(&mut out))
+///     .expect("valid json");
+/// assert_eq!(out, br#"{"a":[1,2.5,"x"],"b":null}"#.to_vec());
+/// ```
+#[cfg(feature = "transcode")]
+pub fn transcode<S>(s: &mut [u8], serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde_ext::Serializer,
+{
+    transcode_with_options(s, serializer, &ParserOptions::default())
+}
+
+/// Same as `transcode` but allows tuning the parser using `ParserOptions`.
+#[cfg(feature = "transcode")]
+pub fn transcode_with_options<S>(
+    s: &mut [u8],
+    serializer: S,
+    options: &ParserOptions,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde_ext::Serializer,
+{
+    use serde_ext::ser::Error as _;
+    let mut deserializer =
+        Deserializer::from_slice_with_options(s, options).map_err(S::Error::custom)?;
+    serde_transcode::transcode(&mut deserializer, serializer)
+}
+
+/// Serializes a value as JSON into a writer. Strings are escaped directly
+/// from the data they borrow from, so serializing a `BorrowedValue` doesn't
+/// copy any of its `Cow` contents.
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: std::io::Write,
+    T: Serialize + ?Sized,
+{
+    let mut serializer = self::se::Serializer::new(writer);
+    value.serialize(&mut serializer)
+}
+
+/// Serializes a value as JSON into a byte vector.
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut v = Vec::with_capacity(512);
+    stry!(to_writer(&mut v, value));
+    Ok(v)
+}
+
+/// Serializes a value as a JSON string.
+pub fn to_string<T>(value: &T) -> Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    // the serializer only ever writes valid utf8
+    to_vec(value).map(|v| unsafe { String::from_utf8_unchecked(v) })
+}
 
 impl serde::de::Error for Error {
     fn custom<T: fmt::Display>(msg: T) -> Self {
@@ -109,70 +302,72 @@ impl<'de> Deserializer<'de> {
 
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn parse_signed(&mut self) -> Result<i64> {
-        match self.next_() {
-            b'-' => match stry!(self.parse_number(true)) {
-                Number::I64(n) => Ok(n),
-                _ => Err(self.error(ErrorType::ExpectedSigned)),
-            },
-            b'0'..=b'9' => match stry!(self.parse_number(false)) {
-                Number::I64(n) => Ok(n),
-                _ => Err(self.error(ErrorType::ExpectedSigned)),
-            },
-            _ => Err(self.error(ErrorType::ExpectedSigned)),
+        let n = match self.next_() {
+            b'-' => stry!(self.parse_number(true)),
+            b'0'..=b'9' => stry!(self.parse_number(false)),
+            _ => return Err(self.error(ErrorType::ExpectedSigned)),
+        };
+        match n.as_i64() {
+            Some(i) => Ok(i),
+            None if n.is_u64() => Err(self.error(ErrorType::Overflow)),
+            None => Err(self.error(ErrorType::ExpectedSigned)),
         }
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn parse_unsigned(&mut self) -> Result<u64> {
-        #[allow(clippy::cast_sign_loss)]
         match self.next_() {
-            b'0'..=b'9' => match stry!(self.parse_number(false)) {
-                Number::I64(n) => Ok(n as u64),
-                _ => Err(self.error(ErrorType::ExpectedUnsigned)),
+            b'0'..=b'9' => match stry!(self.parse_number(false)).as_u64() {
+                Some(u) => Ok(u),
+                None => Err(self.error(ErrorType::ExpectedUnsigned)),
             },
             _ => Err(self.error(ErrorType::ExpectedUnsigned)),
         }
     }
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     fn parse_double(&mut self) -> Result<f64> {
-        #[allow(clippy::cast_possible_wrap, clippy::cast_precision_loss)]
         match self.next_() {
-            b'-' => match stry!(self.parse_number(true)) {
-                Number::F64(n) => Ok(n),
-                Number::I64(n) => Ok(n as f64),
-            },
-            b'0'..=b'9' => match stry!(self.parse_number(false)) {
-                Number::F64(n) => Ok(n),
-                Number::I64(n) => Ok(n as f64),
-            },
+            b'-' => Ok(stry!(self.parse_number(true)).as_f64().unwrap_or_default()),
+            b'0'..=b'9' => Ok(stry!(self.parse_number(false)).as_f64().unwrap_or_default()),
             _ => Err(self.error(ErrorType::ExpectedFloat)),
         }
     }
 }
 
+impl TryFrom<Number> for serde_json::Number {
+    type Error = SerdeConversionError;
+    fn try_from(n: Number) -> ConvertResult<Self> {
+        if let Some(u) = n.as_u64() {
+            Ok(u.into())
+        } else if let Some(i) = n.as_i64() {
+            Ok(i.into())
+        } else {
+            n.as_f64()
+                .and_then(Self::from_f64)
+                .ok_or(SerdeConversionError::NanOrInfinity)
+        }
+    }
+}
+
 impl TryFrom<serde_json::Value> for OwnedValue {
     type Error = SerdeConversionError;
     fn try_from(item: serde_json::Value) -> ConvertResult<Self> {
         use serde_json::Value;
         Ok(match item {
-            Value::Null => Self::Null,
-            Value::Bool(b) => Self::Bool(b),
+            Value::Null => Self::Static(StaticNode::Null),
+            Value::Bool(b) => Self::Static(StaticNode::Bool(b)),
             Value::Number(b) => {
                 if let Some(n) = b.as_i64() {
-                    Self::I64(n)
+                    Self::from(n)
                 } else if let Some(n) = b.as_u64() {
-                    if n > i64::max_value() as u64 {
-                        return Err(SerdeConversionError::IntegerTooLarge);
-                    }
-                    #[allow(clippy::cast_possible_wrap)]
-                    Self::I64(n as i64)
+                    Self::from(n)
                 } else if let Some(n) = b.as_f64() {
-                    Self::F64(n)
+                    Self::from(n)
                 } else {
                     return Err(SerdeConversionError::Oops);
                 }
             }
-            Value::String(b) => Self::String(b),
+            Value::String(b) => Self::from(b),
             Value::Array(a) => Self::Array(
                 a.into_iter()
                     .map(|v| v.try_into())
@@ -180,7 +375,7 @@ impl TryFrom<serde_json::Value> for OwnedValue {
             ),
             Value::Object(o) => Self::Object(
                 o.into_iter()
-                    .map(|(k, v)| Ok((k, v.try_into()?)))
+                    .map(|(k, v)| Ok((crate::value::owned::key(k), v.try_into()?)))
                     .collect::<ConvertResult<crate::value::owned::Object>>()?,
             ),
         })
@@ -192,16 +387,10 @@ impl TryInto<serde_json::Value> for OwnedValue {
     fn try_into(self) -> ConvertResult<serde_json::Value> {
         use serde_json::Value;
         Ok(match self {
-            Self::Null => Value::Null,
-            Self::Bool(b) => Value::Bool(b),
-            Self::I64(n) => Value::Number(n.into()),
-            Self::F64(n) => {
-                if let Some(n) = serde_json::Number::from_f64(n) {
-                    Value::Number(n)
-                } else {
-                    return Err(SerdeConversionError::NanOrInfinity);
-                }
-            }
+            Self::Static(StaticNode::Null) => Value::Null,
+            Self::Static(StaticNode::Bool(b)) => Value::Bool(b),
+            Self::Static(StaticNode::Number(n)) => Value::Number(n.try_into()?),
+            Self::RawNumber(n) => Value::Number(n.parse().map_err(|_| SerdeConversionError::Oops)?),
             Self::String(b) => Value::String(b.to_string()),
             Self::Array(a) => Value::Array(
                 a.into_iter()
@@ -222,19 +411,15 @@ impl<'value> TryFrom<serde_json::Value> for BorrowedValue<'value> {
     fn try_from(item: serde_json::Value) -> ConvertResult<Self> {
         use serde_json::Value;
         Ok(match item {
-            Value::Null => BorrowedValue::Null,
-            Value::Bool(b) => BorrowedValue::Bool(b),
+            Value::Null => BorrowedValue::Static(StaticNode::Null),
+            Value::Bool(b) => BorrowedValue::Static(StaticNode::Bool(b)),
             Value::Number(b) => {
                 if let Some(n) = b.as_i64() {
-                    BorrowedValue::I64(n)
+                    BorrowedValue::from(n)
                 } else if let Some(n) = b.as_u64() {
-                    if n > i64::max_value() as u64 {
-                        return Err(SerdeConversionError::IntegerTooLarge);
-                    }
-                    #[allow(clippy::cast_possible_wrap)]
-                    BorrowedValue::I64(n as i64)
+                    BorrowedValue::from(n)
                 } else if let Some(n) = b.as_f64() {
-                    BorrowedValue::F64(n)
+                    BorrowedValue::from(n)
                 } else {
                     return Err(SerdeConversionError::Oops);
                 }
@@ -259,15 +444,11 @@ impl<'value> TryInto<serde_json::Value> for BorrowedValue<'value> {
     fn try_into(self) -> ConvertResult<serde_json::Value> {
         use serde_json::Value;
         Ok(match self {
-            BorrowedValue::Null => Value::Null,
-            BorrowedValue::Bool(b) => Value::Bool(b),
-            BorrowedValue::I64(n) => Value::Number(n.into()),
-            BorrowedValue::F64(n) => {
-                if let Some(n) = serde_json::Number::from_f64(n) {
-                    Value::Number(n)
-                } else {
-                    return Err(SerdeConversionError::NanOrInfinity);
-                }
+            BorrowedValue::Static(StaticNode::Null) => Value::Null,
+            BorrowedValue::Static(StaticNode::Bool(b)) => Value::Bool(b),
+            BorrowedValue::Static(StaticNode::Number(n)) => Value::Number(n.try_into()?),
+            BorrowedValue::RawNumber(n) => {
+                Value::Number(n.parse().map_err(|_| SerdeConversionError::Oops)?)
             }
             BorrowedValue::String(b) => Value::String(b.to_string()),
             BorrowedValue::Array(a) => Value::Array(
@@ -290,6 +471,177 @@ mod test {
     use crate::{json, BorrowedValue, OwnedValue};
     use serde_json::{json as sjson, Value as SerdeValue};
     use std::convert::TryInto;
+
+    #[test]
+    fn from_slice_with_rest() {
+        let mut input = br#"{"len": 3} abc"#.to_vec();
+        let (v, used): (OwnedValue, _) = super::from_slice_with_rest(&mut input).unwrap();
+        assert_eq!(v, json!({"len": 3}));
+        assert_eq!(&input[used..], b" abc");
+        let mut input = b"[1] [2]".to_vec();
+        let (v, used): (Vec<u8>, _) = super::from_slice_with_rest(&mut input).unwrap();
+        assert_eq!((v, used), (vec![1], 3));
+        assert!(super::from_slice_with_rest::<OwnedValue>(&mut b"  ".to_vec()).is_err());
+    }
+
+    #[test]
+    fn from_reader() {
+        let v: Vec<u32> = super::from_reader(&b"[1, 2, 3]"[..]).unwrap();
+        assert_eq!(v, vec![1, 2, 3]);
+        assert!(super::from_reader::<_, Vec<u32>>(&b"[1, 2"[..]).is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn from_tokio_reader() {
+        use futures::executor::block_on;
+        let v: OwnedValue = block_on(super::from_tokio_reader(&b"{\"a\": [1]}"[..])).unwrap();
+        assert_eq!(v, json!({"a": [1]}));
+        assert!(block_on(super::from_tokio_reader::<_, Vec<u32>>(&b"[1, 2"[..])).is_err());
+    }
+
+    #[cfg(feature = "transcode")]
+    #[test]
+    fn transcode() {
+        use crate::{BigNumberBehavior, ParserOptions};
+        fn t(input: &str, options: &ParserOptions) -> Result<String, serde_json::Error> {
+            let mut d = input.as_bytes().to_vec();
+            let mut out = Vec::new();
+            super::transcode_with_options(
+                &mut d,
+                &mut serde_json::Serializer::new(&mut out),
+                options,
+            )?;
+            Ok(String::from_utf8(out).unwrap())
+        }
+        let options = ParserOptions::default();
+        // keys are sorted so serde_json's map keeps their order
+        let input = r#" {"": "", "a": [], "b": {}, "c": [[{"d": [1, -2, 3.5e3]}], "\"x\n\u00e9"],
+            "e": {"f": {"g": [true, false, null]}}} "#;
+        let expected = serde_json::to_string(&serde_json::from_str::<SerdeValue>(input).unwrap());
+        assert_eq!(t(input, &options).unwrap(), expected.unwrap());
+        assert_eq!(t("42", &options).unwrap(), "42");
+        assert!(t("[1, 2", &options).is_err());
+        assert!(t("{\"a\": 1} 2", &options).is_err());
+        assert!(t("[18446744073709551616]", &options).is_err());
+        let options = ParserOptions {
+            big_numbers: BigNumberBehavior::Raw,
+            ..ParserOptions::default()
+        };
+        assert_eq!(
+            t("[18446744073709551616]", &options).unwrap(),
+            "[\"18446744073709551616\"]"
+        );
+    }
+
+    #[test]
+    fn value_round_trip() {
+        use std::collections::HashMap;
+        let mut m = HashMap::new();
+        m.insert("a".to_string(), vec![1_u8, 2]);
+        let owned = crate::to_value(&m).unwrap();
+        assert_eq!(owned, json!({"a": [1, 2]}));
+        let borrowed = super::to_borrowed_value(&m).unwrap();
+        assert_eq!(borrowed, owned);
+        let back: HashMap<String, Vec<u8>> = crate::from_value(&owned).unwrap();
+        assert_eq!(back, m);
+        let back: HashMap<String, Vec<u8>> = crate::from_value(&borrowed).unwrap();
+        assert_eq!(back, m);
+        let back: HashMap<String, Vec<u8>> = crate::from_value(borrowed).unwrap();
+        assert_eq!(back, m);
+        assert!(crate::from_value::<u8, _>(owned).is_err());
+    }
+
+    #[test]
+    fn deserialize_seed() {
+        use serde::de::{DeserializeSeed, Deserializer, SeqAccess, Visitor};
+        use std::fmt;
+        // Interns the strings of an array into a shared table
+        struct Intern<'t>(&'t mut Vec<String>);
+        impl<'de, 't> DeserializeSeed<'de> for Intern<'t> {
+            type Value = Vec<usize>;
+            fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+                d.deserialize_seq(self)
+            }
+        }
+        impl<'de, 't> Visitor<'de> for Intern<'t> {
+            type Value = Vec<usize>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array of strings")
+            }
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut ids = Vec::new();
+                while let Some(s) = seq.next_element::<&str>()? {
+                    let id = self.0.iter().position(|t| t == s).unwrap_or_else(|| {
+                        self.0.push(s.to_string());
+                        self.0.len() - 1
+                    });
+                    ids.push(id);
+                }
+                Ok(ids)
+            }
+        }
+        let mut table = vec!["b".to_string()];
+        let mut d = br#"["a", "b", "a"]"#.to_vec();
+        let mut de = crate::Deserializer::from_slice(&mut d).unwrap();
+        let ids = Intern(&mut table).deserialize(&mut de).unwrap();
+        assert_eq!(ids, vec![1, 0, 1]);
+        assert_eq!(table, vec!["b".to_string(), "a".to_string()]);
+    }
+
+    #[test]
+    fn field_matching() {
+        use crate::ParserOptions;
+        use serde::Deserialize;
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Inner {
+            id: u8,
+        }
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Outer {
+            inner: Inner,
+            items: Vec<u8>,
+        }
+        let mut options = ParserOptions::default();
+        options.field_matching.case_insensitive = true;
+        let mut d = br#"{"Inner": {"ID": 1}, "other": {"id": 2}, "ITEMS": [3]}"#.to_vec();
+        let v: Outer = super::from_slice_with_options(&mut d, &options).unwrap();
+        assert_eq!(
+            v,
+            Outer {
+                inner: Inner { id: 1 },
+                items: vec![3]
+            }
+        );
+        let mut d = br#"{"Inner": {"id": 1}, "items": []}"#.to_vec();
+        assert!(super::from_slice::<Outer>(&mut d).is_err());
+    }
+
+    #[test]
+    fn into_deserializer() {
+        use crate::Error;
+        use serde::de::{Deserialize, IntoDeserializer};
+        use std::collections::HashMap;
+        fn load<'de, T, D>(d: D) -> Result<T, Error>
+        where
+            T: Deserialize<'de>,
+            D: IntoDeserializer<'de, Error>,
+        {
+            T::deserialize(d.into_deserializer())
+        }
+        let v: OwnedValue = json!({"a": [1, 2]});
+        let m: HashMap<&str, Vec<u8>> = load(&v).unwrap();
+        assert_eq!(m["a"], vec![1, 2]);
+        let m: HashMap<String, Vec<u8>> = load(v).unwrap();
+        assert_eq!(m["a"], vec![1, 2]);
+        let mut d = br#"["x"]"#.to_vec();
+        let v = crate::to_borrowed_value(&mut d).unwrap();
+        let s: Vec<&str> = load(&v).unwrap();
+        assert_eq!(s, vec!["x"]);
+        let s: Vec<String> = load(v).unwrap();
+        assert_eq!(s, vec!["x".to_string()]);
+    }
+
     #[test]
     fn convert_owned_value() {
         let v: OwnedValue = json!({
@@ -1,8 +1,27 @@
-use crate::numberparse::Number;
 use crate::*;
+use serde_ext::de::value::BorrowedStrDeserializer;
 use serde_ext::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
 use serde_ext::forward_to_deserialize_any;
 
+impl<'de> Deserializer<'de> {
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn visit_number<V>(&mut self, minus: bool, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.parse_number(minus) {
+            Ok(n) => super::value::visit_number(n, visitor),
+            Err(ref e)
+                if e.error() == &ErrorType::Overflow
+                    && self.options.big_numbers == BigNumberBehavior::Raw =>
+            {
+                visitor.visit_borrowed_str(stry!(self.parse_number_raw(minus, false)))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
 impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     type Error = Error;
 
@@ -18,19 +37,13 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
             b'"' => {
                 // We don't do the short string optimisation as serde requires
                 // additional checks
-                visitor.visit_borrowed_str(stry!(self.parse_str_()))
+                visitor.visit_borrowed_str(stry!(self.parse_str()))
             }
             b'n' => visitor.visit_unit(),
             b't' => visitor.visit_bool(true),
             b'f' => visitor.visit_bool(false),
-            b'-' => match stry!(self.parse_number(true)) {
-                Number::F64(n) => visitor.visit_f64(n),
-                Number::I64(n) => visitor.visit_i64(n),
-            },
-            b'0'..=b'9' => match stry!(self.parse_number(false)) {
-                Number::F64(n) => visitor.visit_f64(n),
-                Number::I64(n) => visitor.visit_i64(n),
-            },
+            b'-' => self.visit_number(true, visitor),
+            b'0'..=b'9' => self.visit_number(false, visitor),
             b'[' => visitor.visit_seq(CommaSeparated::new(&mut self)),
             b'{' => visitor.visit_map(CommaSeparated::new(&mut self)),
             _c => Err(self.error(ErrorType::UnexpectedCharacter)),
@@ -75,10 +88,10 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         }
         if let Some(next) = self.structural_indexes.get(self.idx + 1) {
             if *next as usize - self.iidx < 32 {
-                return visitor.visit_borrowed_str(stry!(self.parse_str_()));
+                return visitor.visit_borrowed_str(stry!(self.parse_str()));
             }
         }
-        visitor.visit_borrowed_str(stry!(self.parse_str_()))
+        visitor.visit_borrowed_str(stry!(self.parse_str()))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -91,10 +104,10 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         }
         if let Some(next) = self.structural_indexes.get(self.idx + 1) {
             if *next as usize - self.iidx < 32 {
-                return visitor.visit_str(stry!(self.parse_str_()));
+                return visitor.visit_str(stry!(self.parse_str()));
             }
         }
-        visitor.visit_str(stry!(self.parse_str_()))
+        visitor.visit_str(stry!(self.parse_str()))
     }
 
     // The `parse_signed` function is generic over the integer type `T` so here
@@ -311,13 +324,21 @@ impl<'a, 'de> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        if self.options.field_matching.is_exact() {
+            self.deserialize_map(visitor)
+        } else if stry!(self.next()) == b'{' {
+            let mut map = CommaSeparated::new(self);
+            map.fields = Some(fields);
+            visitor.visit_map(map)
+        } else {
+            Err(self.error(ErrorType::ExpectedMap))
+        }
     }
 
     forward_to_deserialize_any! {
@@ -334,6 +355,9 @@ struct CommaSeparated<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
     first: bool,
     len: usize,
+    // The fields of the struct deserialized, if keys are matched to them
+    // using `ParserOptions::field_matching`
+    fields: Option<&'static [&'static str]>,
 }
 
 impl<'a, 'de> CommaSeparated<'a, 'de> {
@@ -343,6 +367,7 @@ impl<'a, 'de> CommaSeparated<'a, 'de> {
             first: true,
             len: de.count_elements(),
             de,
+            fields: None,
         }
     }
 }
@@ -394,7 +419,22 @@ impl<'de, 'a> MapAccess<'de> for CommaSeparated<'a, 'de> {
         } else {
             self.len -= 1;
             self.first = false;
-            seed.deserialize(&mut *self.de).map(Some)
+            if let Some(fields) = self.fields {
+                if stry!(self.de.next()) != b'"' {
+                    return Err(self.de.error(ErrorType::ExpectedString));
+                }
+                let key = stry!(self.de.parse_str());
+                let key = self
+                    .de
+                    .options
+                    .field_matching
+                    .field(key, fields)
+                    .unwrap_or(key);
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            } else {
+                seed.deserialize(&mut *self.de).map(Some)
+            }
         }
     }
 
@@ -0,0 +1,525 @@
+use crate::value::generator::BaseGenerator;
+use crate::{stry, Error, ErrorType, Result};
+use serde_ext::ser::{self, Serialize};
+use std::io;
+use std::io::Write;
+
+type Impossible = ser::Impossible<(), Error>;
+
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+fn io_error(e: io::Error) -> Error {
+    Error::io(e)
+}
+
+fn key_must_be_a_string() -> Error {
+    Error::generic(ErrorType::KeyMustBeAString)
+}
+
+/// Serializer writing JSON straight into a writer, strings are escaped
+/// directly from the data they are borrowed from.
+pub(crate) struct Serializer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Serializer<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write> BaseGenerator for Serializer<W> {
+    type T = W;
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn get_writer(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn write_min(&mut self, _: &[u8], min: u8) -> io::Result<()> {
+        self.writer.write_all(&[min])
+    }
+}
+
+pub(crate) struct Compound<'s, W: Write> {
+    s: &'s mut Serializer<W>,
+    first: bool,
+}
+
+impl<'s, W: Write> Compound<'s, W> {
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn comma(&mut self) -> Result<()> {
+        if self.first {
+            self.first = false;
+            Ok(())
+        } else {
+            self.s.write_char(b',').map_err(io_error)
+        }
+    }
+}
+
+impl<'s, W: Write> ser::Serializer for &'s mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'s, W>;
+    type SerializeTuple = Compound<'s, W>;
+    type SerializeTupleStruct = Compound<'s, W>;
+    type SerializeTupleVariant = Compound<'s, W>;
+    type SerializeMap = Compound<'s, W>;
+    type SerializeStruct = Compound<'s, W>;
+    type SerializeStructVariant = Compound<'s, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.write(if v { b"true" } else { b"false" })
+            .map_err(io_error)
+    }
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.write_int(v).map_err(io_error)
+    }
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        write!(self.writer, "{}", v).map_err(io_error)
+    }
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        itoa::write(&mut self.writer, v)
+            .map(|_| ())
+            .map_err(io_error)
+    }
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        write!(self.writer, "{}", v).map_err(io_error)
+    }
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.serialize_f64(f64::from(v))
+    }
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        // JSON has no representation for NaN or infinity
+        if v.is_finite() {
+            self.write_float(v).map_err(io_error)
+        } else {
+            self.serialize_unit()
+        }
+    }
+    fn serialize_char(self, v: char) -> Result<()> {
+        let mut buf = [0; 4];
+        self.serialize_str(v.encode_utf8(&mut buf))
+    }
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.write_string(v).map_err(io_error)
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        use ser::SerializeSeq;
+        let mut seq = stry!(self.serialize_seq(Some(v.len())));
+        for b in v {
+            stry!(seq.serialize_element(b));
+        }
+        seq.end()
+    }
+    fn serialize_none(self) -> Result<()> {
+        self.serialize_unit()
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<()> {
+        self.write(b"null").map_err(io_error)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_str(variant)
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        stry!(self.write_char(b'{').map_err(io_error));
+        stry!(self.write_string(variant).map_err(io_error));
+        stry!(self.write_char(b':').map_err(io_error));
+        stry!(value.serialize(&mut *self));
+        self.write_char(b'}').map_err(io_error)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        stry!(self.write_char(b'[').map_err(io_error));
+        Ok(Compound {
+            s: self,
+            first: true,
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        stry!(self.write_char(b'{').map_err(io_error));
+        stry!(self.write_string(variant).map_err(io_error));
+        stry!(self.write_char(b':').map_err(io_error));
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        stry!(self.write_char(b'{').map_err(io_error));
+        Ok(Compound {
+            s: self,
+            first: true,
+        })
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        stry!(self.write_char(b'{').map_err(io_error));
+        stry!(self.write_string(variant).map_err(io_error));
+        stry!(self.write_char(b':').map_err(io_error));
+        self.serialize_map(Some(len))
+    }
+}
+
+impl<'s, W: Write> ser::SerializeSeq for Compound<'s, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        stry!(self.comma());
+        value.serialize(&mut *self.s)
+    }
+    fn end(self) -> Result<()> {
+        self.s.write_char(b']').map_err(io_error)
+    }
+}
+
+impl<'s, W: Write> ser::SerializeTuple for Compound<'s, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<()> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'s, W: Write> ser::SerializeTupleStruct for Compound<'s, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<()> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'s, W: Write> ser::SerializeTupleVariant for Compound<'s, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<()> {
+        self.s.write(b"]}").map_err(io_error)
+    }
+}
+
+impl<'s, W: Write> ser::SerializeMap for Compound<'s, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        stry!(self.comma());
+        key.serialize(MapKeySerializer { s: &mut *self.s })
+    }
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        stry!(self.s.write_char(b':').map_err(io_error));
+        value.serialize(&mut *self.s)
+    }
+    fn end(self) -> Result<()> {
+        self.s.write_char(b'}').map_err(io_error)
+    }
+}
+
+impl<'s, W: Write> ser::SerializeStruct for Compound<'s, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        stry!(self.comma());
+        stry!(self.s.write_string(key).map_err(io_error));
+        stry!(self.s.write_char(b':').map_err(io_error));
+        value.serialize(&mut *self.s)
+    }
+    fn end(self) -> Result<()> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl<'s, W: Write> ser::SerializeStructVariant for Compound<'s, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+    fn end(self) -> Result<()> {
+        self.s.write(b"}}").map_err(io_error)
+    }
+}
+
+struct MapKeySerializer<'s, W: Write> {
+    s: &'s mut Serializer<W>,
+}
+
+impl<'s, W: Write> ser::Serializer for MapKeySerializer<'s, W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible;
+    type SerializeTuple = Impossible;
+    type SerializeTupleStruct = Impossible;
+    type SerializeTupleVariant = Impossible;
+    type SerializeMap = Impossible;
+    type SerializeStruct = Impossible;
+    type SerializeStructVariant = Impossible;
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.s.write_string(v).map_err(io_error)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_str(variant)
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.s
+            .write(if v { b"\"true\"" } else { b"\"false\"" })
+            .map_err(io_error)
+    }
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        stry!(self.s.write_char(b'"').map_err(io_error));
+        stry!(self.s.write_int(v).map_err(io_error));
+        self.s.write_char(b'"').map_err(io_error)
+    }
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        write!(self.s.writer, "\"{}\"", v).map_err(io_error)
+    }
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_char(self, v: char) -> Result<()> {
+        let mut buf = [0; 4];
+        self.serialize_str(v.encode_utf8(&mut buf))
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_none(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_unit(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Impossible> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Impossible> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Impossible> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Impossible> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Impossible> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Impossible> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Impossible> {
+        Err(key_must_be_a_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::serde::{to_string, to_vec};
+    use crate::{to_borrowed_value, BorrowedValue};
+    use serde_derive::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum E {
+        Unit,
+        Newtype(u8),
+        Tuple(u8, i8),
+        Struct { a: Option<f32> },
+    }
+
+    #[derive(Serialize)]
+    struct S<'a> {
+        s: &'a str,
+        e: Vec<E>,
+        t: (u64, char),
+        m: BTreeMap<&'a str, ()>,
+    }
+
+    #[test]
+    fn structs() {
+        let mut m = BTreeMap::new();
+        m.insert("k\"", ());
+        let s = S {
+            s: "a\nb",
+            e: vec![
+                E::Unit,
+                E::Newtype(1),
+                E::Tuple(2, -3),
+                E::Struct { a: None },
+                E::Struct { a: Some(0.5) },
+            ],
+            t: (u64::max_value(), 'ä'),
+            m,
+        };
+        assert_eq!(
+            to_string(&s).expect("serializable"),
+            r#"{"s":"a\nb","e":["Unit",{"Newtype":1},{"Tuple":[2,-3]},{"Struct":{"a":null}},{"Struct":{"a":0.5}}],"t":[18446744073709551615,"ä"],"m":{"k\"":null}}"#
+        );
+    }
+
+    #[test]
+    fn borrowed_value() {
+        let mut d = br#"{"a": ["b\"c", 1, 2.5, null, {}], "d\n": true}"#.to_vec();
+        let v: BorrowedValue = to_borrowed_value(&mut d).expect("valid");
+        assert_eq!(to_string(&v).expect("serializable"), v.encode());
+    }
+
+    #[test]
+    fn non_string_keys() {
+        let mut m = BTreeMap::new();
+        m.insert(-1, 2);
+        m.insert(3, 4);
+        assert_eq!(to_string(&m).expect("serializable"), r#"{"-1":2,"3":4}"#);
+        let mut m = BTreeMap::new();
+        m.insert(true, 'a');
+        assert_eq!(to_string(&m).expect("serializable"), r#"{"true":"a"}"#);
+        let mut m = BTreeMap::new();
+        m.insert('k', u64::max_value());
+        assert_eq!(
+            to_string(&m).expect("serializable"),
+            r#"{"k":18446744073709551615}"#
+        );
+        let mut m = BTreeMap::new();
+        m.insert((1, 2), 3);
+        assert!(to_vec(&m).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use super::from_slice_with_options;
+use crate::feed::Scanner;
+use crate::{ParserOptions, Result};
+use serde_ext::Deserialize;
+use std::marker::PhantomData;
+
+/// An iterator that deserializes a `T` from each of the whitespace
+/// separated documents in a buffer, like serde_json's
+/// `StreamDeserializer`.
+///
+/// The buffer is rewritten in the process, so the values can borrow from
+/// it. After a document fails to deserialize the error is returned and
+/// iteration stops, `byte_offset` then points to the start of the failed
+/// document.
+///
+/// ```
+/// use simd_json::serde::StreamDeserializer;
+/// let mut input = br#"[1, 2] [3]  4"#.to_vec();
+/// let mut stream = StreamDeserializer::<Vec<u8>>::new(&mut input);
+/// assert_eq!(stream.next().unwrap().unwrap(), vec![1, 2]);
+/// assert_eq!(stream.byte_offset(), 6);
+/// assert_eq!(stream.next().unwrap().unwrap(), vec![3]);
+/// assert!(stream.next().unwrap().is_err());
+/// assert!(stream.next().is_none());
+/// ```
+pub struct StreamDeserializer<'de, T> {
+    input: &'de mut [u8],
+    offset: usize,
+    options: ParserOptions,
+    failed: bool,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<'de, T> StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    /// Creates a stream over `input` using the default `ParserOptions`
+    pub fn new(input: &'de mut [u8]) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    /// Creates a stream over `input` using the given `ParserOptions` for
+    /// each document
+    pub fn with_options(input: &'de mut [u8], options: ParserOptions) -> Self {
+        Self {
+            input,
+            offset: 0,
+            options,
+            failed: false,
+            output: PhantomData,
+        }
+    }
+
+    /// Number of bytes of the input that have been deserialized
+    /// successfully so far
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'de, T> Iterator for StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let mut scanner = Scanner::default();
+        let end = match scanner.scan(self.input) {
+            Some(end) => end,
+            None if scanner.is_between() => return None,
+            // an unterminated top level scalar or an incomplete document
+            None => self.input.len(),
+        };
+        let input = std::mem::take(&mut self.input);
+        let (document, rest) = input.split_at_mut(end);
+        let (_, document) = document.split_at_mut(scanner.start());
+        self.input = rest;
+        let result = from_slice_with_options(document, &self.options);
+        if result.is_ok() {
+            self.offset += end;
+        } else {
+            self.offset += scanner.start();
+            self.failed = true;
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(serde_ext::Deserialize, Debug, PartialEq)]
+    struct Event<'a> {
+        kind: &'a str,
+        id: u32,
+    }
+
+    #[test]
+    fn borrowed_documents() {
+        let mut input = br#"{"kind": "a", "id": 1}
+            {"kind": "b", "id": 2}{"kind": "c", "id": 3}  "#
+            .to_vec();
+        let mut stream = StreamDeserializer::<Event>::new(&mut input);
+        assert_eq!(stream.next().unwrap().unwrap(), Event { kind: "a", id: 1 });
+        assert_eq!(stream.byte_offset(), 22);
+        let rest: Vec<_> = stream.by_ref().map(|e| e.unwrap().kind).collect();
+        assert_eq!(rest, vec!["b", "c"]);
+        assert_eq!(stream.byte_offset(), 79);
+    }
+
+    #[test]
+    fn stops_at_error() {
+        let mut input = b"1 2 [3, ] 4".to_vec();
+        let mut stream = StreamDeserializer::<serde_json::Value>::new(&mut input);
+        assert_eq!(stream.next().unwrap().unwrap(), serde_json::json!(1));
+        assert_eq!(stream.next().unwrap().unwrap(), serde_json::json!(2));
+        assert_eq!(stream.byte_offset(), 3);
+        assert!(stream.next().unwrap().is_err());
+        assert_eq!(stream.byte_offset(), 4);
+        assert!(stream.next().is_none());
+    }
+}
@@ -1,8 +1,102 @@
 mod borrowed;
+#[cfg(feature = "serde-json-value")]
+mod json;
 mod owned;
 
+use crate::value::is_float_literal;
+use crate::value::number::N;
+use crate::{Error, ErrorType, Number, StaticNode};
+use serde_ext::de::Visitor;
+use serde_ext::ser::{self, Serialize, Serializer};
+
 pub use self::borrowed::from_value as from_borrowed_value;
 pub use self::owned::from_value as from_owned_value;
 
-//TODO: pub use borrowed::to_value as to_borrowed_value;
+pub use self::borrowed::to_value as to_borrowed_value;
 pub use self::owned::to_value as to_owned_value;
+
+impl Serialize for Number {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.n {
+            N::PosInt(u) => serializer.serialize_u64(u),
+            N::NegInt(i) => serializer.serialize_i64(i),
+            N::Float(f) => serializer.serialize_f64(f),
+        }
+    }
+}
+
+impl Serialize for StaticNode {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Number(n) => n.serialize(serializer),
+        }
+    }
+}
+
+// Visits a scalar as the type it is stored as
+fn visit_static<'de, V>(s: StaticNode, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    match s {
+        StaticNode::Null => visitor.visit_unit(),
+        StaticNode::Bool(b) => visitor.visit_bool(b),
+        StaticNode::Number(n) => visit_number(n, visitor),
+    }
+}
+
+// Visits a number as the type it is stored as
+pub(super) fn visit_number<'de, V>(n: Number, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    match n.n {
+        N::PosInt(u) => visitor.visit_u64(u),
+        N::NegInt(i) => visitor.visit_i64(i),
+        N::Float(f) => visitor.visit_f64(f),
+    }
+}
+
+// Serializes a `RawNumber` as the narrowest number type representing it
+fn serialize_raw_number<S>(n: &str, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if !is_float_literal(n) {
+        if let Ok(i) = n.parse::<i64>() {
+            return serializer.serialize_i64(i);
+        } else if let Ok(u) = n.parse::<u64>() {
+            return serializer.serialize_u64(u);
+        }
+    }
+    match n.parse::<f64>() {
+        Ok(f) => serializer.serialize_f64(f),
+        Err(_) => Err(ser::Error::custom(format!("invalid number: {}", n))),
+    }
+}
+
+// Visits a `RawNumber` as the narrowest number type representing it
+fn visit_raw_number<'de, V>(n: &str, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    if !is_float_literal(n) {
+        if let Ok(i) = n.parse::<i64>() {
+            return visitor.visit_i64(i);
+        } else if let Ok(u) = n.parse::<u64>() {
+            return visitor.visit_u64(u);
+        }
+    }
+    match n.parse::<f64>() {
+        Ok(f) => visitor.visit_f64(f),
+        Err(_) => Err(Error::generic(ErrorType::InvalidNumber)),
+    }
+}
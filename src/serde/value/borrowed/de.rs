@@ -1,11 +1,12 @@
 use crate::value::borrowed::{Object, Value};
-use crate::Error;
+use crate::value::object_with_capacity;
+use crate::{Error, StaticNode};
 use serde_ext::de::{
-    self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor,
+    self, Deserialize, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess,
+    Visitor,
 };
 use serde_ext::forward_to_deserialize_any;
 use std::borrow::Cow;
-use std::convert::TryInto;
 use std::fmt;
 
 impl<'de> de::Deserializer<'de> for Value<'de> {
@@ -19,10 +20,8 @@ impl<'de> de::Deserializer<'de> for Value<'de> {
         V: Visitor<'de>,
     {
         match self {
-            Value::Null => visitor.visit_unit(),
-            Value::Bool(b) => visitor.visit_bool(b),
-            Value::I64(n) => visitor.visit_i64(n),
-            Value::F64(n) => visitor.visit_f64(n),
+            Value::Static(s) => super::super::visit_static(s, visitor),
+            Value::RawNumber(n) => super::super::visit_raw_number(&n, visitor),
             Value::String(s) => match s {
                 Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
                 Cow::Owned(s) => visitor.visit_string(s),
@@ -30,7 +29,7 @@ impl<'de> de::Deserializer<'de> for Value<'de> {
             Value::Array(a) => visitor.visit_seq(Array(a.iter())),
             Value::Object(o) => visitor.visit_map(ObjectAccess {
                 i: o.iter(),
-                v: &Value::Null,
+                v: &Value::Static(StaticNode::Null),
             }),
         }
     }
@@ -41,6 +40,23 @@ impl<'de> de::Deserializer<'de> for Value<'de> {
     }
 }
 
+impl<'de> IntoDeserializer<'de, Error> for Value<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'a, 'de: 'a> IntoDeserializer<'a, Error> for &'a Value<'de> {
+    type Deserializer = Value<'a>;
+
+    /// Deserializes a clone of this value.
+    fn into_deserializer(self) -> Self::Deserializer {
+        self.clone()
+    }
+}
+
 struct Array<'de, 'a: 'de>(std::slice::Iter<'de, Value<'a>>);
 
 // `SeqAccess` is provided to the `Visitor` to give it the ability to iterate
@@ -62,7 +78,7 @@ impl<'de, 'a> SeqAccess<'de> for Array<'a, 'de> {
 }
 
 struct ObjectAccess<'de, 'a: 'de> {
-    i: halfbrown::Iter<'de, Cow<'a, str>, Value<'a>>,
+    i: <&'de Object<'a> as IntoIterator>::IntoIter,
     v: &'de Value<'a>,
 }
 
@@ -113,19 +129,19 @@ impl<'de> Visitor<'de> for ValueVisitor {
     /****************** unit ******************/
     #[cfg_attr(not(feature = "no-inline"), inline)]
     fn visit_unit<E>(self) -> Result<Self::Value, E> {
-        Ok(Value::Null)
+        Ok(Value::Static(StaticNode::Null))
     }
 
     /****************** bool ******************/
     #[cfg_attr(not(feature = "no-inline"), inline)]
     fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E> {
-        Ok(Value::Bool(value))
+        Ok(Value::Static(StaticNode::Bool(value)))
     }
 
     /****************** Option ******************/
     #[cfg_attr(not(feature = "no-inline"), inline)]
     fn visit_none<E>(self) -> Result<Self::Value, E> {
-        Ok(Value::Null)
+        Ok(Value::Static(StaticNode::Null))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -150,7 +166,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -158,7 +174,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -166,7 +182,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -174,7 +190,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(value))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     /****************** u64 ******************/
@@ -184,7 +200,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -192,7 +208,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -200,7 +216,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -208,11 +224,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        if let Ok(value) = value.try_into() {
-            Ok(Value::I64(value))
-        } else {
-            Err(E::custom(format!("Integer out of range: {}", value)))
-        }
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     /****************** f64 ******************/
@@ -222,7 +234,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::F64(f64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -230,7 +242,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::F64(value))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     /****************** stringy stuff ******************/
@@ -303,7 +315,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     {
         let size = map.size_hint().unwrap_or_default();
 
-        let mut m = Object::with_capacity(size);
+        let mut m = object_with_capacity(size);
         while let Some(k) = map.next_key::<&str>()? {
             let v = map.next_value()?;
             m.insert(k.into(), v);
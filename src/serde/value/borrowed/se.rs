@@ -1,21 +1,24 @@
-use crate::value::borrowed::Value;
+use super::to_value;
+use crate::value::borrowed::{Object, Value};
+use crate::value::object_with_capacity;
+use crate::{stry, Error, ErrorType, Result, StaticNode};
 use serde_ext::ser::{
     self, Serialize, SerializeMap as SerializeMapTrait, SerializeSeq as SerializeSeqTrait,
 };
 use std::borrow::Cow;
 
+type Impossible<T> = ser::Impossible<T, Error>;
+
 impl<'a> Serialize for Value<'a> {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: ser::Serializer,
     {
         match self {
-            Value::Bool(b) => serializer.serialize_bool(*b),
-            Value::Null => serializer.serialize_unit(),
-            Value::F64(f) => serializer.serialize_f64(*f),
-            Value::I64(i) => serializer.serialize_i64(*i),
-            Value::String(Cow::Borrowed(s)) => serializer.serialize_str(s),
-            Value::String(Cow::Owned(s)) => serializer.serialize_str(&s),
+            Value::Static(s) => s.serialize(serializer),
+            Value::RawNumber(n) => super::super::serialize_raw_number(n, serializer),
+            // both borrowed and owned strings are serialized by reference
+            Value::String(s) => serializer.serialize_str(s),
             Value::Array(v) => {
                 let mut seq = serializer.serialize_seq(Some(v.len()))?;
                 for e in v {
@@ -34,129 +37,100 @@ impl<'a> Serialize for Value<'a> {
     }
 }
 
-/*
-use super::{Map};
-use crate::{stry, ErrorType, Result};
-use std::marker::PhantomData;
-use super::serde::to_value;
-
-type Impossible<T> = ser::Impossible<T, Error>;
-
-pub struct Serializer<'a> {
-    marker: PhantomData<&'a u8>,
-}
-impl<'a> Default for Serializer<'a> {
-    fn default() -> Self {
-        Self {
-            marker: PhantomData,
-        }
-    }
-}
+#[derive(Default)]
+pub struct Serializer {}
 
-impl<'a> serde::Serializer for Serializer<'a> {
-    type Ok = Value<'a>;
+impl ser::Serializer for Serializer {
+    type Ok = Value<'static>;
     type Error = Error;
 
-    type SerializeSeq = SerializeVec<'a>;
-    type SerializeTuple = SerializeVec<'a>;
-    type SerializeTupleStruct = SerializeVec<'a>;
-    type SerializeTupleVariant = SerializeTupleVariant<'a>;
-    type SerializeMap = SerializeMap<'a>;
-    type SerializeStruct = SerializeMap<'a>;
-    type SerializeStructVariant = SerializeStructVariant<'a>;
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeTupleVariant;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeStructVariant;
 
     #[inline]
-    fn serialize_bool(self, value: bool) -> Result<Value<'a>> {
-        Ok(Value::Bool(value))
+    fn serialize_bool(self, value: bool) -> Result<Value<'static>> {
+        Ok(Value::Static(StaticNode::Bool(value)))
     }
 
     #[inline]
-    fn serialize_i8(self, value: i8) -> Result<Value<'a>> {
-        self.serialize_i64(value as i64)
+    fn serialize_i8(self, value: i8) -> Result<Value<'static>> {
+        self.serialize_i64(i64::from(value))
     }
 
     #[inline]
-    fn serialize_i16(self, value: i16) -> Result<Value<'a>> {
-        self.serialize_i64(value as i64)
+    fn serialize_i16(self, value: i16) -> Result<Value<'static>> {
+        self.serialize_i64(i64::from(value))
     }
 
     #[inline]
-    fn serialize_i32(self, value: i32) -> Result<Value<'a>> {
-        self.serialize_i64(value as i64)
+    fn serialize_i32(self, value: i32) -> Result<Value<'static>> {
+        self.serialize_i64(i64::from(value))
     }
 
-    fn serialize_i64(self, value: i64) -> Result<Value<'a>> {
-        Ok(Value::I64(value.into()))
-    }
-
-    #[cfg(feature = "arbitrary_precision")]
-    serde_if_integer128! {
-        fn serialize_i128(self, value: i128) -> Result<Value<'a>> {
-            Ok(Value::Number(value.into()))
-        }
+    fn serialize_i64(self, value: i64) -> Result<Value<'static>> {
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[inline]
-    fn serialize_u8(self, value: u8) -> Result<Value<'a>> {
-        self.serialize_u64(value as u64)
+    fn serialize_u8(self, value: u8) -> Result<Value<'static>> {
+        self.serialize_u64(u64::from(value))
     }
 
     #[inline]
-    fn serialize_u16(self, value: u16) -> Result<Value<'a>> {
-        self.serialize_u64(value as u64)
+    fn serialize_u16(self, value: u16) -> Result<Value<'static>> {
+        self.serialize_u64(u64::from(value))
     }
 
     #[inline]
-    fn serialize_u32(self, value: u32) -> Result<Value<'a>> {
-        self.serialize_u64(value as u64)
+    fn serialize_u32(self, value: u32) -> Result<Value<'static>> {
+        self.serialize_u64(u64::from(value))
     }
 
     #[inline]
-    fn serialize_u64(self, value: u64) -> Result<Value<'a>> {
-        Ok(Value::I64(value as i64))
-    }
-
-    #[cfg(feature = "arbitrary_precision")]
-    serde_if_integer128! {
-        fn serialize_u128(self, value: u128) -> Result<Value<'a>> {
-            Ok(Value::Number(value.into()))
-        }
+    fn serialize_u64(self, value: u64) -> Result<Value<'static>> {
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[inline]
-    fn serialize_f32(self, value: f32) -> Result<Value<'a>> {
-        self.serialize_f64(value as f64)
+    fn serialize_f32(self, value: f32) -> Result<Value<'static>> {
+        self.serialize_f64(f64::from(value))
     }
 
     #[inline]
-    fn serialize_f64(self, value: f64) -> Result<Value<'a>> {
-        Ok(Value::F64(value))
+    fn serialize_f64(self, value: f64) -> Result<Value<'static>> {
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[inline]
-    fn serialize_char(self, value: char) -> Result<Value<'a>> {
-        let mut s = String::new();
-        s.push(value);
-        self.serialize_str(&s)
+    fn serialize_char(self, value: char) -> Result<Value<'static>> {
+        Ok(Value::from(value.to_string()))
     }
 
     #[inline]
-    fn serialize_str(self, value: &str) -> Result<Value<'a>> {
+    fn serialize_str(self, value: &str) -> Result<Value<'static>> {
         Ok(Value::from(value.to_owned()))
     }
 
-    fn serialize_bytes(self, value: &[u8]) -> Result<Value<'a>> {
-        let vec = value.iter().map(|&b| Value::I64(b.into())).collect();
+    fn serialize_bytes(self, value: &[u8]) -> Result<Value<'static>> {
+        let vec = value
+            .iter()
+            .map(|&b| Value::Static(StaticNode::Number(b.into())))
+            .collect();
         Ok(Value::Array(vec))
     }
 
     #[inline]
-    fn serialize_unit(self) -> Result<Value<'a>> {
-        Ok(Value::Null)
+    fn serialize_unit(self) -> Result<Value<'static>> {
+        Ok(Value::Static(StaticNode::Null))
     }
 
     #[inline]
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value<'a>> {
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value<'static>> {
         self.serialize_unit()
     }
 
@@ -166,46 +140,42 @@ impl<'a> serde::Serializer for Serializer<'a> {
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
-    ) -> Result<Value<'a>> {
-        self.serialize_str(variant)
+    ) -> Result<Value<'static>> {
+        Ok(Value::String(Cow::Borrowed(variant)))
     }
 
     #[inline]
-    fn serialize_newtype_struct<T: ?Sized>(
-        self,
-        _name: &'static str,
-        value: &T,
-    ) -> Result<Value<'a>>
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Value<'static>>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         value: &T,
-    ) -> Result<Value<'a>>
+    ) -> Result<Value<'static>>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        let mut values = Map::new();
-        values.insert(variant, stry!(to_value(&mut value)));
+        let mut values = object_with_capacity(1);
+        values.insert(Cow::Borrowed(variant), stry!(to_value(value)));
         Ok(Value::Object(values))
     }
 
     #[inline]
-    fn serialize_none(self) -> Result<Value<'a>> {
+    fn serialize_none(self) -> Result<Value<'static>> {
         self.serialize_unit()
     }
 
     #[inline]
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Value<'a>>
+    fn serialize_some<T>(self, value: &T) -> Result<Value<'static>>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
@@ -241,21 +211,15 @@ impl<'a> serde::Serializer for Serializer<'a> {
         })
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        Ok(SerializeMap::Map {
-            map: Map::new(),
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SerializeMap {
+            map: object_with_capacity(len.unwrap_or(0)),
             next_key: None,
         })
     }
 
-    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
-        match name {
-            #[cfg(feature = "arbitrary_precision")]
-            ::number::TOKEN => Ok(SerializeMap::Number { out_value: None }),
-            #[cfg(feature = "raw_value")]
-            ::raw::TOKEN => Ok(SerializeMap::RawValue { out_value: None }),
-            _ => self.serialize_map(Some(len)),
-        }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
     }
 
     fn serialize_struct_variant(
@@ -263,183 +227,150 @@ impl<'a> serde::Serializer for Serializer<'a> {
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeStructVariant> {
         Ok(SerializeStructVariant {
             name: variant,
-            map: Map::new(),
+            map: object_with_capacity(len),
         })
     }
 }
 
-pub struct SerializeVec<'a> {
-    vec: Vec<Value<'a>>,
+pub struct SerializeVec {
+    vec: Vec<Value<'static>>,
 }
 
-pub struct SerializeTupleVariant<'a> {
-    name: &'a str,
-    vec: Vec<Value<'a>>,
+pub struct SerializeTupleVariant {
+    name: &'static str,
+    vec: Vec<Value<'static>>,
 }
 
-pub enum SerializeMap<'a> {
-    Map {
-        map: Map<'a>,
-        next_key: Option<&'a str>,
-    },
+pub struct SerializeMap {
+    map: Object<'static>,
+    next_key: Option<Cow<'static, str>>,
 }
 
-pub struct SerializeStructVariant<'a> {
-    name: &'a str,
-    map: Map<'a>,
+pub struct SerializeStructVariant {
+    name: &'static str,
+    map: Object<'static>,
 }
 
-impl<'a> serde::ser::SerializeSeq for SerializeVec<'a> {
-    type Ok = Value<'a>;
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = Value<'static>;
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        self.vec.push(stry!(to_value(&value)));
+        self.vec.push(stry!(to_value(value)));
         Ok(())
     }
 
-    fn end(self) -> Result<Value<'a>> {
+    fn end(self) -> Result<Value<'static>> {
         Ok(Value::Array(self.vec))
     }
 }
 
-impl<'a> serde::ser::SerializeTuple for SerializeVec<'a> {
-    type Ok = Value<'a>;
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = Value<'static>;
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        serde::ser::SerializeSeq::serialize_element(self, value)
+        ser::SerializeSeq::serialize_element(self, value)
     }
 
-    fn end(self) -> Result<Value<'a>> {
-        serde::ser::SerializeSeq::end(self)
+    fn end(self) -> Result<Value<'static>> {
+        ser::SerializeSeq::end(self)
     }
 }
 
-impl<'a> serde::ser::SerializeTupleStruct for SerializeVec<'a> {
-    type Ok = Value<'a>;
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = Value<'static>;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        serde::ser::SerializeSeq::serialize_element(self, value)
+        ser::SerializeSeq::serialize_element(self, value)
     }
 
-    fn end(self) -> Result<Value<'a>> {
-        serde::ser::SerializeSeq::end(self)
+    fn end(self) -> Result<Value<'static>> {
+        ser::SerializeSeq::end(self)
     }
 }
 
-impl<'a> serde::ser::SerializeTupleVariant for SerializeTupleVariant<'a> {
-    type Ok = Value<'a>;
+impl ser::SerializeTupleVariant for SerializeTupleVariant {
+    type Ok = Value<'static>;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        self.vec.push(stry!(to_value(&value)));
+        self.vec.push(stry!(to_value(value)));
         Ok(())
     }
 
-    fn end(self) -> Result<Value<'a>> {
-        let mut object = Map::new();
+    fn end(self) -> Result<Value<'static>> {
+        let mut object = object_with_capacity(1);
 
-        object.insert(&self.name, Value::Array(self.vec));
+        object.insert(Cow::Borrowed(self.name), Value::Array(self.vec));
 
         Ok(Value::Object(object))
     }
 }
 
-impl<'a> serde::ser::SerializeMap for SerializeMap<'a> {
-    type Ok = Value<'a>;
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value<'static>;
     type Error = Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<()>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        match *self {
-            SerializeMap::Map {
-                ref mut next_key, ..
-            } => {
-                *next_key = Some(stry!(key.serialize(MapKeySerializer {
-                    marker: PhantomData
-                })));
-                Ok(())
-            }
-            #[cfg(feature = "arbitrary_precision")]
-            SerializeMap::Number { .. } => unreachable!(),
-            #[cfg(feature = "raw_value")]
-            SerializeMap::RawValue { .. } => unreachable!(),
-        }
+        self.next_key = Some(stry!(key.serialize(MapKeySerializer {})));
+        Ok(())
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        match *self {
-            SerializeMap::Map {
-                ref mut map,
-                ref mut next_key,
-            } => {
-                let key = next_key.take();
-                // Panic because this indicates a bug in the program rather than an
-                // expected failure.
-                let key = key.expect("serialize_value called before serialize_key");
-                map.insert(key, stry!(to_value(&value)));
-                Ok(())
-            }
-            #[cfg(feature = "arbitrary_precision")]
-            SerializeMap::Number { .. } => unreachable!(),
-            #[cfg(feature = "raw_value")]
-            SerializeMap::RawValue { .. } => unreachable!(),
-        }
+        let key = self.next_key.take();
+        // Panic because this indicates a bug in the program rather than an
+        // expected failure.
+        let key = key.expect("serialize_value called before serialize_key");
+        self.map.insert(key, stry!(to_value(value)));
+        Ok(())
     }
 
-    fn end(self) -> Result<Value<'a>> {
-        match self {
-            SerializeMap::Map { map, .. } => Ok(Value::Object(map)),
-            #[cfg(feature = "arbitrary_precision")]
-            SerializeMap::Number { .. } => unreachable!(),
-            #[cfg(feature = "raw_value")]
-            SerializeMap::RawValue { .. } => unreachable!(),
-        }
+    fn end(self) -> Result<Value<'static>> {
+        Ok(Value::Object(self.map))
     }
 }
 
-struct MapKeySerializer<'a> {
-    marker: PhantomData<&'a u8>,
-}
+struct MapKeySerializer {}
 
 fn key_must_be_a_string() -> Error {
     Error::generic(ErrorType::KeyMustBeAString)
 }
 
-impl<'a> serde_ext::Serializer for MapKeySerializer<'a> {
-    type Ok = &'a str;
+impl ser::Serializer for MapKeySerializer {
+    type Ok = Cow<'static, str>;
     type Error = Error;
 
-    type SerializeSeq = Impossible<&'a str>;
-    type SerializeTuple = Impossible<&'a str>;
-    type SerializeTupleStruct = Impossible<&'a str>;
-    type SerializeTupleVariant = Impossible<&'a str>;
-    type SerializeMap = Impossible<&'a str>;
-    type SerializeStruct = Impossible<&'a str>;
-    type SerializeStructVariant = Impossible<&'a str>;
+    type SerializeSeq = Impossible<Cow<'static, str>>;
+    type SerializeTuple = Impossible<Cow<'static, str>>;
+    type SerializeTupleStruct = Impossible<Cow<'static, str>>;
+    type SerializeTupleVariant = Impossible<Cow<'static, str>>;
+    type SerializeMap = Impossible<Cow<'static, str>>;
+    type SerializeStruct = Impossible<Cow<'static, str>>;
+    type SerializeStructVariant = Impossible<Cow<'static, str>>;
 
     #[inline]
     fn serialize_unit_variant(
@@ -448,85 +379,68 @@ impl<'a> serde_ext::Serializer for MapKeySerializer<'a> {
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok> {
-        Ok(variant)
+        Ok(Cow::Borrowed(variant))
     }
 
     #[inline]
-    fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
 
-    fn serialize_bool(self, _value: bool) -> Result<Self::Ok> {
-        Err(key_must_be_a_string())
+    fn serialize_bool(self, value: bool) -> Result<Self::Ok> {
+        Ok(Cow::Borrowed(if value { "true" } else { "false" }))
     }
 
-    fn serialize_i8(self, _value: i8) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i8(self, value: i8) -> Result<Self::Ok> {
+        Ok(Cow::Owned(value.to_string()))
     }
 
-    fn serialize_i16(self, _value: i16) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i16(self, value: i16) -> Result<Self::Ok> {
+        Ok(Cow::Owned(value.to_string()))
     }
 
-    fn serialize_i32(self, _value: i32) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i32(self, value: i32) -> Result<Self::Ok> {
+        Ok(Cow::Owned(value.to_string()))
     }
 
-    fn serialize_i64(self, _value: i64) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i64(self, value: i64) -> Result<Self::Ok> {
+        Ok(Cow::Owned(value.to_string()))
     }
 
-    fn serialize_u8(self, _value: u8) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u8(self, value: u8) -> Result<Self::Ok> {
+        Ok(Cow::Owned(value.to_string()))
     }
 
-    fn serialize_u16(self, _value: u16) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u16(self, value: u16) -> Result<Self::Ok> {
+        Ok(Cow::Owned(value.to_string()))
     }
 
-    fn serialize_u32(self, _value: u32) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u32(self, value: u32) -> Result<Self::Ok> {
+        Ok(Cow::Owned(value.to_string()))
     }
 
-    fn serialize_u64(self, _value: u64) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u64(self, value: u64) -> Result<Self::Ok> {
+        Ok(Cow::Owned(value.to_string()))
     }
 
     fn serialize_f32(self, _value: f32) -> Result<Self::Ok> {
-        //Err(key_must_be_a_string())
         Err(key_must_be_a_string())
     }
 
     fn serialize_f64(self, _value: f64) -> Result<Self::Ok> {
-        //Err(key_must_be_a_string())
         Err(key_must_be_a_string())
     }
 
-    fn serialize_char(self, _value: char) -> Result<Self::Ok> {
-        // Ok({
-        //     let mut s = String::new();
-        //     s.push(value);
-        //     s
-        // })
-        Err(key_must_be_a_string())
+    fn serialize_char(self, value: char) -> Result<Self::Ok> {
+        Ok(Cow::Owned(value.to_string()))
     }
 
     #[inline]
     fn serialize_str(self, value: &str) -> Result<Self::Ok> {
-        // TODO: This is ugly
-        //let s = value.to_owned();
-        Ok(value)
+        Ok(Cow::Owned(value.to_owned()))
     }
 
     fn serialize_bytes(self, _value: &[u8]) -> Result<Self::Ok> {
@@ -541,7 +455,7 @@ impl<'a> serde_ext::Serializer for MapKeySerializer<'a> {
         Err(key_must_be_a_string())
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -549,7 +463,7 @@ impl<'a> serde_ext::Serializer for MapKeySerializer<'a> {
         _value: &T,
     ) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         Err(key_must_be_a_string())
     }
@@ -558,9 +472,9 @@ impl<'a> serde_ext::Serializer for MapKeySerializer<'a> {
         Err(key_must_be_a_string())
     }
 
-    fn serialize_some<T: ?Sized>(self, _value: &T) -> Result<Self::Ok>
+    fn serialize_some<T>(self, _value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         Err(key_must_be_a_string())
     }
@@ -610,71 +524,40 @@ impl<'a> serde_ext::Serializer for MapKeySerializer<'a> {
     }
 }
 
-impl<'a> serde::ser::SerializeStruct for SerializeMap<'a> {
-    type Ok = Value<'a>;
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Value<'static>;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        match *self {
-            SerializeMap::Map { .. } => {
-                stry!(serde::ser::SerializeMap::serialize_key(self, key));
-                serde::ser::SerializeMap::serialize_value(self, value)
-            }
-            #[cfg(feature = "arbitrary_precision")]
-            SerializeMap::Number { ref mut out_value } => {
-                if key == ::number::TOKEN {
-                    *out_value = Some(value.serialize(NumberValueEmitter)?);
-                    Ok(())
-                } else {
-                    Err(invalid_number())
-                }
-            }
-            #[cfg(feature = "raw_value")]
-            SerializeMap::RawValue { ref mut out_value } => {
-                if key == ::raw::TOKEN {
-                    *out_value = Some(value.serialize(RawValueEmitter)?);
-                    Ok(())
-                } else {
-                    Err(invalid_raw_value())
-                }
-            }
-        }
+        // field names are static, so the keys borrow them
+        self.map.insert(Cow::Borrowed(key), stry!(to_value(value)));
+        Ok(())
     }
 
-    fn end(self) -> Result<Value<'a>> {
-        match self {
-            SerializeMap::Map { .. } => serde::ser::SerializeMap::end(self),
-            #[cfg(feature = "arbitrary_precision")]
-            SerializeMap::Number { out_value, .. } => {
-                Ok(out_value.expect("number value was not emitted"))
-            }
-            #[cfg(feature = "raw_value")]
-            SerializeMap::RawValue { out_value, .. } => {
-                Ok(out_value.expect("raw value was not emitted"))
-            }
-        }
+    fn end(self) -> Result<Value<'static>> {
+        ser::SerializeMap::end(self)
     }
 }
 
-impl<'a> serde::ser::SerializeStructVariant for SerializeStructVariant<'a> {
-    type Ok = Value<'a>;
+impl ser::SerializeStructVariant for SerializeStructVariant {
+    type Ok = Value<'static>;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        self.map.insert(key, stry!(to_value(&value)));
+        self.map.insert(Cow::Borrowed(key), stry!(to_value(value)));
         Ok(())
     }
 
-    fn end(self) -> Result<Value<'a>> {
-        let mut object = Map::new();
+    fn end(self) -> Result<Value<'static>> {
+        let mut object = object_with_capacity(1);
 
-        object.insert(self.name, Value::Object(self.map));
+        object.insert(Cow::Borrowed(self.name), Value::Object(self.map));
 
         Ok(Value::Object(object))
     }
@@ -682,58 +565,63 @@ impl<'a> serde::ser::SerializeStructVariant for SerializeStructVariant<'a> {
 
 #[cfg(test)]
 mod test {
-    use super::{Map, Value};
-    use serde_json;
+    use super::super::to_value;
+    use crate::{json, BorrowedValue, StaticNode};
+    use serde::Serialize;
+    use std::collections::{BTreeMap, HashMap};
 
     #[test]
     fn null() {
-        let v = Value::Null;
+        let v = BorrowedValue::Static(StaticNode::Null);
         let s = serde_json::to_string(&v).expect("Failed to serialize");
         assert_eq!(s, "null")
     }
 
-    #[test]
-    fn bool_true() {
-        let v = Value::Bool(true);
-        let s = serde_json::to_string(&v).expect("Failed to serialize");
-        assert_eq!(s, "true")
-    }
-
-    #[test]
-    fn bool_false() {
-        let v = Value::Bool(false);
-        let s = serde_json::to_string(&v).expect("Failed to serialize");
-        assert_eq!(s, "false")
-    }
-
-    #[test]
-    fn float() {
-        let v = Value::F64(1.0);
-        let s = serde_json::to_string(&v).expect("Failed to serialize");
-        assert_eq!(s, "1.0")
-    }
-
-    #[test]
-    fn int() {
-        let v = Value::I64(42);
-        let s = serde_json::to_string(&v).expect("Failed to serialize");
-        assert_eq!(s, "42")
-    }
-
     #[test]
     fn arr() {
-        let v = Value::Array(vec![Value::I64(42), Value::I64(23)]);
+        let v = BorrowedValue::Array(vec![BorrowedValue::from(42), BorrowedValue::from(23)]);
         let s = serde_json::to_string(&v).expect("Failed to serialize");
         assert_eq!(s, "[42,23]")
     }
+
     #[test]
-    fn map() {
-        let mut m = Map::new();
-        m.insert("a".into(), Value::from(42));
-        m.insert("b".into(), Value::from(23));
-        let v = Value::Object(m);
-        let s = serde_json::to_string(&v).expect("Failed to serialize");
-        assert_eq!(s, r#"{"a":42,"b":23}"#)
+    fn to_value_keys() {
+        #[derive(Serialize)]
+        enum E {
+            A,
+            B { x: u8 },
+        }
+        #[derive(Serialize)]
+        struct S {
+            id: u32,
+            tags: Vec<char>,
+            e: Vec<E>,
+        }
+        let v = to_value(S {
+            id: 1,
+            tags: vec!['a'],
+            e: vec![E::A, E::B { x: 2 }],
+        })
+        .expect("valid");
+        assert_eq!(
+            v,
+            json!({"id": 1, "tags": ["a"], "e": ["A", {"B": {"x": 2}}]})
+        );
+
+        let mut m = HashMap::new();
+        m.insert(7_u32, "a");
+        m.insert(42, "b");
+        let v = to_value(&m).expect("valid");
+        assert_eq!(v, json!({"7": "a", "42": "b"}));
+        let mut m = BTreeMap::new();
+        m.insert(true, 1);
+        m.insert(false, 0);
+        assert_eq!(to_value(&m).expect("valid"), json!({"true": 1, "false": 0}));
+        let mut m = BTreeMap::new();
+        m.insert('x', -1_i8);
+        assert_eq!(to_value(&m).expect("valid"), json!({"x": -1}));
+        let mut m = BTreeMap::new();
+        m.insert((1, 2), ());
+        assert!(to_value(&m).is_err());
     }
 }
-*/
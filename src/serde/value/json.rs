@@ -0,0 +1,144 @@
+use crate::{ObjectAccess, ObjectIter, ValueAccess, ValueType};
+use serde_json::{Map, Value};
+use std::borrow::Borrow;
+use std::hash::Hash;
+
+impl<'i> ObjectIter<'i> for Map<String, Value> {
+    type Key = String;
+    type Element = Value;
+    type Iter = serde_json::map::Iter<'i>;
+}
+
+impl ObjectAccess for Map<String, Value> {
+    type Key = String;
+    type Element = Value;
+
+    /// `serde_json::Map` needs `Ord` keys to look up borrowed forms so
+    /// this scans the members.
+    #[inline]
+    fn get<Q: ?Sized>(&self, k: &Q) -> Option<&Value>
+    where
+        String: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        Map::iter(self).find_map(|(key, v)| if key.borrow() == k { Some(v) } else { None })
+    }
+
+    #[inline]
+    fn iter(&self) -> serde_json::map::Iter<'_> {
+        Map::iter(self)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        Map::len(self)
+    }
+}
+
+impl ValueAccess for Value {
+    type Key = String;
+    type Array = Vec<Self>;
+    type Object = Map<String, Self>;
+
+    fn value_type(&self) -> ValueType {
+        match self {
+            Self::Null => ValueType::Null,
+            Self::Bool(_) => ValueType::Bool,
+            Self::Number(n) if n.is_f64() => ValueType::F64,
+            Self::Number(_) => ValueType::I64,
+            Self::String(_) => ValueType::String,
+            Self::Array(_) => ValueType::Array,
+            Self::Object(_) => ValueType::Object,
+        }
+    }
+
+    fn is_null(&self) -> bool {
+        Value::is_null(self)
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        Value::as_bool(self)
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        Value::as_i64(self)
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        Value::as_u64(self)
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) if n.is_f64() => n.as_f64(),
+            _ => None,
+        }
+    }
+
+    fn cast_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => n.as_f64(),
+            _ => None,
+        }
+    }
+
+    fn as_string(&self) -> Option<String> {
+        Value::as_str(self).map(String::from)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Value::as_str(self)
+    }
+
+    fn as_array(&self) -> Option<&Vec<Self>> {
+        Value::as_array(self)
+    }
+
+    fn as_object(&self) -> Option<&Map<String, Self>> {
+        Value::as_object(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ArrayAccess, OwnedValue, ValueAccess, ValueType};
+    use serde_json::json;
+    use std::borrow::Borrow;
+    use std::hash::Hash;
+
+    fn summary<V>(v: &V) -> (Option<&str>, Option<u64>, usize)
+    where
+        V: ValueAccess,
+        V::Key: Borrow<str> + Hash + Eq,
+    {
+        let tags = v
+            .get("tags")
+            .and_then(V::as_array)
+            .map_or(0, |a| a.iter().filter(|t| t.is_str()).count());
+        (v.get_str("name"), v.get_u64("count"), tags)
+    }
+
+    #[test]
+    fn generic_over_both_doms() {
+        let serde = json!({"name": "x", "count": 3, "tags": ["a", 1, "b"]});
+        let simd: OwnedValue = crate::json!({"name": "x", "count": 3, "tags": ["a", 1, "b"]});
+        assert_eq!(summary(&serde), (Some("x"), Some(3), 2));
+        assert_eq!(summary(&serde), summary(&simd));
+    }
+
+    #[test]
+    fn numbers() {
+        let v = json!([1, -1, 1.5, u64::max_value()]);
+        assert_eq!(v.get_idx(0).and_then(ValueAccess::as_f64), None);
+        assert_eq!(v.get_idx(0).and_then(ValueAccess::cast_f64), Some(1.0));
+        assert_eq!(v.get_idx(1).and_then(ValueAccess::as_i64), Some(-1));
+        assert_eq!(
+            v.get_idx(2).map(ValueAccess::value_type),
+            Some(ValueType::F64)
+        );
+        assert_eq!(
+            v.get_idx(3).and_then(ValueAccess::as_u64),
+            Some(u64::max_value())
+        );
+    }
+}
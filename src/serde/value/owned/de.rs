@@ -1,12 +1,12 @@
-use crate::value::owned::{Object, Value};
-use crate::{stry, Error};
+use crate::value::object_with_capacity;
+use crate::value::owned::{key, str_into_string, Object, Value};
+use crate::{stry, Error, StaticNode};
 use serde::de::{
     self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Unexpected, Visitor,
 };
 use serde::forward_to_deserialize_any;
 use serde_ext::de::IntoDeserializer;
 use std::borrow::Cow;
-use std::convert::TryInto;
 use std::fmt;
 
 impl<'de> de::Deserializer<'de> for Value {
@@ -20,11 +20,9 @@ impl<'de> de::Deserializer<'de> for Value {
         V: Visitor<'de>,
     {
         match self {
-            Self::Null => visitor.visit_unit(),
-            Self::Bool(b) => visitor.visit_bool(b),
-            Self::I64(n) => visitor.visit_i64(n),
-            Self::F64(n) => visitor.visit_f64(n),
-            Self::String(s) => visitor.visit_string(s),
+            Self::Static(s) => super::super::visit_static(s, visitor),
+            Self::RawNumber(n) => super::super::visit_raw_number(&n, visitor),
+            Self::String(s) => visitor.visit_string(str_into_string(s)),
             Self::Array(a) => visit_array(a, visitor),
             Self::Object(o) => visit_object(o, visitor),
         }
@@ -72,6 +70,24 @@ where
         ))
     }
 }
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Value {
+    type Deserializer = crate::BorrowedValue<'de>;
+
+    /// Deserializes through a `BorrowedValue` referencing the strings of
+    /// this value.
+    fn into_deserializer(self) -> Self::Deserializer {
+        crate::BorrowedValue::from(self)
+    }
+}
+
 struct SeqDeserializer {
     iter: std::vec::IntoIter<Value>,
 }
@@ -404,19 +420,19 @@ impl<'de> Visitor<'de> for ValueVisitor {
     /****************** unit ******************/
     #[cfg_attr(not(feature = "no-inline"), inline)]
     fn visit_unit<E>(self) -> Result<Self::Value, E> {
-        Ok(Value::Null)
+        Ok(Value::Static(StaticNode::Null))
     }
 
     /****************** bool ******************/
     #[cfg_attr(not(feature = "no-inline"), inline)]
     fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E> {
-        Ok(Value::Bool(value))
+        Ok(Value::Static(StaticNode::Bool(value)))
     }
 
     /****************** Option ******************/
     #[cfg_attr(not(feature = "no-inline"), inline)]
     fn visit_none<E>(self) -> Result<Self::Value, E> {
-        Ok(Value::Null)
+        Ok(Value::Static(StaticNode::Null))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -441,7 +457,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -449,7 +465,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -457,7 +473,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -465,7 +481,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(value))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     /****************** u64 ******************/
@@ -475,7 +491,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -483,7 +499,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -491,7 +507,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::I64(i64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -499,12 +515,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        if let Ok(v) = value.try_into() {
-            #[allow(clippy::cast_possible_wrap)]
-            Ok(Value::I64(v))
-        } else {
-            Err(E::custom(format!("Integer out of range: {}", value)))
-        }
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     /****************** f64 ******************/
@@ -514,7 +525,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::F64(f64::from(value)))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -522,7 +533,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::F64(value))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     /****************** stringy stuff ******************/
@@ -547,7 +558,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::String(value.to_owned()))
+        Ok(Value::from(value))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline)]
@@ -555,7 +566,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::String(value))
+        Ok(Value::from(value))
     }
 
     /****************** byte stuff ******************/
@@ -595,10 +606,10 @@ impl<'de> Visitor<'de> for ValueVisitor {
     {
         let size = map.size_hint().unwrap_or_default();
 
-        let mut m = Object::with_capacity(size);
+        let mut m = object_with_capacity(size);
         while let Some(k) = map.next_key()? {
             let v = map.next_value()?;
-            m.insert(k, v);
+            m.insert(key(k), v);
         }
         Ok(Value::Object(m))
     }
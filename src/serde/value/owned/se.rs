@@ -1,6 +1,7 @@
 use super::to_value;
-use crate::value::owned::{Object, Value};
-use crate::{stry, Error, ErrorType, Result};
+use crate::value::object_with_capacity;
+use crate::value::owned::{self, CowValue, Key, Object, Value};
+use crate::{stry, Error, ErrorType, Result, StaticNode};
 use serde::ser::{self, Serialize};
 use serde_ext::ser::{SerializeMap as SerializeMapTrait, SerializeSeq as SerializeSeqTrait};
 
@@ -12,10 +13,8 @@ impl Serialize for Value {
         S: ser::Serializer,
     {
         match self {
-            Self::Bool(b) => serializer.serialize_bool(*b),
-            Self::Null => serializer.serialize_unit(),
-            Self::F64(f) => serializer.serialize_f64(*f),
-            Self::I64(i) => serializer.serialize_i64(*i),
+            Self::Static(s) => s.serialize(serializer),
+            Self::RawNumber(n) => super::super::serialize_raw_number(n, serializer),
             Self::String(s) => serializer.serialize_str(&s),
             Self::Array(v) => {
                 let mut seq = serializer.serialize_seq(Some(v.len()))?;
@@ -27,7 +26,33 @@ impl Serialize for Value {
             Self::Object(m) => {
                 let mut map = serializer.serialize_map(Some(m.len()))?;
                 for (k, v) in m.iter() {
-                    map.serialize_entry(k, v)?;
+                    map.serialize_entry(&**k, v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'v> Serialize for CowValue<'v> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match self {
+            Self::Borrowed(v) => v.serialize(serializer),
+            Self::Owned(v) => v.serialize(serializer),
+            Self::Array(v) => {
+                let mut seq = serializer.serialize_seq(Some(v.len()))?;
+                for e in v {
+                    seq.serialize_element(e)?;
+                }
+                seq.end()
+            }
+            Self::Object(m) => {
+                let mut map = serializer.serialize_map(Some(m.len()))?;
+                for (k, v) in m.iter() {
+                    map.serialize_entry(&**k, v)?;
                 }
                 map.end()
             }
@@ -56,7 +81,7 @@ impl serde::Serializer for Serializer {
 
     #[inline]
     fn serialize_bool(self, value: bool) -> Result<Value> {
-        Ok(Value::Bool(value))
+        Ok(Value::Static(StaticNode::Bool(value)))
     }
 
     #[inline]
@@ -75,13 +100,13 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_i64(self, value: i64) -> Result<Value> {
-        Ok(Value::I64(value))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg(feature = "arbitrary_precision")]
     serde_if_integer128! {
         fn serialize_i128(self, value: i128) -> Result<Value> {
-            Ok(Value::Number(value.into()))
+            Ok(Value::Static(StaticNode::Number(value.into())))
         }
     }
 
@@ -102,14 +127,13 @@ impl serde::Serializer for Serializer {
 
     #[inline]
     fn serialize_u64(self, value: u64) -> Result<Value> {
-        #[allow(clippy::cast_possible_wrap)]
-        Ok(Value::I64(value as i64))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[cfg(feature = "arbitrary_precision")]
     serde_if_integer128! {
         fn serialize_u128(self, value: u128) -> Result<Value> {
-            Ok(Value::Number(value.into()))
+            Ok(Value::Static(StaticNode::Number(value.into())))
         }
     }
 
@@ -120,7 +144,7 @@ impl serde::Serializer for Serializer {
 
     #[inline]
     fn serialize_f64(self, value: f64) -> Result<Value> {
-        Ok(Value::F64(value))
+        Ok(Value::Static(StaticNode::Number(value.into())))
     }
 
     #[inline]
@@ -136,13 +160,16 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_bytes(self, value: &[u8]) -> Result<Value> {
-        let vec = value.iter().map(|&b| Value::I64(b.into())).collect();
+        let vec = value
+            .iter()
+            .map(|&b| Value::Static(StaticNode::Number(b.into())))
+            .collect();
         Ok(Value::Array(vec))
     }
 
     #[inline]
     fn serialize_unit(self) -> Result<Value> {
-        Ok(Value::Null)
+        Ok(Value::Static(StaticNode::Null))
     }
 
     #[inline]
@@ -178,7 +205,7 @@ impl serde::Serializer for Serializer {
     where
         T: Serialize,
     {
-        let mut values = Object::new();
+        let mut values = object_with_capacity(1);
         values.insert(variant.into(), stry!(to_value(&value)));
         Ok(Value::Object(values))
     }
@@ -222,14 +249,14 @@ impl serde::Serializer for Serializer {
         len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
         Ok(SerializeTupleVariant {
-            name: variant.to_owned(),
+            name: Key::from(variant),
             vec: Vec::with_capacity(len),
         })
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
         Ok(SerializeMap::Map {
-            map: Object::new(),
+            map: object_with_capacity(len.unwrap_or(0)),
             next_key: None,
         })
     }
@@ -249,11 +276,11 @@ impl serde::Serializer for Serializer {
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeStructVariant> {
         Ok(SerializeStructVariant {
-            name: variant.to_owned(),
-            map: Object::new(),
+            name: Key::from(variant),
+            map: object_with_capacity(len),
         })
     }
 }
@@ -263,19 +290,16 @@ pub struct SerializeVec {
 }
 
 pub struct SerializeTupleVariant {
-    name: String,
+    name: Key,
     vec: Vec<Value>,
 }
 
 pub enum SerializeMap {
-    Map {
-        map: Object,
-        next_key: Option<String>,
-    },
+    Map { map: Object, next_key: Option<Key> },
 }
 
 pub struct SerializeStructVariant {
-    name: String,
+    name: Key,
     map: Object,
 }
 
@@ -341,7 +365,7 @@ impl serde::ser::SerializeTupleVariant for SerializeTupleVariant {
     }
 
     fn end(self) -> Result<Value> {
-        let mut object = Object::new();
+        let mut object = object_with_capacity(1);
 
         object.insert(self.name, Value::Array(self.vec));
 
@@ -361,7 +385,7 @@ impl serde::ser::SerializeMap for SerializeMap {
             Self::Map {
                 ref mut next_key, ..
             } => {
-                *next_key = Some(stry!(key.serialize(MapKeySerializer {})));
+                *next_key = Some(owned::key(stry!(key.serialize(MapKeySerializer {}))));
                 Ok(())
             }
             #[cfg(feature = "arbitrary_precision")]
@@ -441,67 +465,52 @@ impl serde_ext::Serializer for MapKeySerializer {
         value.serialize(self)
     }
 
-    fn serialize_bool(self, _value: bool) -> Result<Self::Ok> {
-        Err(key_must_be_a_string())
+    fn serialize_bool(self, value: bool) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
-    fn serialize_i8(self, _value: i8) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i8(self, value: i8) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
-    fn serialize_i16(self, _value: i16) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i16(self, value: i16) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
-    fn serialize_i32(self, _value: i32) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i32(self, value: i32) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
-    fn serialize_i64(self, _value: i64) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i64(self, value: i64) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
-    fn serialize_u8(self, _value: u8) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u8(self, value: u8) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
-    fn serialize_u16(self, _value: u16) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u16(self, value: u16) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
-    fn serialize_u32(self, _value: u32) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u32(self, value: u32) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
-    fn serialize_u64(self, _value: u64) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u64(self, value: u64) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
     fn serialize_f32(self, _value: f32) -> Result<Self::Ok> {
-        //Err(key_must_be_a_string())
         Err(key_must_be_a_string())
     }
 
     fn serialize_f64(self, _value: f64) -> Result<Self::Ok> {
-        //Err(key_must_be_a_string())
         Err(key_must_be_a_string())
     }
 
-    fn serialize_char(self, _value: char) -> Result<Self::Ok> {
-        // Ok({
-        //     let mut s = String::new();
-        //     s.push(value);
-        //     s
-        // })
-        Err(key_must_be_a_string())
+    fn serialize_char(self, value: char) -> Result<Self::Ok> {
+        Ok(value.to_string())
     }
 
     #[inline]
@@ -648,7 +657,7 @@ impl serde::ser::SerializeStructVariant for SerializeStructVariant {
     }
 
     fn end(self) -> Result<Value> {
-        let mut object = Object::new();
+        let mut object = object_with_capacity(1);
 
         object.insert(self.name, Value::Object(self.map));
 
@@ -706,6 +715,38 @@ mod test {
         v_arr: Vec<usize>,
         v_null: (),
     }
+    #[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+    #[test]
+    fn presized_objects() {
+        use crate::ValueAccess;
+        #[derive(Serialize)]
+        struct Point {
+            x: u8,
+            y: u8,
+            z: u8,
+        }
+        let v = super::to_value(Point { x: 1, y: 2, z: 3 }).expect("serializable");
+        let o = v.as_object().expect("object");
+        assert_eq!(o.len(), 3);
+        assert_eq!(o.capacity(), 3);
+    }
+    #[test]
+    fn stringified_map_keys() {
+        use std::collections::BTreeMap;
+        let mut m = BTreeMap::new();
+        m.insert(1_u32, "a");
+        m.insert(2, "b");
+        let v = crate::serde::to_owned_value(&m).expect("to_value");
+        assert_eq!(v, crate::json!({"1": "a", "2": "b"}));
+        let mut m = BTreeMap::new();
+        m.insert(false, 'x');
+        let v = crate::serde::to_owned_value(&m).expect("to_value");
+        assert_eq!(v, crate::json!({"false": "x"}));
+        let mut m = BTreeMap::new();
+        m.insert((1, 2), ());
+        assert!(crate::serde::to_owned_value(&m).is_err());
+    }
+
     #[test]
     fn from_slice_to_object() {
         let o = Obj::default();
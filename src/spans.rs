@@ -0,0 +1,323 @@
+use crate::pointer::{array_index, char_at, string_end, tokens, unescape};
+use crate::value::{borrowed, insert_parsed, owned, parsed_object, ObjectMap};
+use crate::{
+    BorrowedValue, Deserializer, Error, ErrorType, KeyInterner, OwnedValue, ParserOptions, Result,
+};
+use std::ops::Range;
+
+/// The byte ranges of all values (and object keys) of a document in its
+/// input.
+///
+/// Spans are recorded from the structural indexes before the input is
+/// parsed, so the ranges refer to the input as it was before parsing
+/// rewrote escaped strings. Values are looked up using JSON pointers
+/// ([RFC 6901]). If an object has duplicate keys the member that is found is
+/// the one the parsed value kept, which depends on the map backing objects:
+/// the first one by default, the last one with `preserve-order` or
+/// `sorted-objects`.
+///
+/// ```
+/// use simd_json::to_borrowed_value_with_spans;
+/// let input = br#"{"port": "eighty", "hosts": ["a", "b"]}"#;
+/// let mut d = input.to_vec();
+/// let (_value, spans) = to_borrowed_value_with_spans(&mut d).unwrap();
+/// let port = spans.get("/port").unwrap();
+/// assert_eq!(&input[port], br#""eighty""#);
+/// assert_eq!(&input[spans.key("/port").unwrap()], br#""port""#);
+/// assert_eq!(&input[spans.get("/hosts/1").unwrap()], br#""b""#);
+/// assert_eq!(spans.get("/hosts/2"), None);
+/// ```
+///
+/// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spans {
+    root: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    range: Range<usize>,
+    children: Children,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Children {
+    None,
+    Array(Vec<Span>),
+    // spans of the keys and values by key, built the same way as parsed
+    // objects so duplicate keys resolve to the member the value kept
+    Object(ObjectMap<String, (Range<usize>, Span)>),
+}
+
+impl Spans {
+    /// Byte range of the value referenced by `pointer`
+    pub fn get(&self, pointer: &str) -> Option<Range<usize>> {
+        self.find(pointer).map(|(_, span)| span.range.clone())
+    }
+
+    /// Byte range of the key (including its quotes) of the object member
+    /// referenced by `pointer`
+    pub fn key(&self, pointer: &str) -> Option<Range<usize>> {
+        self.find(pointer).and_then(|(key, _)| key)
+    }
+
+    fn find(&self, pointer: &str) -> Option<(Option<Range<usize>>, &Span)> {
+        let mut key = None;
+        let mut span = &self.root;
+        for token in tokens(pointer)? {
+            span = match &span.children {
+                Children::None => return None,
+                Children::Array(items) => {
+                    key = None;
+                    items.get(array_index(&token)?)?
+                }
+                Children::Object(members) => {
+                    let (k, v) = members.get(token.as_ref())?;
+                    key = Some(k.clone());
+                    v
+                }
+            };
+        }
+        Some((key, span))
+    }
+}
+
+// Records the span of the value at structural index `i` (offsets in
+// `input` are shifted by `offset`), returns it together with the structural
+// index following the value. Gives up beyond `depth` levels of nesting.
+fn walk(
+    input: &[u8],
+    structurals: &[u32],
+    i: usize,
+    offset: usize,
+    depth: usize,
+) -> Option<(Span, usize)> {
+    let start = *structurals.get(i)? as usize;
+    let depth = depth.checked_sub(1)?;
+    let (children, next) = match char_at(input, structurals, i)? {
+        b'{' => {
+            let mut members = Vec::new();
+            let mut i = i + 1;
+            if char_at(input, structurals, i)? != b'}' {
+                loop {
+                    if char_at(input, structurals, i)? != b'"' {
+                        return None;
+                    }
+                    let key_start = structurals[i] as usize;
+                    let (key_end, escaped) = string_end(input, key_start + 1)?;
+                    let raw = input.get(key_start + 1..key_end)?;
+                    let key = if escaped {
+                        unescape(raw)?
+                    } else {
+                        String::from_utf8(raw.to_vec()).ok()?
+                    };
+                    let (value, next) = walk(input, structurals, i + 2, offset, depth)?;
+                    let key_range = key_start + offset..key_end + 1 + offset;
+                    members.push((key, key_range, value));
+                    i = next;
+                    match char_at(input, structurals, i)? {
+                        b',' => i += 1,
+                        b'}' => break,
+                        _ => return None,
+                    }
+                }
+            }
+            let mut object = parsed_object(members.len());
+            for (key, key_range, value) in members {
+                insert_parsed(&mut object, key, (key_range, value));
+            }
+            (Children::Object(object), i)
+        }
+        b'[' => {
+            let mut items = Vec::new();
+            let mut i = i + 1;
+            if char_at(input, structurals, i)? != b']' {
+                loop {
+                    let (item, next) = walk(input, structurals, i, offset, depth)?;
+                    items.push(item);
+                    i = next;
+                    match char_at(input, structurals, i)? {
+                        b',' => i += 1,
+                        b']' => break,
+                        _ => return None,
+                    }
+                }
+            }
+            (Children::Array(items), i)
+        }
+        b'"' => {
+            let (end, _) = string_end(input, start + 1)?;
+            let range = start + offset..end + 1 + offset;
+            return Some((
+                Span {
+                    range,
+                    children: Children::None,
+                },
+                i + 1,
+            ));
+        }
+        _ => {
+            let len = input[start..]
+                .iter()
+                .position(|c| c.is_ascii_whitespace() || b",:]}[{\"".contains(c))
+                .unwrap_or(input.len() - start);
+            let range = start + offset..start + len + offset;
+            return Some((
+                Span {
+                    range,
+                    children: Children::None,
+                },
+                i + 1,
+            ));
+        }
+    };
+    // `next` is the closing bracket
+    let end = structurals[next] as usize + 1;
+    Some((
+        Span {
+            range: start + offset..end + offset,
+            children,
+        },
+        next + 1,
+    ))
+}
+
+// Runs stage 1 and records the spans before the input gets rewritten
+fn spans(input: &mut [u8], options: &ParserOptions) -> Result<(Range<usize>, Vec<u32>, Spans)> {
+    if input.len() > options.max_document_size {
+        return Err(Error::generic(ErrorType::InputTooLarge));
+    }
+    let range = options.content_range(input);
+    let content = &input[range.clone()];
+    let structurals = match Deserializer::find_structural_indexes(content) {
+        Ok(s) => s,
+        Err(t) => return Err(Error::generic(t)),
+    };
+    // malformed input fails to parse anyway, the error comes from there
+    let root = walk(
+        content,
+        &structurals,
+        1,
+        range.start,
+        options.max_depth.saturating_add(1),
+    )
+    .map_or_else(
+        || Span {
+            range: range.clone(),
+            children: Children::None,
+        },
+        |(span, _)| span,
+    );
+    Ok((range, structurals, Spans { root }))
+}
+
+/// Parses a borrowed value and records the byte range of every value in
+/// the input, see `Spans`.
+pub fn to_borrowed_value_with_spans(input: &mut [u8]) -> Result<(BorrowedValue<'_>, Spans)> {
+    to_borrowed_value_with_spans_and_options(input, &ParserOptions::default())
+}
+
+/// Same as `to_borrowed_value_with_spans` but allows tuning the parser
+/// using `ParserOptions`.
+pub fn to_borrowed_value_with_spans_and_options<'v>(
+    input: &'v mut [u8],
+    options: &ParserOptions,
+) -> Result<(BorrowedValue<'v>, Spans)> {
+    let (range, structurals, spans) = stry!(spans(input, options));
+    let value = stry!(borrowed::to_value_with_structural_indexes(
+        &mut input[range],
+        structurals,
+        options
+    ));
+    Ok((value, spans))
+}
+
+/// Parses an owned value and records the byte range of every value in the
+/// input, see `Spans`.
+pub fn to_owned_value_with_spans(input: &mut [u8]) -> Result<(OwnedValue, Spans)> {
+    to_owned_value_with_spans_and_options(input, &ParserOptions::default())
+}
+
+/// Same as `to_owned_value_with_spans` but allows tuning the parser using
+/// `ParserOptions`.
+pub fn to_owned_value_with_spans_and_options(
+    input: &mut [u8],
+    options: &ParserOptions,
+) -> Result<(OwnedValue, Spans)> {
+    let (range, structurals, spans) = stry!(spans(input, options));
+    let value = stry!(owned::to_value_with_structural_indexes(
+        &mut input[range],
+        structurals,
+        options,
+        &mut KeyInterner::new()
+    ));
+    Ok((value, spans))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValueAccess;
+
+    #[test]
+    fn nested() {
+        let input = "\u{feff}{\"a\\\"\": [1, -2.5e3 , {\"b\": null}], \"c\": {}, \"c\": true}";
+        let mut d = input.as_bytes().to_vec();
+        let options = ParserOptions {
+            skip_bom: true,
+            ..ParserOptions::default()
+        };
+        let (_, spans) = to_owned_value_with_spans_and_options(&mut d, &options).unwrap();
+        let text = |r: Option<Range<usize>>| r.map(|r| &input[r]);
+        assert_eq!(text(spans.get("")), Some(&input[3..]));
+        assert_eq!(text(spans.key("/a\"")), Some("\"a\\\"\""));
+        assert_eq!(text(spans.get("/a\"/1")), Some("-2.5e3"));
+        assert_eq!(text(spans.get("/a\"/2/b")), Some("null"));
+        assert_eq!(spans.key("/a\"/0"), None);
+        assert_eq!(spans.get("/d"), None);
+        assert_eq!(spans.get("c"), None);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        // large enough for a hash table with and without `vec-objects`
+        let mut members: Vec<_> = (0..100).map(|i| format!(r#""k{}": {}"#, i, i)).collect();
+        members.insert(3, r#""c": {}"#.to_string());
+        members.insert(80, r#""k7": "x""#.to_string());
+        for input in &[
+            r#"{"c": {}, "d": 1, "c": true}"#.to_string(),
+            format!("{{{}}}", members.join(", ")),
+            format!(r#"{{"c": {{{}}}, "c": [{{}}]}}"#, members.join(", ")),
+        ] {
+            let mut d = input.as_bytes().to_vec();
+            let (value, spans) = to_borrowed_value_with_spans(&mut d).unwrap();
+            let mut d = input.as_bytes().to_vec();
+            let (_, owned_spans) = to_owned_value_with_spans(&mut d).unwrap();
+            // the spans are those of the members the values kept
+            for path in &[&["c"][..], &["k7"], &["c", "c"], &["c", "k7"], &["c", "0"]] {
+                let kept = path.iter().try_fold(&value, |v, t| match v {
+                    BorrowedValue::Array(a) => a.get(t.parse::<usize>().ok()?),
+                    v => v.get(*t),
+                });
+                let pointer = format!("/{}", path.join("/"));
+                let span = spans.get(&pointer);
+                assert_eq!(span, owned_spans.get(&pointer));
+                assert_eq!(kept.is_some(), span.is_some(), "{:?}", path);
+                if let Some(r) = span {
+                    let mut d = input.as_bytes()[r].to_vec();
+                    assert_eq!(kept, Some(&crate::to_borrowed_value(&mut d).unwrap()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scalars_and_errors() {
+        let mut d = b" 42 ".to_vec();
+        let (value, spans) = to_borrowed_value_with_spans(&mut d).unwrap();
+        assert_eq!(value, 42);
+        assert_eq!(spans.get(""), Some(1..3));
+        assert!(to_borrowed_value_with_spans(&mut b"[1, }".to_vec()).is_err());
+        assert!(to_owned_value_with_spans(&mut b"".to_vec()).is_err());
+    }
+}
@@ -168,10 +168,16 @@ impl<'de> Deserializer<'de> {
                     // within the unicode codepoint handling code.
                     src_i += bs_dist as usize;
                     dst_i += bs_dist as usize;
-                    let (o, s) = if let Ok(r) =
+                    let (o, s) = if unlikely!(self.options.lossy_utf8) {
+                        handle_unicode_codepoint_lossy(
+                            unsafe { src.get_unchecked(src_i..) },
+                            unsafe { dst.get_unchecked_mut(dst_i..) },
+                        )
+                    } else if let Ok(r) =
                         handle_unicode_codepoint(unsafe { src.get_unchecked(src_i..) }, unsafe {
                             dst.get_unchecked_mut(dst_i..)
-                        }) {
+                        })
+                    {
                         r
                     } else {
                         return Err(self.error(ErrorType::InvlaidUnicodeCodepoint));
@@ -1,18 +1,25 @@
 #![allow(dead_code)]
-#[cfg(target_feature = "avx2")]
-use crate::avx2::stage1::SIMDJSON_PADDING;
 use crate::charutils::*;
-#[cfg(target_feature = "neon")]
-use crate::neon::stage1::SIMDJSON_PADDING;
-#[cfg(all(
-    any(target_arch = "x86", target_arch = "x86_64"),
-    not(target_feature = "avx2")
-))]
-use crate::sse42::stage1::SIMDJSON_PADDING;
-use crate::{Deserializer, Error, ErrorType, Result};
+use crate::{Deserializer, Error, ErrorType, ParserOptions, Result};
+
+// The atom checks read 8 bytes at once, atoms closer than that to the end
+// of the input are checked on a copy padded with spaces.
+#[cfg_attr(not(feature = "no-inline"), inline(always))]
+fn padded_atom(loc: &[u8]) -> [u8; 8] {
+    let mut copy = [b' '; 8];
+    copy[..loc.len()].copy_from_slice(loc);
+    copy
+}
 
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 pub fn is_valid_true_atom(loc: &[u8]) -> bool {
+    let padded;
+    let loc = if unlikely!(loc.len() < 8) {
+        padded = padded_atom(loc);
+        &padded[..]
+    } else {
+        loc
+    };
     // TODO is this expensive?
     let mut error: u64;
     unsafe {
@@ -34,6 +41,13 @@ pub fn is_valid_true_atom(loc: &[u8]) -> bool {
 
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 pub fn is_valid_false_atom(loc: &[u8]) -> bool {
+    let padded;
+    let loc = if unlikely!(loc.len() < 8) {
+        padded = padded_atom(loc);
+        &padded[..]
+    } else {
+        loc
+    };
     // TODO: this is ugly and probably copies data every time
     let mut error: u64;
     unsafe {
@@ -59,6 +73,13 @@ pub fn is_valid_false_atom(loc: &[u8]) -> bool {
 
 #[cfg_attr(not(feature = "no-inline"), inline(always))]
 pub fn is_valid_null_atom(loc: &[u8]) -> bool {
+    let padded;
+    let loc = if unlikely!(loc.len() < 8) {
+        padded = padded_atom(loc);
+        &padded[..]
+    } else {
+        loc
+    };
     // TODO is this expensive?
     let mut error: u64;
     unsafe {
@@ -89,8 +110,33 @@ enum StackState {
 }
 
 impl<'de> Deserializer<'de> {
-    pub fn validate(input: &[u8], structural_indexes: &[u32]) -> Result<Vec<usize>> {
-        let mut counts = Vec::with_capacity(structural_indexes.len());
+    pub(crate) fn validate(
+        input: &[u8],
+        structural_indexes: &[u32],
+        options: &ParserOptions,
+    ) -> Result<Vec<usize>> {
+        let mut counts = Vec::new();
+        stry!(Self::validate_into(
+            input,
+            structural_indexes,
+            options,
+            &mut counts
+        ));
+        Ok(counts)
+    }
+
+    // Same as `validate` but writes the element counts into `counts`
+    pub(crate) fn validate_into(
+        input: &[u8],
+        structural_indexes: &[u32],
+        options: &ParserOptions,
+        counts: &mut Vec<usize>,
+    ) -> Result<()> {
+        trace_span!("stage2", structurals = structural_indexes.len());
+        let max_depth = options.max_depth;
+        let max_elements = options.max_elements;
+        counts.clear();
+        counts.reserve(structural_indexes.len());
         let mut stack = Vec::with_capacity(structural_indexes.len());
         unsafe {
             counts.set_len(structural_indexes.len());
@@ -207,17 +253,28 @@ impl<'de> Deserializer<'de> {
                 return Err(Error::new(i, idx, c as char, $t));
             };
         }
-        // State start, we pull this outside of the
-        // loop to reduce the number of requried checks
-        update_char!();
-        match c {
-            b'{' => {
+
+        // Pushes the current scope onto the stack, failing if we would
+        // exceed the maximum depth.
+        macro_rules! push_stack {
+            ($state:expr) => {{
+                if unlikely!(depth >= max_depth) {
+                    fail!(ErrorType::DepthExceeded);
+                }
                 unsafe {
-                    *stack.get_unchecked_mut(depth) = (StackState::Start, last_start, cnt);
+                    *stack.get_unchecked_mut(depth) = ($state, last_start, cnt);
                 }
                 depth += 1;
                 last_start = i;
                 cnt = 1;
+            }};
+        }
+        // State start, we pull this outside of the
+        // loop to reduce the number of requried checks
+        update_char!();
+        match c {
+            b'{' => {
+                push_stack!(StackState::Start);
 
                 update_char!();
                 match c {
@@ -234,12 +291,7 @@ impl<'de> Deserializer<'de> {
                 }
             }
             b'[' => {
-                unsafe {
-                    *stack.get_unchecked_mut(depth) = (StackState::Start, last_start, cnt);
-                }
-                depth += 1;
-                last_start = i;
-                cnt = 1;
+                push_stack!(StackState::Start);
 
                 update_char!();
                 if c == b']' {
@@ -249,54 +301,42 @@ impl<'de> Deserializer<'de> {
                     state = State::MainArraySwitch
                 }
             }
+            b't' | b'f' | b'n' | b'"' | b'-' | b'0'..=b'9' if !options.allow_scalar_root => {
+                fail!(ErrorType::ScalarRoot);
+            }
             b't' => {
-                let len = input.len();
-                let mut copy = vec![0_u8; len + SIMDJSON_PADDING];
-                unsafe {
-                    copy.as_mut_ptr().copy_from(input.as_ptr(), len);
-                    if !is_valid_true_atom(copy.get_unchecked(idx..)) {
-                        fail!(ErrorType::ExpectedNull); // TODO: better error
-                    }
-                };
+                if !is_valid_true_atom(unsafe { input.get_unchecked(idx..) }) {
+                    fail!(ErrorType::ExpectedNull); // TODO: better error
+                }
                 if si.next().is_none() {
-                    return Ok(counts);
+                    return Ok(());
                 } else {
                     fail!(ErrorType::TrailingCharacters);
                 }
             }
             b'f' => {
-                let len = input.len();
-                let mut copy = vec![0_u8; len + SIMDJSON_PADDING];
-                unsafe {
-                    copy.as_mut_ptr().copy_from(input.as_ptr(), len);
-                    if !is_valid_false_atom(copy.get_unchecked(idx..)) {
-                        fail!(ErrorType::ExpectedNull); // TODO: better error
-                    }
-                };
+                if !is_valid_false_atom(unsafe { input.get_unchecked(idx..) }) {
+                    fail!(ErrorType::ExpectedNull); // TODO: better error
+                }
                 if si.next().is_none() {
-                    return Ok(counts);
+                    return Ok(());
                 } else {
                     fail!(ErrorType::TrailingCharacters);
                 }
             }
             b'n' => {
-                let len = input.len();
-                let mut copy = vec![0_u8; len + SIMDJSON_PADDING];
-                unsafe {
-                    copy.as_mut_ptr().copy_from(input.as_ptr(), len);
-                    if !is_valid_null_atom(copy.get_unchecked(idx..)) {
-                        fail!(ErrorType::ExpectedNull); // TODO: better error
-                    }
-                };
+                if !is_valid_null_atom(unsafe { input.get_unchecked(idx..) }) {
+                    fail!(ErrorType::ExpectedNull); // TODO: better error
+                }
                 if si.next().is_none() {
-                    return Ok(counts);
+                    return Ok(());
                 } else {
                     fail!(ErrorType::TrailingCharacters);
                 }
             }
             b'"' | b'-' | b'0'..=b'9' => {
                 if si.next().is_none() {
-                    return Ok(counts);
+                    return Ok(());
                 } else {
                     fail!(ErrorType::TrailingCharacters);
                 }
@@ -341,23 +381,11 @@ impl<'de> Deserializer<'de> {
                             object_continue!();
                         }
                         b'{' => {
-                            unsafe {
-                                *stack.get_unchecked_mut(depth) =
-                                    (StackState::Object, last_start, cnt);
-                            }
-                            depth += 1;
-                            last_start = i;
-                            cnt = 1;
+                            push_stack!(StackState::Object);
                             object_begin!();
                         }
                         b'[' => {
-                            unsafe {
-                                *stack.get_unchecked_mut(depth) =
-                                    (StackState::Object, last_start, cnt);
-                            }
-                            depth += 1;
-                            last_start = i;
-                            cnt = 1;
+                            push_stack!(StackState::Object);
                             array_begin!();
                         }
                         _c => {
@@ -370,6 +398,9 @@ impl<'de> Deserializer<'de> {
                     if depth == 0 {
                         return Err(Error::generic(ErrorType::Syntax));
                     }
+                    if unlikely!(cnt > max_elements) {
+                        fail!(ErrorType::TooManyElements);
+                    }
                     depth -= 1;
                     unsafe {
                         *counts.get_unchecked_mut(last_start) = cnt;
@@ -387,9 +418,9 @@ impl<'de> Deserializer<'de> {
                         StackState::Array => array_continue!(),
                         StackState::Start => {
                             if si.next().is_none() {
-                                return Ok(counts);
+                                return Ok(());
                             } else {
-                                fail!();
+                                fail!(ErrorType::TrailingCharacters);
                             }
                         }
                     };
@@ -423,23 +454,11 @@ impl<'de> Deserializer<'de> {
                             array_continue!();
                         }
                         b'{' => {
-                            unsafe {
-                                *stack.get_unchecked_mut(depth) =
-                                    (StackState::Array, last_start, cnt);
-                            }
-                            depth += 1;
-                            last_start = i;
-                            cnt = 1;
+                            push_stack!(StackState::Array);
                             object_begin!();
                         }
                         b'[' => {
-                            unsafe {
-                                *stack.get_unchecked_mut(depth) =
-                                    (StackState::Array, last_start, cnt);
-                            }
-                            depth += 1;
-                            last_start = i;
-                            cnt = 1;
+                            push_stack!(StackState::Array);
                             array_begin!();
                         }
                         _c => {
@@ -461,6 +480,9 @@ mod test {
         assert!(is_valid_true_atom(b"true    "));
         assert!(!is_valid_true_atom(b"tru     "));
         assert!(!is_valid_true_atom(b" rue    "));
+        assert!(is_valid_true_atom(b"true"));
+        assert!(is_valid_true_atom(b"true]"));
+        assert!(!is_valid_true_atom(b"tru"));
     }
     #[test]
     fn false_atom() {
@@ -469,6 +491,8 @@ mod test {
         assert!(!is_valid_false_atom(b"falsy   "));
         assert!(!is_valid_false_atom(b"fals    "));
         assert!(!is_valid_false_atom(b" alse   "));
+        assert!(is_valid_false_atom(b"false"));
+        assert!(!is_valid_false_atom(b"falsey"));
 
         //unsafe { assert!(!is_valid_false_atom(b"fals    " as *const u8)) }
         //        unsafe { assert!(!is_valid_false_atom(b"false   " as *const u8)) }
@@ -478,5 +502,7 @@ mod test {
         assert!(is_valid_null_atom(b"null    "));
         assert!(!is_valid_null_atom(b"nul     "));
         assert!(!is_valid_null_atom(b" ull    "));
+        assert!(is_valid_null_atom(b"null}"));
+        assert!(!is_valid_null_atom(b"nul"));
     }
 }
@@ -19,6 +19,37 @@ pub(crate) const ESCAPE_MAP: [u8; 256] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
+/// Same as `handle_unicode_codepoint` but used in lossy mode: lone or
+/// mismatched surrogates are written as U+FFFD instead of failing. An
+/// escape with invalid hex digits still writes nothing.
+#[inline(never)]
+pub(crate) fn handle_unicode_codepoint_lossy(src_ptr: &[u8], dst_ptr: &mut [u8]) -> (usize, usize) {
+    const REPLACEMENT: u32 = 0xfffd;
+    if src_ptr.len() < 6 {
+        return (0, 6);
+    }
+    let code_point: u32 = hex_to_u32_nocheck(&src_ptr[2..]);
+    if (code_point >> 16) != 0 {
+        return (0, 6);
+    }
+    if (0xd800..0xdc00).contains(&code_point) {
+        if src_ptr.len() >= 12 && &src_ptr[6..8] == b"\\u" {
+            let code_point_2: u32 = hex_to_u32_nocheck(&src_ptr[8..]);
+            if (0xdc00..0xe000).contains(&code_point_2) {
+                let code_point =
+                    (((code_point - 0xd800) << 10) | (code_point_2 - 0xdc00)) + 0x10000;
+                return (codepoint_to_utf8(code_point, dst_ptr), 12);
+            }
+        }
+        // the following escape, if any, is handled on its own
+        return (codepoint_to_utf8(REPLACEMENT, dst_ptr), 6);
+    }
+    if (0xdc00..0xe000).contains(&code_point) {
+        return (codepoint_to_utf8(REPLACEMENT, dst_ptr), 6);
+    }
+    (codepoint_to_utf8(code_point, dst_ptr), 6)
+}
+
 /// handle a unicode codepoint
 /// write appropriate values into dest
 /// src will advance 6 bytes or 12 bytes
@@ -30,6 +61,10 @@ pub(crate) fn handle_unicode_codepoint(
     mut src_ptr: &[u8],
     dst_ptr: &mut [u8],
 ) -> Result<(usize, usize), ErrorType> {
+    // the escape may be cut off by the end of the input
+    if src_ptr.len() < 6 {
+        return Ok((0, 6));
+    }
     // hex_to_u32_nocheck fills high 16 bits of the return value with 1s if the
     // conversion isn't valid; we defer the check for this to inside the
     // multilingual plane check
@@ -39,7 +74,8 @@ pub(crate) fn handle_unicode_codepoint(
     // check for low surrogate for characters outside the Basic
     // Multilingual Plane.
     if code_point >= 0xd800 && code_point < 0xdc00 {
-        if (unsafe { *src_ptr.get_unchecked(0) } != b'\\')
+        if src_ptr.len() < 6
+            || (unsafe { *src_ptr.get_unchecked(0) } != b'\\')
             || unsafe { *src_ptr.get_unchecked(1) } != b'u'
         {
             return Ok((0, src_offset));
@@ -0,0 +1,171 @@
+use crate::pointer::{locate, skip_value};
+use crate::value::owned::to_value_with_structural_indexes;
+use crate::{
+    Deserializer, Error, ErrorType, KeyInterner, OwnedValue, ParserOptions, Projection, Result,
+};
+
+/// A validated document together with its structural indexes, the tape
+/// the first stage of the parser produces.
+///
+/// Queries on a tape skip finding the structural indexes and only parse
+/// the values they return, so keeping a tape of a cached document (or
+/// turning an `OwnedValue` back into one) makes repeated structural
+/// queries as fast as on a freshly read document. Unlike the functions
+/// taking a `&mut [u8]` the document isn't rewritten by queries.
+///
+/// ```
+/// use simd_json::{json, Projection, Tape};
+/// let v = json!({"user": {"id": 7, "tags": ["a", "b"]}});
+/// let tape = Tape::from_value(&v).unwrap();
+/// assert!(tape.exists("/user/tags/1"));
+/// assert_eq!(tape.pointer("/user/id").unwrap(), Some(json!(7)));
+/// let p = Projection::new().path("/user/tags/0").path("/missing");
+/// assert_eq!(tape.project(&p).unwrap(), vec![Some(json!("a")), None]);
+/// assert_eq!(tape.to_value().unwrap(), v);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tape {
+    input: Vec<u8>,
+    structurals: Vec<u32>,
+    options: ParserOptions,
+}
+
+impl Tape {
+    /// Copies and validates the document in `input` using the default
+    /// `ParserOptions`
+    pub fn from_slice(input: &[u8]) -> Result<Self> {
+        Self::from_slice_with_options(input, &ParserOptions::default())
+    }
+
+    /// Same as `from_slice` but uses the given `ParserOptions`, for
+    /// validating the document and for parsing the values of queries
+    pub fn from_slice_with_options(input: &[u8], options: &ParserOptions) -> Result<Self> {
+        if input.len() > options.max_document_size {
+            return Err(Error::generic(ErrorType::InputTooLarge));
+        }
+        Self::new(input[options.content_range(input)].to_vec(), options)
+    }
+
+    /// Encodes a value into a tape
+    pub fn from_value(value: &OwnedValue) -> Result<Self> {
+        let mut input = Vec::new();
+        value.encode_into(&mut input);
+        Self::new(input, &ParserOptions::default())
+    }
+
+    fn new(input: Vec<u8>, options: &ParserOptions) -> Result<Self> {
+        let structurals = match Deserializer::find_structural_indexes(&input) {
+            Ok(s) => s,
+            Err(t) => return Err(Error::generic(t)),
+        };
+        stry!(Deserializer::validate(&input, &structurals, options));
+        Ok(Self {
+            input,
+            structurals,
+            options: options.clone(),
+        })
+    }
+
+    /// The JSON text of the document
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.input
+    }
+
+    /// Parses the whole document into a value
+    pub fn to_value(&self) -> Result<OwnedValue> {
+        to_value_with_structural_indexes(
+            &mut self.input.clone(),
+            self.structurals.clone(),
+            &self.options,
+            &mut KeyInterner::default(),
+        )
+    }
+
+    /// Checks if the value referenced by a JSON pointer is present, see
+    /// `simd_json::exists`
+    pub fn exists(&self, pointer: &str) -> bool {
+        locate(&self.input, &self.structurals, pointer).is_some()
+    }
+
+    /// Parses only the value referenced by a JSON pointer, returns
+    /// `Ok(None)` if the pointer doesn't resolve
+    pub fn pointer(&self, pointer: &str) -> Result<Option<OwnedValue>> {
+        let start = match locate(&self.input, &self.structurals, pointer) {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+        let end = skip_value(&self.input, &self.structurals, start);
+        // only the subtree is copied, a scalar ends where the next
+        // structural character starts
+        let offset = self.structurals[start];
+        let len = self
+            .structurals
+            .get(end)
+            .map_or(self.input.len(), |i| *i as usize);
+        let mut input = self.input[offset as usize..len].to_vec();
+        // the subtree gets its own extra root element
+        let mut subtree = Vec::with_capacity(end - start + 1);
+        subtree.push(0);
+        subtree.extend(self.structurals[start..end].iter().map(|i| i - offset));
+        to_value_with_structural_indexes(
+            &mut input,
+            subtree,
+            &self.options,
+            &mut KeyInterner::default(),
+        )
+        .map(Some)
+    }
+
+    /// Extracts the values of all paths of a projection, see
+    /// `Projection::project`
+    pub fn project(&self, projection: &Projection) -> Result<Vec<Option<OwnedValue>>> {
+        projection.project_with_structural_indexes(&mut self.input.clone(), &self.structurals)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn queries() {
+        let mut input = br#" {"a": [1, "x\n\u00e9", {"b": null}], "c": -2.5, "d": {}} "#.to_vec();
+        let tape = Tape::from_slice(&input).unwrap();
+        let v = crate::to_owned_value(&mut input).unwrap();
+        assert_eq!(tape.to_value().unwrap(), v);
+        assert_eq!(tape.pointer("").unwrap(), Some(v.clone()));
+        assert_eq!(tape.pointer("/a/1").unwrap(), Some(json!("x\né")));
+        assert_eq!(tape.pointer("/a/2").unwrap(), Some(json!({"b": null})));
+        assert_eq!(tape.pointer("/c").unwrap(), Some(json!(-2.5)));
+        assert_eq!(tape.pointer("/d").unwrap(), Some(json!({})));
+        assert_eq!(tape.pointer("/e").unwrap(), None);
+        assert!(tape.exists("/a/2/b"));
+        assert!(!tape.exists("/a/3"));
+
+        // queries don't rewrite the tape
+        let p = Projection::new().path("/a/1").path("/a").path("/c");
+        let first = tape.project(&p).unwrap();
+        assert_eq!(tape.project(&p).unwrap(), first);
+        assert_eq!(first[0], Some(json!("x\né")));
+
+        let tape = Tape::from_value(&v).unwrap();
+        assert_eq!(tape.to_value().unwrap(), v);
+        assert_eq!(tape.project(&p).unwrap(), first);
+        assert_eq!(
+            Tape::from_value(&json!(42)).unwrap().pointer("").unwrap(),
+            Some(json!(42))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let e = Tape::from_slice(b"[1, 2").unwrap_err();
+        assert_eq!(e.error(), &ErrorType::Syntax);
+        let options = ParserOptions {
+            max_depth: 2,
+            ..ParserOptions::default()
+        };
+        assert!(Tape::from_slice_with_options(b"[[[]]]", &options).is_err());
+        assert!(Tape::from_slice(b"").is_err());
+    }
+}
@@ -0,0 +1,348 @@
+use crate::stringparse::{handle_unicode_codepoint, handle_unicode_codepoint_lossy, ESCAPE_MAP};
+use crate::{
+    is_valid_utf8, BigNumberBehavior, Deserializer, Error, ErrorType, ParserOptions, Result,
+};
+use std::str::{self, Utf8Error};
+
+/// Checks that the input is a valid JSON document without building a value.
+///
+/// This runs stage 1 and the grammar check of stage 2 and then checks the
+/// escapes of all strings and all numbers in place. Nothing is unescaped
+/// and no values are allocated, so it is a lot cheaper than parsing if the
+/// document is to be rejected or passed on as is. Strings and numbers are
+/// checked by the same code the parser uses, so a document passes exactly
+/// when `to_owned_value` would accept it.
+///
+/// ```
+/// let mut d = br#"{"a": [1, 2.5e3, "\u00fc"]}"#.to_vec();
+/// assert!(simd_json::validate(&mut d).is_ok());
+/// let mut d = br#"{"a": [01]}"#.to_vec();
+/// assert!(simd_json::validate(&mut d).is_err());
+/// ```
+pub fn validate(input: &mut [u8]) -> Result<()> {
+    validate_with_options(input, &ParserOptions::default())
+}
+
+/// Same as `validate` but checks the limits set in the `ParserOptions`,
+/// it agrees with `to_owned_value_with_options`.
+pub fn validate_with_options(input: &mut [u8], options: &ParserOptions) -> Result<()> {
+    if unlikely!(input.len() > options.max_document_size) {
+        return Err(Error::generic(ErrorType::InputTooLarge));
+    }
+    let range = options.content_range(input);
+    let content = &mut input[range];
+    match Deserializer::find_structural_indexes(content) {
+        Ok(structural_indexes) => validate_values(content, structural_indexes, options),
+        // the parser repairs the input and starts over in lossy mode
+        Err(ErrorType::InvalidUTF8) if options.lossy_utf8 => {
+            let mut repaired = String::from_utf8_lossy(input).into_owned().into_bytes();
+            validate_with_options(&mut repaired, options)
+        }
+        Err(t) => Err(Error::generic(t)),
+    }
+}
+
+// Runs the grammar check of stage 2 and checks all strings and numbers
+fn validate_values(
+    input: &mut [u8],
+    structural_indexes: Vec<u32>,
+    options: &ParserOptions,
+) -> Result<()> {
+    let counts = stry!(Deserializer::validate(input, &structural_indexes, options));
+    // numbers are checked by the parser itself, it doesn't write to the input
+    let mut de = Deserializer {
+        input,
+        strings: Vec::new(),
+        structural_indexes,
+        idx: 0,
+        counts,
+        str_offset: 0,
+        iidx: 0,
+        options: options.clone(),
+    };
+    // the first structural index is the extra root element
+    for i in 1..de.structural_indexes.len() {
+        let idx = de.structural_indexes[i] as usize;
+        let c = match de.input.get(idx) {
+            Some(c) => *c,
+            None => break,
+        };
+        de.idx = i;
+        de.iidx = idx;
+        // only a number that is the whole document is parsed as the root
+        match c {
+            b'"' => {
+                if let Err((offset, t)) = check_string(de.input, idx + 1, options) {
+                    let character = de.input.get(offset).map_or(c, |c| *c) as char;
+                    return Err(Error::new(i, offset, character, t));
+                }
+            }
+            b'-' => stry!(check_number(&mut de, true, i == 1)),
+            b'0'..=b'9' => stry!(check_number(&mut de, false, i == 1)),
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `input` is valid UTF-8 using the SIMD validator of stage 1,
+/// it can be used for any buffer, not only JSON.
+///
+/// On success the input is returned as `&str`. If the input is invalid the
+/// error is the one `std::str::from_utf8` returns, so it reports the exact
+/// offset of the first invalid sequence.
+///
+/// ```
+/// assert_eq!(simd_json::validate_utf8("grüße".as_bytes()), Ok("grüße"));
+/// let e = simd_json::validate_utf8(b"ok \xff").unwrap_err();
+/// assert_eq!(e.valid_up_to(), 3);
+/// ```
+pub fn validate_utf8(input: &[u8]) -> std::result::Result<&str, Utf8Error> {
+    if unsafe { is_valid_utf8(input) } {
+        Ok(unsafe { str::from_utf8_unchecked(input) })
+    } else {
+        // only pay for finding the error position on invalid input
+        str::from_utf8(input)
+    }
+}
+
+/// Runs stage 1 of the parser on its own: finds the offsets of all
+/// structural characters (`{`, `}`, `[`, `]`, `:` and `,`), the opening
+/// quotes of strings and the first characters of numbers and literals,
+/// skipping over everything inside of strings. `structural_indexes` is
+/// cleared first, so it can be reused.
+///
+/// The grammar is not checked, this fails only for unterminated strings,
+/// control characters in strings, invalid UTF-8, inputs without any
+/// structural characters and inputs larger than 4 GiB.
+///
+/// ```
+/// let mut indexes = Vec::new();
+/// simd_json::find_structural_indexes(br#"{"a": [1, "x,y"]}"#, &mut indexes).unwrap();
+/// assert_eq!(indexes, vec![0, 1, 4, 6, 7, 8, 10, 15, 16]);
+/// ```
+pub fn find_structural_indexes(input: &[u8], structural_indexes: &mut Vec<u32>) -> Result<()> {
+    stry!(
+        Deserializer::find_structural_indexes_into(input, structural_indexes, &mut Vec::new())
+            .map_err(Error::generic)
+    );
+    // drop the extra root element the parser uses
+    structural_indexes.remove(0);
+    Ok(())
+}
+
+type CheckResult = std::result::Result<(), (usize, ErrorType)>;
+
+// Checks the escapes of the string starting after the opening quote at
+// `idx` the way the parser unescapes them and the length of the result
+fn check_string(input: &[u8], mut idx: usize, options: &ParserOptions) -> CheckResult {
+    let start = idx - 1;
+    let mut len = 0;
+    let mut scratch = [0_u8; 4];
+    loop {
+        match input.get(idx) {
+            Some(b'"') => break,
+            Some(b'\\') => (),
+            Some(_) => {
+                idx += 1;
+                len += 1;
+                continue;
+            }
+            None => return Err((idx, ErrorType::UnterminatedString)),
+        }
+        match input.get(idx + 1) {
+            Some(b'u') => {
+                let src = &input[idx..];
+                let (written, read) = if options.lossy_utf8 {
+                    handle_unicode_codepoint_lossy(src, &mut scratch)
+                } else {
+                    handle_unicode_codepoint(src, &mut scratch).unwrap_or((0, 0))
+                };
+                if written == 0 {
+                    return Err((idx, ErrorType::InvlaidUnicodeCodepoint));
+                }
+                idx += read;
+                len += written;
+            }
+            Some(c) if ESCAPE_MAP[*c as usize] != 0 => {
+                idx += 2;
+                len += 1;
+            }
+            _ => return Err((idx, ErrorType::InvalidEscape)),
+        }
+    }
+    if unlikely!(len > options.max_string_length) {
+        Err((start, ErrorType::StringTooLong))
+    } else {
+        Ok(())
+    }
+}
+
+// Checks the number at the current position of `de` like the value
+// builders do, integers that don't fit are fine if they are kept raw
+fn check_number(de: &mut Deserializer, minus: bool, root: bool) -> Result<()> {
+    let r = if root {
+        de.parse_number_root(minus)
+    } else {
+        de.parse_number(minus)
+    };
+    match r {
+        Err(ref e)
+            if e.error() == &ErrorType::Overflow
+                && (de.options.lossless_numbers
+                    || de.options.big_numbers == BigNumberBehavior::Raw) =>
+        {
+            Ok(())
+        }
+        r => r.map(|_| ()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(input: &str) -> Result<()> {
+        let mut d = input.as_bytes().to_vec();
+        validate(&mut d)
+    }
+
+    fn fails(input: &str, t: ErrorType) {
+        assert_eq!(check(input).expect_err(input).error(), &t);
+    }
+
+    #[test]
+    fn valid() {
+        assert!(
+            check(r#"{"a": [1, -0, 0.5, 1e10, -2.5E-3, 1e-400, 18446744073709551615]}"#).is_ok()
+        );
+        assert!(check(r#"["\"\\\/\b\f\n\r\t", "ü😀"]"#).is_ok());
+        assert!(check(r#"[true, false, null, {}, []]"#).is_ok());
+        assert!(check("42").is_ok());
+        // the parser doesn't check the low surrogate any further
+        assert!(check(r#""\ud83d\uede00""#).is_ok());
+    }
+
+    #[test]
+    fn numbers() {
+        fails("-19011462e897924390", ErrorType::InvalidNumber);
+        fails("[1e309]", ErrorType::InvalidExponent);
+        fails("[123456789012345678901234567890]", ErrorType::Overflow);
+        let options = ParserOptions {
+            big_numbers: BigNumberBehavior::Raw,
+            ..ParserOptions::default()
+        };
+        let mut d = b"[123456789012345678901234567890]".to_vec();
+        assert!(validate_with_options(&mut d, &options).is_ok());
+    }
+
+    #[test]
+    fn string_length() {
+        let options = ParserOptions {
+            max_string_length: 4,
+            ..ParserOptions::default()
+        };
+        let mut d = br#"["a\u00fc", "\ud83d\ude00"]"#.to_vec();
+        assert!(validate_with_options(&mut d, &options).is_ok());
+        let mut d = br#"["abc\u00fc"]"#.to_vec();
+        let e = validate_with_options(&mut d, &options).expect_err("too long");
+        assert_eq!(e.error(), &ErrorType::StringTooLong);
+        assert!(crate::to_owned_value_with_options(&mut d, &options).is_err());
+    }
+
+    // `validate` has to agree with the parser on any input
+    #[test]
+    fn matches_parser() {
+        let seeds: &[&[u8]] = &[
+            br#"{"a": [1, -2.5e-3, "x\u00fcy", true, null], "b": {"c": 0.1}}"#,
+            br#"["\ud83d\ude00", "\ud83d\uede00", "\udc00", 1e-400, 1000e-325]"#,
+            b"-19011462e897924390",
+            b"[18446744073709551615, -9223372036854775808, 1.7976931348623157e308]",
+        ];
+        let alphabet = b"0123456789-+.eE\\u\"[]{},: dD8aftn";
+        // xorshift so the test is deterministic without pulling in rand
+        let mut x: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as usize
+        };
+        for seed in seeds {
+            for _ in 0..5000 {
+                let mut d = seed.to_vec();
+                for _ in 0..=next() % 3 {
+                    let at = next() % d.len();
+                    let c = alphabet[next() % alphabet.len()];
+                    match next() % 3 {
+                        0 => d[at] = c,
+                        1 => d.insert(at, c),
+                        _ => {
+                            d.remove(at);
+                            if d.is_empty() {
+                                d.push(c);
+                            }
+                        }
+                    }
+                }
+                let parsed = crate::to_owned_value(&mut d.clone()).is_ok();
+                let validated = validate(&mut d.clone()).is_ok();
+                assert_eq!(validated, parsed, "{}", String::from_utf8_lossy(&d));
+            }
+        }
+    }
+
+    #[test]
+    fn utf8() {
+        let mut long = "a".repeat(63);
+        long.push('ü');
+        long.push_str(&"€😀".repeat(40));
+        assert_eq!(validate_utf8(long.as_bytes()), Ok(long.as_str()));
+        assert_eq!(validate_utf8(b""), Ok(""));
+        for len in 0..130 {
+            let mut bytes = "x".repeat(len).into_bytes();
+            for bad in &[&b"\xff"[..], b"\xe2\x82", b"\xc0\xaf", b"\xed\xa0\x80"] {
+                let mut input = bytes.clone();
+                input.extend_from_slice(bad);
+                let e = validate_utf8(&input).expect_err("invalid utf8");
+                assert_eq!(e.valid_up_to(), len);
+                input.extend_from_slice(b"yz");
+                let e = validate_utf8(&input).expect_err("invalid utf8");
+                assert_eq!(e.valid_up_to(), len);
+            }
+            bytes.extend_from_slice("é".as_bytes());
+            assert!(validate_utf8(&bytes).is_ok());
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        fails("[01]", ErrorType::InvalidNumber);
+        fails("[1.]", ErrorType::InvalidNumber);
+        fails("[-]", ErrorType::InvalidNumber);
+        fails("[1x]", ErrorType::InvalidNumber);
+        fails("[1e+]", ErrorType::InvalidNumber);
+        fails(r#"["\x"]"#, ErrorType::InvalidEscape);
+        fails(r#"["\u00g0"]"#, ErrorType::InvlaidUnicodeCodepoint);
+        fails(r#"["\ud83d"]"#, ErrorType::InvlaidUnicodeCodepoint);
+        assert!(check(r#"{"a" 1}"#).is_err());
+        assert!(check("[1, 2").is_err());
+        assert!(check("[1] 2").is_err());
+    }
+
+    #[test]
+    fn structural_indexes() {
+        let mut indexes = vec![42];
+        find_structural_indexes(br#" true "#, &mut indexes).expect("valid");
+        assert_eq!(indexes, vec![1]);
+        // the grammar isn't checked
+        find_structural_indexes(br#"]]:"a\"b" x"#, &mut indexes).expect("valid");
+        assert_eq!(indexes, vec![0, 1, 2, 3, 10]);
+        let e = find_structural_indexes(br#"["a]"#, &mut indexes).expect_err("unterminated");
+        assert_eq!(e.error(), &ErrorType::Syntax);
+        let e = find_structural_indexes(b"  ", &mut indexes).expect_err("empty");
+        assert_eq!(e.error(), &ErrorType::EOF);
+        let e = find_structural_indexes(b"[\"\xff\"]", &mut indexes).expect_err("utf8");
+        assert_eq!(e.error(), &ErrorType::InvalidUTF8);
+    }
+}
@@ -11,18 +11,216 @@
 /// we do not require prior knowledge sbout string comtent to to take advantage
 /// of it.
 
+#[cfg(feature = "bumpalo")]
+/// Values whose arrays, objects and rewritten keys are allocated in a
+/// `bumpalo` arena instead of on the heap, so a document is freed all at
+/// once by resetting or dropping the arena. Strings are borrowed from the
+/// input like in `BorrowedValue`.
+pub mod arena;
+#[cfg(feature = "base64")]
+mod base64;
 /// Borrowed values, using Cow's for strings using in situ parsing strategies wherever possible
 pub mod borrowed;
+mod datetime;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
 pub(crate) mod generator;
+#[cfg(feature = "wasm")]
+mod js;
+pub(crate) mod number;
 /// Owned, lifetimeless version of the value for times when lifetimes are to be avoided
 pub mod owned;
+mod static_node;
+#[cfg(feature = "proptest")]
+/// proptest strategies for both value types
+pub mod strategy;
+use crate::CompactOptions;
 use std::convert::TryInto;
 
-pub use self::borrowed::{to_value as to_borrowed_value, Value as BorrowedValue};
-pub use self::owned::{to_value as to_owned_value, Value as OwnedValue};
-use halfbrown::HashMap;
+#[cfg(feature = "bumpalo")]
+pub use self::arena::{
+    to_value as to_arena_value, to_value_with_options as to_arena_value_with_options,
+    Value as ArenaValue,
+};
+pub use self::borrowed::{
+    to_value as to_borrowed_value, to_value_with_options as to_borrowed_value_with_options,
+    to_value_with_rest as to_borrowed_value_with_rest, Document, Value as BorrowedValue,
+};
+pub use self::datetime::FromTimestampMillis;
+pub use self::number::Number;
+pub use self::owned::{
+    to_value as to_owned_value, to_value_with_interner as to_owned_value_with_interner,
+    to_value_with_options as to_owned_value_with_options,
+    to_value_with_rest as to_owned_value_with_rest, CowValue, KeyInterner, Value as OwnedValue,
+};
+pub use self::static_node::StaticNode;
+use halfbrown::{DefaultHashBuilder, HashMap};
 use std::borrow::Borrow;
-use std::hash::Hash;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::ops::{Index, IndexMut};
+
+/// Hash builder used by the objects of both value types. This is the
+/// default of `halfbrown` (`ahash`), or `fxhash` with the `fxhash` feature
+/// which is faster for the short keys typical in JSON but not resistant to
+/// hash flooding.
+pub type ObjectHasher = DefaultHashBuilder;
+
+/// Map backing the objects of both value types, a `halfbrown` map that
+/// keeps small objects in a vector and larger ones in a hash table.
+#[cfg(not(any(feature = "preserve-order", feature = "sorted-objects")))]
+pub type ObjectMap<K, V> = HashMap<K, V, ObjectHasher>;
+/// Map backing the objects of both value types, with the `preserve-order`
+/// feature an `IndexMap` that iterates, and so serializes, members in the
+/// order they were inserted or parsed in.
+#[cfg(feature = "preserve-order")]
+pub type ObjectMap<K, V> = indexmap::IndexMap<K, V, ObjectHasher>;
+/// Map backing the objects of both value types, with the `sorted-objects`
+/// feature a `BTreeMap` that iterates, and so serializes, members sorted
+/// by key and uses no memory for a hash table. `preserve-order` takes
+/// precedence if both features are enabled.
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+pub type ObjectMap<K, V> = std::collections::BTreeMap<K, V>;
+
+/// A view into a single member of an object, as returned by
+/// `ValueTrait::entry`.
+#[cfg(not(any(feature = "preserve-order", feature = "sorted-objects")))]
+pub type Entry<'a, V> = halfbrown::Entry<'a, <V as ValueAccess>::Key, V, ObjectHasher>;
+/// A view into a single member of an object, as returned by
+/// `ValueTrait::entry`.
+#[cfg(feature = "preserve-order")]
+pub type Entry<'a, V> = indexmap::map::Entry<'a, <V as ValueAccess>::Key, V>;
+/// A view into a single member of an object, as returned by
+/// `ValueTrait::entry`.
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+pub type Entry<'a, V> = std::collections::btree_map::Entry<'a, <V as ValueAccess>::Key, V>;
+
+/// Objects with up to this many members are parsed into halfbrown's vector
+/// backend with the `vec-objects` feature, `HashMap::with_capacity` only
+/// does so for up to 32.
+#[cfg(feature = "vec-objects")]
+pub const VEC_OBJECT_LIMIT: usize = 64;
+
+// The object for `capacity` members parsed from a document
+#[cfg(not(any(
+    feature = "vec-objects",
+    feature = "preserve-order",
+    feature = "sorted-objects"
+)))]
+#[inline]
+pub(crate) fn parsed_object<K, V>(capacity: usize) -> ObjectMap<K, V> {
+    HashMap::with_capacity(capacity)
+}
+#[cfg(all(
+    feature = "vec-objects",
+    not(any(feature = "preserve-order", feature = "sorted-objects"))
+))]
+#[inline]
+pub(crate) fn parsed_object<K, V>(capacity: usize) -> ObjectMap<K, V> {
+    if capacity <= VEC_OBJECT_LIMIT {
+        HashMap::vec_with_capacity(capacity)
+    } else {
+        HashMap::with_capacity(capacity)
+    }
+}
+#[cfg(any(feature = "preserve-order", feature = "sorted-objects"))]
+#[inline]
+pub(crate) fn parsed_object<K, V>(capacity: usize) -> ObjectMap<K, V> {
+    object_with_capacity(capacity)
+}
+
+// An empty object with room for `capacity` members
+#[cfg(not(any(feature = "preserve-order", feature = "sorted-objects")))]
+#[inline]
+pub(crate) fn object_with_capacity<K, V>(capacity: usize) -> ObjectMap<K, V> {
+    HashMap::with_capacity(capacity)
+}
+#[cfg(feature = "preserve-order")]
+#[inline]
+pub(crate) fn object_with_capacity<K, V>(capacity: usize) -> ObjectMap<K, V> {
+    ObjectMap::with_capacity_and_hasher(capacity, ObjectHasher::default())
+}
+// a `BTreeMap` allocates its nodes as members are added
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+#[inline]
+pub(crate) fn object_with_capacity<K, V>(_capacity: usize) -> ObjectMap<K, V> {
+    ObjectMap::new()
+}
+
+// Adds a member of a parsed object, the parser doesn't check for duplicate
+// keys if the map doesn't need it.
+#[cfg(not(any(feature = "preserve-order", feature = "sorted-objects")))]
+#[inline]
+pub(crate) fn insert_parsed<K: Hash + Eq, V>(o: &mut ObjectMap<K, V>, k: K, v: V) {
+    o.insert_nocheck(k, v);
+}
+#[cfg(feature = "preserve-order")]
+#[inline]
+pub(crate) fn insert_parsed<K: Hash + Eq, V>(o: &mut ObjectMap<K, V>, k: K, v: V) {
+    o.insert(k, v);
+}
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+#[inline]
+pub(crate) fn insert_parsed<K: Ord, V>(o: &mut ObjectMap<K, V>, k: K, v: V) {
+    o.insert(k, v);
+}
+
+// An array or object whose members a DOM builder is parsing, with the
+// number of members left and, for objects, the key of the current one.
+// The builders keep these on a stack instead of recursing so deeply
+// nested documents can't overflow the call stack.
+pub(crate) enum Open<A, O, K> {
+    Array(A, usize),
+    Object(O, usize, K),
+}
+
+// If a number literal, as kept by `RawNumber`, represents a float
+#[inline]
+pub(crate) fn is_float_literal(n: &str) -> bool {
+    n.bytes().any(|b| b == b'.' || b == b'e' || b == b'E')
+}
+
+// Compares two floats the way values do: by their bits, so `-0.0` and
+// `0.0` differ, with all NaNs being equal to each other
+#[inline]
+pub(crate) fn float_eq(v1: f64, v2: f64) -> bool {
+    (v1.is_nan() && v2.is_nan()) || v1.to_bits() == v2.to_bits()
+}
+
+// A number as values compare it: integers exactly, everything else,
+// including integer literals too large for an `i128`, as a float
+pub(crate) enum Num {
+    Int(i128),
+    Float(f64),
+}
+
+impl Num {
+    pub(crate) fn of<V: ValueAccess>(v: &V) -> Self {
+        if v.value_type() == ValueType::I64 {
+            if let Some(i) = v.as_i128() {
+                return Num::Int(i);
+            }
+        }
+        Num::Float(v.cast_f64().unwrap_or(f64::NAN))
+    }
+}
+
+impl PartialEq for Num {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Num::Int(v1), Num::Int(v2)) => v1 == v2,
+            (Num::Float(v1), Num::Float(v2)) => float_eq(*v1, *v2),
+            _ => false,
+        }
+    }
+}
+
+// Compares two numbers by value where at least one is a `RawNumber`, so
+// `1.0` and `1.00` are equal
+pub(crate) fn raw_number_eq<A: ValueAccess, B: ValueAccess>(a: &A, b: &B) -> bool {
+    let is_number = |t| t == ValueType::I64 || t == ValueType::F64;
+    is_number(a.value_type()) && is_number(b.value_type()) && Num::of(a) == Num::of(b)
+}
 
 /// Types of JSON values
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -43,40 +241,281 @@ pub enum ValueType {
     Object,
 }
 
-/// The `ValueTrait` exposes common interface for values, this allows using both
-/// `BorrowedValue` and `OwnedValue` nearly interchangable
-pub trait ValueTrait:
-    From<i8>
-    + From<i16>
-    + From<i32>
-    + From<i64>
-    + From<u8>
-    + From<u16>
-    + From<u32>
-    + From<u64>
-    + From<f32>
-    + From<f64>
-    + From<String>
-    + From<bool>
-    + From<()>
-    + PartialEq<i8>
-    + PartialEq<i16>
-    + PartialEq<i32>
-    + PartialEq<i64>
-    + PartialEq<i128>
-    + PartialEq<u8>
-    + PartialEq<u16>
-    + PartialEq<u32>
-    + PartialEq<u64>
-    + PartialEq<u128>
-    + PartialEq<f32>
-    + PartialEq<f64>
-    + PartialEq<String>
-    + PartialEq<bool>
-    + PartialEq<()>
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Null => "null",
+            Self::Bool => "bool",
+            Self::I64 => "integer",
+            Self::F64 => "float",
+            Self::String => "string",
+            Self::Array => "array",
+            Self::Object => "object",
+        })
+    }
+}
+
+/// Error returned by the `try_as_*` functions of `ValueAccess` when the
+/// value doesn't have the requested type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TryTypeError {
+    /// The type that was requested
+    pub expected: ValueType,
+    /// The type the value actually has
+    pub got: ValueType,
+}
+
+impl fmt::Display for TryTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.expected == self.got {
+            write!(f, "{} out of range", self.got)
+        } else {
+            write!(f, "expected {}, found {}", self.expected, self.got)
+        }
+    }
+}
+
+impl std::error::Error for TryTypeError {}
+
+fn type_error<V: ValueAccess>(v: &V, expected: ValueType) -> TryTypeError {
+    TryTypeError {
+        expected,
+        got: v.value_type(),
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+    pub struct Bounds<T>(T);
+    impl<T> Sealed for Bounds<T> {}
+}
+use sealed::{Bounds, Sealed};
+
+/// The iterator of `ArrayAccess`, a trait of its own since the iterator
+/// borrows the array for `'i` and associated types can't take lifetimes.
+/// The hidden parameter limits `'i` to lifetimes the array outlives, it
+/// never needs to be named.
+pub trait ArrayIter<'i, B: Sealed = Bounds<&'i Self>> {
+    /// The type of the elements, the same as `ArrayAccess::Element`
+    type Element: 'i;
+    /// The iterator over the elements
+    type Iter: Iterator<Item = &'i Self::Element>;
+}
+
+/// Read-only access to the elements of an array, see `ValueAccess`.
+pub trait ArrayAccess: for<'i> ArrayIter<'i, Element = <Self as ArrayAccess>::Element> {
+    /// The type of the elements
+    type Element;
+
+    /// Gets a ref to the element at index `i`
+    fn get(&self, i: usize) -> Option<&<Self as ArrayAccess>::Element>;
+
+    /// Iterates over the elements
+    fn iter(&self) -> <Self as ArrayIter<'_>>::Iter;
+
+    /// Number of elements
+    fn len(&self) -> usize;
+
+    /// If the array has no elements
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'i, T> ArrayIter<'i> for Vec<T> {
+    type Element = T;
+    type Iter = std::slice::Iter<'i, T>;
+}
+
+impl<T> ArrayAccess for Vec<T> {
+    type Element = T;
+
+    #[inline]
+    fn get(&self, i: usize) -> Option<&T> {
+        self.as_slice().get(i)
+    }
+
+    #[inline]
+    fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+/// The iterator of `ObjectAccess`, see `ArrayIter`.
+pub trait ObjectIter<'i, B: Sealed = Bounds<&'i Self>> {
+    /// The type of the keys, the same as `ObjectAccess::Key`
+    type Key: 'i;
+    /// The type of the values, the same as `ObjectAccess::Element`
+    type Element: 'i;
+    /// The iterator over the members
+    type Iter: Iterator<Item = (&'i Self::Key, &'i Self::Element)>;
+}
+
+/// Read-only access to the members of an object, see `ValueAccess`.
+pub trait ObjectAccess:
+    for<'i> ObjectIter<'i, Key = <Self as ObjectAccess>::Key, Element = <Self as ObjectAccess>::Element>
+{
+    /// The type of the keys
+    type Key;
+    /// The type of the values
+    type Element;
+
+    /// Gets a ref to the value of the member `k`
+    fn get<Q: ?Sized>(&self, k: &Q) -> Option<&<Self as ObjectAccess>::Element>
+    where
+        <Self as ObjectAccess>::Key: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord;
+
+    /// Gets a ref to the value of the member whose key matches `k`
+    /// ignoring ASCII case, an exact match is preferred. This tries `k` and
+    /// its ASCII-lowercased form with a hash lookup, then scans all keys,
+    /// so it is fast for objects parsed with `KeyCase::Lowercase`. Which of
+    /// several matching keys is found is unspecified.
+    fn get_ignore_case(&self, k: &str) -> Option<&<Self as ObjectAccess>::Element>
+    where
+        <Self as ObjectAccess>::Key: Borrow<str> + Hash + Eq,
+    {
+        if let Some(v) = self.get(k) {
+            return Some(v);
+        }
+        if k.bytes().any(|c| c.is_ascii_uppercase()) {
+            if let Some(v) = self.get(k.to_ascii_lowercase().as_str()) {
+                return Some(v);
+            }
+        }
+        self.iter()
+            .find(|(key, _)| (*key).borrow().eq_ignore_ascii_case(k))
+            .map(|(_, v)| v)
+    }
+
+    /// Iterates over the members
+    fn iter(&self) -> <Self as ObjectIter<'_>>::Iter;
+
+    /// Number of members
+    fn len(&self) -> usize;
+
+    /// If the object has no members
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'i, K, V, S> ObjectIter<'i> for HashMap<K, V, S> {
+    type Key = K;
+    type Element = V;
+    type Iter = halfbrown::Iter<'i, K, V>;
+}
+
+impl<K, V, S> ObjectAccess for HashMap<K, V, S>
+where
+    S: BuildHasher,
+{
+    type Key = K;
+    type Element = V;
+
+    #[inline]
+    fn get<Q: ?Sized>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        HashMap::get(self, k)
+    }
+
+    #[inline]
+    fn iter(&self) -> halfbrown::Iter<'_, K, V> {
+        HashMap::iter(self)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+}
+
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+impl<'i, K, V> ObjectIter<'i> for std::collections::BTreeMap<K, V> {
+    type Key = K;
+    type Element = V;
+    type Iter = std::collections::btree_map::Iter<'i, K, V>;
+}
+
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+impl<K: Ord, V> ObjectAccess for std::collections::BTreeMap<K, V> {
+    type Key = K;
+    type Element = V;
+
+    #[inline]
+    fn get<Q: ?Sized>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        std::collections::BTreeMap::get(self, k)
+    }
+
+    #[inline]
+    fn iter(&self) -> std::collections::btree_map::Iter<'_, K, V> {
+        std::collections::BTreeMap::iter(self)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        std::collections::BTreeMap::len(self)
+    }
+}
+
+#[cfg(feature = "preserve-order")]
+impl<'i, K, V, S> ObjectIter<'i> for indexmap::IndexMap<K, V, S> {
+    type Key = K;
+    type Element = V;
+    type Iter = indexmap::map::Iter<'i, K, V>;
+}
+
+#[cfg(feature = "preserve-order")]
+impl<K, V, S> ObjectAccess for indexmap::IndexMap<K, V, S>
+where
+    S: BuildHasher,
 {
+    type Key = K;
+    type Element = V;
+
+    #[inline]
+    fn get<Q: ?Sized>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        indexmap::IndexMap::get(self, k)
+    }
+
+    #[inline]
+    fn iter(&self) -> indexmap::map::Iter<'_, K, V> {
+        indexmap::IndexMap::iter(self)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        indexmap::IndexMap::len(self)
+    }
+}
+
+/// Read-only access to a value, implemented by the value types and by
+/// adapters that can't be constructed or changed, like views of other
+/// representations of JSON. Construction and mutation are provided by
+/// `ValueTrait`.
+pub trait ValueAccess: Sized {
     /// The type for Objects
     type Key;
+    /// The type of arrays
+    type Array: ArrayAccess<Element = Self>;
+    /// The type of objects
+    type Object: ObjectAccess<Key = Self::Key, Element = Self>;
 
     /// Gets a ref to a value based on a key, returns `None` if the
     /// current Value isn't an Object or doesn't contain the key
@@ -84,19 +523,113 @@ pub trait ValueTrait:
     fn get<Q: ?Sized>(&self, k: &Q) -> Option<&Self>
     where
         Self::Key: Borrow<Q> + Hash + Eq,
-        Q: Hash + Eq,
+        Q: Hash + Eq + Ord,
     {
         self.as_object().and_then(|a| a.get(k))
     }
 
-    /// Same as `get` but returns a mutable ref instead
-    //    fn get_amut(&mut self, k: &str) -> Option<&mut Self>;
-    fn get_mut<Q: ?Sized>(&mut self, k: &Q) -> Option<&mut Self>
+    /// Same as `get` but matches the key ignoring ASCII case, for objects
+    /// with inconsistently cased keys like HTTP headers, see
+    /// `ObjectAccess::get_ignore_case`.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess};
+    /// let v = json!({"Content-Type": "text/plain", "content-length": 5});
+    /// assert_eq!(v.get_ignore_case("content-type"), Some(&json!("text/plain")));
+    /// assert_eq!(v.get_ignore_case("Content-Length"), Some(&json!(5)));
+    /// assert_eq!(v.get_ignore_case("Accept"), None);
+    /// ```
+    fn get_ignore_case(&self, k: &str) -> Option<&Self>
+    where
+        Self::Key: Borrow<str> + Hash + Eq,
+    {
+        self.as_object().and_then(|o| o.get_ignore_case(k))
+    }
+
+    /// Gets the value of `k` as a bool, returns `None` if the current
+    /// Value isn't an Object, doesn't contain the key or its value isn't
+    /// a bool.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess};
+    /// let v = json!({"ok": true, "n": 7, "name": "x"});
+    /// assert_eq!(v.get_bool("ok"), Some(true));
+    /// assert_eq!(v.get_u64("n"), Some(7));
+    /// assert_eq!(v.get_str("name"), Some("x"));
+    /// assert_eq!(v.get_str("n"), None);
+    /// ```
+    fn get_bool<Q: ?Sized>(&self, k: &Q) -> Option<bool>
+    where
+        Self::Key: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        self.get(k).and_then(Self::as_bool)
+    }
+
+    /// Gets the value of `k` as an i64, returns `None` if the current
+    /// Value isn't an Object, doesn't contain the key or its value isn't
+    /// an i64.
+    fn get_i64<Q: ?Sized>(&self, k: &Q) -> Option<i64>
     where
         Self::Key: Borrow<Q> + Hash + Eq,
-        Q: Hash + Eq,
+        Q: Hash + Eq + Ord,
     {
-        self.as_object_mut().and_then(|m| m.get_mut(&k))
+        self.get(k).and_then(Self::as_i64)
+    }
+
+    /// Gets the value of `k` as an u64, returns `None` if the current
+    /// Value isn't an Object, doesn't contain the key or its value isn't
+    /// an u64.
+    fn get_u64<Q: ?Sized>(&self, k: &Q) -> Option<u64>
+    where
+        Self::Key: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        self.get(k).and_then(Self::as_u64)
+    }
+
+    /// Gets the value of `k` as a f64, returns `None` if the current
+    /// Value isn't an Object, doesn't contain the key or its value isn't
+    /// a f64.
+    fn get_f64<Q: ?Sized>(&self, k: &Q) -> Option<f64>
+    where
+        Self::Key: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        self.get(k).and_then(Self::as_f64)
+    }
+
+    /// Gets the value of `k` as a string, returns `None` if the current
+    /// Value isn't an Object, doesn't contain the key or its value isn't
+    /// a string.
+    fn get_str<Q: ?Sized>(&self, k: &Q) -> Option<&str>
+    where
+        Self::Key: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        self.get(k).and_then(Self::as_str)
+    }
+
+    /// Gets the value of `k` as an array, returns `None` if the current
+    /// Value isn't an Object, doesn't contain the key or its value isn't
+    /// an array.
+    fn get_array<Q: ?Sized>(&self, k: &Q) -> Option<&Self::Array>
+    where
+        Self::Key: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        self.get(k).and_then(Self::as_array)
+    }
+
+    /// Gets the value of `k` as an object, returns `None` if the current
+    /// Value isn't an Object, doesn't contain the key or its value isn't
+    /// an object.
+    fn get_object<Q: ?Sized>(&self, k: &Q) -> Option<&Self::Object>
+    where
+        Self::Key: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        self.get(k).and_then(Self::as_object)
     }
 
     /// Gets a ref to a value based on n index, returns `None` if the
@@ -106,11 +639,6 @@ pub trait ValueTrait:
         self.as_array().and_then(|a| a.get(i))
     }
 
-    /// Same as `get_idx` but returns a mutable ref instead
-    fn get_idx_mut(&mut self, i: usize) -> Option<&mut Self> {
-        self.as_array_mut().and_then(|a| a.get_mut(i))
-    }
-
     /// Returns the type of the current Valye
     #[deprecated(since = "0.1.21", note = "please use value_type instead")]
     fn kind(&self) -> ValueType {
@@ -125,6 +653,7 @@ pub trait ValueTrait:
 
     /// Tries to represent the value as a bool
     fn as_bool(&self) -> Option<bool>;
+
     /// returns true if the current value a bool
     fn is_bool(&self) -> bool {
         self.as_bool().is_some()
@@ -134,6 +663,7 @@ pub trait ValueTrait:
     fn as_i128(&self) -> Option<i128> {
         self.as_i64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a i128
     fn is_i128(&self) -> bool {
         self.as_i128().is_some()
@@ -141,6 +671,7 @@ pub trait ValueTrait:
 
     /// Tries to represent the value as an i64
     fn as_i64(&self) -> Option<i64>;
+
     /// returns true if the current value can be represented as a i64
     fn is_i64(&self) -> bool {
         self.as_i64().is_some()
@@ -150,6 +681,7 @@ pub trait ValueTrait:
     fn as_i32(&self) -> Option<i32> {
         self.as_i64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a i32
     fn is_i32(&self) -> bool {
         self.as_i32().is_some()
@@ -159,6 +691,7 @@ pub trait ValueTrait:
     fn as_i16(&self) -> Option<i16> {
         self.as_i64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a i16
     fn is_i16(&self) -> bool {
         self.as_i16().is_some()
@@ -168,6 +701,7 @@ pub trait ValueTrait:
     fn as_i8(&self) -> Option<i8> {
         self.as_i64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a i8
     fn is_i8(&self) -> bool {
         self.as_i8().is_some()
@@ -177,6 +711,7 @@ pub trait ValueTrait:
     fn as_u128(&self) -> Option<u128> {
         self.as_u64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a u128
     fn is_u128(&self) -> bool {
         self.as_u128().is_some()
@@ -186,6 +721,7 @@ pub trait ValueTrait:
     fn as_u64(&self) -> Option<u64> {
         self.as_i64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a u64
     fn is_u64(&self) -> bool {
         self.as_u64().is_some()
@@ -195,6 +731,7 @@ pub trait ValueTrait:
     fn as_usize(&self) -> Option<usize> {
         self.as_u64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a usize
     fn is_usize(&self) -> bool {
         self.as_usize().is_some()
@@ -204,6 +741,7 @@ pub trait ValueTrait:
     fn as_u32(&self) -> Option<u32> {
         self.as_u64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a u32
     fn is_u32(&self) -> bool {
         self.as_u32().is_some()
@@ -213,6 +751,7 @@ pub trait ValueTrait:
     fn as_u16(&self) -> Option<u16> {
         self.as_u64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a u16
     fn is_u16(&self) -> bool {
         self.as_u16().is_some()
@@ -222,6 +761,7 @@ pub trait ValueTrait:
     fn as_u8(&self) -> Option<u8> {
         self.as_u64().and_then(|u| u.try_into().ok())
     }
+
     /// returns true if the current value can be represented as a u8
     fn is_u8(&self) -> bool {
         self.as_u8().is_some()
@@ -229,18 +769,91 @@ pub trait ValueTrait:
 
     /// Tries to represent the value as a f64
     fn as_f64(&self) -> Option<f64>;
+
     /// returns true if the current value can be represented as a f64
     fn is_f64(&self) -> bool {
         self.as_f64().is_some()
     }
+
     /// Casts the current value to a f64 if possible, this will turn integer
     /// values into floats.
     fn cast_f64(&self) -> Option<f64>;
+
     /// returns true if the current value can be cast into a f64
     fn is_f64_castable(&self) -> bool {
         self.cast_f64().is_some()
     }
 
+    /// Same as `as_i64` but also accepts strings holding an integer, for
+    /// input that quotes numbers inconsistently.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess};
+    /// let v = json!({"a": 7, "b": "7", "c": "7.5", "d": true});
+    /// assert_eq!(v["a"].as_i64_lenient(), Some(7));
+    /// assert_eq!(v["b"].as_i64_lenient(), Some(7));
+    /// assert_eq!(v["c"].as_i64_lenient(), None);
+    /// assert_eq!(v["c"].as_f64_lenient(), Some(7.5));
+    /// assert_eq!(v["d"].as_i64_lenient(), None);
+    /// ```
+    fn as_i64_lenient(&self) -> Option<i64> {
+        self.as_i64()
+            .or_else(|| self.as_str().and_then(|s| s.parse().ok()))
+    }
+
+    /// Same as `cast_f64` but also accepts strings holding a finite
+    /// number.
+    fn as_f64_lenient(&self) -> Option<f64> {
+        self.cast_f64().or_else(|| {
+            self.as_str()
+                .and_then(|s| s.parse::<f64>().ok())
+                .filter(|f| f.is_finite())
+        })
+    }
+
+    /// Copies an array of numbers into a `Vec<f64>`, integers are cast to
+    /// floats. Returns `None` if the value isn't an array or any of its
+    /// elements isn't a number. To skip building a value for a large array
+    /// use `simd_json::to_f64_vec` instead.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess};
+    /// assert_eq!(json!([1, 2.5]).to_vec_f64(), Some(vec![1.0, 2.5]));
+    /// assert_eq!(json!([1, 2.5]).to_vec_i64(), None);
+    /// assert_eq!(json!([1, "2"]).to_vec_f64(), None);
+    /// ```
+    fn to_vec_f64(&self) -> Option<Vec<f64>> {
+        self.as_array()
+            .and_then(|a| a.iter().map(Self::cast_f64).collect())
+    }
+
+    /// Copies an array of integers into a `Vec<i64>`. Returns `None` if the
+    /// value isn't an array or any of its elements isn't an integer.
+    fn to_vec_i64(&self) -> Option<Vec<i64>> {
+        self.as_array()
+            .and_then(|a| a.iter().map(Self::as_i64).collect())
+    }
+
+    /// Same as `as_bool` but also accepts the strings `"true"` and
+    /// `"false"` and the numbers `1` and `0`.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess};
+    /// let v = json!({"a": false, "b": "true", "c": 1, "d": 2});
+    /// assert_eq!(v["a"].as_bool_lenient(), Some(false));
+    /// assert_eq!(v["b"].as_bool_lenient(), Some(true));
+    /// assert_eq!(v["c"].as_bool_lenient(), Some(true));
+    /// assert_eq!(v["d"].as_bool_lenient(), None);
+    /// ```
+    fn as_bool_lenient(&self) -> Option<bool> {
+        self.as_bool()
+            .or_else(|| match (self.as_str(), self.as_i64()) {
+                (Some("true"), _) | (_, Some(1)) => Some(true),
+                (Some("false"), _) | (_, Some(0)) => Some(false),
+                _ => None,
+            })
+    }
+
     /// Tries to represent the value as a f32
     fn as_f32(&self) -> Option<f32> {
         self.as_f64().and_then(|u| {
@@ -253,6 +866,7 @@ pub trait ValueTrait:
             }
         })
     }
+
     /// returns true if the current value can be represented as a f64
     fn is_f32(&self) -> bool {
         self.as_f32().is_some()
@@ -264,6 +878,7 @@ pub trait ValueTrait:
         note = "This will allocate a new string please use as_str instead"
     )]
     fn as_string(&self) -> Option<String>;
+
     /// returns true if the current value can be represented as a String
     #[deprecated(since = "0.1.20", note = "Please use is_str instead")]
     fn is_string(&self) -> bool {
@@ -272,26 +887,568 @@ pub trait ValueTrait:
 
     /// Tries to represent the value as a &str
     fn as_str(&self) -> Option<&str>;
+
     /// returns true if the current value can be represented as a str
     fn is_str(&self) -> bool {
         self.as_str().is_some()
     }
 
+    /// Decodes a base64 (standard alphabet, padded) string value into the
+    /// bytes it encodes, returns `None` if the value isn't a string or
+    /// isn't valid base64.
+    #[cfg(feature = "base64")]
+    fn as_bytes_base64(&self) -> Option<Vec<u8>> {
+        self.as_str().and_then(base64::decode)
+    }
+
+    /// Interprets the value as a point in time in milliseconds since the
+    /// unix epoch. Strings are parsed as RFC 3339 timestamps (e.g.
+    /// `2019-10-12T07:20:50.52Z`), integers are taken as milliseconds.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess};
+    /// let v = json!({"created": "2019-10-12T09:20:50.52+02:00", "updated": 1570864850520_i64});
+    /// assert_eq!(v["created"].as_timestamp_millis(), Some(1_570_864_850_520));
+    /// assert_eq!(v["updated"].as_timestamp_millis(), Some(1_570_864_850_520));
+    /// ```
+    fn as_timestamp_millis(&self) -> Option<i64> {
+        match self.value_type() {
+            ValueType::String => self.as_str().and_then(datetime::parse_rfc3339_millis),
+            _ => self.as_i64(),
+        }
+    }
+
+    /// Same as `as_timestamp_millis` but returns a `chrono::DateTime<Utc>`
+    /// (with the `chrono` feature), a `time::OffsetDateTime` in UTC (with
+    /// the `time` feature) or a `SystemTime`.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess};
+    /// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    /// let v = json!("1970-01-01T00:00:01.5Z");
+    /// let t: SystemTime = v.as_datetime().unwrap();
+    /// assert_eq!(t, UNIX_EPOCH + Duration::from_millis(1500));
+    /// ```
+    fn as_datetime<T: FromTimestampMillis>(&self) -> Option<T> {
+        T::from_timestamp_millis(self.as_timestamp_millis()?)
+    }
+
     /// Tries to represent the value as an array and returns a refference to it
-    fn as_array(&self) -> Option<&Vec<Self>>;
-    /// Tries to represent the value as an array and returns a mutable refference to it
-    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>>;
+    fn as_array(&self) -> Option<&Self::Array>;
+
     /// returns true if the current value can be represented as an array
     fn is_array(&self) -> bool {
         self.as_array().is_some()
     }
 
     /// Tries to represent the value as an object and returns a refference to it
-    fn as_object(&self) -> Option<&HashMap<Self::Key, Self>>;
-    /// Tries to represent the value as an object and returns a mutable refference to it
-    fn as_object_mut(&mut self) -> Option<&mut HashMap<Self::Key, Self>>;
+    fn as_object(&self) -> Option<&Self::Object>;
+
     /// returns true if the current value can be represented as an object
     fn is_object(&self) -> bool {
         self.as_object().is_some()
     }
+
+    /// Tries to represent the value as a bool, fails with a `TryTypeError`
+    /// describing the expected and actual type otherwise.
+    ///
+    /// ```
+    /// use simd_json::{json, TryTypeError, ValueAccess, ValueType};
+    /// let v = json!({"a": 1});
+    /// let e = v.try_as_str().unwrap_err();
+    /// assert_eq!(e, TryTypeError { expected: ValueType::String, got: ValueType::Object });
+    /// assert_eq!(e.to_string(), "expected string, found object");
+    /// ```
+    fn try_as_bool(&self) -> Result<bool, TryTypeError> {
+        self.as_bool()
+            .ok_or_else(|| type_error(self, ValueType::Bool))
+    }
+
+    /// Tries to represent the value as an i128, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_i128(&self) -> Result<i128, TryTypeError> {
+        self.as_i128()
+            .ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an i64, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_i64(&self) -> Result<i64, TryTypeError> {
+        self.as_i64()
+            .ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an i32, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_i32(&self) -> Result<i32, TryTypeError> {
+        self.as_i32()
+            .ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an i16, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_i16(&self) -> Result<i16, TryTypeError> {
+        self.as_i16()
+            .ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an i8, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_i8(&self) -> Result<i8, TryTypeError> {
+        self.as_i8().ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an u128, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_u128(&self) -> Result<u128, TryTypeError> {
+        self.as_u128()
+            .ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an u64, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_u64(&self) -> Result<u64, TryTypeError> {
+        self.as_u64()
+            .ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an usize, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_usize(&self) -> Result<usize, TryTypeError> {
+        self.as_usize()
+            .ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an u32, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_u32(&self) -> Result<u32, TryTypeError> {
+        self.as_u32()
+            .ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an u16, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_u16(&self) -> Result<u16, TryTypeError> {
+        self.as_u16()
+            .ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as an u8, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_u8(&self) -> Result<u8, TryTypeError> {
+        self.as_u8().ok_or_else(|| type_error(self, ValueType::I64))
+    }
+
+    /// Tries to represent the value as a f64, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_f64(&self) -> Result<f64, TryTypeError> {
+        self.as_f64()
+            .ok_or_else(|| type_error(self, ValueType::F64))
+    }
+
+    /// Tries to represent the value as a f32, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_f32(&self) -> Result<f32, TryTypeError> {
+        self.as_f32()
+            .ok_or_else(|| type_error(self, ValueType::F64))
+    }
+
+    /// Tries to represent the value as a str, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_str(&self) -> Result<&str, TryTypeError> {
+        self.as_str()
+            .ok_or_else(|| type_error(self, ValueType::String))
+    }
+
+    /// Tries to represent the value as an array, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_array(&self) -> Result<&Self::Array, TryTypeError> {
+        self.as_array()
+            .ok_or_else(|| type_error(self, ValueType::Array))
+    }
+
+    /// Tries to represent the value as an object, fails with a `TryTypeError`
+    /// otherwise.
+    fn try_as_object(&self) -> Result<&Self::Object, TryTypeError> {
+        self.as_object()
+            .ok_or_else(|| type_error(self, ValueType::Object))
+    }
+
+    /// Checks if `needle` equals this value or any value nested within it.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess};
+    /// let v = json!({"users": [{"name": "ann", "ssn": "123-45-6789"}]});
+    /// assert!(v.contains(&"123-45-6789"));
+    /// assert!(v.contains(&json!({"name": "ann", "ssn": "123-45-6789"})));
+    /// assert!(!v.contains(&"ann "));
+    /// ```
+    fn contains<T: ?Sized>(&self, needle: &T) -> bool
+    where
+        Self: PartialEq<T>,
+    {
+        let mut stack = vec![self];
+        while let Some(v) = stack.pop() {
+            if v == needle {
+                return true;
+            }
+            if let Some(a) = v.as_array() {
+                stack.extend(a.iter());
+            } else if let Some(o) = v.as_object() {
+                stack.extend(o.iter().map(|(_, v)| v));
+            }
+        }
+        false
+    }
+
+    /// Returns all values (including this one) `predicate` is true for,
+    /// together with their JSON pointers ([RFC 6901]). Values are visited
+    /// depth first, parents before their children, object members in the
+    /// order the object iterates them.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess};
+    /// let v = json!({"a": [1, "x", 3], "b/c": {"d": 4}});
+    /// let mut found: Vec<_> = v
+    ///     .find_all(|v| v.as_u64().map_or(false, |n| n > 2))
+    ///     .into_iter()
+    ///     .map(|(pointer, v)| (pointer, v.as_u64()))
+    ///     .collect();
+    /// found.sort();
+    /// assert_eq!(
+    ///     found,
+    ///     vec![("/a/2".to_string(), Some(3)), ("/b~1c/d".to_string(), Some(4))]
+    /// );
+    /// ```
+    ///
+    /// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+    fn find_all<F>(&self, mut predicate: F) -> Vec<(String, &Self)>
+    where
+        F: FnMut(&Self) -> bool,
+        Self::Key: Borrow<str>,
+    {
+        let mut found = Vec::new();
+        find_into(self, &mut String::new(), &mut predicate, &mut found);
+        found
+    }
+}
+
+/// The `ValueTrait` exposes common interface for values, this allows using both
+/// `BorrowedValue` and `OwnedValue` nearly interchangable. It adds
+/// construction and mutation to the read-only `ValueAccess`.
+pub trait ValueTrait:
+    ValueAccess<Array = Vec<Self>, Object = ObjectMap<<Self as ValueAccess>::Key, Self>>
+    + From<i8>
+    + From<i16>
+    + From<i32>
+    + From<i64>
+    + From<u8>
+    + From<u16>
+    + From<u32>
+    + From<u64>
+    + From<f32>
+    + From<f64>
+    + From<String>
+    + From<bool>
+    + From<()>
+    + PartialEq<i8>
+    + PartialEq<i16>
+    + PartialEq<i32>
+    + PartialEq<i64>
+    + PartialEq<i128>
+    + PartialEq<u8>
+    + PartialEq<u16>
+    + PartialEq<u32>
+    + PartialEq<u64>
+    + PartialEq<u128>
+    + PartialEq<f32>
+    + PartialEq<f64>
+    + PartialEq<String>
+    + PartialEq<bool>
+    + PartialEq<()>
+    + for<'k> Index<&'k str, Output = Self>
+    + for<'k> IndexMut<&'k str>
+{
+    /// Same as `get` but returns a mutable ref instead
+    //    fn get_amut(&mut self, k: &str) -> Option<&mut Self>;
+    fn get_mut<Q: ?Sized>(&mut self, k: &Q) -> Option<&mut Self>
+    where
+        Self::Key: Borrow<Q> + Hash + Eq + Ord,
+        Q: Hash + Eq + Ord,
+    {
+        self.as_object_mut().and_then(|m| m.get_mut(k))
+    }
+
+    /// Gets the entry for `key` for in-place manipulation, returns `None`
+    /// if the current Value isn't an Object. This avoids looking up the
+    /// key twice when updating or inserting.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess, ValueTrait};
+    /// let mut counts = json!({});
+    /// for word in &["a", "b", "a"] {
+    ///     if let Some(e) = counts.entry((*word).into()) {
+    ///         let n = e.or_insert_with(|| 0.into());
+    ///         *n = (n.as_i64().unwrap_or(0) + 1).into();
+    ///     }
+    /// }
+    /// assert_eq!(counts, json!({"a": 2, "b": 1}));
+    /// ```
+    fn entry(&mut self, key: Self::Key) -> Option<Entry<'_, Self>>
+    where
+        Self::Key: Hash + Eq + Ord,
+    {
+        self.as_object_mut().map(|m| m.entry(key))
+    }
+
+    /// Same as `get_idx` but returns a mutable ref instead
+    fn get_idx_mut(&mut self, i: usize) -> Option<&mut Self> {
+        self.as_array_mut().and_then(|a| a.get_mut(i))
+    }
+
+    /// Tries to represent the value as an array and returns a mutable refference to it
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>>;
+
+    /// Tries to represent the value as an object and returns a mutable refference to it
+    fn as_object_mut(&mut self) -> Option<&mut ObjectMap<Self::Key, Self>>;
+
+    /// Takes the value out, leaving `null` in its place.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueTrait};
+    /// let mut v = json!({"a": [1, 2]});
+    /// let a = v["a"].take();
+    /// assert_eq!(a, json!([1, 2]));
+    /// assert_eq!(v, json!({"a": null}));
+    /// ```
+    fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::from(()))
+    }
+
+    /// Replaces the value with `value`, returning the old one.
+    fn replace(&mut self, value: Self) -> Self {
+        std::mem::replace(self, value)
+    }
+
+    /// Takes the array out of the value without cloning it, gives the
+    /// value back if it isn't an array.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueTrait};
+    /// let v = json!({"a": 1});
+    /// let v = v.into_array().unwrap_err();
+    /// let o = v.into_object().unwrap();
+    /// assert_eq!(o.len(), 1);
+    /// ```
+    fn into_array(self) -> Result<Vec<Self>, Self>;
+
+    /// Takes the object out of the value without cloning it, gives the
+    /// value back if it isn't an object.
+    fn into_object(self) -> Result<ObjectMap<Self::Key, Self>, Self>;
+
+    /// Takes the string out of the value, gives the value back if it isn't
+    /// a string.
+    fn into_string(self) -> Result<String, Self>;
+
+    /// Same as `into_array` but fails with a `TryTypeError`
+    fn try_into_array(self) -> Result<Vec<Self>, TryTypeError> {
+        self.into_array()
+            .map_err(|v| type_error(&v, ValueType::Array))
+    }
+
+    /// Same as `into_object` but fails with a `TryTypeError`
+    fn try_into_object(self) -> Result<ObjectMap<Self::Key, Self>, TryTypeError> {
+        self.into_object()
+            .map_err(|v| type_error(&v, ValueType::Object))
+    }
+
+    /// Same as `into_string` but fails with a `TryTypeError`
+    fn try_into_string(self) -> Result<String, TryTypeError> {
+        self.into_string()
+            .map_err(|v| type_error(&v, ValueType::String))
+    }
+
+    /// Calls `f` with the JSON pointer ([RFC 6901]) of and a mutable
+    /// reference to this value and every value nested within it. Parents
+    /// are visited before their children, so if `f` replaces a value the
+    /// children of the replacement are visited.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess, ValueTrait};
+    /// let mut v = json!({"user": {"name": "ann", "password": "hunter2"}, "password": null});
+    /// v.walk_mut(|pointer, v| {
+    ///     if pointer.ends_with("/password") && v.is_str() {
+    ///         *v = "***".into();
+    ///     }
+    /// });
+    /// assert_eq!(v, json!({"user": {"name": "ann", "password": "***"}, "password": null}));
+    /// ```
+    ///
+    /// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+    fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&str, &mut Self),
+        Self::Key: Borrow<str>,
+    {
+        walk_into(self, &mut String::new(), &mut f, true);
+    }
+
+    /// Replaces this value and every value nested within it with the
+    /// result of calling `f` with its JSON pointer and the value itself.
+    /// Children are mapped before their parents, so `f` sees containers
+    /// with their already mapped members.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueAccess, ValueTrait};
+    /// let mut v = json!({"created": 1577836800, "tags": ["a", "b"]});
+    /// v.map_values(|pointer, v| match (pointer, v.as_u64()) {
+    ///     ("/created", Some(secs)) => (secs * 1000).into(),
+    ///     _ if v.is_str() => v.as_str().unwrap_or_default().to_uppercase().into(),
+    ///     _ => v,
+    /// });
+    /// assert_eq!(v, json!({"created": 1577836800000_u64, "tags": ["A", "B"]}));
+    /// ```
+    fn map_values<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&str, Self) -> Self,
+        Self::Key: Borrow<str>,
+    {
+        let mut path = String::new();
+        walk_into(
+            self,
+            &mut path,
+            &mut |pointer: &str, v: &mut Self| {
+                let value = std::mem::replace(v, Self::from(()));
+                *v = f(pointer, value);
+            },
+            false,
+        );
+    }
+
+    /// Recursively removes `null` members and members that are empty
+    /// arrays or objects (after their own members were removed), returns
+    /// the number of values removed. See `compact_with_options` to choose
+    /// what is removed.
+    ///
+    /// ```
+    /// use simd_json::{json, ValueTrait};
+    /// let mut v = json!({"id": 1, "tags": [], "meta": {"note": null}, "list": [null]});
+    /// assert_eq!(v.compact(), 3);
+    /// assert_eq!(v, json!({"id": 1, "list": [null]}));
+    /// ```
+    fn compact(&mut self) -> usize
+    where
+        Self::Key: Hash + Eq + Ord,
+    {
+        self.compact_with_options(&CompactOptions::default())
+    }
+
+    /// Same as `compact` but removes what `options` selects
+    fn compact_with_options(&mut self, options: &CompactOptions) -> usize
+    where
+        Self::Key: Hash + Eq + Ord,
+    {
+        compact_into(self, options)
+    }
+}
+
+fn removable<V: ValueTrait>(v: &V, options: &CompactOptions) -> bool {
+    match v.value_type() {
+        ValueType::Null => options.nulls,
+        ValueType::Array => options.empty_arrays && v.as_array().map_or(false, Vec::is_empty),
+        ValueType::Object => options.empty_objects && v.as_object().map_or(false, |o| o.is_empty()),
+        _ => false,
+    }
+}
+
+fn compact_into<V>(v: &mut V, options: &CompactOptions) -> usize
+where
+    V: ValueTrait,
+    V::Key: Hash + Eq + Ord,
+{
+    let mut removed = 0;
+    if let Some(a) = v.as_array_mut() {
+        for item in a.iter_mut() {
+            removed += compact_into(item, options);
+        }
+        if options.in_arrays {
+            let len = a.len();
+            a.retain(|item| !removable(item, options));
+            removed += len - a.len();
+        }
+    } else if let Some(o) = v.as_object_mut() {
+        o.retain(|_, member| {
+            removed += compact_into(member, options);
+            if removable(member, options) {
+                removed += 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+    removed
+}
+
+pub(crate) fn push_token(path: &mut String, token: &str) {
+    path.push('/');
+    path.push_str(&token.replace('~', "~0").replace('/', "~1"));
+}
+
+// Visits `v` and its children, either parents first or children first
+fn walk_into<V, F>(v: &mut V, path: &mut String, f: &mut F, parents_first: bool)
+where
+    V: ValueTrait,
+    V::Key: Borrow<str>,
+    F: FnMut(&str, &mut V),
+{
+    if parents_first {
+        f(path, v);
+    }
+    let len = path.len();
+    if let Some(a) = v.as_array_mut() {
+        for (i, item) in a.iter_mut().enumerate() {
+            push_token(path, &i.to_string());
+            walk_into(item, path, f, parents_first);
+            path.truncate(len);
+        }
+    } else if let Some(o) = v.as_object_mut() {
+        for (k, member) in o.iter_mut() {
+            push_token(path, k.borrow());
+            walk_into(member, path, f, parents_first);
+            path.truncate(len);
+        }
+    }
+    if !parents_first {
+        f(path, v);
+    }
+}
+
+fn find_into<'v, V, F>(
+    v: &'v V,
+    path: &mut String,
+    predicate: &mut F,
+    found: &mut Vec<(String, &'v V)>,
+) where
+    V: ValueAccess,
+    V::Key: Borrow<str>,
+    F: FnMut(&V) -> bool,
+{
+    if predicate(v) {
+        found.push((path.clone(), v));
+    }
+    let len = path.len();
+    if let Some(a) = v.as_array() {
+        for (i, item) in a.iter().enumerate() {
+            push_token(path, &i.to_string());
+            find_into(item, path, predicate, found);
+            path.truncate(len);
+        }
+    } else if let Some(o) = v.as_object() {
+        for (k, member) in o.iter() {
+            push_token(path, k.borrow());
+            find_into(member, path, predicate, found);
+            path.truncate(len);
+        }
+    }
 }
@@ -0,0 +1,463 @@
+use crate::value::{
+    is_float_literal, ArrayAccess, ArrayIter, ObjectAccess, ObjectIter, Open, StaticNode,
+    ValueAccess, ValueType,
+};
+use crate::{BigNumberBehavior, Deserializer, ErrorType, ParserOptions, Result};
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use std::borrow::Borrow;
+use std::hash::Hash;
+
+/// Parses a slice of bytes into a value allocated in `bump`. Like
+/// `to_borrowed_value` this rewrites the slice to de-escape strings and
+/// the value references it.
+///
+/// ```
+/// use bumpalo::Bump;
+/// use simd_json::{json, OwnedValue, ValueAccess};
+/// let mut bump = Bump::new();
+/// for line in &["{\"id\": 1, \"tags\": [\"a\"]}", "{\"id\": 2, \"tags\": []}"] {
+///     let mut d = line.as_bytes().to_vec();
+///     let v = simd_json::to_arena_value(&mut d, &bump).unwrap();
+///     assert!(v.get_u64("id").is_some());
+///     assert_eq!(v.get_array("tags").map(|t| t.len() < 2), Some(true));
+///     drop(v);
+///     // frees the arrays and objects of the document at once
+///     bump.reset();
+/// }
+/// ```
+pub fn to_value<'v>(s: &'v mut [u8], bump: &'v Bump) -> Result<Value<'v>> {
+    to_value_with_options(s, bump, &ParserOptions::default())
+}
+
+/// Same as `to_value` but allows tuning the parser using `ParserOptions`.
+pub fn to_value_with_options<'v>(
+    s: &'v mut [u8],
+    bump: &'v Bump,
+    options: &ParserOptions,
+) -> Result<Value<'v>> {
+    let de = stry!(Deserializer::from_slice_with_options(s, options));
+    trace_span!("dom");
+    ArenaDeserializer { de, bump }.parse()
+}
+
+/// JSON-DOM Value allocated in an arena, read it with `ValueAccess`. As the
+/// arrays and objects can't grow without the arena it is read-only, convert
+/// it to an `OwnedValue` to change it or keep it past the arena.
+///
+/// It is a type of its own rather than a mode of `BorrowedValue` because
+/// the arrays and objects of `BorrowedValue` are a `Vec` and a `halfbrown`
+/// map using the global allocator, and stable Rust can't hand those an
+/// arena. Switching them to `bumpalo` collections would change the public
+/// variants of `BorrowedValue` for every user, not only the ones parsing
+/// into an arena.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'v> {
+    /// Static values
+    Static(StaticNode),
+    /// A number kept as its literal, see `ParserOptions::lossless_numbers`
+    RawNumber(&'v str),
+    /// string type
+    String(&'v str),
+    /// array type
+    Array(Vec<'v, Value<'v>>),
+    /// object type
+    Object(Object<'v>),
+}
+
+/// Representation of a JSON object in an arena, its members are kept in
+/// the order they were parsed in and are searched one by one, like the
+/// vector backend `halfbrown` uses for small objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object<'v> {
+    members: Vec<'v, (&'v str, Value<'v>)>,
+}
+
+impl<'v> Object<'v> {
+    fn with_capacity_in(capacity: usize, bump: &'v Bump) -> Self {
+        Self {
+            members: Vec::with_capacity_in(capacity, bump),
+        }
+    }
+
+    // Replaces the value of an existing member or adds a new one
+    fn insert(&mut self, key: &'v str, value: Value<'v>) {
+        if let Some(m) = self.members.iter_mut().find(|(k, _)| *k == key) {
+            m.1 = value;
+        } else {
+            self.members.push((key, value));
+        }
+    }
+}
+
+impl<'i, 'v> ArrayIter<'i> for Vec<'v, Value<'v>> {
+    type Element = Value<'v>;
+    type Iter = std::slice::Iter<'i, Value<'v>>;
+}
+
+impl<'v> ArrayAccess for Vec<'v, Value<'v>> {
+    type Element = Value<'v>;
+
+    #[inline]
+    fn get(&self, i: usize) -> Option<&Value<'v>> {
+        self.as_slice().get(i)
+    }
+
+    #[inline]
+    fn iter(&self) -> std::slice::Iter<'_, Value<'v>> {
+        self.as_slice().iter()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+}
+
+/// Iterator over the members of an arena `Object`
+pub struct Iter<'i, 'v> {
+    members: std::slice::Iter<'i, (&'v str, Value<'v>)>,
+}
+
+impl<'i, 'v> Iterator for Iter<'i, 'v> {
+    type Item = (&'i &'v str, &'i Value<'v>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.members.next().map(|(k, v)| (k, v))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.members.size_hint()
+    }
+}
+
+impl<'i, 'v> ObjectIter<'i> for Object<'v> {
+    type Key = &'v str;
+    type Element = Value<'v>;
+    type Iter = Iter<'i, 'v>;
+}
+
+impl<'v> ObjectAccess for Object<'v> {
+    type Key = &'v str;
+    type Element = Value<'v>;
+
+    #[inline]
+    fn get<Q: ?Sized>(&self, k: &Q) -> Option<&Value<'v>>
+    where
+        &'v str: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + Ord,
+    {
+        self.members
+            .iter()
+            .find_map(|(key, v)| if key.borrow() == k { Some(v) } else { None })
+    }
+
+    #[inline]
+    fn iter(&self) -> Iter<'_, 'v> {
+        Iter {
+            members: self.members.iter(),
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+}
+
+impl<'v> ValueAccess for Value<'v> {
+    type Key = &'v str;
+    type Array = Vec<'v, Self>;
+    type Object = Object<'v>;
+
+    fn value_type(&self) -> ValueType {
+        match self {
+            Self::Static(s) => s.value_type(),
+            Self::RawNumber(n) if is_float_literal(n) => ValueType::F64,
+            Self::RawNumber(_) => ValueType::I64,
+            Self::String(_) => ValueType::String,
+            Self::Array(_) => ValueType::Array,
+            Self::Object(_) => ValueType::Object,
+        }
+    }
+
+    fn is_null(&self) -> bool {
+        match self {
+            Self::Static(s) => s.is_null(),
+            _ => false,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Static(s) => s.as_bool(),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Static(s) => s.as_i64(),
+            Self::RawNumber(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_i128(&self) -> Option<i128> {
+        match self {
+            Self::Static(s) => s.as_i128(),
+            Self::RawNumber(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Static(s) => s.as_u64(),
+            Self::RawNumber(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Static(s) => s.as_f64(),
+            Self::RawNumber(n) if is_float_literal(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn cast_f64(&self) -> Option<f64> {
+        match self {
+            Self::Static(s) => s.cast_f64(),
+            Self::RawNumber(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_string(&self) -> Option<String> {
+        self.as_str().map(String::from)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&Vec<'v, Self>> {
+        match self {
+            Self::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    fn as_object(&self) -> Option<&Object<'v>> {
+        match self {
+            Self::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Value<'_>> for crate::OwnedValue {
+    fn from(v: &Value<'_>) -> Self {
+        match v {
+            Value::Static(s) => Self::Static(*s),
+            Value::RawNumber(n) => Self::RawNumber((*n).to_string()),
+            Value::String(s) => Self::from(*s),
+            Value::Array(a) => Self::Array(a.iter().map(Self::from).collect()),
+            Value::Object(o) => Self::Object(
+                o.members
+                    .iter()
+                    .map(|(k, v)| (super::owned::key((*k).to_string()), Self::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+struct ArenaDeserializer<'de> {
+    de: Deserializer<'de>,
+    bump: &'de Bump,
+}
+
+impl<'de> ArenaDeserializer<'de> {
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn parse(&mut self) -> Result<Value<'de>> {
+        let mut stack: std::vec::Vec<Open<Vec<'de, Value<'de>>, Object<'de>, &'de str>> =
+            std::vec::Vec::new();
+        loop {
+            let mut value = match self.de.next_() {
+                b'"' => Value::String(stry!(self.de.parse_str())),
+                b'-' => stry!(self.parse_number(true, stack.is_empty())),
+                b'0'..=b'9' => stry!(self.parse_number(false, stack.is_empty())),
+                b'n' => Value::Static(StaticNode::Null),
+                b't' => Value::Static(StaticNode::Bool(true)),
+                b'f' => Value::Static(StaticNode::Bool(false)),
+                b'[' => {
+                    let es = self.de.count_elements();
+                    if unlikely!(es == 0) {
+                        self.de.skip();
+                        Value::Array(Vec::new_in(self.bump))
+                    } else {
+                        stack.push(Open::Array(Vec::with_capacity_in(es, self.bump), es));
+                        continue;
+                    }
+                }
+                b'{' => {
+                    let es = self.de.count_elements();
+                    if unlikely!(es == 0) {
+                        self.de.skip();
+                        Value::Object(Object::with_capacity_in(0, self.bump))
+                    } else {
+                        let key = stry!(self.parse_key());
+                        let res = Object::with_capacity_in(es, self.bump);
+                        stack.push(Open::Object(res, es, key));
+                        continue;
+                    }
+                }
+                _c => return Err(self.de.error(ErrorType::UnexpectedCharacter)),
+            };
+            // Adds the value to the innermost open array or object, the
+            // ones that are complete become the value for their parent
+            loop {
+                value = match stack.pop() {
+                    None => return Ok(value),
+                    Some(Open::Array(mut res, left)) => {
+                        res.push(value);
+                        self.de.skip();
+                        if left > 1 {
+                            stack.push(Open::Array(res, left - 1));
+                            break;
+                        }
+                        Value::Array(res)
+                    }
+                    Some(Open::Object(mut res, left, key)) => {
+                        if self.de.options.key_case.is_some() {
+                            res.insert(key, value);
+                        } else {
+                            res.members.push((key, value));
+                        }
+                        self.de.skip();
+                        if left > 1 {
+                            let key = stry!(self.parse_key());
+                            stack.push(Open::Object(res, left - 1, key));
+                            break;
+                        }
+                        Value::Object(res)
+                    }
+                };
+            }
+        }
+    }
+
+    // Parses the key of the next member of an object and moves to its value
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn parse_key(&mut self) -> Result<&'de str> {
+        self.de.skip();
+        let key = stry!(self.de.parse_str());
+        self.de.skip();
+        Ok(match self.de.options.key_case {
+            // rewritten keys go into the arena as well
+            Some(case) => self.bump.alloc_str(&case.apply(key)),
+            None => key,
+        })
+    }
+
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    fn parse_number(&mut self, minus: bool, root: bool) -> Result<Value<'de>> {
+        if unlikely!(self.de.options.lossless_numbers) {
+            return self.parse_raw_number(minus, root);
+        }
+        let n = if root {
+            self.de.parse_number_root(minus)
+        } else {
+            self.de.parse_number(minus)
+        };
+        match n {
+            Err(ref e)
+                if e.error() == &ErrorType::Overflow
+                    && self.de.options.big_numbers == BigNumberBehavior::Raw =>
+            {
+                self.parse_raw_number(minus, root)
+            }
+            n => n.map(|n| Value::Static(n.into())),
+        }
+    }
+
+    #[inline(never)]
+    fn parse_raw_number(&mut self, minus: bool, root: bool) -> Result<Value<'de>> {
+        self.de.parse_number_raw(minus, root).map(Value::RawNumber)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{KeyCase, OwnedValue};
+
+    #[test]
+    fn parse() {
+        let bump = Bump::new();
+        let mut d =
+            br#"{"a": [1, -2, 3.5, "x\ny"], "b": {"c": null, "d": true}, "e": {}}"#.to_vec();
+        let v = to_value(&mut d, &bump).expect("valid");
+        assert_eq!(
+            OwnedValue::from(&v),
+            json!({"a": [1, -2, 3.5, "x\ny"], "b": {"c": null, "d": true}, "e": {}})
+        );
+        assert_eq!(v.value_type(), ValueType::Object);
+        let a = v.get_array("a").expect("array");
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.get(1).and_then(Value::as_i64), Some(-2));
+        assert_eq!(a.get(3).and_then(Value::as_str), Some("x\ny"));
+        assert_eq!(v.get("b").and_then(|b| b.get_bool("d")), Some(true));
+        assert!(v
+            .get("b")
+            .and_then(|b| b.get("c"))
+            .map_or(false, Value::is_null));
+        assert_eq!(v.get_object("e").map(ObjectAccess::len), Some(0));
+        assert!(v.get("f").is_none());
+        let mut d = b"[1, 2".to_vec();
+        assert!(to_value(&mut d, &bump).is_err());
+    }
+
+    #[test]
+    fn allocates_in_arena() {
+        let mut bump = Bump::new();
+        let mut d = br#"[[1, 2], {"a": [3]}, []]"#.to_vec();
+        let before = bump.allocated_bytes();
+        {
+            let v = to_value(&mut d, &bump).expect("valid");
+            assert_eq!(OwnedValue::from(&v), json!([[1, 2], {"a": [3]}, []]));
+        }
+        assert!(bump.allocated_bytes() > before);
+        bump.reset();
+        let mut d = br#"{"level": "INFO"}"#.to_vec();
+        let v = to_value(&mut d, &bump).expect("valid");
+        assert_eq!(v.get_str("level"), Some("INFO"));
+    }
+
+    #[test]
+    fn options() {
+        let bump = Bump::new();
+        let options = ParserOptions {
+            lossless_numbers: true,
+            key_case: Some(KeyCase::SnakeCase),
+            ..ParserOptions::default()
+        };
+        let mut d = br#"{"userId": 1.10, "user_id": 2, "Name": "x"}"#.to_vec();
+        let v = to_value_with_options(&mut d, &bump, &options).expect("valid");
+        assert_eq!(v.as_object().map(ObjectAccess::len), Some(2));
+        assert_eq!(v.get("user_id"), Some(&Value::RawNumber("2")));
+        assert_eq!(v.get_str("name"), Some("x"));
+        let mut d = b"1.10".to_vec();
+        let v = to_value_with_options(&mut d, &bump, &options).expect("valid");
+        assert_eq!(v.as_f64(), Some(1.1));
+        assert_eq!(v.value_type(), ValueType::F64);
+    }
+}
@@ -1,28 +1,96 @@
 ///A dom object that references the raw input data to avoid allocations
 // it tradecs having lifetimes for a gain in performance.
 mod cmp;
+mod document;
 mod from;
 mod serialize;
 
-use crate::value::{ValueTrait, ValueType};
-use crate::{stry, unlikely, Deserializer, ErrorType, Result};
-use halfbrown::HashMap;
+pub use self::document::Document;
+
+use crate::parser::Buffers;
+use crate::value::{
+    insert_parsed, is_float_literal, parsed_object, ObjectMap, Open, StaticNode, ValueAccess,
+    ValueTrait, ValueType,
+};
+use crate::{
+    stry, unlikely, BigNumberBehavior, Deserializer, ErrorType, OwnedValue, ParserOptions, Result,
+};
 use std::borrow::Cow;
 use std::fmt;
-use std::ops::Index;
+use std::ops::{Index, IndexMut};
 
 /// Representation of a JSON object
 #[deprecated(since = "0.1.21", note = "Please use Object instead")]
 pub type Map<'v> = Object<'v>;
 /// Representation of a JSON object
-pub type Object<'v> = HashMap<Cow<'v, str>, Value<'v>>;
+pub type Object<'v> = ObjectMap<Cow<'v, str>, Value<'v>>;
 
 /// Parses a slice of butes into a Value dom. This function will
 /// rewrite the slice to de-escape strings.
 /// As we reference parts of the input slice the resulting dom
 /// has the dame lifetime as the slice it was created from.
 pub fn to_value<'v>(s: &'v mut [u8]) -> Result<Value<'v>> {
-    let de = stry!(Deserializer::from_slice(s));
+    #[cfg(feature = "parser-pool")]
+    return crate::with_parser(move |p| p.to_borrowed_value(s));
+    #[cfg(not(feature = "parser-pool"))]
+    {
+        let de = stry!(Deserializer::from_slice(s));
+        trace_span!("dom");
+        BorrowDeserializer::from_deserializer(de).parse()
+    }
+}
+
+/// Same as `to_value` but allows tuning the parser using `ParserOptions`.
+pub fn to_value_with_options<'v>(s: &'v mut [u8], options: &ParserOptions) -> Result<Value<'v>> {
+    #[cfg(feature = "parser-pool")]
+    return crate::with_parser(move |p| p.to_borrowed_value_with_options(s, options));
+    #[cfg(not(feature = "parser-pool"))]
+    {
+        let de = stry!(Deserializer::from_slice_with_options(s, options));
+        trace_span!("dom");
+        BorrowDeserializer::from_deserializer(de).parse()
+    }
+}
+
+/// Parses the first document in the input and returns it together with
+/// the number of bytes it took up, so whatever follows it starts at that
+/// offset. The rest of the input isn't looked at, see `TrailingData`.
+pub fn to_value_with_rest<'v>(
+    s: &'v mut [u8],
+    options: &ParserOptions,
+) -> Result<(Value<'v>, usize)> {
+    let end = options.document_end(s);
+    to_value_with_options(&mut s[..end], options).map(|v| (v, end))
+}
+
+// Same as `to_value_with_options` but reuses the parser buffers in
+// `buffers`, see `Parser`.
+pub(crate) fn to_value_with_buffers<'v>(
+    s: &'v mut [u8],
+    options: &ParserOptions,
+    buffers: &mut Buffers,
+) -> Result<Value<'v>> {
+    let de = stry!(Deserializer::from_slice_with_buffers(s, options, buffers));
+    trace_span!("dom");
+    let mut de = BorrowDeserializer::from_deserializer(de);
+    let v = de.parse();
+    de.de.into_buffers(buffers);
+    v
+}
+
+// Builds a value from structural indexes that were computed ahead of
+// time, see `Deserializer::from_structural_indexes`.
+pub(crate) fn to_value_with_structural_indexes<'v>(
+    s: &'v mut [u8],
+    structural_indexes: Vec<u32>,
+    options: &ParserOptions,
+) -> Result<Value<'v>> {
+    let de = stry!(Deserializer::from_structural_indexes(
+        s,
+        structural_indexes,
+        options
+    ));
+    trace_span!("dom");
     BorrowDeserializer::from_deserializer(de).parse()
 }
 
@@ -30,14 +98,11 @@ pub fn to_value<'v>(s: &'v mut [u8]) -> Result<Value<'v>> {
 /// to access it'scontent
 #[derive(Debug, Clone)]
 pub enum Value<'v> {
-    /// null
-    Null,
-    /// boolean type
-    Bool(bool),
-    /// float type
-    F64(f64),
-    /// integer type
-    I64(i64),
+    /// null, booleans and numbers
+    Static(StaticNode),
+    /// number as it appeared in the input, only produced when parsing with
+    /// `ParserOptions::lossless_numbers` or `BigNumberBehavior::Raw`
+    RawNumber(Cow<'v, str>),
     /// string type
     String(Cow<'v, str>),
     /// array type
@@ -47,6 +112,13 @@ pub enum Value<'v> {
 }
 
 impl<'v> Value<'v> {
+    /// Creates a string value holding `bytes` encoded as base64
+    /// (standard alphabet, padded).
+    #[cfg(feature = "base64")]
+    pub fn from_bytes_base64(bytes: &[u8]) -> Self {
+        Self::String(crate::value::base64::encode(bytes).into())
+    }
+
     /// Enforces static lifetime on a borrowed value, this will
     /// force all strings to become owned COW's, the same applies for
     /// Object keys.
@@ -55,6 +127,7 @@ impl<'v> Value<'v> {
             use std::mem::transmute;
             transmute(match self {
                 Self::String(Cow::Borrowed(s)) => Self::String(Cow::Owned(s.to_owned())),
+                Self::RawNumber(Cow::Borrowed(n)) => Self::RawNumber(Cow::Owned(n.to_owned())),
                 Self::Array(arr) => Self::Array(arr.into_iter().map(Value::into_static).collect()),
                 Self::Object(obj) => Self::Object(
                     obj.into_iter()
@@ -66,6 +139,12 @@ impl<'v> Value<'v> {
         }
     }
 
+    /// Converts the value into an `OwnedValue`, strings and keys that
+    /// are already owned are moved instead of copied.
+    pub fn into_owned(self) -> OwnedValue {
+        self.into()
+    }
+
     /// Clones the current value and enforces a static lifetime, it works the same
     /// as `into_static` but includes cloning logic
     pub fn clone_static(&self) -> Value<'static> {
@@ -79,24 +158,23 @@ impl<'v> Value<'v> {
                         .map(|(k, v)| (Cow::Owned(k.to_string()), v.clone_static()))
                         .collect(),
                 ),
-                Self::Null => Self::Null,
-                Self::F64(v) => Self::F64(*v),
-                Self::I64(v) => Self::I64(*v),
-                Self::Bool(v) => Self::Bool(*v),
+                Self::Static(s) => Self::Static(*s),
+                Self::RawNumber(n) => Self::RawNumber(Cow::Owned(n.to_string())),
             })
         }
     }
 }
 
-impl<'v> ValueTrait for Value<'v> {
+impl<'v> ValueAccess for Value<'v> {
     type Key = Cow<'v, str>;
+    type Array = Vec<Self>;
+    type Object = ObjectMap<Self::Key, Self>;
 
     fn value_type(&self) -> ValueType {
         match self {
-            Value::Null => ValueType::Null,
-            Value::Bool(_) => ValueType::Bool,
-            Value::F64(_) => ValueType::F64,
-            Value::I64(_) => ValueType::I64,
+            Value::Static(s) => s.value_type(),
+            Value::RawNumber(n) if is_float_literal(n) => ValueType::F64,
+            Value::RawNumber(_) => ValueType::I64,
             Value::String(_) => ValueType::String,
             Value::Array(_) => ValueType::Array,
             Value::Object(_) => ValueType::Object,
@@ -105,45 +183,54 @@ impl<'v> ValueTrait for Value<'v> {
 
     fn is_null(&self) -> bool {
         match self {
-            Value::Null => true,
+            Value::Static(s) => s.is_null(),
             _ => false,
         }
     }
 
     fn as_bool(&self) -> Option<bool> {
         match self {
-            Value::Bool(b) => Some(*b),
+            Value::Static(s) => s.as_bool(),
             _ => None,
         }
     }
 
     fn as_i64(&self) -> Option<i64> {
         match self {
-            Value::I64(i) => Some(*i),
+            Value::Static(s) => s.as_i64(),
+            Value::RawNumber(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_i128(&self) -> Option<i128> {
+        match self {
+            Value::Static(s) => s.as_i128(),
+            Value::RawNumber(n) => n.parse().ok(),
             _ => None,
         }
     }
 
     fn as_u64(&self) -> Option<u64> {
-        #[allow(clippy::cast_sign_loss)]
         match self {
-            Value::I64(i) if *i >= 0 => Some(*i as u64),
+            Value::Static(s) => s.as_u64(),
+            Value::RawNumber(n) => n.parse().ok(),
             _ => None,
         }
     }
 
     fn as_f64(&self) -> Option<f64> {
         match self {
-            Value::F64(i) => Some(*i),
+            Value::Static(s) => s.as_f64(),
+            Value::RawNumber(n) if is_float_literal(n) => n.parse().ok(),
             _ => None,
         }
     }
 
     fn cast_f64(&self) -> Option<f64> {
-        #[allow(clippy::cast_precision_loss)]
         match self {
-            Value::F64(i) => Some(*i),
-            Value::I64(i) => Some(*i as f64),
+            Value::Static(s) => s.cast_f64(),
+            Value::RawNumber(n) => n.parse().ok(),
             _ => None,
         }
     }
@@ -170,6 +257,15 @@ impl<'v> ValueTrait for Value<'v> {
         }
     }
 
+    fn as_object(&self) -> Option<&ObjectMap<Self::Key, Self>> {
+        match self {
+            Value::Object(m) => Some(m),
+            _ => None,
+        }
+    }
+}
+
+impl<'v> ValueTrait for Value<'v> {
     fn as_array_mut(&mut self) -> Option<&mut Vec<Value<'v>>> {
         match self {
             Value::Array(a) => Some(a),
@@ -177,17 +273,31 @@ impl<'v> ValueTrait for Value<'v> {
         }
     }
 
-    fn as_object(&self) -> Option<&HashMap<Self::Key, Self>> {
+    fn as_object_mut(&mut self) -> Option<&mut ObjectMap<Self::Key, Self>> {
         match self {
             Value::Object(m) => Some(m),
             _ => None,
         }
     }
 
-    fn as_object_mut(&mut self) -> Option<&mut HashMap<Self::Key, Self>> {
+    fn into_array(self) -> std::result::Result<Vec<Self>, Self> {
         match self {
-            Value::Object(m) => Some(m),
-            _ => None,
+            Value::Array(a) => Ok(a),
+            v => Err(v),
+        }
+    }
+
+    fn into_object(self) -> std::result::Result<ObjectMap<Self::Key, Self>, Self> {
+        match self {
+            Value::Object(m) => Ok(m),
+            v => Err(v),
+        }
+    }
+
+    fn into_string(self) -> std::result::Result<String, Self> {
+        match self {
+            Value::String(s) => Ok(s.into_owned()),
+            v => Err(v),
         }
     }
 }
@@ -195,10 +305,8 @@ impl<'v> ValueTrait for Value<'v> {
 impl<'v> fmt::Display for Value<'v> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Value::Null => write!(f, "null"),
-            Value::Bool(b) => write!(f, "{}", b),
-            Value::I64(n) => write!(f, "{}", n),
-            Value::F64(n) => write!(f, "{}", n),
+            Value::Static(s) => write!(f, "{}", s),
+            Value::RawNumber(n) => write!(f, "{}", n),
             Value::String(s) => write!(f, "{}", s),
             Value::Array(a) => write!(f, "{:?}", a),
             Value::Object(o) => write!(f, "{:?}", o),
@@ -209,14 +317,75 @@ impl<'v> fmt::Display for Value<'v> {
 impl<'v> Index<&str> for Value<'v> {
     type Output = Value<'v>;
     fn index(&self, index: &str) -> &Value<'v> {
-        static NULL: Value = Value::Null;
+        static NULL: Value = Value::Static(StaticNode::Null);
         self.get(index).unwrap_or(&NULL)
     }
 }
 
+impl<'v> IndexMut<&str> for Value<'v> {
+    /// Returns the value for `index`, inserting `null` if the key is
+    /// missing. A `null` value is turned into an empty object first.
+    ///
+    /// # Panics
+    ///
+    /// If the value is neither an object nor `null`.
+    fn index_mut(&mut self, index: &str) -> &mut Self {
+        if let Self::Static(StaticNode::Null) = self {
+            *self = Self::Object(Object::default());
+        }
+        match self {
+            Self::Object(o) => o
+                .entry(Cow::Owned(index.to_owned()))
+                .or_insert(Self::Static(StaticNode::Null)),
+            _ => panic!(
+                "cannot index into a {:?} with the key {:?}",
+                self.value_type(),
+                index
+            ),
+        }
+    }
+}
+
+/// Owning iterator over a value, see the `IntoIterator` impl of `Value`.
+pub struct IntoIter<'v>(IntoIterInner<'v>);
+
+enum IntoIterInner<'v> {
+    Array(std::vec::IntoIter<Value<'v>>),
+    Object(<Object<'v> as IntoIterator>::IntoIter),
+    Empty,
+}
+
+impl<'v> Iterator for IntoIter<'v> {
+    type Item = (Option<Cow<'v, str>>, Value<'v>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IntoIterInner::Array(a) => a.next().map(|v| (None, v)),
+            IntoIterInner::Object(o) => o.next().map(|(k, v)| (Some(k), v)),
+            IntoIterInner::Empty => None,
+        }
+    }
+}
+
+impl<'v> IntoIterator for Value<'v> {
+    type Item = (Option<Cow<'v, str>>, Value<'v>);
+    type IntoIter = IntoIter<'v>;
+
+    /// Consumes the value, yielding the elements of an array with a
+    /// `None` key or the members of an object with their key. Other values
+    /// yield nothing.
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(match self {
+            Value::Array(a) => IntoIterInner::Array(a.into_iter()),
+            Value::Object(o) => IntoIterInner::Object(o.into_iter()),
+            _ => IntoIterInner::Empty,
+        })
+    }
+}
+
 impl<'v> Default for Value<'v> {
     fn default() -> Self {
-        Value::Null
+        Value::Static(StaticNode::Null)
     }
 }
 
@@ -230,75 +399,112 @@ impl<'de> BorrowDeserializer<'de> {
 
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     pub fn parse(&mut self) -> Result<Value<'de>> {
-        match self.de.next_() {
-            b'"' => self.de.parse_str_().map(Value::from),
-            b'-' => self.de.parse_number_root(true).map(Value::from),
-            b'0'..=b'9' => self.de.parse_number_root(false).map(Value::from),
-            b'n' => Ok(Value::Null),
-            b't' => Ok(Value::Bool(true)),
-            b'f' => Ok(Value::Bool(false)),
-            b'[' => self.parse_array(),
-            b'{' => self.parse_map(),
-            _c => Err(self.de.error(ErrorType::UnexpectedCharacter)),
-        }
-    }
-
+        let mut stack: Vec<Open<Vec<Value<'de>>, Object<'de>, Cow<'de, str>>> = Vec::new();
+        loop {
+            let mut value = match self.de.next_() {
+                b'"' => Value::from(stry!(self.de.parse_str())),
+                b'n' => Value::Static(StaticNode::Null),
+                b't' => Value::Static(StaticNode::Bool(true)),
+                b'f' => Value::Static(StaticNode::Bool(false)),
+                b'-' => stry!(self.parse_number(true, stack.is_empty())),
+                b'0'..=b'9' => stry!(self.parse_number(false, stack.is_empty())),
+                b'[' => {
+                    let es = self.de.count_elements();
+                    if unlikely!(es == 0) {
+                        self.de.skip();
+                        Value::Array(Vec::new())
+                    } else {
+                        stack.push(Open::Array(Vec::with_capacity(es), es));
+                        continue;
+                    }
+                }
+                b'{' => {
+                    // We short cut for empty objects
+                    let es = self.de.count_elements();
+                    if unlikely!(es == 0) {
+                        self.de.skip();
+                        Value::Object(Object::default())
+                    } else {
+                        let key = stry!(self.parse_key());
+                        stack.push(Open::Object(parsed_object(es), es, key));
+                        continue;
+                    }
+                }
+                _c => return Err(self.de.error(ErrorType::UnexpectedCharacter)),
+            };
+            // Adds the value to the innermost open array or object, the
+            // ones that are complete become the value for their parent
+            loop {
+                value = match stack.pop() {
+                    None => return Ok(value),
+                    Some(Open::Array(mut res, left)) => {
+                        res.push(value);
+                        self.de.skip();
+                        if left > 1 {
+                            stack.push(Open::Array(res, left - 1));
+                            break;
+                        }
+                        Value::Array(res)
+                    }
+                    Some(Open::Object(mut res, left, key)) => {
+                        if self.de.options.key_case.is_some() {
+                            res.insert(key, value);
+                        } else {
+                            insert_parsed(&mut res, key, value);
+                        }
+                        self.de.skip();
+                        if left > 1 {
+                            let key = stry!(self.parse_key());
+                            stack.push(Open::Object(res, left - 1, key));
+                            break;
+                        }
+                        Value::Object(res)
+                    }
+                };
+            }
+        }
+    }
+
+    // Parses the key of the next member of an object and moves to its value
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_value(&mut self) -> Result<Value<'de>> {
-        match self.de.next_() {
-            b'"' => self.de.parse_str_().map(Value::from),
-            b'-' => self.de.parse_number_(true).map(Value::from),
-            b'0'..=b'9' => self.de.parse_number_(false).map(Value::from),
-            b'n' => Ok(Value::Null),
-            b't' => Ok(Value::Bool(true)),
-            b'f' => Ok(Value::Bool(false)),
-            b'[' => self.parse_array(),
-            b'{' => self.parse_map(),
-            _c => Err(self.de.error(ErrorType::UnexpectedCharacter)),
-        }
+    fn parse_key(&mut self) -> Result<Cow<'de, str>> {
+        self.de.skip();
+        let key = stry!(self.de.parse_str());
+        // We have to call parse short str twice since parse_short_str
+        // does not move the cursor forward
+        self.de.skip();
+        Ok(match self.de.options.key_case {
+            Some(case) => case.apply(key).into(),
+            None => key.into(),
+        })
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_array(&mut self) -> Result<Value<'de>> {
-        let es = self.de.count_elements();
-        if unlikely!(es == 0) {
-            self.de.skip();
-            return Ok(Value::Array(Vec::new()));
-        }
-        let mut res = Vec::with_capacity(es);
-
-        for _i in 0..es {
-            res.push(stry!(self.parse_value()));
-            self.de.skip();
-        }
-        Ok(Value::Array(res))
-    }
-
-    #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_map(&mut self) -> Result<Value<'de>> {
-        // We short cut for empty arrays
-        let es = self.de.count_elements();
-
-        if unlikely!(es == 0) {
-            self.de.skip();
-            return Ok(Value::Object(Object::new()));
-        }
-
-        let mut res = Object::with_capacity(es);
-
-        // Since we checked if it's empty we know that we at least have one
-        // element so we eat this
-
-        for _ in 0..es {
-            self.de.skip();
-            let key = stry!(self.de.parse_str_());
-            // We have to call parse short str twice since parse_short_str
-            // does not move the cursor forward
-            self.de.skip();
-            res.insert_nocheck(key.into(), stry!(self.parse_value()));
-            self.de.skip();
-        }
-        Ok(Value::Object(res))
+    fn parse_number(&mut self, minus: bool, root: bool) -> Result<Value<'de>> {
+        if unlikely!(self.de.options.lossless_numbers) {
+            return self.parse_raw_number(minus, root);
+        }
+        let n = if root {
+            self.de.parse_number_root(minus)
+        } else {
+            self.de.parse_number(minus)
+        };
+        match n {
+            Err(ref e)
+                if e.error() == &ErrorType::Overflow
+                    && self.de.options.big_numbers == BigNumberBehavior::Raw =>
+            {
+                self.parse_raw_number(minus, root)
+            }
+            n => n.map(Value::from),
+        }
+    }
+
+    #[inline(never)]
+    fn parse_raw_number(&mut self, minus: bool, root: bool) -> Result<Value<'de>> {
+        self.de
+            .parse_number_raw(minus, root)
+            .map(|n| Value::RawNumber(Cow::Borrowed(n)))
     }
 }
 
@@ -598,7 +804,7 @@ mod test {
 
     #[test]
     fn conversions_object() {
-        let v = Value::from(Object::new());
+        let v = Value::from(Object::default());
         assert!(v.is_object());
         assert_eq!(v.value_type(), ValueType::Object);
     }
@@ -613,10 +819,10 @@ mod test {
     use proptest::prelude::*;
     fn arb_value() -> BoxedStrategy<Value<'static>> {
         let leaf = prop_oneof![
-            Just(Value::Null),
-            any::<bool>().prop_map(Value::Bool),
-            any::<i64>().prop_map(Value::I64),
-            any::<f64>().prop_map(Value::F64),
+            Just(Value::Static(StaticNode::Null)),
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            any::<f64>().prop_map(Value::from),
             ".*".prop_map(Value::from),
         ];
         leaf.prop_recursive(
@@ -746,4 +952,51 @@ mod test {
         let v: Value = false.into();
         assert_eq!(v, false);
     }
+
+    #[test]
+    fn owned_round_trip() {
+        let mut d = br#"{"a": ["b", 1, 2.5, null, {"c\n": true}]}"#.to_vec();
+        let v = to_value(&mut d).expect("valid");
+        let o = v.clone().into_owned();
+        assert_eq!(OwnedValue::from(&v), o);
+        {
+            let b = o.to_borrowed();
+            assert_eq!(b, v);
+            match b["a"].get_idx(0) {
+                Some(Value::String(Cow::Borrowed(s))) => assert_eq!(*s, "b"),
+                other => panic!("expected a borrowed string, got {:?}", other),
+            }
+        }
+        assert_eq!(o.into_borrowed(), v);
+    }
+
+    #[test]
+    fn entry() {
+        let mut v = Value::from(Object::default());
+        for k in &["x", "y", "x"] {
+            v.entry((*k).into())
+                .expect("object")
+                .and_modify(|n| *n = Value::from(n.as_i64().unwrap_or(0) + 1))
+                .or_insert_with(|| Value::from(1));
+        }
+        assert_eq!(v["x"], 2);
+        assert_eq!(v["y"], 1);
+        assert!(Value::Static(StaticNode::Null).entry("x".into()).is_none());
+    }
+
+    #[test]
+    fn typed_getters() {
+        let mut d = br#"{"a": [1], "o": {"b": -2}, "f": 1.5, "s": "t"}"#.to_vec();
+        let v = to_value(&mut d).expect("valid");
+        assert_eq!(v.get_array("a").map(Vec::len), Some(1));
+        assert_eq!(
+            v.get_object("o").and_then(|o| o.get("b")),
+            Some(&Value::from(-2))
+        );
+        assert_eq!(v.get_f64("f"), Some(1.5));
+        assert_eq!(v.get_str("s"), Some("t"));
+        assert_eq!(v.get_i64("s"), None);
+        assert_eq!(v.get_u64("missing"), None);
+        assert_eq!(Value::Static(StaticNode::Null).get_bool("a"), None);
+    }
 }
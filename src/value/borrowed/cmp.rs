@@ -1,18 +1,20 @@
 use super::Value;
-use crate::{OwnedValue, ValueTrait};
-use float_cmp::approx_eq;
+use crate::value::raw_number_eq;
+use crate::{OwnedValue, StaticNode, ValueAccess};
 
 impl<'a> PartialEq for Value<'a> {
     fn eq(&self, other: &Self) -> bool {
         #[allow(clippy::default_trait_access)]
         match (self, other) {
-            (Self::Null, Self::Null) => true,
-            (Self::Bool(v1), Self::Bool(v2)) => v1.eq(v2),
-            (Self::I64(v1), Self::I64(v2)) => v1.eq(v2),
-            (Self::F64(v1), Self::F64(v2)) => approx_eq!(f64, *v1, *v2),
+            (Self::Static(StaticNode::Null), Self::Static(StaticNode::Null)) => true,
+            (Self::Static(StaticNode::Bool(v1)), Self::Static(StaticNode::Bool(v2))) => v1.eq(v2),
+            (Self::Static(StaticNode::Number(v1)), Self::Static(StaticNode::Number(v2))) => {
+                v1.eq(v2)
+            }
             (Self::String(v1), Self::String(v2)) => v1.eq(v2),
             (Self::Array(v1), Self::Array(v2)) => v1.eq(v2),
             (Self::Object(v1), Self::Object(v2)) => v1.eq(v2),
+            (Self::RawNumber(_), _) | (_, Self::RawNumber(_)) => raw_number_eq(self, other),
             _ => false,
         }
     }
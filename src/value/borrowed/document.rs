@@ -0,0 +1,92 @@
+use super::{to_value_with_options, Value};
+use crate::{OwnedValue, ParserOptions, Result};
+use std::fmt;
+
+/// A parsed document that owns its input together with the borrowed value
+/// referencing it.
+///
+/// This gives the performance of parsing into a `BorrowedValue` without
+/// the lifetime tying the value to the input buffer, so a document can be
+/// stored in a struct or sent to another thread.
+///
+/// ```
+/// use simd_json::{Document, ValueAccess};
+/// let doc = Document::parse(br#"{"name": "simd"}"#.to_vec()).expect("valid json");
+/// let name = std::thread::spawn(move || {
+///     doc.value().get("name").and_then(ValueAccess::as_str).map(String::from)
+/// })
+/// .join()
+/// .unwrap();
+/// assert_eq!(name.as_deref(), Some("simd"));
+/// ```
+pub struct Document {
+    // `value` borrows from the heap buffer of `input`, it has to be declared
+    // first so it is dropped first. The buffer is never touched again after
+    // parsing and doesn't move when the document does.
+    value: Value<'static>,
+    input: Vec<u8>,
+}
+
+impl Document {
+    /// Parses `input` into a document using the default `ParserOptions`
+    pub fn parse(input: Vec<u8>) -> Result<Self> {
+        Self::parse_with_options(input, &ParserOptions::default())
+    }
+
+    /// Parses `input` into a document using the given `ParserOptions`
+    pub fn parse_with_options(mut input: Vec<u8>, options: &ParserOptions) -> Result<Self> {
+        // The value can only be reached through `value`, which ties it to
+        // the lifetime of the document again.
+        let slice: &'static mut [u8] =
+            unsafe { std::slice::from_raw_parts_mut(input.as_mut_ptr(), input.len()) };
+        let value = to_value_with_options(slice, options)?;
+        Ok(Self { value, input })
+    }
+
+    /// The value of the document
+    pub fn value(&self) -> &Value<'_> {
+        &self.value
+    }
+
+    /// Size of the input buffer in bytes
+    pub fn input_len(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Converts the document into an owned value, copying all strings
+    pub fn into_owned_value(self) -> OwnedValue {
+        let Self { value, input } = self;
+        let owned = value.into_owned();
+        drop(input);
+        owned
+    }
+}
+
+impl fmt::Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Document").field(&self.value).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValueAccess;
+
+    struct Holder {
+        doc: Document,
+    }
+
+    #[test]
+    fn owns_input() {
+        let holder = Holder {
+            doc: Document::parse(br#"{"a": ["x\ny", 1]}"#.to_vec()).expect("valid"),
+        };
+        let moved = Box::new(holder);
+        let a = moved.doc.value().get("a").expect("a");
+        assert_eq!(a.get_idx(0).and_then(ValueAccess::as_str), Some("x\ny"));
+        assert_eq!(moved.doc.value(), &json!({"a": ["x\ny", 1]}));
+        assert_eq!(moved.doc.into_owned_value(), json!({"a": ["x\ny", 1]}));
+        assert!(Document::parse(b"[1,".to_vec()).is_err());
+    }
+}
@@ -1,33 +1,54 @@
 use super::{Object, Value};
-use crate::numberparse::Number;
-use crate::OwnedValue;
+use crate::value::owned;
+use crate::{Number, OwnedValue, StaticNode, ValueAccess};
 use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::iter::FromIterator;
 
+impl<'a> From<StaticNode> for Value<'a> {
+    #[inline]
+    fn from(s: StaticNode) -> Self {
+        Value::Static(s)
+    }
+}
+
 impl<'a> From<Number> for Value<'a> {
     #[inline]
     fn from(n: Number) -> Self {
-        match n {
-            Number::F64(n) => Value::F64(n),
-            Number::I64(n) => Value::I64(n),
-        }
+        Value::Static(n.into())
     }
 }
 
 impl<'a> From<OwnedValue> for Value<'a> {
     fn from(b: OwnedValue) -> Self {
         match b {
-            OwnedValue::Null => Value::Null,
-            OwnedValue::Bool(b) => Value::Bool(b),
-            OwnedValue::F64(f) => Value::F64(f),
-            OwnedValue::I64(i) => Value::I64(i),
-            OwnedValue::String(s) => Value::from(s.to_string()),
+            OwnedValue::Static(s) => Value::Static(s),
+            OwnedValue::RawNumber(n) => Value::RawNumber(Cow::Owned(n)),
+            OwnedValue::String(s) => Value::String(Cow::Owned(owned::str_into_string(s))),
             OwnedValue::Array(a) => {
                 Value::Array(a.into_iter().map(|v| v.into()).collect::<Vec<Value>>())
             }
-            OwnedValue::Object(m) => {
-                Value::Object(m.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
-            }
+            OwnedValue::Object(m) => Value::Object(
+                m.into_iter()
+                    .map(|(k, v)| (Cow::Owned(owned::key_into_string(k)), v.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedValue> for Value<'a> {
+    fn from(b: &'a OwnedValue) -> Self {
+        match b {
+            OwnedValue::Static(s) => Value::Static(*s),
+            OwnedValue::RawNumber(n) => Value::RawNumber(Cow::Borrowed(n)),
+            OwnedValue::String(s) => Value::String(Cow::Borrowed(s)),
+            OwnedValue::Array(a) => Value::Array(a.iter().map(Value::from).collect()),
+            OwnedValue::Object(m) => Value::Object(
+                m.iter()
+                    .map(|(k, v)| (Cow::Borrowed(&**k), Value::from(v)))
+                    .collect(),
+            ),
         }
     }
 }
@@ -57,12 +78,12 @@ impl<'v> From<String> for Value<'v> {
 impl<'v> From<bool> for Value<'v> {
     #[inline]
     fn from(b: bool) -> Self {
-        Value::Bool(b)
+        Value::Static(StaticNode::Bool(b))
     }
 }
 impl<'v> From<()> for Value<'v> {
     fn from(_b: ()) -> Self {
-        Value::Null
+        Value::Static(StaticNode::Null)
     }
 }
 
@@ -70,28 +91,28 @@ impl<'v> From<()> for Value<'v> {
 impl<'v> From<i8> for Value<'v> {
     #[inline]
     fn from(i: i8) -> Self {
-        Value::I64(i64::from(i))
+        Value::Static(i.into())
     }
 }
 
 impl<'v> From<i16> for Value<'v> {
     #[inline]
     fn from(i: i16) -> Self {
-        Value::I64(i64::from(i))
+        Value::Static(i.into())
     }
 }
 
 impl<'v> From<i32> for Value<'v> {
     #[inline]
     fn from(i: i32) -> Self {
-        Value::I64(i64::from(i))
+        Value::Static(i.into())
     }
 }
 
 impl<'v> From<i64> for Value<'v> {
     #[inline]
     fn from(i: i64) -> Self {
-        Value::I64(i)
+        Value::Static(i.into())
     }
 }
 
@@ -99,36 +120,34 @@ impl<'v> From<i64> for Value<'v> {
 impl<'v> From<u8> for Value<'v> {
     #[inline]
     fn from(i: u8) -> Self {
-        Value::I64(i64::from(i))
+        Value::Static(i.into())
     }
 }
 
 impl<'v> From<u16> for Value<'v> {
     #[inline]
     fn from(i: u16) -> Self {
-        Value::I64(i64::from(i))
+        Value::Static(i.into())
     }
 }
 
 impl<'v> From<u32> for Value<'v> {
     #[inline]
     fn from(i: u32) -> Self {
-        Value::I64(i64::from(i))
+        Value::Static(i.into())
     }
 }
 
 impl<'v> From<u64> for Value<'v> {
     #[inline]
     fn from(i: u64) -> Self {
-        #[allow(clippy::cast_possible_wrap)]
-        Value::I64(i as i64)
+        Value::Static(i.into())
     }
 }
 
 impl<'v> From<usize> for Value<'v> {
     fn from(i: usize) -> Self {
-        #[allow(clippy::cast_possible_wrap)]
-        Self::I64(i as i64)
+        Self::Static(i.into())
     }
 }
 
@@ -136,14 +155,14 @@ impl<'v> From<usize> for Value<'v> {
 impl<'v> From<f32> for Value<'v> {
     #[inline]
     fn from(f: f32) -> Self {
-        Value::F64(f64::from(f))
+        Value::Static(f.into())
     }
 }
 
 impl<'v> From<f64> for Value<'v> {
     #[inline]
     fn from(f: f64) -> Self {
-        Value::F64(f)
+        Value::Static(f.into())
     }
 }
 
@@ -156,6 +175,46 @@ where
     }
 }
 
+impl<'v, S> From<&[S]> for Value<'v>
+where
+    S: Clone,
+    Value<'v>: From<S>,
+{
+    fn from(v: &[S]) -> Self {
+        Value::Array(v.iter().cloned().map(Value::from).collect())
+    }
+}
+
+impl<'v, S> From<Option<S>> for Value<'v>
+where
+    Value<'v>: From<S>,
+{
+    /// `None` becomes `null`
+    fn from(v: Option<S>) -> Self {
+        v.map_or(Value::Static(StaticNode::Null), Value::from)
+    }
+}
+
+impl<'v, K, V, H> From<HashMap<K, V, H>> for Value<'v>
+where
+    K: Into<Cow<'v, str>>,
+    V: Into<Value<'v>>,
+{
+    fn from(m: HashMap<K, V, H>) -> Self {
+        m.into_iter().collect()
+    }
+}
+
+impl<'v, K, V> From<BTreeMap<K, V>> for Value<'v>
+where
+    K: Into<Cow<'v, str>>,
+    V: Into<Value<'v>>,
+{
+    fn from(m: BTreeMap<K, V>) -> Self {
+        m.into_iter().collect()
+    }
+}
+
 impl<'v, V: Into<Value<'v>>> FromIterator<V> for Value<'v> {
     fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
         Value::Array(iter.into_iter().map(Into::into).collect())
@@ -172,6 +231,48 @@ impl<'v, K: Into<Cow<'v, str>>, V: Into<Value<'v>>> FromIterator<(K, V)> for Val
     }
 }
 
+/// Appends to an array, a `null` value is turned into an empty array
+/// first.
+///
+/// # Panics
+///
+/// If the value is neither an array nor `null`.
+impl<'v, V: Into<Value<'v>>> Extend<V> for Value<'v> {
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+        if let Value::Static(StaticNode::Null) = self {
+            *self = Value::Array(Vec::new());
+        }
+        match self {
+            Value::Array(a) => a.extend(iter.into_iter().map(Into::into)),
+            _ => panic!("cannot extend a {:?} with values", self.value_type()),
+        }
+    }
+}
+
+/// Inserts into an object, a `null` value is turned into an empty object
+/// first.
+///
+/// # Panics
+///
+/// If the value is neither an object nor `null`.
+impl<'v, K: Into<Cow<'v, str>>, V: Into<Value<'v>>> Extend<(K, V)> for Value<'v> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        if let Value::Static(StaticNode::Null) = self {
+            *self = Value::Object(Object::default());
+        }
+        match self {
+            Value::Object(o) => {
+                for (k, v) in iter {
+                    o.insert(Into::into(k), Into::into(v));
+                }
+            }
+            _ => panic!("cannot extend a {:?} with members", self.value_type()),
+        }
+    }
+}
+
+// a `BTreeMap` object is covered by the impl for any `BTreeMap`
+#[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
 impl<'v> From<Object<'v>> for Value<'v> {
     fn from(v: Object<'v>) -> Self {
         Self::Object(v)
@@ -7,7 +7,9 @@
 use super::{Object, Value};
 use crate::stry;
 use crate::value::generator::*;
-use crate::value::ValueTrait;
+use crate::value::{StaticNode, ValueAccess, ValueTrait};
+use crate::{RedactMode, SerializeOptions, Truncation};
+use std::fmt;
 use std::io;
 use std::io::Write;
 
@@ -28,6 +30,16 @@ impl<'value> Value<'value> {
         g.consume()
     }
 
+    /// Encodes the value into it's JSON representation appending it to
+    /// `buf`. The buffer is not cleared first, so one buffer can be reused
+    /// (after calling `clear` on it) for many values without allocating a
+    /// new `String` each time.
+    pub fn encode_into(&self, buf: &mut Vec<u8>) {
+        let mut g = DumpGenerator::with_buffer(std::mem::take(buf));
+        let _ = g.write_json(self);
+        *buf = g.into_buffer();
+    }
+
     /// Encodes the value into it's JSON representation as a string (pretty printed)
     #[deprecated(since = "0.1.21", note = "Please use encode instead")]
     pub fn to_string_pp(&self) -> String {
@@ -58,6 +70,46 @@ impl<'value> Value<'value> {
         let mut g = PrettyWriterGenerator::new(w, 2);
         g.write_json(self)
     }
+
+    /// Encodes the value into it's JSON representation into a `fmt::Write`
+    /// like a `String` or a `fmt::Formatter`, for `io::Write` targets use
+    /// `write`.
+    pub fn write_into<W>(&self, w: &mut W) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let mut w = FmtWriter::new(w);
+        let mut g = WriterGenerator::new(&mut w);
+        if g.write_json(self).is_err() {
+            return Err(fmt::Error);
+        }
+        w.finish().map_err(|_| fmt::Error)
+    }
+
+    /// Encodes the value into it's JSON representation as a string, formatted
+    /// according to `options`
+    pub fn encode_with_options(&self, options: &SerializeOptions) -> String {
+        let mut code = Vec::with_capacity(1024);
+        let _ = self.write_with_options(&mut code, options);
+        // Original strings were unicode, numbers are all ASCII,
+        // therefore this is safe.
+        unsafe { String::from_utf8_unchecked(code) }
+    }
+
+    /// Encodes the value into it's JSON representation into a Writer, formatted
+    /// according to `options`
+    pub fn write_with_options<'writer, W>(
+        &self,
+        w: &mut W,
+        options: &SerializeOptions,
+    ) -> io::Result<()>
+    where
+        W: 'writer + Write,
+    {
+        let mut g = OptionsGenerator::new(w, options.clone());
+        stry!(g.write_json(self));
+        g.finish()
+    }
 }
 
 trait Generator: BaseGenerator {
@@ -66,26 +118,60 @@ trait Generator: BaseGenerator {
 
     #[inline(always)]
     fn write_object(&mut self, object: &Object) -> io::Result<()> {
+        let limits = self.limits();
+        if !object.is_empty() && limits.max_depth.map_or(false, |max| self.depth() >= max) {
+            return self.write_string(&format!("…({} members)", object.len()));
+        }
+        let max_items = limits.max_items.unwrap_or_else(usize::max_value);
         stry!(self.write_char(b'{'));
-        let mut iter = object.iter();
+        let mut written = 0;
+        let mut elided = 0;
 
-        if let Some((key, value)) = iter.next() {
-            self.indent();
+        for (key, value) in object.iter() {
+            let redact = self.redact(key);
+            if redact == Some(RedactMode::Drop) {
+                continue;
+            }
+            if written == max_items {
+                elided += 1;
+                continue;
+            }
+            if written == 0 {
+                self.indent();
+            } else {
+                stry!(self.write_comma());
+            }
+            written += 1;
             stry!(self.new_line());
-            stry!(self.write_string(key));
-            stry!(self.write_min(b": ", b':'));
-            stry!(self.write_json(value));
-        } else {
-            stry!(self.write_char(b'}'));
-            return Ok(());
+            match self.key_case() {
+                Some(case) => stry!(self.write_string(&case.apply(key))),
+                None => stry!(self.write_string(key)),
+            }
+            stry!(self.write_colon());
+            if redact.is_some() {
+                stry!(self.write(b"\"***\""));
+            } else {
+                self.enter_key(key);
+                stry!(self.write_json(value));
+                self.leave();
+            }
         }
 
-        for (key, value) in iter {
-            stry!(self.write_char(b','));
+        if elided > 0 {
+            if written == 0 {
+                self.indent();
+            } else {
+                stry!(self.write_comma());
+            }
+            written += 1;
             stry!(self.new_line());
-            stry!(self.write_string(key));
-            stry!(self.write_min(b": ", b':'));
-            stry!(self.write_json(value));
+            stry!(self.write_string("…"));
+            stry!(self.write_colon());
+            stry!(self.write_string(&format!("(+{} members)", elided)));
+        }
+
+        if written == 0 {
+            return self.write_char(b'}');
         }
 
         self.dedent();
@@ -96,29 +182,64 @@ trait Generator: BaseGenerator {
     #[inline(always)]
     fn write_json(&mut self, json: &Value) -> io::Result<()> {
         match *json {
-            Value::Null => self.write(b"null"),
-            Value::String(ref string) => self.write_string(string),
-            Value::I64(number) => self.write_int(number),
-            Value::F64(number) => self.write_float(number),
-            Value::Bool(true) => self.write(b"true"),
-            Value::Bool(false) => self.write(b"false"),
+            Value::Static(StaticNode::Null) => self.write(b"null"),
+            Value::String(ref string) => self.write_string_limited(string),
+            Value::Static(StaticNode::Number(number)) => self.write_number(number),
+            Value::RawNumber(ref number) => self.write(number.as_bytes()),
+            Value::Static(StaticNode::Bool(true)) => self.write(b"true"),
+            Value::Static(StaticNode::Bool(false)) => self.write(b"false"),
             Value::Array(ref array) => {
+                let limits = self.limits();
+                if !array.is_empty() && limits.max_depth.map_or(false, |max| self.depth() >= max) {
+                    return self.write_string(&format!("…({} items)", array.len()));
+                }
+                let shown = limits
+                    .max_items
+                    .map_or(array.len(), |max| max.min(array.len()));
+                let marker = if shown < array.len() {
+                    Some(format!("…(+{} items)", array.len() - shown))
+                } else {
+                    None
+                };
+                let array = &array[..shown];
+                if let Some(options) = self.scalar_array_options() {
+                    if !array.iter().any(|v| v.is_array() || v.is_object()) {
+                        let mut items: Vec<Vec<u8>> = array
+                            .iter()
+                            .map(|v| v.encode_with_options(&options).into_bytes())
+                            .collect();
+                        if let Some(marker) = marker {
+                            let options = SerializeOptions {
+                                truncate: Truncation::default(),
+                                ..options
+                            };
+                            let marker = Value::from(marker);
+                            items.push(marker.encode_with_options(&options).into_bytes());
+                        }
+                        return self.write_scalar_array(&items);
+                    }
+                }
                 stry!(self.write_char(b'['));
-                let mut iter = array.iter();
+                if array.is_empty() && marker.is_none() {
+                    return self.write_char(b']');
+                }
 
-                if let Some(item) = iter.next() {
-                    self.indent();
+                self.indent();
+                for (i, item) in array.iter().enumerate() {
+                    if i > 0 {
+                        stry!(self.write_comma());
+                    }
                     stry!(self.new_line());
+                    self.enter_index(i);
                     stry!(self.write_json(item));
-                } else {
-                    stry!(self.write_char(b']'));
-                    return Ok(());
+                    self.leave();
                 }
-
-                for item in iter {
-                    stry!(self.write_char(b','));
+                if let Some(marker) = marker {
+                    if !array.is_empty() {
+                        stry!(self.write_comma());
+                    }
                     stry!(self.new_line());
-                    stry!(self.write_json(item));
+                    stry!(self.write_string(&marker));
                 }
 
                 self.dedent();
@@ -156,20 +277,29 @@ where
     type V = Value<'value>;
 }
 
+impl<'w, 'value, W> Generator for OptionsGenerator<'w, W, Value<'value>>
+where
+    W: Write,
+{
+    type T = ColumnWriter<'w, W>;
+    type V = Value<'value>;
+}
+
 #[cfg(test)]
 mod test {
     use super::Value;
+    use crate::StaticNode;
     #[test]
     fn null() {
-        assert_eq!(Value::Null.encode(), "null")
+        assert_eq!(Value::Static(StaticNode::Null).encode(), "null")
     }
     #[test]
     fn bool_true() {
-        assert_eq!(Value::Bool(true).encode(), "true")
+        assert_eq!(Value::Static(StaticNode::Bool(true)).encode(), "true")
     }
     #[test]
     fn bool_false() {
-        assert_eq!(Value::Bool(false).encode(), "false")
+        assert_eq!(Value::Static(StaticNode::Bool(false)).encode(), "false")
     }
     fn assert_str(from: &str, to: &str) {
         assert_eq!(Value::String(from.into()).encode(), to)
@@ -193,4 +323,12 @@ mod test {
             r#""this is a test a \\\"long\\\" test that should span the 32 byte boundary""#,
         );
     }
+
+    #[test]
+    fn encode_into() {
+        let mut buf = Vec::new();
+        Value::from("a\"b").encode_into(&mut buf);
+        Value::Static(StaticNode::Null).encode_into(&mut buf);
+        assert_eq!(buf, br#""a\"b"null"#.to_vec());
+    }
 }
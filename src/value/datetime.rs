@@ -0,0 +1,225 @@
+// Parsing of RFC 3339 timestamps as used by the datetime accessors
+// of `ValueTrait`.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Points in time `ValueAccess::as_datetime` can return
+pub trait FromTimestampMillis: Sized {
+    /// Converts milliseconds since the unix epoch, `None` if they are out
+    /// of the range of the type
+    fn from_timestamp_millis(millis: i64) -> Option<Self>;
+}
+
+impl FromTimestampMillis for SystemTime {
+    #[allow(clippy::cast_sign_loss)]
+    fn from_timestamp_millis(millis: i64) -> Option<Self> {
+        let d = Duration::from_millis(millis.wrapping_abs() as u64);
+        if millis >= 0 {
+            UNIX_EPOCH.checked_add(d)
+        } else {
+            UNIX_EPOCH.checked_sub(d)
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromTimestampMillis for chrono::DateTime<chrono::Utc> {
+    fn from_timestamp_millis(millis: i64) -> Option<Self> {
+        chrono::DateTime::from_timestamp_millis(millis)
+    }
+}
+
+#[cfg(feature = "time")]
+impl FromTimestampMillis for time::OffsetDateTime {
+    fn from_timestamp_millis(millis: i64) -> Option<Self> {
+        time::OffsetDateTime::from_unix_timestamp_nanos(i128::from(millis) * 1_000_000).ok()
+    }
+}
+
+#[inline]
+fn digits(s: &[u8], start: usize, len: usize) -> Option<i64> {
+    s.get(start..start + len)?.iter().try_fold(0, |acc, c| {
+        if c.is_ascii_digit() {
+            Some(acc * 10 + i64::from(c - b'0'))
+        } else {
+            None
+        }
+    })
+}
+
+#[inline]
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 for a date in the proleptic gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Parses a RFC 3339 timestamp like `2019-10-12T07:20:50.52Z` into
+/// milliseconds since the unix epoch, fractions below a millisecond
+/// are truncated.
+pub(crate) fn parse_rfc3339_millis(s: &str) -> Option<i64> {
+    let s = s.as_bytes();
+    if s.len() < 20
+        || s[4] != b'-'
+        || s[7] != b'-'
+        || !(s[10] == b'T' || s[10] == b't' || s[10] == b' ')
+        || s[13] != b':'
+        || s[16] != b':'
+    {
+        return None;
+    }
+    let year = digits(s, 0, 4)?;
+    let month = digits(s, 5, 2)?;
+    let day = digits(s, 8, 2)?;
+    let hour = digits(s, 11, 2)?;
+    let minute = digits(s, 14, 2)?;
+    // 60 is allowed for leap seconds
+    let second = digits(s, 17, 2)?;
+    if !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut i = 19;
+    let mut millis = 0;
+    if s[i] == b'.' {
+        i += 1;
+        let start = i;
+        while s.get(i).map_or(false, u8::is_ascii_digit) {
+            if i - start < 3 {
+                millis = millis * 10 + i64::from(s[i] - b'0');
+            }
+            i += 1;
+        }
+        match i - start {
+            0 => return None,
+            1 => millis *= 100,
+            2 => millis *= 10,
+            _ => (),
+        }
+    }
+
+    let offset = match s.get(i..)? {
+        b"Z" | b"z" => 0,
+        [sign @ b'+', ..] | [sign @ b'-', ..] if s.len() == i + 6 && s[i + 3] == b':' => {
+            let h = digits(s, i + 1, 2)?;
+            let m = digits(s, i + 4, 2)?;
+            if h > 23 || m > 59 {
+                return None;
+            }
+            let offset = (h * 60 + m) * 60;
+            if *sign == b'-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    let seconds = days * 86_400 + hour * 3_600 + minute * 60 + second - offset;
+    Some(seconds * 1_000 + millis)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rfc3339() {
+        assert_eq!(parse_rfc3339_millis("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_rfc3339_millis("2019-10-12T07:20:50.52Z"),
+            Some(1_570_864_850_520)
+        );
+        assert_eq!(
+            parse_rfc3339_millis("2019-10-12t09:20:50.5209+02:00"),
+            Some(1_570_864_850_520)
+        );
+        assert_eq!(
+            parse_rfc3339_millis("2000-02-29 00:00:00-01:30"),
+            Some(951_787_800_000)
+        );
+        assert_eq!(parse_rfc3339_millis("1969-12-31T23:59:59.9Z"), Some(-100));
+        assert_eq!(parse_rfc3339_millis("2001-02-29T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339_millis("2019-10-12T07:20:50."), None);
+        assert_eq!(parse_rfc3339_millis("2019-10-12T07:20:50"), None);
+        assert_eq!(parse_rfc3339_millis("2019-10-12T07:20:50+0200"), None);
+        assert_eq!(parse_rfc3339_millis("2019-13-12T07:20:50Z"), None);
+        assert_eq!(parse_rfc3339_millis("2019-10-12T07:20:50Zx"), None);
+    }
+
+    #[test]
+    fn system_time() {
+        use crate::{OwnedValue, ValueAccess};
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+        let t: Option<SystemTime> = OwnedValue::from("2019-10-12T07:20:50.52Z").as_datetime();
+        assert_eq!(
+            t,
+            Some(UNIX_EPOCH + Duration::from_millis(1_570_864_850_520))
+        );
+        let t: Option<SystemTime> = OwnedValue::from(-100).as_datetime();
+        assert_eq!(t, UNIX_EPOCH.checked_sub(Duration::from_millis(100)));
+        assert_eq!(OwnedValue::from("nope").as_datetime::<SystemTime>(), None);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono() {
+        use crate::{OwnedValue, ValueAccess};
+        let d: Option<chrono::DateTime<chrono::Utc>> =
+            OwnedValue::from("2019-10-12T09:20:50.52+02:00").as_datetime();
+        assert_eq!(
+            d,
+            chrono::DateTime::from_timestamp(1_570_864_850, 520_000_000)
+        );
+        let d: Option<chrono::DateTime<chrono::Utc>> = OwnedValue::from(-100).as_datetime();
+        assert_eq!(d.map(|d| d.timestamp_millis()), Some(-100));
+        assert_eq!(
+            OwnedValue::from("nope").as_datetime::<chrono::DateTime<chrono::Utc>>(),
+            None
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time() {
+        use crate::{OwnedValue, ValueAccess};
+        let d: time::OffsetDateTime = OwnedValue::from("2019-10-12T09:20:50.52+02:00")
+            .as_datetime()
+            .expect("datetime");
+        assert_eq!(d.unix_timestamp(), 1_570_864_850);
+        assert_eq!(d.millisecond(), 520);
+        assert_eq!(d.offset(), time::UtcOffset::UTC);
+        let d: Option<time::OffsetDateTime> = OwnedValue::from(-100).as_datetime();
+        assert_eq!(d.map(|d| d.unix_timestamp_nanos()), Some(-100_000_000));
+        assert_eq!(
+            OwnedValue::from("nope").as_datetime::<time::OffsetDateTime>(),
+            None
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{BorrowedValue, OwnedValue};
+use arbitrary::{Arbitrary, Result, Unstructured};
+use std::iter::FromIterator;
+
+// Bounds keeping generated values small enough to be useful for fuzzing
+const MAX_DEPTH: usize = 8;
+const MAX_ITEMS: usize = 16;
+
+fn value<'a, V>(u: &mut Unstructured<'a>, depth: usize) -> Result<V>
+where
+    V: From<()> + From<bool> + From<i64> + From<f64> + From<&'a str>,
+    V: FromIterator<V> + FromIterator<(&'a str, V)>,
+{
+    // containers are only generated while below the maximum depth
+    let kinds = if depth < MAX_DEPTH { 6 } else { 4 };
+    Ok(match u.int_in_range(0..=kinds)? {
+        0 => V::from(()),
+        1 => V::from(bool::arbitrary(u)?),
+        2 => V::from(i64::arbitrary(u)?),
+        3 => {
+            // JSON can't represent NaN or infinities
+            let f = f64::arbitrary(u)?;
+            V::from(if f.is_finite() { f } else { 0.0 })
+        }
+        4 => V::from(<&str>::arbitrary(u)?),
+        5 => {
+            let len = u.int_in_range(0..=MAX_ITEMS)?;
+            let items: Result<Vec<V>> = (0..len).map(|_| value(u, depth + 1)).collect();
+            items?.into_iter().collect()
+        }
+        _ => {
+            let len = u.int_in_range(0..=MAX_ITEMS)?;
+            let members: Result<Vec<(&str, V)>> = (0..len)
+                .map(|_| Ok((<&str>::arbitrary(u)?, value(u, depth + 1)?)))
+                .collect();
+            members?.into_iter().collect()
+        }
+    })
+}
+
+/// Generates values nested at most 8 levels deep with at most 16 elements
+/// per array or object, floats are always finite.
+impl<'a> Arbitrary<'a> for OwnedValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        value(u, 0)
+    }
+}
+
+/// Generates values the same way as for `OwnedValue`, strings borrow from
+/// the fuzzer input.
+impl<'a> Arbitrary<'a> for BorrowedValue<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        value(u, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BorrowedValue, OwnedValue, ValueAccess};
+
+    fn depth<V: ValueAccess>(v: &V) -> usize {
+        use crate::{ArrayAccess, ObjectAccess};
+        if let Some(a) = v.as_array() {
+            1 + a.iter().map(depth).max().unwrap_or(0)
+        } else if let Some(o) = v.as_object() {
+            1 + o.iter().map(|(_, v)| depth(v)).max().unwrap_or(0)
+        } else {
+            0
+        }
+    }
+
+    #[test]
+    fn arbitrary_values() {
+        let data: Vec<u8> = (0..4096_u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let owned = OwnedValue::arbitrary(&mut u).unwrap();
+            assert!(depth(&owned) <= MAX_DEPTH);
+            let mut encoded = owned.encode().into_bytes();
+            assert_eq!(crate::to_owned_value(&mut encoded).unwrap(), owned);
+        }
+        let mut u = Unstructured::new(&data);
+        let borrowed = BorrowedValue::arbitrary(&mut u).unwrap();
+        assert!(depth(&borrowed) <= MAX_DEPTH);
+    }
+}
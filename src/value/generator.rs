@@ -4,7 +4,10 @@
 //
 // https://github.com/maciejhirsz/json-rust/blob/master/src/codegen.rs
 
+use crate::value::number::N;
 use crate::value::ValueTrait;
+use std::borrow::Cow;
+use std::fmt;
 use std::io;
 use std::io::Write;
 use std::marker::PhantomData;
@@ -81,6 +84,83 @@ pub trait BaseGenerator {
     #[inline(always)]
     fn dedent(&mut self) {}
 
+    #[inline(always)]
+    fn write_colon(&mut self) -> io::Result<()> {
+        self.write_min(b": ", b':')
+    }
+
+    #[inline(always)]
+    fn write_comma(&mut self) -> io::Result<()> {
+        self.write_char(b',')
+    }
+
+    /// If arrays that only contain scalars should be passed to
+    /// `write_scalar_array` in their encoded form, returns the options
+    /// to encode the items with.
+    #[inline(always)]
+    fn scalar_array_options(&self) -> Option<SerializeOptions> {
+        None
+    }
+
+    /// If the member `key` of the object being written is redacted
+    #[inline(always)]
+    fn redact(&self, _key: &str) -> Option<RedactMode> {
+        None
+    }
+
+    /// Limits on how much of the value is written
+    #[inline(always)]
+    fn limits(&self) -> Truncation {
+        Truncation::default()
+    }
+
+    /// Number of arrays and objects the value being written is nested in
+    #[inline(always)]
+    fn depth(&self) -> usize {
+        0
+    }
+
+    /// Case the keys of objects are rewritten to
+    #[inline(always)]
+    fn key_case(&self) -> Option<KeyCase> {
+        None
+    }
+
+    /// Called before writing the value of the member `key`
+    #[inline(always)]
+    fn enter_key(&mut self, _key: &str) {}
+
+    /// Called before writing the element `index` of an array
+    #[inline(always)]
+    fn enter_index(&mut self, _index: usize) {}
+
+    /// Called after writing a member's value or an array element
+    #[inline(always)]
+    fn leave(&mut self) {}
+
+    fn write_scalar_array(&mut self, items: &[Vec<u8>]) -> io::Result<()> {
+        stry!(self.write_char(b'['));
+        let mut iter = items.iter();
+
+        if let Some(item) = iter.next() {
+            self.indent();
+            stry!(self.new_line());
+            stry!(self.write(item));
+        } else {
+            return self.write_char(b']');
+        }
+
+        for item in iter {
+            stry!(self.write_comma());
+            stry!(self.new_line());
+            stry!(self.write(item));
+        }
+
+        self.dedent();
+        stry!(self.new_line());
+        self.write_char(b']')
+    }
+
     #[inline(never)]
     fn write_string_complex(&mut self, string: &[u8], mut start: usize) -> io::Result<()> {
         stry!(self.write(&string[..start]));
@@ -101,36 +181,28 @@ pub trait BaseGenerator {
 
     #[inline(always)]
     fn write_string(&mut self, string: &str) -> io::Result<()> {
-        stry!(self.write_char(b'"'));
-        let mut string = string.as_bytes();
-        let mut len = string.len();
-        let mut idx = 0;
-
-        unsafe {
-            // Looking at the table above the lower 5 bits are entirely
-            // quote characters that gives us a bitmask of 0x1f for that
-            // region, only quote (`"`) and backslash (`\`) are not in
-            // this range.
-            stry!(write_str_simd(
-                self.get_writer(),
-                &mut string,
-                &mut len,
-                &mut idx
-            ));
-        }
-        // Legacy code to handle the remainder of the code
-        for (index, ch) in string.iter().enumerate() {
-            if ESCAPED[*ch as usize] > 0 {
-                self.write_string_complex(string, index)?;
-                return self.write_char(b'"');
+        write_string_fast(self, string)
+    }
+
+    // Writes a string value, cut to the limits' `max_string_length`
+    #[inline(always)]
+    fn write_string_limited(&mut self, string: &str) -> io::Result<()> {
+        if let Some(max) = self.limits().max_string_length {
+            if let Some((end, _)) = string.char_indices().nth(max) {
+                let rest = string[end..].chars().count();
+                return self.write_string(&format!("{}…(+{} chars)", &string[..end], rest));
             }
         }
-        stry!(self.write(string));
-        self.write_char(b'"')
+        self.write_string(string)
     }
 
+    /// Writes a float, JSON has no representation for NaN or the
+    /// infinities so like serde_json they are written as `null`.
     #[inline(always)]
     fn write_float(&mut self, num: f64) -> io::Result<()> {
+        if unlikely!(!num.is_finite()) {
+            return self.write(b"null");
+        }
         let mut buffer = ryu::Buffer::new();
         let s = buffer.format(num);
         self.get_writer().write_all(s.as_bytes())
@@ -141,6 +213,15 @@ pub trait BaseGenerator {
         itoa::write(self.get_writer(), num).map(|_| ())
         //self.write(num.to_string().as_bytes())
     }
+
+    #[inline(always)]
+    fn write_number(&mut self, num: Number) -> io::Result<()> {
+        match num.n {
+            N::PosInt(u) => itoa::write(self.get_writer(), u).map(|_| ()),
+            N::NegInt(i) => self.write_int(i),
+            N::Float(f) => self.write_float(f),
+        }
+    }
 }
 
 /****** Pretty Generator ******/
@@ -157,11 +238,24 @@ impl<VT: ValueTrait> DumpGenerator<VT> {
         }
     }
 
+    /// Creates a generator appending to an existing buffer
+    pub fn with_buffer(code: Vec<u8>) -> Self {
+        Self {
+            _value: PhantomData,
+            code,
+        }
+    }
+
     pub fn consume(self) -> String {
         // Original strings were unicode, numbers are all ASCII,
         // therefore this is safe.
         unsafe { String::from_utf8_unchecked(self.code) }
     }
+
+    /// Returns the buffer including everything written so far
+    pub fn into_buffer(self) -> Vec<u8> {
+        self.code
+    }
 }
 
 impl<VT: ValueTrait> BaseGenerator for DumpGenerator<VT> {
@@ -355,6 +449,433 @@ where
     }
 }
 
+/****** Options Generator ******/
+
+// Number of characters in a slice of utf8 encoded bytes
+#[inline(always)]
+fn char_count(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|b| (**b & 0xC0) != 0x80).count()
+}
+
+/// Writer that keeps track of the column the next character is written to.
+pub struct ColumnWriter<'w, W: 'w + Write> {
+    writer: &'w mut W,
+    column: usize,
+}
+
+impl<'w, W: Write> Write for ColumnWriter<'w, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = stry!(self.writer.write(buf));
+        let buf = &buf[..written];
+        match buf.iter().rposition(|b| *b == b'\n') {
+            Some(nl) => self.column = char_count(&buf[nl + 1..]),
+            None => self.column += char_count(buf),
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writer forwarding to a `fmt::Write`. The generator may split multi byte
+/// characters across writes so incomplete sequences are held back until
+/// the rest of the character arrives.
+pub struct FmtWriter<'w, W: 'w + fmt::Write + ?Sized> {
+    writer: &'w mut W,
+    pending: Vec<u8>,
+}
+
+impl<'w, W: fmt::Write + ?Sized> FmtWriter<'w, W> {
+    pub fn new(writer: &'w mut W) -> Self {
+        Self {
+            writer,
+            pending: Vec::new(),
+        }
+    }
+
+    // Writes the valid UTF-8 prefix of `bytes`, returns its length
+    fn write_valid(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let valid = match std::str::from_utf8(bytes) {
+            Ok(_) => bytes.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid utf-8")),
+        };
+        // the prefix was validated above
+        let s = unsafe { std::str::from_utf8_unchecked(&bytes[..valid]) };
+        self.writer
+            .write_str(s)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "formatter error"))?;
+        Ok(valid)
+    }
+
+    /// Checks that no incomplete character is left over
+    pub fn finish(self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "invalid utf-8"))
+        }
+    }
+}
+
+impl<'w, W: fmt::Write + ?Sized> Write for FmtWriter<'w, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            let written = stry!(self.write_valid(buf));
+            self.pending.extend_from_slice(&buf[written..]);
+        } else {
+            let mut pending = std::mem::replace(&mut self.pending, Vec::new());
+            pending.extend_from_slice(buf);
+            let written = stry!(self.write_valid(&pending));
+            pending.drain(..written);
+            self.pending = pending;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct OptionsGenerator<'w, W, V>
+where
+    W: 'w + Write,
+    V: ValueTrait,
+{
+    writer: ColumnWriter<'w, W>,
+    options: SerializeOptions,
+    dent: usize,
+    // tokens of the redacted pointers and the path to the value being
+    // written, only tracked if there are any
+    redacted: Vec<Vec<String>>,
+    path: Vec<String>,
+    depth: usize,
+    _value: PhantomData<V>,
+}
+
+impl<'w, W, V> OptionsGenerator<'w, W, V>
+where
+    W: 'w + Write,
+    V: ValueTrait,
+{
+    pub fn new(writer: &'w mut W, options: SerializeOptions) -> Self {
+        let redacted = options
+            .redact
+            .pointers
+            .iter()
+            .filter_map(|p| pointer::tokens(p))
+            .filter(|t| !t.is_empty())
+            .map(|t| t.into_iter().map(Cow::into_owned).collect())
+            .collect();
+        OptionsGenerator {
+            writer: ColumnWriter { writer, column: 0 },
+            options,
+            dent: 0,
+            redacted,
+            path: Vec::new(),
+            depth: 0,
+            _value: PhantomData,
+        }
+    }
+
+    /// Finishes the document after the value was written
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.options.trailing_newline {
+            stry!(self.write_char(b'\n'));
+        }
+        Ok(())
+    }
+
+    fn write_separator(&mut self) -> io::Result<()> {
+        if self.options.space_after_comma {
+            self.write(b", ")
+        } else {
+            self.write_char(b',')
+        }
+    }
+
+    // Writes a string applying the additional escaping requested by the
+    // options, all ASCII characters that need escaping go through the
+    // `ESCAPED` table like they do in `write_string_complex`.
+    #[inline(never)]
+    fn write_string_escaped(&mut self, string: &str) -> io::Result<()> {
+        stry!(self.write_char(b'"'));
+        let mut start = 0;
+        for (index, ch) in string.char_indices() {
+            if ch.is_ascii() {
+                let byte = ch as u8;
+                let escape = ESCAPED[byte as usize];
+                let html = self.options.escape_html
+                    && match byte {
+                        b'<' | b'>' | b'&' | b'/' => true,
+                        _ => false,
+                    };
+                if escape == 0 && !html {
+                    continue;
+                }
+                stry!(self.write(string[start..index].as_bytes()));
+                if escape == b'u' || (html && byte != b'/') {
+                    stry!(write!(self.writer, "\\u{:04x}", byte));
+                } else if escape > 0 {
+                    stry!(self.write(&[b'\\', escape]));
+                } else {
+                    stry!(self.write(b"\\/"));
+                }
+            } else if self.options.escape_non_ascii {
+                stry!(self.write(string[start..index].as_bytes()));
+                let mut buf = [0; 2];
+                for unit in ch.encode_utf16(&mut buf) {
+                    stry!(write!(self.writer, "\\u{:04x}", unit));
+                }
+            } else {
+                continue;
+            }
+            start = index + ch.len_utf8();
+        }
+        stry!(self.write(string[start..].as_bytes()));
+        self.write_char(b'"')
+    }
+}
+
+impl<'w, W, V> BaseGenerator for OptionsGenerator<'w, W, V>
+where
+    W: Write,
+    V: ValueTrait,
+{
+    type T = ColumnWriter<'w, W>;
+
+    #[inline(always)]
+    fn get_writer(&mut self) -> &mut Self::T {
+        &mut self.writer
+    }
+
+    #[inline(always)]
+    fn write_min(&mut self, slice: &[u8], min: u8) -> io::Result<()> {
+        if self.options.indent.is_some() {
+            self.write(slice)
+        } else {
+            self.write_char(min)
+        }
+    }
+
+    fn write_colon(&mut self) -> io::Result<()> {
+        if self.options.space_after_colon {
+            self.write(b": ")
+        } else {
+            self.write_char(b':')
+        }
+    }
+
+    fn write_comma(&mut self) -> io::Result<()> {
+        if self.options.indent.is_some() {
+            self.write_char(b',')
+        } else {
+            self.write_separator()
+        }
+    }
+
+    fn new_line(&mut self) -> io::Result<()> {
+        if let Some(spaces_per_indent) = self.options.indent {
+            stry!(self.write_char(b'\n'));
+            for _ in 0..(self.dent * spaces_per_indent as usize) {
+                stry!(self.write_char(b' '));
+            }
+        }
+        Ok(())
+    }
+
+    fn indent(&mut self) {
+        self.dent += 1;
+    }
+
+    fn dedent(&mut self) {
+        self.dent -= 1;
+    }
+
+    fn scalar_array_options(&self) -> Option<SerializeOptions> {
+        if self.options.indent.is_some() && self.options.max_line_width.is_some() {
+            Some(SerializeOptions {
+                indent: None,
+                trailing_newline: false,
+                max_line_width: None,
+                redact: Redaction::default(),
+                ..self.options.clone()
+            })
+        } else {
+            None
+        }
+    }
+
+    fn redact(&self, key: &str) -> Option<RedactMode> {
+        let path = &self.path;
+        let matches = |pointer: &Vec<String>| {
+            pointer.len() == path.len() + 1
+                && pointer
+                    .iter()
+                    .zip(path.iter().map(String::as_str).chain(Some(key)))
+                    .all(|(p, t)| p == "*" || p == t)
+        };
+        if self.options.redact.keys.iter().any(|k| k == key) || self.redacted.iter().any(matches) {
+            Some(self.options.redact.mode)
+        } else {
+            None
+        }
+    }
+
+    fn limits(&self) -> Truncation {
+        self.options.truncate
+    }
+
+    fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn key_case(&self) -> Option<KeyCase> {
+        self.options.key_case
+    }
+
+    fn enter_key(&mut self, key: &str) {
+        self.depth += 1;
+        if !self.redacted.is_empty() {
+            self.path.push(key.to_string());
+        }
+    }
+
+    fn enter_index(&mut self, index: usize) {
+        self.depth += 1;
+        if !self.redacted.is_empty() {
+            self.path.push(index.to_string());
+        }
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+        self.path.pop();
+    }
+
+    fn write_string(&mut self, string: &str) -> io::Result<()> {
+        if self.options.escape_non_ascii || self.options.escape_html {
+            self.write_string_escaped(string)
+        } else {
+            write_string_fast(self, string)
+        }
+    }
+
+    fn write_float(&mut self, num: f64) -> io::Result<()> {
+        // like `BaseGenerator::write_float`
+        if !num.is_finite() {
+            return self.write(b"null");
+        }
+        match self.options.float_format {
+            FloatFormat::Shortest => {
+                let mut buffer = ryu::Buffer::new();
+                let s = buffer.format(num);
+                self.write(s.as_bytes())
+            }
+            FloatFormat::Fixed(precision) => {
+                write!(self.writer, "{:.*}", usize::from(precision), num)
+            }
+            FloatFormat::Scientific { threshold } => {
+                let s = format!("{:e}", num);
+                let exp: i32 = s
+                    .rsplit('e')
+                    .next()
+                    .and_then(|e| e.parse().ok())
+                    .unwrap_or(0);
+                if exp.abs() >= i32::from(threshold) {
+                    self.write(s.as_bytes())
+                } else {
+                    write_plain_float(&mut self.writer, num)
+                }
+            }
+        }
+    }
+
+    fn write_scalar_array(&mut self, items: &[Vec<u8>]) -> io::Result<()> {
+        let width = self.options.max_line_width.unwrap_or_else(usize::max_value);
+        let sep = if self.options.space_after_comma { 2 } else { 1 };
+        let inline = items.iter().map(|i| char_count(i) + sep).sum::<usize>() + 2 - sep;
+        if items.len() < 2 || self.writer.column + inline <= width {
+            stry!(self.write_char(b'['));
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    stry!(self.write_separator());
+                }
+                stry!(self.write(item));
+            }
+            return self.write_char(b']');
+        }
+
+        stry!(self.write_char(b'['));
+        self.indent();
+        stry!(self.new_line());
+        let last = items.len() - 1;
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                // the item needs to fit including the comma following it
+                let needed = sep - 1 + char_count(item) + if i == last { 0 } else { 1 };
+                stry!(self.write_char(b','));
+                if self.writer.column + needed > width {
+                    stry!(self.new_line());
+                } else if self.options.space_after_comma {
+                    stry!(self.write_char(b' '));
+                }
+            }
+            stry!(self.write(item));
+        }
+        self.dedent();
+        stry!(self.new_line());
+        self.write_char(b']')
+    }
+}
+
+// Writes a string escaping only what JSON requires, this is the default
+// implementation of `BaseGenerator::write_string`.
+#[inline(always)]
+fn write_string_fast<G: BaseGenerator + ?Sized>(g: &mut G, string: &str) -> io::Result<()> {
+    stry!(g.write_char(b'"'));
+    let mut string = string.as_bytes();
+    let mut len = string.len();
+    let mut idx = 0;
+
+    unsafe {
+        // Looking at the table above the lower 5 bits are entirely
+        // quote characters that gives us a bitmask of 0x1f for that
+        // region, only quote (`"`) and backslash (`\`) are not in
+        // this range.
+        stry!(write_str_simd(
+            g.get_writer(),
+            &mut string,
+            &mut len,
+            &mut idx
+        ));
+    }
+    // Legacy code to handle the remainder of the code
+    for (index, ch) in string.iter().enumerate() {
+        if ESCAPED[*ch as usize] > 0 {
+            g.write_string_complex(string, index)?;
+            return g.write_char(b'"');
+        }
+    }
+    stry!(g.write(string));
+    g.write_char(b'"')
+}
+
+// Writes the shortest representation of `num` that round-trips without
+// using exponent notation.
+fn write_plain_float<W: Write>(w: &mut W, num: f64) -> io::Result<()> {
+    let s = num.to_string();
+    stry!(w.write_all(s.as_bytes()));
+    if num.is_finite() && !s.contains('.') {
+        w.write_all(b".0")
+    } else {
+        Ok(())
+    }
+}
+
 // From: https://github.com/dtolnay/fastwrite/blob/master/src/lib.rs#L68
 //
 // LLVM is not able to lower `Vec::extend_from_slice` into a memcpy, so this
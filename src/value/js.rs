@@ -0,0 +1,55 @@
+use crate::{ArrayAccess, BorrowedValue, ObjectAccess, OwnedValue, ValueAccess, ValueType};
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::JsValue;
+
+// Builds the JavaScript value directly, integers become numbers (and lose
+// precision beyond 2^53) the same way `JSON.parse` would treat them.
+fn to_js<V>(value: &V) -> JsValue
+where
+    V: ValueAccess,
+    V::Key: AsRef<str>,
+{
+    match value.value_type() {
+        ValueType::Null => JsValue::NULL,
+        ValueType::Bool => JsValue::from_bool(value.as_bool().unwrap_or_default()),
+        ValueType::I64 | ValueType::F64 => {
+            JsValue::from_f64(value.cast_f64().unwrap_or(std::f64::NAN))
+        }
+        ValueType::String => JsValue::from_str(value.as_str().unwrap_or_default()),
+        ValueType::Array => {
+            let array = Array::new();
+            if let Some(a) = value.as_array() {
+                for v in a.iter() {
+                    array.push(&to_js(v));
+                }
+            }
+            array.into()
+        }
+        ValueType::Object => {
+            let object = Object::default();
+            if let Some(o) = value.as_object() {
+                for (k, v) in o.iter() {
+                    // setting a property on a plain object can't fail
+                    let _ = Reflect::set(&object, &JsValue::from_str(k.as_ref()), &to_js(v));
+                }
+            }
+            object.into()
+        }
+    }
+}
+
+/// Converts a value into a JavaScript value without going through
+/// `JSON.parse`, objects become plain objects and integers become numbers.
+impl<'v> From<&BorrowedValue<'v>> for JsValue {
+    fn from(value: &BorrowedValue<'v>) -> Self {
+        to_js(value)
+    }
+}
+
+/// Converts a value into a JavaScript value without going through
+/// `JSON.parse`, objects become plain objects and integers become numbers.
+impl From<&OwnedValue> for JsValue {
+    fn from(value: &OwnedValue) -> Self {
+        to_js(value)
+    }
+}
@@ -0,0 +1,236 @@
+use crate::value::float_eq;
+use std::fmt;
+
+/// A JSON number: an integer that fits an `i64` or a `u64`, or a float.
+///
+/// Integers are kept exactly, so values above `i64::max_value()` don't have
+/// to be squeezed into an `i64`.
+///
+/// ```
+/// use simd_json::Number;
+/// let n = Number::from(u64::max_value());
+/// assert!(n.is_u64() && !n.is_i64());
+/// assert_eq!(n.as_u64(), Some(u64::max_value()));
+/// assert_eq!(n.as_i64(), None);
+/// assert_eq!(Number::from(-1).as_u64(), None);
+/// assert_eq!(Number::from(1.5).as_f64(), Some(1.5));
+/// assert_eq!(Number::from(1).as_f64(), Some(1.0));
+/// ```
+#[derive(Clone, Copy)]
+pub struct Number {
+    pub(crate) n: N,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum N {
+    // non negative integers
+    PosInt(u64),
+    // always less than zero
+    NegInt(i64),
+    Float(f64),
+}
+
+impl Number {
+    /// Creates a float number, returns `None` for NaN and infinities as
+    /// JSON can't represent them
+    pub fn from_f64(f: f64) -> Option<Self> {
+        if f.is_finite() {
+            Some(Self::from(f))
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the number is an integer that fits an `i64`
+    pub fn is_i64(&self) -> bool {
+        match self.n {
+            N::PosInt(u) => u <= i64::max_value() as u64,
+            N::NegInt(_) => true,
+            N::Float(_) => false,
+        }
+    }
+
+    /// Returns true if the number is a non negative integer
+    pub fn is_u64(&self) -> bool {
+        match self.n {
+            N::PosInt(_) => true,
+            N::NegInt(_) | N::Float(_) => false,
+        }
+    }
+
+    /// Returns true if the number is a float
+    pub fn is_f64(&self) -> bool {
+        match self.n {
+            N::Float(_) => true,
+            N::PosInt(_) | N::NegInt(_) => false,
+        }
+    }
+
+    /// The number as an `i64` if it is an integer that fits one
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::PosInt(u) if u <= i64::max_value() as u64 => Some(u as i64),
+            N::NegInt(i) => Some(i),
+            N::PosInt(_) | N::Float(_) => None,
+        }
+    }
+
+    /// The number as a `u64` if it is a non negative integer
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            N::PosInt(u) => Some(u),
+            N::NegInt(_) | N::Float(_) => None,
+        }
+    }
+
+    /// The number as a float, integers are converted (and may lose
+    /// precision)
+    #[allow(clippy::cast_precision_loss)]
+    pub fn as_f64(&self) -> Option<f64> {
+        Some(match self.n {
+            N::PosInt(u) => u as f64,
+            N::NegInt(i) => i as f64,
+            N::Float(f) => f,
+        })
+    }
+
+    /// The number as an `i128`, which holds every integer exactly
+    pub fn as_i128(&self) -> Option<i128> {
+        match self.n {
+            N::PosInt(u) => Some(i128::from(u)),
+            N::NegInt(i) => Some(i128::from(i)),
+            N::Float(_) => None,
+        }
+    }
+}
+
+/// Integers are equal if they have the same value, floats if they have the
+/// same bits like values, so `-0.0 != 0.0` while all NaNs are equal. An
+/// integer is never equal to a float.
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.n, other.n) {
+            (N::PosInt(v1), N::PosInt(v2)) => v1 == v2,
+            (N::NegInt(v1), N::NegInt(v2)) => v1 == v2,
+            (N::Float(v1), N::Float(v2)) => float_eq(v1, v2),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Number {}
+
+impl fmt::Debug for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.n {
+            N::PosInt(u) => write!(f, "{}", u),
+            N::NegInt(i) => write!(f, "{}", i),
+            N::Float(v) => write!(f, "{:?}", v),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.n {
+            N::PosInt(u) => write!(f, "{}", u),
+            N::NegInt(i) => write!(f, "{}", i),
+            N::Float(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+macro_rules! from_signed {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Number {
+                #[inline]
+                #[allow(clippy::cast_sign_loss, clippy::cast_lossless)]
+                fn from(i: $t) -> Self {
+                    let n = if i < 0 {
+                        N::NegInt(i as i64)
+                    } else {
+                        N::PosInt(i as u64)
+                    };
+                    Self { n }
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! from_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Number {
+                #[inline]
+                #[allow(clippy::cast_lossless)]
+                fn from(u: $t) -> Self {
+                    Self { n: N::PosInt(u as u64) }
+                }
+            }
+        )*
+    };
+}
+
+from_signed!(i8, i16, i32, i64, isize);
+from_unsigned!(u8, u16, u32, u64, usize);
+
+impl From<f32> for Number {
+    #[inline]
+    fn from(f: f32) -> Self {
+        Self::from(f64::from(f))
+    }
+}
+
+impl From<f64> for Number {
+    #[inline]
+    fn from(f: f64) -> Self {
+        Self { n: N::Float(f) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let max = Number::from(u64::max_value());
+        assert!(max.is_u64() && !max.is_i64() && !max.is_f64());
+        assert_eq!(max.as_i128(), Some(i128::from(u64::max_value())));
+        let min = Number::from(i64::min_value());
+        assert!(min.is_i64() && !min.is_u64());
+        assert_eq!(min.as_i64(), Some(i64::min_value()));
+        assert_eq!(Number::from(7_u8), Number::from(7_i64));
+        assert_eq!(Number::from(7_i32).as_u64(), Some(7));
+        assert_ne!(Number::from(1), Number::from(1.0));
+        assert_ne!(Number::from(0.1 + 0.2), Number::from(0.3));
+        assert_ne!(Number::from(-0.0), Number::from(0.0));
+        assert_eq!(Number::from_f64(std::f64::NAN), None);
+        assert_eq!(Number::from(2.5).as_i64(), None);
+        assert_eq!(Number::from(-3).to_string(), "-3");
+        assert_eq!(format!("{:?}", Number::from(1.0)), "1.0");
+    }
+
+    #[test]
+    fn parse_and_encode() {
+        use crate::ValueAccess;
+        let mut d = br#"[18446744073709551615, -9223372036854775808, 1.5]"#.to_vec();
+        let v = crate::to_owned_value(&mut d).unwrap();
+        assert_eq!(
+            v.get_idx(0).and_then(ValueAccess::as_u64),
+            Some(u64::max_value())
+        );
+        assert_eq!(v.get_idx(0).and_then(ValueAccess::as_i64), None);
+        assert_eq!(
+            v.get_idx(1).and_then(ValueAccess::as_i64),
+            Some(i64::min_value())
+        );
+        assert_eq!(
+            v.encode(),
+            "[18446744073709551615,-9223372036854775808,1.5]"
+        );
+    }
+}
@@ -1,20 +1,165 @@
 /// A lifetime less DOM implementation. It uses strings to make te
 /// structure fully owned, avoiding lifetimes at the cost of performance.
 mod cmp;
+mod cow;
 mod from;
-mod serialize;
+mod intern;
+pub(crate) mod serialize;
 
-use crate::value::{ValueTrait, ValueType};
-use crate::{stry, unlikely, Deserializer, ErrorType, Result};
-use halfbrown::HashMap;
+pub use self::cow::CowValue;
+pub use self::intern::KeyInterner;
+
+use crate::parser::Buffers;
+use crate::value::{
+    insert_parsed, is_float_literal, parsed_object, ObjectMap, Open, StaticNode, ValueAccess,
+    ValueTrait, ValueType,
+};
+use crate::{
+    stry, unlikely, BigNumberBehavior, BorrowedValue, Deserializer, ErrorType, ParserOptions,
+    Result,
+};
 use std::fmt;
-use std::ops::Index;
+use std::ops::{Index, IndexMut};
 
 /// Representation of a JSON object
 #[deprecated(since = "0.1.21", note = "Please use Object instead")]
 pub type Map = Object;
+/// Type of the keys of a JSON object
+#[cfg(not(feature = "arc-keys"))]
+pub type Key = String;
+/// Type of the keys of a JSON object, reference counted so identical keys
+/// can share one allocation
+#[cfg(feature = "arc-keys")]
+pub type Key = std::sync::Arc<str>;
 /// Representation of a JSON object
-pub type Object = HashMap<String, Value>;
+pub type Object = ObjectMap<Key, Value>;
+
+// Conversions between `String` and `Key`, these are free unless keys are
+// reference counted.
+#[cfg(not(feature = "arc-keys"))]
+#[inline]
+pub(crate) fn key(s: String) -> Key {
+    s
+}
+#[cfg(feature = "arc-keys")]
+#[inline]
+pub(crate) fn key(s: String) -> Key {
+    s.into()
+}
+#[cfg(not(feature = "arc-keys"))]
+#[inline]
+pub(crate) fn key_into_string(k: Key) -> String {
+    k
+}
+#[cfg(feature = "arc-keys")]
+#[inline]
+pub(crate) fn key_into_string(k: Key) -> String {
+    k.to_string()
+}
+
+/// Type of string values
+#[cfg(not(any(feature = "arc-strings", feature = "compact-strings")))]
+pub type Str = String;
+/// Type of string values, strings of up to 24 bytes are stored inline
+/// without a heap allocation
+#[cfg(all(feature = "compact-strings", not(feature = "arc-strings")))]
+pub type Str = compact_str::CompactString;
+/// Type of string values, reference counted so cloning a value doesn't
+/// copy its strings
+#[cfg(feature = "arc-strings")]
+pub type Str = std::sync::Arc<str>;
+
+// Converts a `Str` back into a `String`, free unless strings are
+// reference counted.
+#[cfg(not(any(feature = "arc-strings", feature = "compact-strings")))]
+#[inline]
+pub(crate) fn str_into_string(s: Str) -> String {
+    s
+}
+#[cfg(all(feature = "compact-strings", not(feature = "arc-strings")))]
+#[inline]
+pub(crate) fn str_into_string(s: Str) -> String {
+    s.into_string()
+}
+#[cfg(feature = "arc-strings")]
+#[inline]
+pub(crate) fn str_into_string(s: Str) -> String {
+    s.to_string()
+}
+
+// Heap memory used by keys and strings, reference counted ones also count
+// their counters.
+#[cfg(not(feature = "arc-keys"))]
+fn key_heap_size(k: &Key) -> usize {
+    k.capacity()
+}
+#[cfg(feature = "arc-keys")]
+fn key_heap_size(k: &Key) -> usize {
+    k.len() + 2 * std::mem::size_of::<usize>()
+}
+#[cfg(not(any(feature = "arc-strings", feature = "compact-strings")))]
+fn str_heap_size(s: &Str) -> usize {
+    s.capacity()
+}
+#[cfg(all(feature = "compact-strings", not(feature = "arc-strings")))]
+fn str_heap_size(s: &Str) -> usize {
+    if s.is_heap_allocated() {
+        s.capacity()
+    } else {
+        0
+    }
+}
+#[cfg(feature = "arc-strings")]
+fn str_heap_size(s: &Str) -> usize {
+    s.len() + 2 * std::mem::size_of::<usize>()
+}
+#[cfg(not(feature = "arc-strings"))]
+fn shrink_str(s: &mut Str) {
+    s.shrink_to_fit();
+}
+#[cfg(feature = "arc-strings")]
+fn shrink_str(_s: &mut Str) {}
+
+// Buckets of a hashbrown table with room for `capacity` entries, tables
+// have one control byte per bucket and an extra group of them.
+#[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+fn buckets(capacity: usize) -> usize {
+    if capacity < 8 {
+        (capacity + 1).next_power_of_two()
+    } else {
+        (capacity * 8 / 7).next_power_of_two()
+    }
+}
+
+// Heap memory used by the table of an object, halfbrown keeps small objects
+// in a vector and larger ones in a hashbrown table.
+#[cfg(not(any(feature = "preserve-order", feature = "sorted-objects")))]
+fn table_size(o: &Object) -> usize {
+    let entry = std::mem::size_of::<(Key, Value)>();
+    if o.is_map() {
+        buckets(o.capacity()) * (entry + 1) + 16
+    } else {
+        o.capacity() * entry
+    }
+}
+// `IndexMap` keeps the members and their hashes in a vector and their
+// indexes in a hashbrown table.
+#[cfg(feature = "preserve-order")]
+fn table_size(o: &Object) -> usize {
+    let entry = std::mem::size_of::<(usize, Key, Value)>();
+    let index = std::mem::size_of::<usize>();
+    o.capacity() * entry + buckets(o.capacity()) * (index + 1) + 16
+}
+// A `BTreeMap` keeps up to 11 members in each node, counted as if the
+// nodes were full.
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+fn table_size(o: &Object) -> usize {
+    const MEMBERS: usize = 11;
+    let node = MEMBERS * std::mem::size_of::<(Key, Value)>() + 2 * std::mem::size_of::<usize>();
+    let full = o.len() / MEMBERS;
+    let partial = o.len() - full * MEMBERS > 0;
+    (full + usize::from(partial)) * node
+}
 
 /// Parses a slice of bytes into a Value dom. This function will
 /// rewrite the slice to de-escape strings.
@@ -22,8 +167,93 @@ pub type Object = HashMap<String, Value>;
 /// owned memory whereever required thus returning a value without
 /// a lifetime.
 pub fn to_value(s: &mut [u8]) -> Result<Value> {
-    let de = stry!(Deserializer::from_slice(s));
-    OwnedDeserializer::from_deserializer(de).parse()
+    #[cfg(feature = "parser-pool")]
+    return crate::with_parser(|p| p.to_owned_value(s));
+    #[cfg(not(feature = "parser-pool"))]
+    {
+        let de = stry!(Deserializer::from_slice(s));
+        trace_span!("dom");
+        OwnedDeserializer::new(de, &mut KeyInterner::default()).parse()
+    }
+}
+
+/// Same as `to_value` but allows tuning the parser using `ParserOptions`.
+pub fn to_value_with_options(s: &mut [u8], options: &ParserOptions) -> Result<Value> {
+    to_value_with_interner(s, options, &mut KeyInterner::default())
+}
+
+/// Parses the first document in the input and returns it together with
+/// the number of bytes it took up, so whatever follows it starts at that
+/// offset. The rest of the input isn't looked at, see `TrailingData`.
+///
+/// ```
+/// use simd_json::{json, ParserOptions};
+/// let mut d = b"{\"a\": 1}\x1e garbage".to_vec();
+/// let options = ParserOptions::default();
+/// let (v, end) = simd_json::to_owned_value_with_rest(&mut d, &options).unwrap();
+/// assert_eq!(v, json!({"a": 1}));
+/// assert_eq!(&d[end..], b"\x1e garbage");
+/// ```
+pub fn to_value_with_rest(s: &mut [u8], options: &ParserOptions) -> Result<(Value, usize)> {
+    let end = options.document_end(s);
+    to_value_with_options(&mut s[..end], options).map(|v| (v, end))
+}
+
+/// Same as `to_value_with_options` but interns object keys with the given
+/// `KeyInterner`, so keys are shared with previously parsed values.
+pub fn to_value_with_interner(
+    s: &mut [u8],
+    options: &ParserOptions,
+    keys: &mut KeyInterner,
+) -> Result<Value> {
+    #[cfg(feature = "parser-pool")]
+    return crate::with_parser(|p| to_value_with_buffers(s, options, keys, &mut p.buffers));
+    #[cfg(not(feature = "parser-pool"))]
+    to_value_with_buffers(s, options, keys, &mut Buffers::default())
+}
+
+// Same as `to_value_with_interner` but reuses the parser buffers in
+// `buffers`, see `Parser`.
+pub(crate) fn to_value_with_buffers(
+    s: &mut [u8],
+    options: &ParserOptions,
+    keys: &mut KeyInterner,
+    buffers: &mut Buffers,
+) -> Result<Value> {
+    match Deserializer::from_slice_with_buffers(s, options, buffers) {
+        Ok(de) => {
+            trace_span!("dom");
+            OwnedDeserializer::new(de, keys).parse_into(buffers)
+        }
+        Err(e) if options.lossy_utf8 && e.error() == &ErrorType::InvalidUTF8 => {
+            let mut repaired = String::from_utf8_lossy(s).into_owned().into_bytes();
+            let de = stry!(Deserializer::from_slice_with_buffers(
+                &mut repaired,
+                options,
+                buffers
+            ));
+            trace_span!("dom");
+            OwnedDeserializer::new(de, keys).parse_into(buffers)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// Builds a value from structural indexes that were computed ahead of
+// time, see `Deserializer::from_structural_indexes`.
+pub(crate) fn to_value_with_structural_indexes(
+    s: &mut [u8],
+    structural_indexes: Vec<u32>,
+    options: &ParserOptions,
+    keys: &mut KeyInterner,
+) -> Result<Value> {
+    let de = stry!(Deserializer::from_structural_indexes(
+        s,
+        structural_indexes,
+        options
+    ));
+    trace_span!("dom");
+    OwnedDeserializer::new(de, keys).parse()
 }
 
 /// Owned JSON-DOM Value, consider using the `ValueTrait`
@@ -32,31 +262,115 @@ pub fn to_value(s: &mut [u8]) -> Result<Value> {
 /// for getting rid of lifetimes.
 #[derive(Debug, Clone)]
 pub enum Value {
-    /// null
-    Null,
-    /// boolean type
-    Bool(bool),
-    /// float type
-    F64(f64),
-    /// integer type
-    I64(i64),
+    /// null, booleans and numbers
+    Static(StaticNode),
+    /// number as it appeared in the input, only produced when parsing with
+    /// `ParserOptions::lossless_numbers` or `BigNumberBehavior::Raw`
+    RawNumber(String),
     /// string type
-    String(String),
+    String(Str),
     /// array type
     Array(Vec<Value>),
     /// object type
     Object(Object),
 }
 
-impl ValueTrait for Value {
-    type Key = String;
+impl Value {
+    /// Creates a string value holding `bytes` encoded as base64
+    /// (standard alphabet, padded).
+    ///
+    /// ```
+    /// use simd_json::{OwnedValue, ValueAccess};
+    /// let v = OwnedValue::from_bytes_base64(b"\x00\xffbin");
+    /// assert_eq!(v, "AP9iaW4=");
+    /// assert_eq!(v.as_bytes_base64().as_deref(), Some(&b"\x00\xffbin"[..]));
+    /// ```
+    #[cfg(feature = "base64")]
+    pub fn from_bytes_base64(bytes: &[u8]) -> Self {
+        Self::from(crate::value::base64::encode(bytes))
+    }
+
+    /// Converts the value into a `BorrowedValue`, strings and keys are
+    /// moved into owned `Cow`s so nothing is copied.
+    pub fn into_borrowed<'v>(self) -> BorrowedValue<'v> {
+        self.into()
+    }
+
+    /// Creates a `BorrowedValue` that borrows all strings and keys
+    /// from this value.
+    pub fn to_borrowed(&self) -> BorrowedValue<'_> {
+        self.into()
+    }
+
+    /// The memory used by the value in bytes: its own size and everything
+    /// it owns on the heap, including the spare capacity of strings,
+    /// arrays and objects. Reference counted keys and strings are counted
+    /// once for every reference to them.
+    ///
+    /// ```
+    /// use simd_json::{json, OwnedValue, ValueTrait};
+    /// let mut v = json!({"a": [1, 2, 3]});
+    /// let size = v.mem_size();
+    /// assert!(size >= std::mem::size_of::<OwnedValue>() * 5);
+    /// v["a"].as_array_mut().unwrap().reserve(100);
+    /// assert!(v.mem_size() > size);
+    /// v.shrink_to_fit();
+    /// assert!(v.mem_size() <= size);
+    /// ```
+    pub fn mem_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.heap_size()
+    }
+
+    fn heap_size(&self) -> usize {
+        match self {
+            Self::Static(_) => 0,
+            Self::RawNumber(n) => n.capacity(),
+            Self::String(s) => str_heap_size(s),
+            Self::Array(a) => {
+                a.capacity() * std::mem::size_of::<Self>()
+                    + a.iter().map(Self::heap_size).sum::<usize>()
+            }
+            Self::Object(o) => {
+                table_size(o)
+                    + o.iter()
+                        .map(|(k, v)| key_heap_size(k) + v.heap_size())
+                        .sum::<usize>()
+            }
+        }
+    }
+
+    /// Recursively releases the spare capacity of strings, arrays and
+    /// objects. Keys are left as they are, they never have spare capacity
+    /// when parsed.
+    pub fn shrink_to_fit(&mut self) {
+        match self {
+            Self::Static(_) => (),
+            Self::RawNumber(n) => n.shrink_to_fit(),
+            Self::String(s) => shrink_str(s),
+            Self::Array(a) => {
+                a.shrink_to_fit();
+                a.iter_mut().for_each(Self::shrink_to_fit);
+            }
+            Self::Object(o) => {
+                // a `BTreeMap` has no spare capacity
+                #[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+                o.shrink_to_fit();
+                o.values_mut().for_each(Self::shrink_to_fit);
+            }
+        }
+    }
+}
+
+impl ValueAccess for Value {
+    type Key = Key;
+    type Array = Vec<Self>;
+    type Object = ObjectMap<Self::Key, Self>;
 
     fn value_type(&self) -> ValueType {
         match self {
-            Self::Null => ValueType::Null,
-            Self::Bool(_) => ValueType::Bool,
-            Self::F64(_) => ValueType::F64,
-            Self::I64(_) => ValueType::I64,
+            Self::Static(s) => s.value_type(),
+            Self::RawNumber(n) if is_float_literal(n) => ValueType::F64,
+            Self::RawNumber(_) => ValueType::I64,
             Self::String(_) => ValueType::String,
             Self::Array(_) => ValueType::Array,
             Self::Object(_) => ValueType::Object,
@@ -65,59 +379,68 @@ impl ValueTrait for Value {
 
     fn is_null(&self) -> bool {
         match self {
-            Self::Null => true,
+            Self::Static(s) => s.is_null(),
             _ => false,
         }
     }
 
     fn as_bool(&self) -> Option<bool> {
         match self {
-            Self::Bool(b) => Some(*b),
+            Self::Static(s) => s.as_bool(),
             _ => None,
         }
     }
 
     fn as_i64(&self) -> Option<i64> {
         match self {
-            Self::I64(i) => Some(*i),
+            Self::Static(s) => s.as_i64(),
+            Self::RawNumber(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_i128(&self) -> Option<i128> {
+        match self {
+            Self::Static(s) => s.as_i128(),
+            Self::RawNumber(n) => n.parse().ok(),
             _ => None,
         }
     }
 
     fn as_u64(&self) -> Option<u64> {
-        #[allow(clippy::cast_sign_loss)]
         match self {
-            Self::I64(i) if *i >= 0 => Some(*i as u64),
+            Self::Static(s) => s.as_u64(),
+            Self::RawNumber(n) => n.parse().ok(),
             _ => None,
         }
     }
 
     fn as_f64(&self) -> Option<f64> {
         match self {
-            Self::F64(i) => Some(*i),
+            Self::Static(s) => s.as_f64(),
+            Self::RawNumber(n) if is_float_literal(n) => n.parse().ok(),
             _ => None,
         }
     }
 
     fn cast_f64(&self) -> Option<f64> {
-        #[allow(clippy::cast_precision_loss)]
         match self {
-            Self::F64(i) => Some(*i),
-            Self::I64(i) => Some(*i as f64),
+            Self::Static(s) => s.cast_f64(),
+            Self::RawNumber(n) => n.parse().ok(),
             _ => None,
         }
     }
 
     fn as_string(&self) -> Option<String> {
         match self {
-            Self::String(s) => Some(s.clone()),
+            Self::String(s) => Some(s.to_string()),
             _ => None,
         }
     }
 
     fn as_str(&self) -> Option<&str> {
         match self {
-            Self::String(s) => Some(s.as_str()),
+            Self::String(s) => Some(&**s),
             _ => None,
         }
     }
@@ -129,6 +452,15 @@ impl ValueTrait for Value {
         }
     }
 
+    fn as_object(&self) -> Option<&ObjectMap<Self::Key, Self>> {
+        match self {
+            Self::Object(m) => Some(m),
+            _ => None,
+        }
+    }
+}
+
+impl ValueTrait for Value {
     fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
         match self {
             Self::Array(a) => Some(a),
@@ -136,16 +468,31 @@ impl ValueTrait for Value {
         }
     }
 
-    fn as_object(&self) -> Option<&HashMap<Self::Key, Self>> {
+    fn as_object_mut(&mut self) -> Option<&mut ObjectMap<Self::Key, Self>> {
         match self {
             Self::Object(m) => Some(m),
             _ => None,
         }
     }
-    fn as_object_mut(&mut self) -> Option<&mut HashMap<Self::Key, Self>> {
+
+    fn into_array(self) -> std::result::Result<Vec<Self>, Self> {
         match self {
-            Self::Object(m) => Some(m),
-            _ => None,
+            Self::Array(a) => Ok(a),
+            v => Err(v),
+        }
+    }
+
+    fn into_object(self) -> std::result::Result<ObjectMap<Self::Key, Self>, Self> {
+        match self {
+            Self::Object(m) => Ok(m),
+            v => Err(v),
+        }
+    }
+
+    fn into_string(self) -> std::result::Result<String, Self> {
+        match self {
+            Self::String(s) => Ok(str_into_string(s)),
+            v => Err(v),
         }
     }
 }
@@ -153,11 +500,8 @@ impl ValueTrait for Value {
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Null => f.write_str("null"),
-            Self::Bool(false) => f.write_str("false"),
-            Self::Bool(true) => f.write_str("true"),
-            Self::I64(n) => f.write_str(&n.to_string()),
-            Self::F64(n) => f.write_str(&n.to_string()),
+            Self::Static(s) => write!(f, "{}", s),
+            Self::RawNumber(n) => f.write_str(n),
             Self::String(s) => write!(f, "{}", s),
             Self::Array(a) => write!(f, "{:?}", a),
             Self::Object(o) => write!(f, "{:?}", o),
@@ -168,96 +512,211 @@ impl fmt::Display for Value {
 impl Index<&str> for Value {
     type Output = Self;
     fn index(&self, index: &str) -> &Self {
-        static NULL: Value = Value::Null;
+        static NULL: Value = Value::Static(StaticNode::Null);
         self.get(index).unwrap_or(&NULL)
     }
 }
 
+impl IndexMut<&str> for Value {
+    /// Returns the value for `index`, inserting `null` if the key is
+    /// missing. A `null` value is turned into an empty object first.
+    ///
+    /// # Panics
+    ///
+    /// If the value is neither an object nor `null`.
+    fn index_mut(&mut self, index: &str) -> &mut Self {
+        if let Self::Static(StaticNode::Null) = self {
+            *self = Self::Object(Object::default());
+        }
+        match self {
+            Self::Object(o) => o
+                .entry(index.into())
+                .or_insert(Self::Static(StaticNode::Null)),
+            _ => panic!(
+                "cannot index into a {:?} with the key {:?}",
+                self.value_type(),
+                index
+            ),
+        }
+    }
+}
+
+/// Owning iterator over a value, see the `IntoIterator` impl of `Value`.
+pub struct IntoIter(IntoIterInner);
+
+enum IntoIterInner {
+    Array(std::vec::IntoIter<Value>),
+    Object(<Object as IntoIterator>::IntoIter),
+    Empty,
+}
+
+impl Iterator for IntoIter {
+    type Item = (Option<Key>, Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IntoIterInner::Array(a) => a.next().map(|v| (None, v)),
+            IntoIterInner::Object(o) => o.next().map(|(k, v)| (Some(k), v)),
+            IntoIterInner::Empty => None,
+        }
+    }
+}
+
+impl IntoIterator for Value {
+    type Item = (Option<Key>, Value);
+    type IntoIter = IntoIter;
+
+    /// Consumes the value, yielding the elements of an array with a
+    /// `None` key or the members of an object with their key. Other values
+    /// yield nothing.
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(match self {
+            Value::Array(a) => IntoIterInner::Array(a.into_iter()),
+            Value::Object(o) => IntoIterInner::Object(o.into_iter()),
+            _ => IntoIterInner::Empty,
+        })
+    }
+}
+
 impl Default for Value {
     fn default() -> Self {
-        Self::Null
+        Self::Static(StaticNode::Null)
     }
 }
 
-struct OwnedDeserializer<'de> {
+struct OwnedDeserializer<'de, 'k> {
     de: Deserializer<'de>,
+    keys: &'k mut KeyInterner,
 }
 
-impl<'de> OwnedDeserializer<'de> {
-    pub fn from_deserializer(de: Deserializer<'de>) -> Self {
-        Self { de }
+impl<'de, 'k> OwnedDeserializer<'de, 'k> {
+    pub fn new(de: Deserializer<'de>, keys: &'k mut KeyInterner) -> Self {
+        Self { de, keys }
     }
+    // Parses the value and gives the buffers of the parser back
+    fn parse_into(mut self, buffers: &mut Buffers) -> Result<Value> {
+        let v = self.parse();
+        self.de.into_buffers(buffers);
+        v
+    }
+
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
     pub fn parse(&mut self) -> Result<Value> {
-        match self.de.next_() {
-            b'"' => self.de.parse_str_().map(Value::from),
-            b'n' => Ok(Value::Null),
-            b't' => Ok(Value::Bool(true)),
-            b'f' => Ok(Value::Bool(false)),
-            b'-' => self.de.parse_number_root(true).map(Value::from),
-            b'0'..=b'9' => self.de.parse_number_root(false).map(Value::from),
-            b'[' => self.parse_array(),
-            b'{' => self.parse_map(),
-            _c => Err(self.de.error(ErrorType::UnexpectedCharacter)),
+        let mut stack: Vec<Open<Vec<Value>, Object, Key>> = Vec::new();
+        loop {
+            let mut value = match self.de.next_() {
+                b'"' => stry!(self.parse_string()),
+                b'n' => Value::Static(StaticNode::Null),
+                b't' => Value::Static(StaticNode::Bool(true)),
+                b'f' => Value::Static(StaticNode::Bool(false)),
+                b'-' => stry!(self.parse_number(true, stack.is_empty())),
+                b'0'..=b'9' => stry!(self.parse_number(false, stack.is_empty())),
+                b'[' => {
+                    let es = self.de.count_elements();
+                    if unlikely!(es == 0) {
+                        self.de.skip();
+                        Value::Array(Vec::new())
+                    } else {
+                        stack.push(Open::Array(Vec::with_capacity(es), es));
+                        continue;
+                    }
+                }
+                b'{' => {
+                    // We short cut for empty objects
+                    let es = self.de.count_elements();
+                    if unlikely!(es == 0) {
+                        self.de.skip();
+                        Value::Object(Object::default())
+                    } else {
+                        let key = stry!(self.parse_key());
+                        stack.push(Open::Object(parsed_object(es), es, key));
+                        continue;
+                    }
+                }
+                _c => return Err(self.de.error(ErrorType::UnexpectedCharacter)),
+            };
+            // Adds the value to the innermost open array or object, the
+            // ones that are complete become the value for their parent
+            loop {
+                value = match stack.pop() {
+                    None => return Ok(value),
+                    Some(Open::Array(mut res, left)) => {
+                        res.push(value);
+                        self.de.skip();
+                        if left > 1 {
+                            stack.push(Open::Array(res, left - 1));
+                            break;
+                        }
+                        Value::Array(res)
+                    }
+                    Some(Open::Object(mut res, left, key)) => {
+                        if self.de.options.key_case.is_some() {
+                            res.insert(key, value);
+                        } else {
+                            insert_parsed(&mut res, key, value);
+                        }
+                        self.de.skip();
+                        if left > 1 {
+                            let key = stry!(self.parse_key());
+                            stack.push(Open::Object(res, left - 1, key));
+                            break;
+                        }
+                        Value::Object(res)
+                    }
+                };
+            }
         }
     }
 
+    // Parses the key of the next member of an object and moves to its value
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_value(&mut self) -> Result<Value> {
-        match self.de.next_() {
-            b'"' => self.de.parse_str_().map(Value::from),
-            b'n' => Ok(Value::Null),
-            b't' => Ok(Value::Bool(true)),
-            b'f' => Ok(Value::Bool(false)),
-            b'-' => self.de.parse_number(true).map(Value::from),
-            b'0'..=b'9' => self.de.parse_number(false).map(Value::from),
-            b'[' => self.parse_array(),
-            b'{' => self.parse_map(),
-            _c => Err(self.de.error(ErrorType::UnexpectedCharacter)),
-        }
+    fn parse_key(&mut self) -> Result<Key> {
+        self.de.skip();
+        let key = stry!(self.de.parse_str());
+        // We have to call parse short str twice since parse_short_str
+        // does not move the cursor forward
+        self.de.skip();
+        Ok(match self.de.options.key_case {
+            Some(case) => self.keys.intern(&case.apply(key)),
+            None => self.keys.intern(key),
+        })
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_array(&mut self) -> Result<Value> {
-        let es = self.de.count_elements();
-        if unlikely!(es == 0) {
-            self.de.skip();
-            return Ok(Value::Array(Vec::new()));
+    fn parse_string(&mut self) -> Result<Value> {
+        let s = stry!(self.de.parse_str());
+        match self.de.options.intern_strings {
+            Some(max) if s.len() <= max => Ok(Value::String(self.keys.intern_str(s))),
+            _ => Ok(Value::from(s)),
         }
-        let mut res = Vec::with_capacity(es);
-
-        for _i in 0..es {
-            res.push(stry!(self.parse_value()));
-            self.de.skip();
-        }
-        Ok(Value::Array(res))
     }
 
     #[cfg_attr(not(feature = "no-inline"), inline(always))]
-    fn parse_map(&mut self) -> Result<Value> {
-        // We short cut for empty arrays
-        let es = self.de.count_elements();
-
-        if unlikely!(es == 0) {
-            self.de.skip();
-            return Ok(Value::Object(Object::new()));
+    fn parse_number(&mut self, minus: bool, root: bool) -> Result<Value> {
+        if unlikely!(self.de.options.lossless_numbers) {
+            return self.parse_raw_number(minus, root);
         }
-
-        let mut res = Object::with_capacity(es);
-
-        // Since we checked if it's empty we know that we at least have one
-        // element so we eat this
-
-        for _ in 0..es {
-            self.de.skip();
-            let key = stry!(self.de.parse_str_());
-            // We have to call parse short str twice since parse_short_str
-            // does not move the cursor forward
-            self.de.skip();
-            res.insert_nocheck(key.into(), stry!(self.parse_value()));
-            self.de.skip();
+        let n = if root {
+            self.de.parse_number_root(minus)
+        } else {
+            self.de.parse_number(minus)
+        };
+        match n {
+            Err(ref e)
+                if e.error() == &ErrorType::Overflow
+                    && self.de.options.big_numbers == BigNumberBehavior::Raw =>
+            {
+                self.parse_raw_number(minus, root)
+            }
+            n => n.map(Value::from),
         }
-        Ok(Value::Object(res))
+    }
+
+    #[inline(never)]
+    fn parse_raw_number(&mut self, minus: bool, root: bool) -> Result<Value> {
+        self.de
+            .parse_number_raw(minus, root)
+            .map(|n| Value::RawNumber(n.to_owned()))
     }
 }
 
@@ -557,7 +1016,7 @@ mod test {
 
     #[test]
     fn conversions_object() {
-        let v = Value::from(Object::new());
+        let v = Value::from(Object::default());
         assert!(v.is_object());
         assert_eq!(v.value_type(), ValueType::Object);
     }
@@ -571,10 +1030,10 @@ mod test {
     use proptest::prelude::*;
     fn arb_value() -> BoxedStrategy<Value> {
         let leaf = prop_oneof![
-            Just(Value::Null),
-            any::<bool>().prop_map(Value::Bool),
-            any::<i64>().prop_map(Value::I64),
-            any::<f64>().prop_map(Value::F64),
+            Just(Value::Static(StaticNode::Null)),
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            any::<f64>().prop_map(Value::from),
             ".*".prop_map(Value::from),
         ];
         leaf.prop_recursive(
@@ -586,7 +1045,7 @@ mod test {
                     // Take the inner strategy and make the two recursive cases.
                     prop::collection::vec(inner.clone(), 0..10).prop_map(Value::Array),
                     prop::collection::hash_map(".*", inner.clone(), 0..10)
-                        .prop_map(|m| Value::Object(m.into_iter().collect())),
+                        .prop_map(|m| m.into_iter().collect::<Value>()),
                 ]
             },
         )
@@ -695,4 +1154,624 @@ mod test {
         let v: Value = false.into();
         assert_eq!(v, false);
     }
+
+    #[test]
+    fn index_mut() {
+        let mut v = Value::Static(StaticNode::Null);
+        v["a"]["b"] = Value::from(1);
+        v["c"] = Value::from("d");
+        assert_eq!(v["a"]["b"], 1);
+        assert_eq!(v["c"], "d");
+        assert_eq!(v["e"], Value::Static(StaticNode::Null));
+        v["a"]["b"] = Value::from(2);
+        assert_eq!(v, json!({"a": {"b": 2}, "c": "d"}));
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_mut_scalar() {
+        let mut v = Value::from(1);
+        v["a"] = Value::Static(StaticNode::Null);
+    }
+
+    fn generic_index<V: ValueTrait>(v: &mut V) {
+        v["x"] = V::from(true);
+    }
+
+    #[test]
+    fn index_via_trait() {
+        let mut v = Value::Static(StaticNode::Null);
+        generic_index(&mut v);
+        assert_eq!(v["x"], true);
+        let mut v = crate::BorrowedValue::Static(StaticNode::Null);
+        generic_index(&mut v);
+        assert_eq!(v["x"], true);
+    }
+
+    #[test]
+    fn try_as() {
+        use crate::TryTypeError;
+        let v = Value::from(300);
+        assert_eq!(v.try_as_u16(), Ok(300));
+        assert_eq!(
+            v.try_as_u8(),
+            Err(TryTypeError {
+                expected: ValueType::I64,
+                got: ValueType::I64
+            })
+        );
+        assert_eq!(
+            v.try_as_f64().map_err(|e| e.to_string()),
+            Err("expected float, found integer".to_string())
+        );
+        assert_eq!(Value::from("a").try_as_str(), Ok("a"));
+        assert!(Value::Static(StaticNode::Null).try_as_array().is_err());
+    }
+
+    #[cfg(not(any(feature = "preserve-order", feature = "sorted-objects")))]
+    #[test]
+    fn parsed_object_backend() {
+        let object = |n: usize| {
+            let members: Vec<_> = (0..n).map(|i| format!(r#""k{}": {}"#, i, i)).collect();
+            let mut d = format!("{{{}}}", members.join(", ")).into_bytes();
+            to_value(&mut d).expect("valid")
+        };
+        let v = object(40);
+        assert_eq!(v.get_u64("k39"), Some(39));
+        let o = v.as_object().expect("object");
+        assert_eq!(o.is_vec(), cfg!(feature = "vec-objects"));
+        let v = object(100);
+        assert_eq!(v.get_u64("k99"), Some(99));
+        assert!(v.as_object().expect("object").is_map());
+        let v = object(8);
+        assert!(v.as_object().expect("object").is_vec());
+    }
+
+    #[cfg(feature = "preserve-order")]
+    #[test]
+    fn preserve_order() {
+        let input = r#"{"z":1,"a":{"y":2,"b":[3]},"m":4}"#;
+        let mut d = input.as_bytes().to_vec();
+        let mut v = to_value(&mut d).expect("valid");
+        assert_eq!(v.encode(), input);
+        let mut d = input.as_bytes().to_vec();
+        assert_eq!(
+            crate::to_borrowed_value(&mut d).expect("valid").encode(),
+            input
+        );
+        #[cfg(feature = "serde_impl")]
+        assert_eq!(crate::serde::to_string(&v).expect("valid"), input);
+        assert_eq!(json!({"b": 1, "a": 2}).encode(), r#"{"b":1,"a":2}"#);
+        // order doesn't matter for equality
+        assert_eq!(json!({"b": 1, "a": 2}), json!({"a": 2, "b": 1}));
+        let o = v.as_object_mut().expect("object");
+        o.insert(key("c".to_string()), json!(5));
+        o.shift_remove("a");
+        assert_eq!(v.encode(), r#"{"z":1,"m":4,"c":5}"#);
+    }
+
+    #[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+    #[test]
+    fn sorted_objects() {
+        let input = r#"{"z":1,"a":{"y":2,"b":[3]},"m":4,"a":5}"#;
+        let mut d = input.as_bytes().to_vec();
+        let mut v = to_value(&mut d).expect("valid");
+        // the last of duplicate keys is kept
+        let sorted = r#"{"a":5,"m":4,"z":1}"#;
+        assert_eq!(v.encode(), sorted);
+        let mut d = input.as_bytes().to_vec();
+        assert_eq!(
+            crate::to_borrowed_value(&mut d).expect("valid").encode(),
+            sorted
+        );
+        #[cfg(feature = "serde_impl")]
+        assert_eq!(crate::serde::to_string(&v).expect("valid"), sorted);
+        assert_eq!(json!({"b": 1, "a": 2}).encode(), r#"{"a":2,"b":1}"#);
+        let o = v.as_object_mut().expect("object");
+        o.insert(key("c".to_string()), json!(6));
+        o.remove("m");
+        assert_eq!(v.encode(), r#"{"a":5,"c":6,"z":1}"#);
+        if let Some(e) = v.entry(key("b".to_string())) {
+            e.or_insert_with(|| json!(7));
+        }
+        assert_eq!(v.get_u64("b"), Some(7));
+        assert!(v.mem_size() > std::mem::size_of::<Value>());
+    }
+
+    #[cfg(all(feature = "compact-strings", not(feature = "arc-strings")))]
+    #[test]
+    fn compact_strings() {
+        let mut d = br#"["short", "a string that is too long to be inlined"]"#.to_vec();
+        let v = to_value(&mut d).expect("valid");
+        let (short, long) = (v.get_idx(0).expect("short"), v.get_idx(1).expect("long"));
+        let value_size = std::mem::size_of::<Value>();
+        assert_eq!(short.mem_size(), value_size);
+        assert!(long.mem_size() > value_size);
+        match (short, long) {
+            (Value::String(s1), Value::String(s2)) => {
+                assert!(!s1.is_heap_allocated());
+                assert!(s2.is_heap_allocated());
+            }
+            _ => panic!("not a string"),
+        }
+        let mut s = String::with_capacity(64);
+        s.push('x');
+        assert_eq!(Value::from(s).mem_size(), value_size);
+        assert_eq!(
+            v.encode(),
+            r#"["short","a string that is too long to be inlined"]"#
+        );
+        #[cfg(feature = "serde_impl")]
+        {
+            let w: Value = crate::serde::from_str(&mut v.encode()).expect("valid");
+            assert_eq!(v, w);
+            assert_eq!(crate::serde::to_string(&w).expect("valid"), v.encode());
+        }
+        assert_eq!(short.clone().into_string().as_deref(), Ok("short"));
+    }
+
+    #[test]
+    fn hash_eq_ord() {
+        use std::collections::{BTreeSet, HashSet};
+        let records = [
+            json!({"a": 1, "b": [true, null]}),
+            json!({"b": [true, null], "a": 1}),
+            json!({"a": 1.0, "b": [true, null]}),
+            Value::from(f64::NAN),
+            Value::from(f64::NAN),
+            Value::RawNumber("7".into()),
+            Value::from(7),
+        ];
+        let unique: HashSet<&Value> = records.iter().collect();
+        assert_eq!(unique.len(), 4);
+        let sorted: BTreeSet<&Value> = records.iter().collect();
+        assert_eq!(sorted.len(), 4);
+
+        let mut values = vec![
+            json!({}),
+            json!([1, 2]),
+            json!("a"),
+            Value::from(f64::NAN),
+            Value::from(2.5),
+            Value::from(2),
+            Value::from(2.0),
+            Value::from(-1e300),
+            json!([1]),
+            Value::from(true),
+            Value::Static(StaticNode::Null),
+        ];
+        values.sort();
+        assert_eq!(
+            values
+                .iter()
+                .map(|v| match v.as_f64() {
+                    Some(f) if f.is_nan() => "NaN".to_string(),
+                    _ => v.encode(),
+                })
+                .collect::<Vec<_>>(),
+            vec!["null", "true", "-1e300", "2", "2.0", "2.5", "NaN", "\"a\"", "[1]", "[1,2]", "{}"]
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_eq_ord() {
+        use std::cmp::Ordering;
+        // unless the backend removes them parsed objects keep duplicate keys
+        let docs = [
+            r#"{"dup": 1, "dup": 2}"#,
+            r#"{"dup": 2, "dup": 1}"#,
+            r#"{"dup": 1, "dup": 1}"#,
+            r#"{"dup": 1, "other": 1}"#,
+        ];
+        let values: Vec<Value> = docs
+            .iter()
+            .map(|d| to_value(&mut d.as_bytes().to_vec()).expect("valid"))
+            .collect();
+        for a in &values {
+            assert_eq!(a, a);
+            assert_eq!(a, &a.clone());
+            assert_eq!(a.cmp(a), Ordering::Equal);
+            for b in &values {
+                assert_eq!(a == b, a.cmp(b) == Ordering::Equal, "{} {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn exact_floats() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::{BTreeSet, HashSet};
+        use std::hash::{Hash, Hasher};
+        // floats only a few ULPs apart are all distinct
+        let mut f = 1.0_f64;
+        let mut values = Vec::new();
+        for _ in 0..2000 {
+            values.push(Value::from(f));
+            f = f64::from_bits(f.to_bits() + 1);
+        }
+        values.reverse();
+        values.sort();
+        assert!(values.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(values.iter().collect::<BTreeSet<_>>().len(), 2000);
+        assert_eq!(values.iter().collect::<HashSet<_>>().len(), 2000);
+        let hashes: HashSet<u64> = values
+            .iter()
+            .map(|v| {
+                let mut h = DefaultHasher::new();
+                v.hash(&mut h);
+                h.finish()
+            })
+            .collect();
+        assert_eq!(hashes.len(), 2000);
+
+        // NaNs are all equal, `-0.0` is a different value than `0.0`
+        let nan = Value::from(f64::from_bits(f64::NAN.to_bits() | 1));
+        let other_nan = Value::from(f64::NAN);
+        let (neg_zero, zero) = (Value::from(-0.0), Value::from(0.0));
+        assert_eq!(nan, other_nan);
+        assert_ne!(neg_zero, zero);
+        assert!(neg_zero < zero);
+        assert!(neg_zero.approx_eq(&zero));
+        let set: HashSet<Value> = vec![nan, other_nan, neg_zero, zero].into_iter().collect();
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn raw_numbers_by_value() {
+        use std::cmp::Ordering;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let hash = |v: &Value| {
+            let mut h = DefaultHasher::new();
+            v.hash(&mut h);
+            h.finish()
+        };
+        let raw = |s: &str| Value::RawNumber(s.to_string());
+        let values = vec![
+            raw("1.0"),
+            raw("1.00"),
+            raw("10e-1"),
+            Value::from(1.0),
+            raw("1"),
+            Value::from(1),
+            raw("-0"),
+            raw("-0.0"),
+            Value::from(-0.0),
+            raw("0.0"),
+            raw("18446744073709551615"),
+            Value::from(u64::MAX),
+            raw("1.8446744073709552e19"),
+            raw("100000000000000000000000000000000000000000"),
+            raw("1e41"),
+            raw("1e400"),
+            Value::from(f64::NAN),
+        ];
+        // `==`, `Ord` and `Hash` agree and are transitive
+        for a in &values {
+            for b in &values {
+                assert_eq!(a == b, a.cmp(b) == Ordering::Equal, "{} {}", a, b);
+                assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{} {}", a, b);
+                if a == b {
+                    assert_eq!(hash(a), hash(b), "{} {}", a, b);
+                }
+                for c in &values {
+                    if a == b && b == c {
+                        assert_eq!(a, c);
+                    }
+                    if a <= b && b <= c {
+                        assert!(a <= c, "{} {} {}", a, b, c);
+                    }
+                }
+            }
+        }
+        assert_eq!(raw("1.0"), raw("1.00"));
+        assert_eq!(raw("1.0"), Value::from(1.0));
+        assert_eq!(raw("-0"), raw("0"));
+        assert_ne!(raw("-0.0"), raw("0.0"));
+        assert_ne!(raw("1.0"), raw("1"));
+        assert!(raw("1") < raw("1.0"));
+        assert!(raw("1.00") < raw("2"));
+    }
+
+    #[test]
+    fn contains_and_find_all() {
+        let v = json!({"a": [{"id": 1}, {"id": 2, "x": null}], "~": "id"});
+        assert!(v.contains(&2));
+        assert!(v.contains(&()));
+        assert!(v.contains(&json!({"id": 1})));
+        assert!(!v.contains(&3));
+        assert!(Value::from(3).contains(&3));
+        let mut ids: Vec<_> = v
+            .find_all(|v| v.get("id").is_some())
+            .into_iter()
+            .map(|(pointer, v)| (pointer, v["id"].as_u64()))
+            .collect();
+        ids.sort();
+        assert_eq!(
+            ids,
+            vec![("/a/0".to_string(), Some(1)), ("/a/1".to_string(), Some(2))]
+        );
+        let found = v.find_all(|v| v == "id");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "/~0");
+        assert_eq!(v.find_all(|_| true).len(), 8);
+    }
+
+    #[test]
+    fn walk_mut_and_map_values() {
+        let mut v = json!({"a": [1, {"b": 2}], "c/d": 3});
+        let mut visited = Vec::new();
+        v.walk_mut(|pointer, v| {
+            visited.push(pointer.to_string());
+            if pointer == "/a/1" {
+                *v = json!([4, 5]);
+            }
+        });
+        visited.sort();
+        assert_eq!(
+            visited,
+            vec!["", "/a", "/a/0", "/a/1", "/a/1/0", "/a/1/1", "/c~1d"]
+        );
+        assert_eq!(v, json!({"a": [1, [4, 5]], "c/d": 3}));
+
+        let mut order = Vec::new();
+        v.map_values(|pointer, v| {
+            order.push(pointer.to_string());
+            match v.as_i64() {
+                Some(n) => (n * 10).into(),
+                None if v.is_array() => v.as_array().map_or(0, Vec::len).into(),
+                None => v,
+            }
+        });
+        assert_eq!(v, json!({"a": 2, "c/d": 30}));
+        assert_eq!(order.last().map(String::as_str), Some(""));
+        assert!(order.iter().position(|p| p == "/a/1/0") < order.iter().position(|p| p == "/a"));
+
+        let mut d = br#"["x", ["y"]]"#.to_vec();
+        let mut v = crate::to_borrowed_value(&mut d).expect("valid");
+        v.map_values(|_, v| if v.is_str() { "z".into() } else { v });
+        assert_eq!(v, json!(["z", ["z"]]));
+    }
+
+    #[test]
+    fn compact() {
+        use crate::CompactOptions;
+        let mut v = json!({"a": [{"b": null}, [], 0], "c": {"d": {}}, "e": ""});
+        let options = CompactOptions {
+            nulls: false,
+            ..CompactOptions::default()
+        };
+        assert_eq!(v.clone().compact_with_options(&options), 2);
+        let options = CompactOptions {
+            empty_arrays: false,
+            in_arrays: true,
+            ..CompactOptions::default()
+        };
+        let mut w = v.clone();
+        assert_eq!(w.compact_with_options(&options), 4);
+        assert_eq!(w, json!({"a": [[], 0], "e": ""}));
+        assert_eq!(v.compact(), 3);
+        assert_eq!(v, json!({"a": [{}, [], 0], "e": ""}));
+        let mut d = br#"[null, {"x": null}]"#.to_vec();
+        let mut v = crate::to_borrowed_value(&mut d).expect("valid");
+        assert_eq!(v.compact(), 1);
+        assert_eq!(v, json!([null, {}]));
+        assert_eq!(Value::Static(StaticNode::Null).compact(), 0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "preserve-order", allow(deprecated))]
+    fn into_containers() {
+        let v = json!({"a": [1, "x"], "s": "str"});
+        let mut o = v.into_object().expect("object");
+        let a = o.remove("a").expect("a").into_array().expect("array");
+        assert_eq!(a.len(), 2);
+        let n = a.into_iter().next().expect("element");
+        let n = n.into_string().expect_err("not a string");
+        assert_eq!(n, 1);
+        let e = n.try_into_object().expect_err("not an object");
+        assert_eq!(e.expected, ValueType::Object);
+        assert_eq!(e.got, ValueType::I64);
+        let s = o.remove("s").expect("s").try_into_string();
+        assert_eq!(s, Ok("str".to_string()));
+        let mut d = br#"["borrowed"]"#.to_vec();
+        let v = crate::to_borrowed_value(&mut d).expect("valid");
+        let mut a = v.try_into_array().expect("array");
+        assert_eq!(
+            a.pop().expect("element").into_string().ok(),
+            Some("borrowed".into())
+        );
+    }
+
+    #[test]
+    fn take_and_replace() {
+        let mut v = json!({"a": {"b": 1}, "c": [true]});
+        let b = v["a"]["b"].take();
+        assert_eq!(b, 1);
+        let c = v["c"].replace(b);
+        assert_eq!(c, json!([true]));
+        assert_eq!(v, json!({"a": {"b": null}, "c": 1}));
+        let mut d = br#"{"x": "y"}"#.to_vec();
+        let mut v = crate::to_borrowed_value(&mut d).expect("valid");
+        assert_eq!(v.take(), json!({"x": "y"}));
+        assert!(v.is_null());
+    }
+
+    #[test]
+    fn collect_and_extend() {
+        let mut v: Value = (1..3).collect();
+        v.extend(vec!["x"]);
+        assert_eq!(v, json!([1, 2, "x"]));
+        let mut v: Value = vec![("a", 1)].into_iter().collect();
+        v.extend(vec![("b", true)]);
+        assert_eq!(v, json!({"a": 1, "b": true}));
+        let mut v = Value::Static(StaticNode::Null);
+        v.extend(vec![("c", "d")]);
+        assert_eq!(v, json!({"c": "d"}));
+        let mut v = crate::BorrowedValue::Static(StaticNode::Null);
+        v.extend(vec![1, 2]);
+        assert_eq!(v, json!([1, 2]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn extend_mismatch() {
+        let mut v = json!({});
+        v.extend(vec![1]);
+    }
+
+    #[test]
+    fn from_collections() {
+        use std::collections::{BTreeMap, HashMap};
+        let mut m = HashMap::new();
+        m.insert("a", Some(1));
+        m.insert("b", None);
+        assert_eq!(Value::from(m), json!({"a": 1, "b": null}));
+        let mut m = BTreeMap::new();
+        m.insert("c".to_string(), vec![true]);
+        assert_eq!(Value::from(m.clone()), json!({"c": [true]}));
+        assert_eq!(crate::BorrowedValue::from(m), json!({"c": [true]}));
+        let s: &[u8] = &[1, 2];
+        assert_eq!(Value::from(s), json!([1, 2]));
+        assert_eq!(crate::BorrowedValue::from(&["x"][..]), json!(["x"]));
+        assert_eq!(Value::from(Some("y")), "y");
+    }
+
+    #[test]
+    fn try_from_value() {
+        use std::collections::HashMap;
+        use std::convert::{TryFrom, TryInto};
+        let v: Vec<u8> = json!([1, 2]).try_into().expect("bytes");
+        assert_eq!(v, vec![1, 2]);
+        let e = Vec::<u8>::try_from(json!([1, 256])).expect_err("too big");
+        assert_eq!(e.to_string(), "integer out of range");
+        let e = Vec::<u8>::try_from(json!({})).expect_err("object");
+        assert_eq!(e.to_string(), "expected array, found object");
+        let m: HashMap<String, Vec<String>> = json!({"a": ["x"]}).try_into().expect("map");
+        assert_eq!(m["a"], vec!["x".to_string()]);
+        assert_eq!(f64::try_from(json!(1)), Ok(1.0));
+        assert_eq!(bool::try_from(json!(true)), Ok(true));
+        let e = String::try_from(json!(null)).expect_err("null");
+        assert_eq!((e.expected, e.got), (ValueType::String, ValueType::Null));
+    }
+
+    #[test]
+    fn key_case() {
+        use crate::{KeyCase, ParserOptions, SerializeOptions};
+        let options = ParserOptions {
+            key_case: Some(KeyCase::SnakeCase),
+            ..ParserOptions::default()
+        };
+        let mut d = br#"{"userId": 1, "user_id": 2, "Nested": [{"HTTPCode": 3}]}"#.to_vec();
+        let v = crate::to_owned_value_with_options(&mut d, &options).expect("valid");
+        assert_eq!(v, json!({"user_id": 2, "nested": [{"http_code": 3}]}));
+        let mut d = br#"{"a-b": {"c d": null}}"#.to_vec();
+        let v = crate::to_borrowed_value_with_options(&mut d, &options).expect("valid");
+        assert_eq!(v, json!({"a_b": {"c_d": null}}));
+        let options = SerializeOptions {
+            key_case: Some(KeyCase::CamelCase),
+            ..SerializeOptions::default()
+        };
+        assert_eq!(v.encode_with_options(&options), r#"{"aB":{"cD":null}}"#);
+    }
+
+    #[test]
+    fn get_ignore_case() {
+        use crate::{KeyCase, ParserOptions};
+        let mut d = br#"{"X-Id": 1, "x-id": 2, "Accept": {"Q": 3}}"#.to_vec();
+        let v = crate::to_owned_value(&mut d).expect("valid");
+        assert_eq!(v.get_ignore_case("X-Id"), Some(&json!(1)));
+        assert_eq!(v.get_ignore_case("x-id"), Some(&json!(2)));
+        assert!(v.get_ignore_case("X-ID").is_some());
+        assert_eq!(
+            v.get_ignore_case("ACCEPT")
+                .and_then(|a| a.get_ignore_case("q")),
+            Some(&json!(3))
+        );
+        assert_eq!(v.get_ignore_case("Accept-Encoding"), None);
+        assert_eq!(json!([1]).get_ignore_case("a"), None);
+        let options = ParserOptions {
+            key_case: Some(KeyCase::Lowercase),
+            ..ParserOptions::default()
+        };
+        let mut d = br#"{"Content_Type": "a", "ETag": "b"}"#.to_vec();
+        let v = crate::to_borrowed_value_with_options(&mut d, &options).expect("valid");
+        assert_eq!(v, json!({"content_type": "a", "etag": "b"}));
+        assert_eq!(v.get_ignore_case("ETag"), Some(&json!("b").into()));
+    }
+
+    #[test]
+    fn mem_size() {
+        let value_size = std::mem::size_of::<Value>();
+        assert_eq!(json!(1).mem_size(), value_size);
+        #[cfg(not(any(feature = "arc-strings", feature = "compact-strings")))]
+        {
+            let mut s = String::with_capacity(64);
+            s.push('x');
+            let mut v = Value::from(s);
+            assert!(v.mem_size() >= value_size + 64);
+            v.shrink_to_fit();
+            assert!(v.mem_size() < value_size + 64);
+        }
+        let mut d = br#"{"a": 1, "b": 2, "c": 3, "d": 4, "e": 5, "f": 6, "g": 7, "h": 8, "i": 9, "j": 10, "k": 11, "l": 12, "m": 13, "n": 14, "o": 15, "p": 16, "q": 17, "r": 18, "s": 19, "t": 20, "u": 21, "v": 22, "w": 23, "x": 24, "y": 25, "z": 26, "aa": 27, "ab": 28, "ac": 29, "ad": 30, "ae": 31, "af": 32, "ag": 33}"#.to_vec();
+        let v = to_value(&mut d).expect("valid");
+        let entries = 33 * std::mem::size_of::<(Key, Value)>();
+        assert!(v.mem_size() >= value_size + entries);
+        #[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+        {
+            let mut v = v;
+            let size = v.mem_size();
+            v.as_object_mut().expect("object").reserve(1000);
+            assert!(v.mem_size() >= size + 1000 * std::mem::size_of::<(Key, Value)>());
+            v.shrink_to_fit();
+            assert!(v.mem_size() <= size);
+        }
+    }
+
+    #[test]
+    fn lenient() {
+        let v = json!({"n": "-3", "f": "1e3", "nan": "NaN", "b": "false", "z": 0, "x": "yes"});
+        assert_eq!(v["n"].as_i64_lenient(), Some(-3));
+        assert_eq!(v["n"].as_f64_lenient(), Some(-3.0));
+        assert_eq!(v["f"].as_i64_lenient(), None);
+        assert_eq!(v["f"].as_f64_lenient(), Some(1000.0));
+        assert_eq!(v["nan"].as_f64_lenient(), None);
+        assert_eq!(v["b"].as_bool_lenient(), Some(false));
+        assert_eq!(v["z"].as_bool_lenient(), Some(false));
+        assert_eq!(v["x"].as_bool_lenient(), None);
+        assert_eq!(v["missing"].as_i64_lenient(), None);
+    }
+
+    #[test]
+    fn into_iter() {
+        let elements: Vec<_> = json!([1, "a"]).into_iter().collect();
+        assert_eq!(elements, vec![(None, json!(1)), (None, json!("a"))]);
+        for (k, v) in json!({"k": true}) {
+            assert_eq!(k.as_deref(), Some("k"));
+            assert_eq!(v, true);
+        }
+        assert_eq!(json!(1).into_iter().count(), 0);
+        let mut d = br#"{"b": [null]}"#.to_vec();
+        let v = crate::to_borrowed_value(&mut d).expect("valid");
+        let (k, v) = v.into_iter().next().expect("member");
+        assert_eq!(k.as_deref(), Some("b"));
+        assert_eq!(v.into_iter().count(), 1);
+    }
+
+    #[cfg(feature = "arc-strings")]
+    #[test]
+    fn shared_strings() {
+        use std::sync::Arc;
+        let v = json!({"msg": "a long message"});
+        let w = v.clone();
+        match (&v["msg"], &w["msg"]) {
+            (Value::String(s1), Value::String(s2)) => assert!(Arc::ptr_eq(s1, s2)),
+            _ => panic!("not a string"),
+        }
+        assert_eq!(w.into_string(), Err(json!({"msg": "a long message"})));
+        assert_eq!(
+            v["msg"].clone().into_string().as_deref(),
+            Ok("a long message")
+        );
+    }
 }
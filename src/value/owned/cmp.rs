@@ -1,24 +1,34 @@
-use super::Value;
-use crate::{BorrowedValue, ValueTrait};
-
+use super::{Key, Object, Value};
+use crate::value::{raw_number_eq, Num};
+use crate::{BorrowedValue, StaticNode, ValueAccess, ValueType};
 use float_cmp::approx_eq;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 impl PartialEq<BorrowedValue<'_>> for Value {
     fn eq(&self, other: &BorrowedValue<'_>) -> bool {
         #[allow(clippy::default_trait_access)]
         match (self, other) {
-            (Self::Null, BorrowedValue::Null) => true,
-            (Self::Bool(v1), BorrowedValue::Bool(v2)) => v1.eq(v2),
-            (Self::I64(v1), BorrowedValue::I64(v2)) => v1.eq(v2),
-            (Self::F64(v1), BorrowedValue::F64(v2)) => approx_eq!(f64, *v1, *v2),
-            (Self::String(v1), BorrowedValue::String(v2)) => v1.eq(v2),
+            (Self::Static(StaticNode::Null), BorrowedValue::Static(StaticNode::Null)) => true,
+            (Self::Static(StaticNode::Bool(v1)), BorrowedValue::Static(StaticNode::Bool(v2))) => {
+                v1.eq(v2)
+            }
+            (
+                Self::Static(StaticNode::Number(v1)),
+                BorrowedValue::Static(StaticNode::Number(v2)),
+            ) => v1.eq(v2),
+            (Self::String(v1), BorrowedValue::String(v2)) => **v1 == **v2,
             (Self::Array(v1), BorrowedValue::Array(v2)) => v1.eq(v2),
             (Self::Object(v1), BorrowedValue::Object(v2)) => {
                 if v1.len() != v2.len() {
                     return false;
                 }
                 v1.iter()
-                    .all(|(key, value)| v2.get(key.as_str()).map_or(false, |v| value == v))
+                    .all(|(key, value)| v2.get(&**key).map_or(false, |v| value == v))
+            }
+            (Self::RawNumber(_), _) | (_, BorrowedValue::RawNumber(_)) => {
+                raw_number_eq(self, other)
             }
             _ => false,
         }
@@ -29,18 +39,166 @@ impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         #[allow(clippy::default_trait_access)]
         match (self, other) {
-            (Self::Null, Self::Null) => true,
-            (Self::Bool(v1), Self::Bool(v2)) => v1.eq(v2),
-            (Self::I64(v1), Self::I64(v2)) => v1.eq(v2),
-            (Self::F64(v1), Self::F64(v2)) => approx_eq!(f64, *v1, *v2),
+            (Self::Static(StaticNode::Null), Self::Static(StaticNode::Null)) => true,
+            (Self::Static(StaticNode::Bool(v1)), Self::Static(StaticNode::Bool(v2))) => v1.eq(v2),
+            (Self::Static(StaticNode::Number(v1)), Self::Static(StaticNode::Number(v2))) => {
+                v1.eq(v2)
+            }
             (Self::String(v1), Self::String(v2)) => v1.eq(v2),
             (Self::Array(v1), Self::Array(v2)) => v1.eq(v2),
-            (Self::Object(v1), Self::Object(v2)) => v1.eq(v2),
+            // parsed objects can hold duplicate keys, comparing the sorted
+            // members keeps this consistent with `Ord`
+            (Self::Object(v1), Self::Object(v2)) => {
+                v1.len() == v2.len() && sorted(v1) == sorted(v2)
+            }
+            (Self::RawNumber(_), _) | (_, Self::RawNumber(_)) => raw_number_eq(self, other),
             _ => false,
         }
     }
 }
 
+/// Values are `Eq` since floats are compared by their bits: all NaNs are
+/// equal to each other and `-0.0` is not equal to `0.0`. Use `approx_eq`
+/// to compare floats within a few ULPs.
+impl Eq for Value {}
+
+impl Value {
+    /// Same as `==` but floats are equal if they are within a few ULPs of
+    /// each other, so `0.1 + 0.2` matches `0.3` and `-0.0` matches `0.0`.
+    /// Unlike `==` this isn't transitive, so it can't back `Eq` or `Hash`.
+    ///
+    /// ```
+    /// use simd_json::{json, OwnedValue};
+    /// let a = json!({"x": [0.1 + 0.2]});
+    /// assert_ne!(a, json!({"x": [0.3]}));
+    /// assert!(a.approx_eq(&json!({"x": [0.3]})));
+    /// assert!(!a.approx_eq(&json!({"x": [0.4]})));
+    /// ```
+    pub fn approx_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Array(v1), Self::Array(v2)) => {
+                v1.len() == v2.len() && v1.iter().zip(v2).all(|(a, b)| a.approx_eq(b))
+            }
+            (Self::Object(v1), Self::Object(v2)) => {
+                v1.len() == v2.len()
+                    && v1
+                        .iter()
+                        .all(|(k, v)| v2.get(k).map_or(false, |v2| v.approx_eq(v2)))
+            }
+            _ if self.value_type() == ValueType::F64 && other.value_type() == ValueType::F64 => {
+                match (self.cast_f64(), other.cast_f64()) {
+                    (Some(v1), Some(v2)) => (v1.is_nan() && v2.is_nan()) || approx_eq!(f64, v1, v2),
+                    _ => false,
+                }
+            }
+            _ => self == other,
+        }
+    }
+}
+
+// Compares an integer with a float, NaN is greater than all numbers
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn cmp_int_float(i: i128, f: f64) -> Ordering {
+    if f.is_nan() || f >= 1.7e38 {
+        Ordering::Less
+    } else if f <= -1.7e38 {
+        Ordering::Greater
+    } else {
+        let t = f.trunc();
+        i.cmp(&(t as i128))
+            .then_with(|| t.partial_cmp(&f).unwrap_or(Ordering::Equal))
+    }
+}
+
+// Orders numbers by value, only numbers of the same value that aren't
+// equal, an integer and a float, are ordered by their kind
+fn cmp_num(a: &Value, b: &Value) -> Ordering {
+    match (Num::of(a), Num::of(b)) {
+        (Num::Int(i1), Num::Int(i2)) => i1.cmp(&i2),
+        (Num::Int(i), Num::Float(f)) => cmp_int_float(i, f).then(Ordering::Less),
+        (Num::Float(f), Num::Int(i)) => cmp_int_float(i, f).reverse().then(Ordering::Greater),
+        (Num::Float(f1), Num::Float(f2)) => match (f1.is_nan(), f2.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            _ => f1.total_cmp(&f2),
+        },
+    }
+}
+
+fn rank(v: &Value) -> u8 {
+    match v.value_type() {
+        ValueType::Null => 0,
+        ValueType::Bool => 1,
+        ValueType::I64 | ValueType::F64 => 2,
+        ValueType::String => 3,
+        ValueType::Array => 4,
+        ValueType::Object => 5,
+    }
+}
+
+// The members of an object sorted by key and, for duplicate keys, by value
+fn sorted(o: &Object) -> Vec<(&Key, &Value)> {
+    let mut entries: Vec<_> = o.iter().collect();
+    entries.sort();
+    entries
+}
+
+/// A total order over values, consistent with `==`: `null` < booleans <
+/// numbers < strings < arrays < objects. Numbers are ordered by value
+/// (NaN last, `-0.0` before `0.0`, integers before floats of the same
+/// value), arrays lexicographically and objects by their members sorted by
+/// key and value.
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Static(StaticNode::Bool(v1)), Self::Static(StaticNode::Bool(v2))) => v1.cmp(v2),
+            (Self::String(v1), Self::String(v2)) => v1.cmp(v2),
+            (Self::Array(v1), Self::Array(v2)) => v1.cmp(v2),
+            (Self::Object(v1), Self::Object(v2)) => sorted(v1).cmp(&sorted(v2)),
+            _ => match rank(self).cmp(&rank(other)) {
+                Ordering::Equal if rank(self) == 2 => cmp_num(self, other),
+                o => o,
+            },
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Hashes consistently with `==`: integers by value, floats by their bits
+/// with all NaNs hashing the same. Objects are hashed independently of
+/// their members' order.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        rank(self).hash(state);
+        match self {
+            Self::Static(StaticNode::Null) => (),
+            Self::Static(StaticNode::Bool(b)) => b.hash(state),
+            Self::Static(StaticNode::Number(_)) | Self::RawNumber(_) => match Num::of(self) {
+                Num::Int(i) => i.hash(state),
+                Num::Float(f) if f.is_nan() => f64::NAN.to_bits().hash(state),
+                Num::Float(f) => f.to_bits().hash(state),
+            },
+            Self::String(s) => s.hash(state),
+            Self::Array(a) => a.hash(state),
+            Self::Object(o) => {
+                o.len().hash(state);
+                let members = o.iter().fold(0_u64, |sum, m| {
+                    let mut h = DefaultHasher::new();
+                    m.hash(&mut h);
+                    sum.wrapping_add(h.finish())
+                });
+                members.hash(state);
+            }
+        }
+    }
+}
+
 impl<T> PartialEq<&T> for Value
 where
     Value: PartialEq<T>,
@@ -0,0 +1,253 @@
+use super::{Key, Value};
+use crate::pointer::{array_index, tokens};
+use crate::value::{ObjectMap, ValueAccess, ValueTrait, ValueType};
+
+/// A copy-on-write view of an `OwnedValue`.
+///
+/// It starts out borrowing a (shared) document and only copies what is
+/// modified: getting a mutable reference to a member of an array or object
+/// turns just that container into a list of borrowed members, so changing
+/// one field of a large document copies the containers on the path to it
+/// instead of the whole document.
+///
+/// ```
+/// use simd_json::{json, CowValue};
+/// let doc = json!({"user": {"name": "ann", "roles": ["admin"]}, "items": [1, 2, 3]});
+/// let mut v = CowValue::new(&doc);
+/// if let Some(name) = v.pointer_mut("/user/name") {
+///     name.set("bob");
+/// }
+/// assert!(v.get_mut("items").unwrap().is_borrowed());
+/// assert_eq!(v, json!({"user": {"name": "bob", "roles": ["admin"]}, "items": [1, 2, 3]}));
+/// assert_eq!(doc["user"]["name"], "ann");
+/// ```
+#[derive(Debug, Clone)]
+pub enum CowValue<'v> {
+    /// a value of the shared document
+    Borrowed(&'v Value),
+    /// a value that was set or copied
+    Owned(Value),
+    /// an array some members of which were modified
+    Array(Vec<CowValue<'v>>),
+    /// an object some members of which were modified
+    Object(ObjectMap<Key, CowValue<'v>>),
+}
+
+impl<'v> CowValue<'v> {
+    /// Creates a view borrowing `value`
+    pub fn new(value: &'v Value) -> Self {
+        Self::Borrowed(value)
+    }
+
+    /// Returns true if the value still is a reference into the shared
+    /// document
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            Self::Borrowed(_) => true,
+            _ => false,
+        }
+    }
+
+    /// The value, unless parts of it were copied into the view
+    pub fn as_value(&self) -> Option<&Value> {
+        match self {
+            Self::Borrowed(v) => Some(v),
+            Self::Owned(v) => Some(v),
+            Self::Array(_) | Self::Object(_) => None,
+        }
+    }
+
+    /// The type of the value
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Self::Borrowed(v) => v.value_type(),
+            Self::Owned(v) => v.value_type(),
+            Self::Array(_) => ValueType::Array,
+            Self::Object(_) => ValueType::Object,
+        }
+    }
+
+    /// Replaces the value
+    pub fn set<V>(&mut self, value: V)
+    where
+        V: Into<Value>,
+    {
+        *self = Self::Owned(value.into());
+    }
+
+    /// The members of the value if it is an array, copying only the array
+    /// itself, the members keep borrowing from the document
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        let members = match self {
+            Self::Array(a) => return Some(a),
+            Self::Borrowed(Value::Array(a)) => a.iter().map(Self::Borrowed).collect(),
+            Self::Owned(v @ Value::Array(_)) => match v.take() {
+                Value::Array(a) => a.into_iter().map(Self::Owned).collect(),
+                _ => unreachable!(),
+            },
+            _ => return None,
+        };
+        *self = Self::Array(members);
+        match self {
+            Self::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// The members of the value if it is an object, copying only the
+    /// object itself, the members keep borrowing from the document
+    pub fn as_object_mut(&mut self) -> Option<&mut ObjectMap<Key, Self>> {
+        let members = match self {
+            Self::Object(o) => return Some(o),
+            Self::Borrowed(Value::Object(o)) => o
+                .iter()
+                .map(|(k, v)| (k.clone(), Self::Borrowed(v)))
+                .collect(),
+            Self::Owned(v @ Value::Object(_)) => match v.take() {
+                Value::Object(o) => o.into_iter().map(|(k, v)| (k, Self::Owned(v))).collect(),
+                _ => unreachable!(),
+            },
+            _ => return None,
+        };
+        *self = Self::Object(members);
+        match self {
+            Self::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Gets a mutable reference to the member `key` if the value is an
+    /// object
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Self> {
+        self.as_object_mut().and_then(|o| o.get_mut(key))
+    }
+
+    /// Gets a mutable reference to the element `i` if the value is an
+    /// array
+    pub fn get_idx_mut(&mut self, i: usize) -> Option<&mut Self> {
+        self.as_array_mut().and_then(|a| a.get_mut(i))
+    }
+
+    /// Gets a mutable reference to the value referenced by a JSON pointer
+    /// ([RFC 6901]), copying only the containers along the path.
+    ///
+    /// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Self> {
+        let mut value = self;
+        for token in tokens(pointer)? {
+            value = match value.value_type() {
+                ValueType::Object => value.get_mut(&token)?,
+                ValueType::Array => value.get_idx_mut(array_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// Converts the view into a value, cloning everything still borrowed
+    pub fn into_value(self) -> Value {
+        match self {
+            Self::Borrowed(v) => v.clone(),
+            Self::Owned(v) => v,
+            Self::Array(a) => Value::Array(a.into_iter().map(Self::into_value).collect()),
+            Self::Object(o) => {
+                Value::Object(o.into_iter().map(|(k, v)| (k, v.into_value())).collect())
+            }
+        }
+    }
+
+    /// Clones the view into a value
+    pub fn to_value(&self) -> Value {
+        self.clone().into_value()
+    }
+}
+
+impl<'v> From<&'v Value> for CowValue<'v> {
+    #[inline]
+    fn from(v: &'v Value) -> Self {
+        Self::Borrowed(v)
+    }
+}
+
+impl<'v> From<Value> for CowValue<'v> {
+    #[inline]
+    fn from(v: Value) -> Self {
+        Self::Owned(v)
+    }
+}
+
+impl<'v> PartialEq<Value> for CowValue<'v> {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Self::Borrowed(v), _) => *v == other,
+            (Self::Owned(v), _) => v == other,
+            (Self::Array(a1), Value::Array(a2)) => {
+                a1.len() == a2.len() && a1.iter().zip(a2).all(|(v1, v2)| v1 == v2)
+            }
+            (Self::Object(o1), Value::Object(o2)) => {
+                o1.len() == o2.len()
+                    && o1
+                        .iter()
+                        .all(|(k, v1)| o2.get(&**k).map_or(false, |v2| v1 == v2))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(feature = "preserve-order", allow(deprecated))]
+    fn copy_on_write() {
+        let doc = json!({"a": [1, {"b": 2}], "c": "x", "d": {"e": null}});
+        let mut v = CowValue::new(&doc);
+        assert!(v.pointer_mut("/a/2").is_none());
+        assert!(v.pointer_mut("/c/0").is_none());
+        v.pointer_mut("/a/1/b").unwrap().set(3);
+        v.get_mut("d")
+            .and_then(CowValue::as_object_mut)
+            .unwrap()
+            .insert("f".into(), json!(true).into());
+        v.as_object_mut().unwrap().remove("c");
+        assert!(v
+            .get_mut("a")
+            .unwrap()
+            .get_idx_mut(0)
+            .unwrap()
+            .is_borrowed());
+        assert_eq!(v.value_type(), ValueType::Object);
+        assert!(v.as_value().is_none());
+        let expected = json!({"a": [1, {"b": 3}], "d": {"e": null, "f": true}});
+        assert_eq!(v, expected);
+        assert_eq!(v.to_value(), expected);
+        assert_eq!(v.into_value(), expected);
+        assert_eq!(doc["a"].get_idx(1).unwrap()["b"], 2);
+    }
+
+    #[test]
+    fn owned() {
+        let mut v = CowValue::from(json!({"a": [1, 2]}));
+        v.get_mut("a")
+            .unwrap()
+            .as_array_mut()
+            .unwrap()
+            .push(json!(3).into());
+        assert_eq!(v, json!({"a": [1, 2, 3]}));
+        assert_ne!(v, json!({"a": [1, 2]}));
+        assert_ne!(v, json!([1, 2, 3]));
+    }
+
+    #[cfg(feature = "serde_impl")]
+    #[test]
+    fn serialize() {
+        let doc = json!({"a": [1, "x"], "b": null});
+        let mut v = CowValue::new(&doc);
+        v.pointer_mut("/a/1").unwrap().set("y");
+        let s = crate::serde::to_string(&v).unwrap();
+        let mut d = s.into_bytes();
+        assert_eq!(v, crate::to_owned_value(&mut d).unwrap());
+    }
+}
@@ -1,33 +1,54 @@
-use super::{Object, Value};
-use crate::numberparse::Number;
-use crate::BorrowedValue;
+use super::{key, key_into_string, Key, Object, Str, Value};
+use crate::value::type_error;
+use crate::{BorrowedValue, Number, StaticNode, TryTypeError, ValueAccess, ValueTrait, ValueType};
 use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::hash::BuildHasher;
 use std::iter::FromIterator;
 
+impl From<StaticNode> for Value {
+    #[inline]
+    fn from(s: StaticNode) -> Self {
+        Self::Static(s)
+    }
+}
+
 impl From<Number> for Value {
     #[inline]
     fn from(n: Number) -> Self {
-        match n {
-            Number::F64(n) => Self::F64(n),
-            Number::I64(n) => Self::I64(n),
-        }
+        Self::Static(n.into())
     }
 }
 
 impl From<crate::BorrowedValue<'_>> for Value {
     fn from(b: BorrowedValue<'_>) -> Self {
         match b {
-            BorrowedValue::Null => Self::Null,
-            BorrowedValue::Bool(b) => Self::Bool(b),
-            BorrowedValue::F64(f) => Self::F64(f),
-            BorrowedValue::I64(i) => Self::I64(i),
-            BorrowedValue::String(s) => Self::from(s.to_string()),
+            BorrowedValue::Static(s) => Self::Static(s),
+            BorrowedValue::RawNumber(n) => Self::RawNumber(n.into_owned()),
+            BorrowedValue::String(s) => Self::String(Str::from(s)),
             BorrowedValue::Array(a) => {
                 Self::Array(a.into_iter().map(|v| v.into()).collect::<Vec<Self>>())
             }
             BorrowedValue::Object(m) => Self::Object(
                 m.into_iter()
-                    .map(|(k, v)| (k.to_string(), v.into()))
+                    .map(|(k, v)| (key(k.into_owned()), v.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<&crate::BorrowedValue<'_>> for Value {
+    fn from(b: &BorrowedValue<'_>) -> Self {
+        match b {
+            BorrowedValue::Static(s) => Self::Static(*s),
+            BorrowedValue::RawNumber(n) => Self::RawNumber(n.to_string()),
+            BorrowedValue::String(s) => Self::String(Str::from(&**s)),
+            BorrowedValue::Array(a) => Self::Array(a.iter().map(Self::from).collect()),
+            BorrowedValue::Object(m) => Self::Object(
+                m.iter()
+                    .map(|(k, v)| (Key::from(&**k), Self::from(v)))
                     .collect(),
             ),
         }
@@ -38,25 +59,25 @@ impl From<crate::BorrowedValue<'_>> for Value {
 
 impl From<&str> for Value {
     fn from(s: &str) -> Self {
-        Self::String(s.to_owned())
+        Self::String(Str::from(s))
     }
 }
 
 impl<'v> From<Cow<'v, str>> for Value {
     fn from(c: Cow<'v, str>) -> Self {
-        Self::String(c.to_string())
+        Self::String(Str::from(c))
     }
 }
 
 impl From<String> for Value {
     fn from(s: String) -> Self {
-        Self::String(s)
+        Self::String(Str::from(s))
     }
 }
 
 impl From<&String> for Value {
     fn from(s: &String) -> Self {
-        Self::String(s.to_owned())
+        Self::String(Str::from(s.as_str()))
     }
 }
 
@@ -64,84 +85,82 @@ impl From<&String> for Value {
 
 impl From<bool> for Value {
     fn from(b: bool) -> Self {
-        Self::Bool(b)
+        Self::Static(StaticNode::Bool(b))
     }
 }
 
 impl From<()> for Value {
     fn from(_b: ()) -> Self {
-        Self::Null
+        Self::Static(StaticNode::Null)
     }
 }
 
 /********* i_ **********/
 impl From<i8> for Value {
     fn from(i: i8) -> Self {
-        Self::I64(i64::from(i))
+        Self::Static(i.into())
     }
 }
 
 impl From<i16> for Value {
     fn from(i: i16) -> Self {
-        Self::I64(i64::from(i))
+        Self::Static(i.into())
     }
 }
 
 impl From<i32> for Value {
     fn from(i: i32) -> Self {
-        Self::I64(i64::from(i))
+        Self::Static(i.into())
     }
 }
 
 impl From<i64> for Value {
     fn from(i: i64) -> Self {
-        Self::I64(i)
+        Self::Static(i.into())
     }
 }
 
 /********* u_ **********/
 impl From<u8> for Value {
     fn from(i: u8) -> Self {
-        Self::I64(i64::from(i))
+        Self::Static(i.into())
     }
 }
 
 impl From<u16> for Value {
     fn from(i: u16) -> Self {
-        Self::I64(i64::from(i))
+        Self::Static(i.into())
     }
 }
 
 impl From<u32> for Value {
     fn from(i: u32) -> Self {
-        Self::I64(i64::from(i))
+        Self::Static(i.into())
     }
 }
 
 impl From<u64> for Value {
     fn from(i: u64) -> Self {
-        #[allow(clippy::cast_possible_wrap)]
-        Self::I64(i as i64)
+        Self::Static(i.into())
     }
 }
 
 impl From<usize> for Value {
     fn from(i: usize) -> Self {
-        #[allow(clippy::cast_possible_wrap)]
-        Self::I64(i as i64)
+        Self::Static(i.into())
     }
 }
 
 /********* f_ **********/
 impl From<f32> for Value {
     fn from(f: f32) -> Self {
-        Self::F64(f64::from(f))
+        Self::Static(f.into())
     }
 }
 
 impl From<f64> for Value {
     fn from(f: f64) -> Self {
-        Self::F64(f)
+        Self::Static(f.into())
     }
 }
 
@@ -154,6 +173,59 @@ where
     }
 }
 
+impl<S> From<&[S]> for Value
+where
+    S: Clone,
+    Value: From<S>,
+{
+    fn from(v: &[S]) -> Self {
+        Self::Array(v.iter().cloned().map(Self::from).collect())
+    }
+}
+
+impl<S> From<Option<S>> for Value
+where
+    Value: From<S>,
+{
+    /// `None` becomes `null`
+    fn from(v: Option<S>) -> Self {
+        v.map_or(Self::Static(StaticNode::Null), Self::from)
+    }
+}
+
+impl<K, V, H> From<HashMap<K, V, H>> for Value
+where
+    K: Into<String>,
+    V: Into<Value>,
+{
+    fn from(m: HashMap<K, V, H>) -> Self {
+        m.into_iter().collect()
+    }
+}
+
+#[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
+impl<K, V> From<BTreeMap<K, V>> for Value
+where
+    K: Into<String>,
+    V: Into<Value>,
+{
+    fn from(m: BTreeMap<K, V>) -> Self {
+        m.into_iter().collect()
+    }
+}
+// objects are `BTreeMap`s themselves, the keys only have to convert into
+// the key type
+#[cfg(all(feature = "sorted-objects", not(feature = "preserve-order")))]
+impl<K, V> From<BTreeMap<K, V>> for Value
+where
+    K: Into<Key>,
+    V: Into<Value>,
+{
+    fn from(m: BTreeMap<K, V>) -> Self {
+        Self::Object(m.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
 impl<V: Into<Value>> FromIterator<V> for Value {
     fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
         Self::Array(iter.into_iter().map(Into::into).collect())
@@ -164,14 +236,118 @@ impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Value {
     fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
         Self::Object(
             iter.into_iter()
-                .map(|(k, v)| (Into::into(k), Into::into(v)))
+                .map(|(k, v)| (key(Into::into(k)), Into::into(v)))
                 .collect(),
         )
     }
 }
 
+/// Appends to an array, a `null` value is turned into an empty array
+/// first.
+///
+/// # Panics
+///
+/// If the value is neither an array nor `null`.
+impl<V: Into<Value>> Extend<V> for Value {
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+        if let Self::Static(StaticNode::Null) = self {
+            *self = Self::Array(Vec::new());
+        }
+        match self {
+            Self::Array(a) => a.extend(iter.into_iter().map(Into::into)),
+            _ => panic!("cannot extend a {:?} with values", self.value_type()),
+        }
+    }
+}
+
+/// Inserts into an object, a `null` value is turned into an empty object
+/// first.
+///
+/// # Panics
+///
+/// If the value is neither an object nor `null`.
+impl<K: Into<String>, V: Into<Value>> Extend<(K, V)> for Value {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        if let Self::Static(StaticNode::Null) = self {
+            *self = Self::Object(Object::default());
+        }
+        match self {
+            Self::Object(o) => {
+                for (k, v) in iter {
+                    o.insert(key(Into::into(k)), Into::into(v));
+                }
+            }
+            _ => panic!("cannot extend a {:?} with members", self.value_type()),
+        }
+    }
+}
+
+// a `BTreeMap` object is covered by the impl for any `BTreeMap`
+#[cfg(not(all(feature = "sorted-objects", not(feature = "preserve-order"))))]
 impl From<Object> for Value {
     fn from(v: Object) -> Self {
         Self::Object(v)
     }
 }
+
+macro_rules! try_from_value {
+    ($t:ty, $f:ident) => {
+        impl TryFrom<Value> for $t {
+            type Error = TryTypeError;
+            fn try_from(v: Value) -> Result<Self, Self::Error> {
+                v.$f()
+            }
+        }
+    };
+}
+
+try_from_value!(bool, try_as_bool);
+try_from_value!(i8, try_as_i8);
+try_from_value!(i16, try_as_i16);
+try_from_value!(i32, try_as_i32);
+try_from_value!(i64, try_as_i64);
+try_from_value!(u8, try_as_u8);
+try_from_value!(u16, try_as_u16);
+try_from_value!(u32, try_as_u32);
+try_from_value!(u64, try_as_u64);
+try_from_value!(usize, try_as_usize);
+try_from_value!(f32, try_as_f32);
+try_from_value!(String, try_into_string);
+
+impl TryFrom<Value> for f64 {
+    type Error = TryTypeError;
+    /// Integers are cast to floats
+    fn try_from(v: Value) -> Result<Self, Self::Error> {
+        v.cast_f64().ok_or_else(|| type_error(&v, ValueType::F64))
+    }
+}
+
+impl<T> TryFrom<Value> for Vec<T>
+where
+    T: TryFrom<Value>,
+    T::Error: From<TryTypeError>,
+{
+    type Error = T::Error;
+    /// Converts every element, failing on the first one that can't be
+    /// converted.
+    fn try_from(v: Value) -> Result<Self, Self::Error> {
+        v.try_into_array()?.into_iter().map(T::try_from).collect()
+    }
+}
+
+impl<T, H> TryFrom<Value> for HashMap<String, T, H>
+where
+    T: TryFrom<Value>,
+    T::Error: From<TryTypeError>,
+    H: BuildHasher + Default,
+{
+    type Error = T::Error;
+    /// Converts every member, failing on the first one that can't be
+    /// converted.
+    fn try_from(v: Value) -> Result<Self, Self::Error> {
+        v.try_into_object()?
+            .into_iter()
+            .map(|(k, v)| Ok((key_into_string(k), T::try_from(v)?)))
+            .collect()
+    }
+}
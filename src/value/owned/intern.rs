@@ -0,0 +1,147 @@
+use super::{Key, Str};
+#[cfg(feature = "arc-keys")]
+use crate::ObjectHasher;
+#[cfg(feature = "arc-keys")]
+use std::collections::HashSet;
+
+/// Interns object keys while parsing owned values so identical keys share
+/// one allocation, for example the keys of millions of records read from
+/// the same NDJSON stream.
+///
+/// Keys can only be shared with the `arc-keys` feature, which makes `Key`
+/// a reference counted `Arc<str>`. Without it keys are plain `String`s and
+/// the interner does nothing. The interner keeps every distinct key it has
+/// seen alive, so it should not be used for objects keyed by unbounded
+/// data like ids; `clear` releases them.
+///
+/// With `ParserOptions::intern_strings` short string values are interned
+/// as well, they are shared with equal keys if `arc-strings` is enabled.
+#[derive(Debug, Default, Clone)]
+pub struct KeyInterner {
+    #[cfg(feature = "arc-keys")]
+    keys: HashSet<Key, ObjectHasher>,
+}
+
+impl KeyInterner {
+    /// Creates a new, empty interner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(feature = "arc-keys"))]
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    pub(crate) fn intern(&mut self, key: &str) -> Key {
+        key.to_owned()
+    }
+
+    #[cfg(feature = "arc-keys")]
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    pub(crate) fn intern(&mut self, key: &str) -> Key {
+        if let Some(k) = self.keys.get(key) {
+            k.clone()
+        } else {
+            let k = Key::from(key);
+            self.keys.insert(k.clone());
+            k
+        }
+    }
+
+    #[cfg(not(feature = "arc-strings"))]
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    pub(crate) fn intern_str(&mut self, s: &str) -> Str {
+        Str::from(s)
+    }
+
+    // keys and strings are both `Arc<str>`s so they share one set
+    #[cfg(feature = "arc-strings")]
+    #[cfg_attr(not(feature = "no-inline"), inline(always))]
+    pub(crate) fn intern_str(&mut self, s: &str) -> Str {
+        self.intern(s)
+    }
+
+    /// Number of distinct keys (and interned strings) held by the interner
+    pub fn len(&self) -> usize {
+        #[cfg(feature = "arc-keys")]
+        return self.keys.len();
+        #[cfg(not(feature = "arc-keys"))]
+        0
+    }
+
+    /// Returns true if the interner holds no keys
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all interned keys and strings, ones still in use stay alive
+    pub fn clear(&mut self) {
+        #[cfg(feature = "arc-keys")]
+        self.keys.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::value::owned::to_value_with_interner;
+    use crate::ParserOptions;
+
+    #[test]
+    fn shares_keys() {
+        let mut keys = KeyInterner::new();
+        let options = ParserOptions::default();
+        let mut d = br#"[{"level": 1, "msg": "a"}, {"level": 2, "msg": "b"}]"#.to_vec();
+        let v = to_value_with_interner(&mut d, &options, &mut keys).expect("valid");
+        let mut d = br#"{"level": 3}"#.to_vec();
+        let w = to_value_with_interner(&mut d, &options, &mut keys).expect("valid");
+        assert_eq!(w, json!({"level": 3}));
+        assert_eq!(
+            v,
+            json!([{"level": 1, "msg": "a"}, {"level": 2, "msg": "b"}])
+        );
+        #[cfg(feature = "arc-keys")]
+        {
+            use crate::ValueAccess;
+            use std::sync::Arc;
+            assert_eq!(keys.len(), 2);
+            let key = |v: &super::super::Value| {
+                v.as_object()
+                    .and_then(|o| o.keys().find(|k| &***k == "level"))
+                    .cloned()
+                    .expect("key")
+            };
+            let first = key(v.get_idx(0).expect("record"));
+            assert!(Arc::ptr_eq(&first, &key(v.get_idx(1).expect("record"))));
+            assert!(Arc::ptr_eq(&first, &key(&w)));
+        }
+        keys.clear();
+        assert!(keys.is_empty());
+    }
+
+    #[test]
+    fn shares_strings() {
+        let mut keys = KeyInterner::new();
+        let options = ParserOptions {
+            intern_strings: Some(8),
+            ..ParserOptions::default()
+        };
+        let mut d = br#"[{"level": "INFO", "msg": "a long message"}, {"level": "INFO"}]"#.to_vec();
+        let v = to_value_with_interner(&mut d, &options, &mut keys).expect("valid");
+        assert_eq!(
+            v,
+            json!([{"level": "INFO", "msg": "a long message"}, {"level": "INFO"}])
+        );
+        #[cfg(feature = "arc-strings")]
+        {
+            use super::super::Value;
+            use crate::ValueAccess;
+            use std::sync::Arc;
+            // `level`, `msg` and `INFO`
+            assert_eq!(keys.len(), 3);
+            let level = |i| match v.get_idx(i).map(|r| &r["level"]) {
+                Some(Value::String(s)) => s.clone(),
+                _ => panic!("not a string"),
+            };
+            assert!(Arc::ptr_eq(&level(0), &level(1)));
+        }
+    }
+}
@@ -7,7 +7,9 @@
 use super::{Object, Value};
 use crate::stry;
 use crate::value::generator::*;
-use crate::value::ValueTrait;
+use crate::value::{StaticNode, ValueAccess, ValueTrait};
+use crate::{RedactMode, SerializeOptions, Truncation};
+use std::fmt;
 use std::io;
 use std::io::Write;
 
@@ -28,6 +30,16 @@ impl Value {
         g.consume()
     }
 
+    /// Encodes the value into it's JSON representation appending it to
+    /// `buf`. The buffer is not cleared first, so one buffer can be reused
+    /// (after calling `clear` on it) for many values without allocating a
+    /// new `String` each time.
+    pub fn encode_into(&self, buf: &mut Vec<u8>) {
+        let mut g = DumpGenerator::with_buffer(std::mem::take(buf));
+        let _ = g.write_json(self);
+        *buf = g.into_buffer();
+    }
+
     /// Encodes the value into it's JSON representation as a string (pretty printed)
     #[deprecated(since = "0.1.21", note = "Please use encode instead")]
     pub fn to_string_pp(&self) -> String {
@@ -58,34 +70,108 @@ impl Value {
         let mut g = PrettyWriterGenerator::new(w, 2);
         g.write_json(self)
     }
+
+    /// Encodes the value into it's JSON representation into a `fmt::Write`
+    /// like a `String` or a `fmt::Formatter`, for `io::Write` targets use
+    /// `write`.
+    pub fn write_into<W>(&self, w: &mut W) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let mut w = FmtWriter::new(w);
+        let mut g = WriterGenerator::new(&mut w);
+        if g.write_json(self).is_err() {
+            return Err(fmt::Error);
+        }
+        w.finish().map_err(|_| fmt::Error)
+    }
+
+    /// Encodes the value into it's JSON representation as a string, formatted
+    /// according to `options`
+    pub fn encode_with_options(&self, options: &SerializeOptions) -> String {
+        let mut code = Vec::with_capacity(1024);
+        let _ = self.write_with_options(&mut code, options);
+        // Original strings were unicode, numbers are all ASCII,
+        // therefore this is safe.
+        unsafe { String::from_utf8_unchecked(code) }
+    }
+
+    /// Encodes the value into it's JSON representation into a Writer, formatted
+    /// according to `options`
+    pub fn write_with_options<'writer, W>(
+        &self,
+        w: &mut W,
+        options: &SerializeOptions,
+    ) -> io::Result<()>
+    where
+        W: 'writer + Write,
+    {
+        let mut g = OptionsGenerator::new(w, options.clone());
+        stry!(g.write_json(self));
+        g.finish()
+    }
 }
 
-trait Generator: BaseGenerator {
+pub(crate) trait Generator: BaseGenerator {
     type T: Write;
     type V: ValueTrait;
 
     #[inline(always)]
     fn write_object(&mut self, object: &Object) -> io::Result<()> {
+        let limits = self.limits();
+        if !object.is_empty() && limits.max_depth.map_or(false, |max| self.depth() >= max) {
+            return self.write_string(&format!("…({} members)", object.len()));
+        }
+        let max_items = limits.max_items.unwrap_or_else(usize::max_value);
         stry!(self.write_char(b'{'));
-        let mut iter = object.iter();
+        let mut written = 0;
+        let mut elided = 0;
 
-        if let Some((key, value)) = iter.next() {
-            self.indent();
+        for (key, value) in object.iter() {
+            let redact = self.redact(key);
+            if redact == Some(RedactMode::Drop) {
+                continue;
+            }
+            if written == max_items {
+                elided += 1;
+                continue;
+            }
+            if written == 0 {
+                self.indent();
+            } else {
+                stry!(self.write_comma());
+            }
+            written += 1;
             stry!(self.new_line());
-            stry!(self.write_string(key));
-            stry!(self.write_min(b": ", b':'));
-            stry!(self.write_json(value));
-        } else {
-            stry!(self.write_char(b'}'));
-            return Ok(());
+            match self.key_case() {
+                Some(case) => stry!(self.write_string(&case.apply(key))),
+                None => stry!(self.write_string(key)),
+            }
+            stry!(self.write_colon());
+            if redact.is_some() {
+                stry!(self.write(b"\"***\""));
+            } else {
+                self.enter_key(key);
+                stry!(self.write_json(value));
+                self.leave();
+            }
         }
 
-        for (key, value) in iter {
-            stry!(self.write_char(b','));
+        if elided > 0 {
+            if written == 0 {
+                self.indent();
+            } else {
+                stry!(self.write_comma());
+            }
+            written += 1;
             stry!(self.new_line());
-            stry!(self.write_string(key));
-            stry!(self.write_min(b": ", b':'));
-            stry!(self.write_json(value));
+            stry!(self.write_string("…"));
+            stry!(self.write_colon());
+            stry!(self.write_string(&format!("(+{} members)", elided)));
+        }
+
+        if written == 0 {
+            return self.write_char(b'}');
         }
 
         self.dedent();
@@ -96,29 +182,64 @@ trait Generator: BaseGenerator {
     #[inline(always)]
     fn write_json(&mut self, json: &Value) -> io::Result<()> {
         match *json {
-            Value::Null => self.write(b"null"),
-            Value::String(ref string) => self.write_string(string),
-            Value::I64(number) => self.write_int(number),
-            Value::F64(number) => self.write_float(number),
-            Value::Bool(true) => self.write(b"true"),
-            Value::Bool(false) => self.write(b"false"),
+            Value::Static(StaticNode::Null) => self.write(b"null"),
+            Value::String(ref string) => self.write_string_limited(string),
+            Value::Static(StaticNode::Number(number)) => self.write_number(number),
+            Value::RawNumber(ref number) => self.write(number.as_bytes()),
+            Value::Static(StaticNode::Bool(true)) => self.write(b"true"),
+            Value::Static(StaticNode::Bool(false)) => self.write(b"false"),
             Value::Array(ref array) => {
+                let limits = self.limits();
+                if !array.is_empty() && limits.max_depth.map_or(false, |max| self.depth() >= max) {
+                    return self.write_string(&format!("…({} items)", array.len()));
+                }
+                let shown = limits
+                    .max_items
+                    .map_or(array.len(), |max| max.min(array.len()));
+                let marker = if shown < array.len() {
+                    Some(format!("…(+{} items)", array.len() - shown))
+                } else {
+                    None
+                };
+                let array = &array[..shown];
+                if let Some(options) = self.scalar_array_options() {
+                    if !array.iter().any(|v| v.is_array() || v.is_object()) {
+                        let mut items: Vec<Vec<u8>> = array
+                            .iter()
+                            .map(|v| v.encode_with_options(&options).into_bytes())
+                            .collect();
+                        if let Some(marker) = marker {
+                            let options = SerializeOptions {
+                                truncate: Truncation::default(),
+                                ..options
+                            };
+                            let marker = Value::from(marker);
+                            items.push(marker.encode_with_options(&options).into_bytes());
+                        }
+                        return self.write_scalar_array(&items);
+                    }
+                }
                 stry!(self.write_char(b'['));
-                let mut iter = array.iter();
+                if array.is_empty() && marker.is_none() {
+                    return self.write_char(b']');
+                }
 
-                if let Some(item) = iter.next() {
-                    self.indent();
+                self.indent();
+                for (i, item) in array.iter().enumerate() {
+                    if i > 0 {
+                        stry!(self.write_comma());
+                    }
                     stry!(self.new_line());
+                    self.enter_index(i);
                     stry!(self.write_json(item));
-                } else {
-                    stry!(self.write_char(b']'));
-                    return Ok(());
+                    self.leave();
                 }
-
-                for item in iter {
-                    stry!(self.write_char(b','));
+                if let Some(marker) = marker {
+                    if !array.is_empty() {
+                        stry!(self.write_comma());
+                    }
                     stry!(self.new_line());
-                    stry!(self.write_json(item));
+                    stry!(self.write_string(&marker));
                 }
 
                 self.dedent();
@@ -156,20 +277,58 @@ where
     type V = Value;
 }
 
+impl<'w, W> Generator for OptionsGenerator<'w, W, Value>
+where
+    W: Write,
+{
+    type T = ColumnWriter<'w, W>;
+    type V = Value;
+}
+
 #[cfg(test)]
 mod test {
     use super::Value;
+    use crate::StaticNode;
+    use crate::{FloatFormat, SerializeOptions, Truncation};
     #[test]
     fn null() {
-        assert_eq!(Value::Null.encode(), "null")
+        assert_eq!(Value::Static(StaticNode::Null).encode(), "null")
+    }
+
+    #[test]
+    fn non_finite_floats() {
+        let v = Value::from(vec![f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 0.5]);
+        let expected = "[null,null,null,0.5]";
+        assert_eq!(v.encode(), expected);
+        assert_eq!(crate::BorrowedValue::from(v.clone()).encode(), expected);
+        assert_eq!(
+            v.encode_with_options(&SerializeOptions::default()),
+            expected
+        );
+        for (float_format, expected) in &[
+            (FloatFormat::Fixed(2), "[null,null,null,0.50]"),
+            (
+                FloatFormat::Scientific { threshold: 1 },
+                "[null,null,null,5e-1]",
+            ),
+        ] {
+            let options = SerializeOptions {
+                float_format: *float_format,
+                ..SerializeOptions::default()
+            };
+            assert_eq!(v.encode_with_options(&options), *expected);
+        }
+        assert_eq!(v.encode_pp(), "[\n  null,\n  null,\n  null,\n  0.5\n]");
+        #[cfg(feature = "serde_impl")]
+        assert_eq!(crate::serde::to_string(&v).expect("valid"), expected);
     }
     #[test]
     fn bool_true() {
-        assert_eq!(Value::Bool(true).encode(), "true")
+        assert_eq!(Value::Static(StaticNode::Bool(true)).encode(), "true")
     }
     #[test]
     fn bool_false() {
-        assert_eq!(Value::Bool(false).encode(), "false")
+        assert_eq!(Value::Static(StaticNode::Bool(false)).encode(), "false")
     }
     fn assert_str(from: &str, to: &str) {
         assert_eq!(Value::String(from.into()).encode(), to)
@@ -193,4 +352,187 @@ mod test {
             r#""this is a test a \\\"long\\\" test that should span the 32 byte boundary""#,
         );
     }
+
+    #[test]
+    fn options_default_matches_encode() {
+        let v = json!({"a": [1, 2.5, "3"], "b": {"c": null}});
+        assert_eq!(
+            v.encode_with_options(&SerializeOptions::default()),
+            v.encode()
+        );
+        assert_eq!(
+            v.encode_with_options(&SerializeOptions::pretty()),
+            v.encode_pp()
+        );
+    }
+
+    #[test]
+    fn options_separators() {
+        let v = json!({"a": [1, 2]});
+        let options = SerializeOptions {
+            space_after_colon: true,
+            space_after_comma: true,
+            trailing_newline: true,
+            ..SerializeOptions::default()
+        };
+        assert_eq!(v.encode_with_options(&options), "{\"a\": [1, 2]}\n");
+        let options = SerializeOptions {
+            space_after_colon: false,
+            ..SerializeOptions::pretty()
+        };
+        assert_eq!(
+            v.encode_with_options(&options),
+            "{\n  \"a\":[\n    1,\n    2\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn options_line_width() {
+        let options = SerializeOptions {
+            max_line_width: Some(16),
+            ..SerializeOptions::pretty()
+        };
+        let v = json!({"a": [1, 2, 3], "b": [[], {}], "c": []});
+        assert_eq!(
+            v.encode_with_options(&options),
+            "{\n  \"a\": [1, 2, 3],\n  \"b\": [\n    [],\n    {}\n  ],\n  \"c\": []\n}"
+        );
+        let v = json!([100, 200, 300, 400, 500, "ä"]);
+        assert_eq!(
+            v.encode_with_options(&options),
+            "[\n  100, 200, 300,\n  400, 500, \"ä\"\n]"
+        );
+    }
+
+    #[test]
+    fn options_float_format() {
+        let options = SerializeOptions {
+            max_line_width: Some(20),
+            float_format: FloatFormat::Scientific { threshold: 3 },
+            ..SerializeOptions::pretty()
+        };
+        let v = json!({"a": [1.5, 1234.5, 0.001], "b": -2e-10});
+        assert_eq!(
+            v.encode_with_options(&options),
+            "{\n  \"a\": [\n    1.5, 1.2345e3,\n    1e-3\n  ],\n  \"b\": -2e-10\n}"
+        );
+        let options = SerializeOptions {
+            float_format: FloatFormat::Scientific {
+                threshold: u16::max_value(),
+            },
+            ..SerializeOptions::default()
+        };
+        let v = json!([1e22, -1.0, 1e-7]);
+        assert_eq!(
+            v.encode_with_options(&options),
+            "[10000000000000000000000.0,-1.0,0.0000001]"
+        );
+    }
+
+    #[test]
+    fn options_escaping() {
+        let v = json!({"k\u{e4}": "</script>&\"a\u{1f600}\n"});
+        let options = SerializeOptions {
+            escape_non_ascii: true,
+            ..SerializeOptions::default()
+        };
+        assert_eq!(
+            v.encode_with_options(&options),
+            r#"{"k\u00e4":"</script>&\"a\ud83d\ude00\n"}"#
+        );
+        let options = SerializeOptions {
+            escape_html: true,
+            ..SerializeOptions::default()
+        };
+        assert_eq!(
+            v.encode_with_options(&options),
+            "{\"k\u{e4}\":\"\\u003c\\/script\\u003e\\u0026\\\"a\u{1f600}\\n\"}"
+        );
+    }
+
+    #[test]
+    fn write_into() {
+        let v = json!({"a": ["\u{e4}\u{1f600}", 1.5, null]});
+        let mut s = String::from(">");
+        v.write_into(&mut s).expect("valid");
+        assert_eq!(s, format!(">{}", v.encode()));
+        let long = "\u{1f600}".repeat(40);
+        let v = json!([long]);
+        let mut s = String::new();
+        v.write_into(&mut s).expect("valid");
+        assert_eq!(s, v.encode());
+    }
+
+    #[test]
+    fn encode_into() {
+        let v = json!({"a": [1, "b\n"], "c": null});
+        let mut buf = b"> ".to_vec();
+        v.encode_into(&mut buf);
+        assert_eq!(buf, format!("> {}", v.encode()).into_bytes());
+        let capacity = buf.capacity();
+        buf.clear();
+        Value::from(42).encode_into(&mut buf);
+        assert_eq!(buf, b"42");
+        assert_eq!(buf.capacity(), capacity);
+    }
+
+    #[test]
+    fn options_redact() {
+        use crate::{RedactMode, Redaction};
+        let v = json!({"a": {"b": [{"c": 2, "d": 3}], "token": 1}, "token": "t"});
+        let mut options = SerializeOptions {
+            redact: Redaction {
+                keys: vec!["token".into()],
+                pointers: vec!["/a/b/*/c".into(), "/*/x".into(), "bad".into()],
+                mode: RedactMode::Drop,
+            },
+            ..SerializeOptions::pretty()
+        };
+        assert_eq!(
+            v.encode_with_options(&options),
+            "{\n  \"a\": {\n    \"b\": [\n      {\n        \"d\": 3\n      }\n    ]\n  }\n}"
+        );
+        options.indent = None;
+        options.redact.mode = RedactMode::Mask;
+        assert_eq!(
+            v.encode_with_options(&options),
+            r#"{"a": {"b": [{"c": "***", "d": 3}], "token": "***"}, "token": "***"}"#
+        );
+        let v = json!({"token": "t"});
+        options.redact.mode = RedactMode::Drop;
+        assert_eq!(v.encode_with_options(&options), "{}");
+    }
+
+    #[test]
+    fn options_truncate() {
+        let v = json!({"a": "äöü€x", "b": [1, 2, 3, 4], "c": [{"b": []}, {}], "d": []});
+        let mut options = SerializeOptions {
+            max_line_width: Some(80),
+            truncate: Truncation {
+                max_string_length: Some(3),
+                max_items: Some(3),
+                max_depth: None,
+            },
+            ..SerializeOptions::pretty()
+        };
+        assert_eq!(
+            v.encode_with_options(&options),
+            "{\n  \"a\": \"äöü…(+2 chars)\",\n  \"b\": [1, 2, 3, \"…(+1 items)\"],\n  \"c\": [\n    {\n      \"b\": []\n    },\n    {}\n  ],\n  \"…\": \"(+1 members)\"\n}"
+        );
+        options.indent = None;
+        options.truncate.max_items = Some(0);
+        assert_eq!(v.encode_with_options(&options), r#"{"…": "(+4 members)"}"#);
+        assert_eq!(
+            json!([1]).encode_with_options(&options),
+            r#"["…(+1 items)"]"#
+        );
+        options.truncate = Truncation {
+            max_depth: Some(2),
+            ..Truncation::default()
+        };
+        assert_eq!(v["c"].encode_with_options(&options), r#"[{"b": []}, {}]"#);
+        options.truncate.max_depth = Some(0);
+        assert_eq!(v.encode_with_options(&options), r#""…(4 members)""#);
+        assert_eq!(v["d"].encode_with_options(&options), "[]");
+    }
 }
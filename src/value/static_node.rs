@@ -0,0 +1,118 @@
+use crate::value::{Number, ValueType};
+use std::fmt;
+
+/// The scalar values `null`, booleans and numbers. They don't own any
+/// memory, so they are `Copy` and can be moved between `OwnedValue` and
+/// `BorrowedValue` for free.
+///
+/// ```
+/// use simd_json::{BorrowedValue, OwnedValue, StaticNode};
+/// let n = StaticNode::from(42);
+/// assert_eq!(n.as_u64(), Some(42));
+/// assert_eq!(OwnedValue::from(n), BorrowedValue::from(n));
+/// assert_eq!(StaticNode::Null.to_string(), "null");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaticNode {
+    /// null
+    Null,
+    /// boolean type
+    Bool(bool),
+    /// number type
+    Number(Number),
+}
+
+impl StaticNode {
+    /// The type of the scalar
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Self::Null => ValueType::Null,
+            Self::Bool(_) => ValueType::Bool,
+            Self::Number(n) if n.is_f64() => ValueType::F64,
+            Self::Number(_) => ValueType::I64,
+        }
+    }
+
+    /// Returns true if the scalar is `null`
+    pub fn is_null(&self) -> bool {
+        match self {
+            Self::Null => true,
+            _ => false,
+        }
+    }
+
+    /// The boolean if the scalar is one
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The number if the scalar is one
+    pub fn as_number(&self) -> Option<Number> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The scalar as an `i64` if it is an integer that fits one
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(|n| n.as_i64())
+    }
+
+    /// The scalar as an `u64` if it is a non negative integer
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(|n| n.as_u64())
+    }
+
+    /// The scalar as an `i128` if it is an integer
+    pub fn as_i128(&self) -> Option<i128> {
+        self.as_number().and_then(|n| n.as_i128())
+    }
+
+    /// The scalar as a float if it is a float
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number()
+            .filter(Number::is_f64)
+            .and_then(|n| n.as_f64())
+    }
+
+    /// The scalar as a float if it is any number
+    pub fn cast_f64(&self) -> Option<f64> {
+        self.as_number().and_then(|n| n.as_f64())
+    }
+}
+
+impl fmt::Display for StaticNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(false) => f.write_str("false"),
+            Self::Bool(true) => f.write_str("true"),
+            Self::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl From<()> for StaticNode {
+    #[inline]
+    fn from(_: ()) -> Self {
+        Self::Null
+    }
+}
+
+impl From<bool> for StaticNode {
+    #[inline]
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl<T: Into<Number>> From<T> for StaticNode {
+    #[inline]
+    fn from(n: T) -> Self {
+        Self::Number(n.into())
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{BorrowedValue, OwnedValue, StaticNode};
+use proptest::arbitrary::Arbitrary;
+use proptest::prelude::*;
+
+/// A strategy for owned values nested at most `depth` levels deep, aiming
+/// for at most `size` nodes with up to `items` elements per array or
+/// object. Floats are always finite so the values can be encoded as JSON.
+///
+/// ```
+/// use proptest::strategy::{Strategy, ValueTree};
+/// use proptest::test_runner::TestRunner;
+/// use simd_json::value::strategy::owned_value;
+/// let mut runner = TestRunner::default();
+/// let v = owned_value(4, 64, 8).new_tree(&mut runner).unwrap().current();
+/// let mut encoded = v.encode().into_bytes();
+/// assert_eq!(simd_json::to_owned_value(&mut encoded).unwrap(), v);
+/// ```
+pub fn owned_value(depth: u32, size: u32, items: u32) -> BoxedStrategy<OwnedValue> {
+    let leaf = prop_oneof![
+        Just(OwnedValue::Static(StaticNode::Null)),
+        any::<bool>().prop_map(OwnedValue::from),
+        any::<i64>().prop_map(OwnedValue::from),
+        (prop::num::f64::NORMAL | prop::num::f64::ZERO).prop_map(OwnedValue::from),
+        ".*".prop_map(OwnedValue::from),
+    ];
+    let items = items as usize;
+    leaf.prop_recursive(depth, size, items as u32, move |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..=items).prop_map(OwnedValue::Array),
+            prop::collection::hash_map(".*", inner, 0..=items)
+                .prop_map(|m| m.into_iter().collect::<OwnedValue>()),
+        ]
+    })
+    .boxed()
+}
+
+/// Same as `owned_value` for borrowed values, their strings are owned.
+pub fn borrowed_value(depth: u32, size: u32, items: u32) -> BoxedStrategy<BorrowedValue<'static>> {
+    owned_value(depth, size, items)
+        .prop_map(BorrowedValue::from)
+        .boxed()
+}
+
+/// Values nested up to 8 levels deep with up to 256 nodes and 10 elements
+/// per array or object.
+impl Arbitrary for OwnedValue {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        owned_value(8, 256, 10)
+    }
+}
+
+/// Values nested up to 8 levels deep with up to 256 nodes and 10 elements
+/// per array or object.
+impl Arbitrary for BorrowedValue<'static> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        borrowed_value(8, 256, 10)
+    }
+}
@@ -0,0 +1,33 @@
+/// JSON extractor and response for web frameworks using this crate instead
+/// of `serde_json`, the `axum` feature implements it for axum and the
+/// `actix` feature for actix-web.
+///
+/// As an extractor it buffers the request body and deserializes it in
+/// place, requests without a JSON content type are rejected with `415
+/// Unsupported Media Type` and invalid bodies with `400 Bad Request`. As a
+/// response it serializes the value with a JSON content type.
+///
+/// ```ignore
+/// use simd_json::SimdJson;
+///
+/// async fn create_user(SimdJson(user): SimdJson<User>) -> SimdJson<Created> {
+///     SimdJson(Created { id: insert(user) })
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SimdJson<T>(pub T);
+
+// `application/json` or any `application/*+json` type
+pub(crate) fn is_json_content_type(content_type: Option<&str>) -> bool {
+    let content_type = match content_type {
+        Some(c) => c,
+        None => return false,
+    };
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime == "application/json" || (mime.starts_with("application/") && mime.ends_with("+json"))
+}
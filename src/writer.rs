@@ -0,0 +1,338 @@
+use crate::value::generator::{BaseGenerator, OptionsGenerator};
+use crate::value::owned::serialize::Generator;
+use crate::{OwnedValue, SerializeOptions};
+use std::io;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    Array { empty: bool },
+    Object { empty: bool, has_key: bool },
+}
+
+/// Writes a JSON document incrementally using push style calls, without
+/// building a value first. Nesting is validated as the document is written,
+/// calls that would produce invalid JSON fail with an
+/// `io::ErrorKind::InvalidInput` error and write nothing.
+///
+/// Since nothing is buffered `max_line_width` of the options is ignored.
+///
+/// ```
+/// use simd_json::JsonWriter;
+/// let mut out = Vec::new();
+/// let mut w = JsonWriter::new(&mut out);
+/// w.begin_object().unwrap();
+/// w.key("rows").unwrap();
+/// w.begin_array().unwrap();
+/// for i in 0..3 {
+///     w.value_i64(i).unwrap();
+/// }
+/// w.end_array().unwrap();
+/// w.key("done").unwrap();
+/// w.value_bool(true).unwrap();
+/// w.end_object().unwrap();
+/// w.finish().unwrap();
+/// assert_eq!(out, br#"{"rows":[0,1,2],"done":true}"#);
+/// ```
+pub struct JsonWriter<'w, W: 'w + Write> {
+    g: OptionsGenerator<'w, W, OwnedValue>,
+    stack: Vec<Scope>,
+    written: bool,
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+impl<'w, W: Write> JsonWriter<'w, W> {
+    /// Creates a writer producing compact output
+    pub fn new(writer: &'w mut W) -> Self {
+        Self::with_options(writer, SerializeOptions::default())
+    }
+
+    /// Creates a writer formatting its output according to `options`
+    pub fn with_options(writer: &'w mut W, options: SerializeOptions) -> Self {
+        Self {
+            g: OptionsGenerator::new(writer, options),
+            stack: Vec::new(),
+            written: false,
+        }
+    }
+
+    /// Current nesting depth
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    // Writes whatever has to precede a value in the current scope
+    fn before_value(&mut self) -> io::Result<()> {
+        match self.stack.last_mut() {
+            None if self.written => Err(invalid("document already complete")),
+            None => {
+                self.written = true;
+                Ok(())
+            }
+            Some(Scope::Object { has_key, .. }) => {
+                if *has_key {
+                    *has_key = false;
+                    Ok(())
+                } else {
+                    Err(invalid("expected a key"))
+                }
+            }
+            Some(Scope::Array { empty }) => {
+                let was_empty = *empty;
+                *empty = false;
+                if was_empty {
+                    self.g.indent();
+                } else {
+                    stry!(self.g.write_comma());
+                }
+                self.g.new_line()
+            }
+        }
+    }
+
+    /// Writes the key for the next member of the current object
+    pub fn key(&mut self, key: &str) -> io::Result<()> {
+        match self.stack.last_mut() {
+            Some(Scope::Object { empty, has_key }) if !*has_key => {
+                let was_empty = *empty;
+                *empty = false;
+                *has_key = true;
+                if was_empty {
+                    self.g.indent();
+                } else {
+                    stry!(self.g.write_comma());
+                }
+                stry!(self.g.new_line());
+                stry!(self.g.write_string(key));
+                self.g.write_colon()
+            }
+            Some(Scope::Object { .. }) => Err(invalid("expected a value")),
+            _ => Err(invalid("keys are only allowed in objects")),
+        }
+    }
+
+    /// Starts an object, it has to be closed with `end_object`
+    pub fn begin_object(&mut self) -> io::Result<()> {
+        stry!(self.before_value());
+        self.stack.push(Scope::Object {
+            empty: true,
+            has_key: false,
+        });
+        self.g.write_char(b'{')
+    }
+
+    /// Ends the current object
+    pub fn end_object(&mut self) -> io::Result<()> {
+        match self.stack.last() {
+            Some(Scope::Object { empty, has_key }) if !*has_key => {
+                if !*empty {
+                    self.g.dedent();
+                    stry!(self.g.new_line());
+                }
+                self.stack.pop();
+                self.g.write_char(b'}')
+            }
+            Some(Scope::Object { .. }) => Err(invalid("expected a value")),
+            _ => Err(invalid("no object to end")),
+        }
+    }
+
+    /// Starts an array, it has to be closed with `end_array`
+    pub fn begin_array(&mut self) -> io::Result<()> {
+        stry!(self.before_value());
+        self.stack.push(Scope::Array { empty: true });
+        self.g.write_char(b'[')
+    }
+
+    /// Ends the current array
+    pub fn end_array(&mut self) -> io::Result<()> {
+        match self.stack.last() {
+            Some(Scope::Array { empty }) => {
+                if !*empty {
+                    self.g.dedent();
+                    stry!(self.g.new_line());
+                }
+                self.stack.pop();
+                self.g.write_char(b']')
+            }
+            _ => Err(invalid("no array to end")),
+        }
+    }
+
+    /// Writes a complete value, this can be a scalar or a fragment that is
+    /// small enough to be built in memory.
+    pub fn value(&mut self, value: &OwnedValue) -> io::Result<()> {
+        stry!(self.before_value());
+        self.g.write_json(value)
+    }
+
+    /// Ends the innermost open array or object
+    pub fn end(&mut self) -> io::Result<()> {
+        match self.stack.last() {
+            Some(Scope::Array { .. }) => self.end_array(),
+            Some(Scope::Object { .. }) => self.end_object(),
+            None => Err(invalid("nothing to end")),
+        }
+    }
+
+    /// Writes a string value
+    pub fn value_str(&mut self, value: &str) -> io::Result<()> {
+        stry!(self.before_value());
+        self.g.write_string(value)
+    }
+
+    /// Writes an integer value
+    pub fn value_i64(&mut self, value: i64) -> io::Result<()> {
+        stry!(self.before_value());
+        self.g.write_int(value)
+    }
+
+    /// Writes an unsigned integer value, like the ones above `i64::MAX`
+    pub fn value_u64(&mut self, value: u64) -> io::Result<()> {
+        stry!(self.before_value());
+        itoa::write(self.g.get_writer(), value).map(|_| ())
+    }
+
+    /// Writes a float value, `NaN` and the infinities are written as `null`
+    /// like everywhere else in this crate.
+    pub fn value_f64(&mut self, value: f64) -> io::Result<()> {
+        stry!(self.before_value());
+        self.g.write_float(value)
+    }
+
+    /// Writes a boolean value
+    pub fn value_bool(&mut self, value: bool) -> io::Result<()> {
+        stry!(self.before_value());
+        self.g.write(if value { b"true" } else { b"false" })
+    }
+
+    /// Writes `null`
+    pub fn value_null(&mut self) -> io::Result<()> {
+        stry!(self.before_value());
+        self.g.write(b"null")
+    }
+
+    /// Checks that the document is complete and finishes it
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.written {
+            Err(invalid("no value written"))
+        } else if !self.stack.is_empty() {
+            Err(invalid("unclosed array or object"))
+        } else {
+            stry!(self.g.finish());
+            self.g.get_writer().flush()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pretty() {
+        let mut out = Vec::new();
+        let mut w = JsonWriter::with_options(&mut out, SerializeOptions::pretty());
+        w.begin_object().unwrap();
+        w.key("a").unwrap();
+        w.begin_array().unwrap();
+        w.value_null().unwrap();
+        w.value_str("\"x\"").unwrap();
+        w.begin_object().unwrap();
+        w.end_object().unwrap();
+        w.end_array().unwrap();
+        w.key("b").unwrap();
+        w.value_f64(1.5).unwrap();
+        w.end_object().unwrap();
+        w.finish().unwrap();
+        let v = json!({"a": [null, "\"x\"", {}], "b": 1.5});
+        assert_eq!(String::from_utf8(out).unwrap(), v.encode_pp());
+    }
+
+    #[test]
+    fn nesting_errors() {
+        // invalid calls must not write anything, in particular no commas,
+        // new lines or indentation
+        for options in &[SerializeOptions::default(), SerializeOptions::pretty()] {
+            let mut out = Vec::new();
+            let mut w = JsonWriter::with_options(&mut out, options.clone());
+            assert!(w.key("a").is_err());
+            assert!(w.end_array().is_err());
+            assert!(w.end().is_err());
+            w.begin_object().unwrap();
+            assert!(w.value_i64(1).is_err());
+            assert!(w.value_u64(1).is_err());
+            assert!(w.begin_array().is_err());
+            assert!(w.end_array().is_err());
+            w.key("a").unwrap();
+            assert!(w.key("b").is_err());
+            assert!(w.end_object().is_err());
+            assert!(w.end().is_err());
+            w.begin_array().unwrap();
+            w.value_u64(u64::MAX).unwrap();
+            assert!(w.key("b").is_err());
+            assert!(w.end_object().is_err());
+            assert_eq!(w.depth(), 2);
+            w.end_array().unwrap();
+            assert!(w.value_str("c").is_err());
+            w.key("b").unwrap();
+            w.value_i64(-1).unwrap();
+            w.end_object().unwrap();
+            assert!(w.value_i64(1).is_err());
+            assert!(w.value(&json!([1])).is_err());
+            assert!(w.begin_object().is_err());
+            assert!(w.end().is_err());
+            w.finish().unwrap();
+            let expected = json!({"a": [u64::MAX], "b": -1});
+            let expected = if options.indent.is_some() {
+                expected.encode_pp()
+            } else {
+                expected.encode()
+            };
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn non_finite_floats() {
+        let mut out = Vec::new();
+        let mut w = JsonWriter::new(&mut out);
+        w.begin_array().unwrap();
+        w.value_f64(f64::NAN).unwrap();
+        w.value_f64(f64::INFINITY).unwrap();
+        w.value(&OwnedValue::from(f64::NEG_INFINITY)).unwrap();
+        w.value(&json!([f64::NAN, 0.5])).unwrap();
+        w.end_array().unwrap();
+        w.finish().unwrap();
+        assert_eq!(out, b"[null,null,null,[null,0.5]]");
+    }
+
+    #[test]
+    fn incomplete() {
+        let mut out = Vec::new();
+        assert!(JsonWriter::new(&mut out).finish().is_err());
+        let mut w = JsonWriter::new(&mut out);
+        w.begin_array().unwrap();
+        assert!(w.finish().is_err());
+    }
+
+    #[test]
+    fn values_and_end() {
+        let mut out = Vec::new();
+        let mut w = JsonWriter::with_options(&mut out, SerializeOptions::pretty());
+        w.begin_array().unwrap();
+        w.value(&json!({"a": [1, 2]})).unwrap();
+        w.begin_object().unwrap();
+        w.key("b").unwrap();
+        w.value(&OwnedValue::from("c")).unwrap();
+        w.end().unwrap();
+        w.end().unwrap();
+        assert!(w.end().is_err());
+        w.finish().unwrap();
+        let v = json!([{"a": [1, 2]}, {"b": "c"}]);
+        assert_eq!(String::from_utf8(out).unwrap(), v.encode_pp());
+    }
+}
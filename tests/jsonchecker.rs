@@ -72,6 +72,7 @@ pass!(pass13);
 pass!(pass14);
 pass!(pass15);
 pass!(pass16);
+pass!(pass17_u64);
 
 // fail!(fail01_EXCLUDED);
 fail!(fail02);
@@ -116,7 +117,6 @@ fail!(fail37);
 fail!(fail38);
 //fail!(fail39_EXCLUDED);
 
-fail!(fail40_s64boverflow);
 fail!(fail41_toolarge);
 fail!(fail42);
 fail!(fail43);